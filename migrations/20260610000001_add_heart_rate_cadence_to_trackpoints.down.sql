@@ -0,0 +1,3 @@
+ALTER TABLE trackpoints
+    DROP COLUMN heart_rate,
+    DROP COLUMN cadence;
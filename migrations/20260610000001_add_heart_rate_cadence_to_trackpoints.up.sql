@@ -0,0 +1,4 @@
+-- Optional sensor channels recorded by TCX (and later FIT/Strava) imports.
+ALTER TABLE trackpoints
+    ADD COLUMN heart_rate SMALLINT,
+    ADD COLUMN cadence    SMALLINT;
@@ -0,0 +1,21 @@
+-- Background upload jobs: POST /activities/upload/{user_id} returns a job id
+-- immediately and the import runs in a background task that records its
+-- progress here.  `result` holds the final UploadResponse as JSON.
+CREATE TABLE upload_jobs (
+    id              UUID PRIMARY KEY,
+    user_id         UUID        NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status          TEXT        NOT NULL DEFAULT 'queued'
+                    CHECK (status IN ('queued', 'running', 'completed', 'failed')),
+    total_items     INTEGER     NOT NULL DEFAULT 0,
+    processed_items INTEGER     NOT NULL DEFAULT 0,
+    inserted        INTEGER     NOT NULL DEFAULT 0,
+    duplicates      INTEGER     NOT NULL DEFAULT 0,
+    skipped         INTEGER     NOT NULL DEFAULT 0,
+    result          JSONB,
+    error           TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at      TIMESTAMPTZ,
+    finished_at     TIMESTAMPTZ
+);
+
+CREATE INDEX idx_upload_jobs_user_created ON upload_jobs (user_id, created_at DESC);
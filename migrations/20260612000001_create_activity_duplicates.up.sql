@@ -0,0 +1,40 @@
+-- Cross-source duplicates: the same run imported from two sources (e.g. a
+-- Runkeeper row and a Strava activity).
+--
+-- status = 'suspected': both activities exist; `other_id` is the newer one,
+--                       waiting for the user to merge or dismiss the pair.
+-- status = 'merged':    only `activity_id` (the canonical activity) remains;
+--                       the record merged away is kept in the snapshot columns
+--                       so re-imports of it are recognised.
+-- status = 'dismissed': the user confirmed the pair are different runs.
+CREATE TABLE activity_duplicates (
+    id            UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id       UUID        NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    activity_id   UUID        NOT NULL REFERENCES activities(id) ON DELETE CASCADE,
+    other_id      UUID        REFERENCES activities(id) ON DELETE CASCADE,
+    score         REAL        NOT NULL,
+    status        TEXT        NOT NULL DEFAULT 'suspected'
+                  CHECK (status IN ('suspected', 'merged', 'dismissed')),
+    source        VARCHAR(20),
+    external_id   VARCHAR(64),
+    date          TIMESTAMP,
+    name          TEXT,
+    activity_type TEXT,
+    distance      REAL,
+    duration      TEXT,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at   TIMESTAMPTZ
+);
+
+CREATE INDEX idx_activity_duplicates_user_status ON activity_duplicates (user_id, status);
+
+-- One pending review per pair.
+CREATE UNIQUE INDEX uq_activity_duplicates_suspected_pair
+    ON activity_duplicates (activity_id, other_id)
+    WHERE status = 'suspected';
+
+-- Merged-away records with a source ID are stored once, so re-syncing them is
+-- recognised as a duplicate instead of being matched again.
+CREATE UNIQUE INDEX uq_activity_duplicates_merged_source
+    ON activity_duplicates (user_id, source, external_id)
+    WHERE status = 'merged' AND external_id IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Back to per-source units. Converted pace requirements stay in seconds per km,
+-- which the old evaluator also accepted.
+UPDATE activities
+SET average_pace = CASE
+        WHEN source = 'runkeeper'
+            THEN FLOOR(ROUND(average_pace::NUMERIC) / 60) + MOD(ROUND(average_pace::NUMERIC), 60) / 100
+        ELSE average_pace / 60
+    END
+WHERE average_pace > 0;
@@ -0,0 +1,17 @@
+-- activities.average_pace becomes seconds per km for every source.
+-- Runkeeper rows held M.SS (6.56 = 6:56/km); all other sources decimal min/km.
+UPDATE activities
+SET average_pace = CASE
+        WHEN source = 'runkeeper'
+            THEN FLOOR(average_pace) * 60 + ROUND(((average_pace - FLOOR(average_pace)) * 100)::NUMERIC)
+        ELSE average_pace * 60
+    END
+WHERE average_pace > 0;
+
+-- Pace requirements: values under 60 were M.SS (older plan-generator output);
+-- the rest are already seconds per km.
+UPDATE challenge_workout_requirements
+SET value = FLOOR(value) * 60 + ROUND(((value - FLOOR(value)) * 100)::NUMERIC)
+WHERE requirement_type IN ('pace_faster_than', 'pace_slower_than')
+  AND value > 0
+  AND value < 60;
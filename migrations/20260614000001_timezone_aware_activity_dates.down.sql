@@ -0,0 +1,9 @@
+ALTER TABLE users DROP COLUMN timezone;
+
+ALTER TABLE activity_duplicates
+    ALTER COLUMN date TYPE TIMESTAMP USING date AT TIME ZONE 'UTC';
+
+ALTER TABLE activities DROP COLUMN utc_offset;
+
+ALTER TABLE activities
+    ALTER COLUMN date TYPE TIMESTAMP USING date AT TIME ZONE 'UTC';
@@ -0,0 +1,15 @@
+-- activities.date becomes an instant.  Existing values were written as UTC
+-- (GPX/FIT/TCX/Strava) or as the Runkeeper export's wall-clock time; both are
+-- read as UTC since the export never said which zone it used.
+ALTER TABLE activities
+    ALTER COLUMN date TYPE TIMESTAMPTZ USING date AT TIME ZONE 'UTC';
+
+-- Offset of the clock the activity was recorded on, in seconds east of UTC.
+-- NULL when the source did not report one.
+ALTER TABLE activities ADD COLUMN utc_offset INTEGER;
+
+ALTER TABLE activity_duplicates
+    ALTER COLUMN date TYPE TIMESTAMPTZ USING date AT TIME ZONE 'UTC';
+
+-- IANA zone that day, week and month boundaries are computed in.
+ALTER TABLE users ADD COLUMN timezone TEXT NOT NULL DEFAULT 'UTC';
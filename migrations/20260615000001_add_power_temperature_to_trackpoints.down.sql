@@ -0,0 +1,3 @@
+ALTER TABLE trackpoints
+    DROP COLUMN power,
+    DROP COLUMN temperature;
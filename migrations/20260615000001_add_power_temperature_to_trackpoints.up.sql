@@ -0,0 +1,4 @@
+-- Remaining sensor channels: power in watts, temperature in °C.
+ALTER TABLE trackpoints
+    ADD COLUMN power       SMALLINT,
+    ADD COLUMN temperature REAL;
@@ -0,0 +1,18 @@
+-- Track points exactly as the source delivered them.  `trackpoints` holds the
+-- cleaned track; keeping the raw copy lets the cleaning be re-run.  No unique
+-- (activity_id, time) here: repeated timestamps are part of the raw data.
+CREATE TABLE raw_trackpoints (
+    id          UUID PRIMARY KEY,
+    activity_id UUID NOT NULL REFERENCES activities (id) ON DELETE CASCADE,
+    lat         DOUBLE PRECISION NOT NULL,
+    lon         DOUBLE PRECISION NOT NULL,
+    elevation   REAL NOT NULL,
+    time        TIMESTAMPTZ NOT NULL,
+    speed       DOUBLE PRECISION,
+    heart_rate  SMALLINT,
+    cadence     SMALLINT,
+    power       SMALLINT,
+    temperature REAL
+);
+
+CREATE INDEX idx_raw_trackpoints_activity_time ON raw_trackpoints (activity_id, time);
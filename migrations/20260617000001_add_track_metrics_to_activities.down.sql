@@ -0,0 +1,7 @@
+ALTER TABLE activities
+    DROP COLUMN track_distance,
+    DROP COLUMN moving_time,
+    DROP COLUMN elapsed_time,
+    DROP COLUMN moving_pace,
+    DROP COLUMN elevation_gain,
+    DROP COLUMN elevation_loss;
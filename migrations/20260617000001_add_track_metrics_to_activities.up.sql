@@ -0,0 +1,10 @@
+-- Metrics derived from the cleaned GPS track, next to the summary the source
+-- reported.  NULL for activities without a track (and for older imports until
+-- their track is re-cleaned).
+ALTER TABLE activities
+    ADD COLUMN track_distance REAL,
+    ADD COLUMN moving_time    INTEGER,
+    ADD COLUMN elapsed_time   INTEGER,
+    ADD COLUMN moving_pace    REAL,
+    ADD COLUMN elevation_gain REAL,
+    ADD COLUMN elevation_loss REAL;
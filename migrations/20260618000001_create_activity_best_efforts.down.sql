@@ -0,0 +1,6 @@
+DELETE FROM personal_records WHERE category IN ('400m', '1k', '1_mile');
+ALTER TABLE personal_records DROP CONSTRAINT personal_records_category_check;
+ALTER TABLE personal_records ADD CONSTRAINT personal_records_category_check
+    CHECK (category IN ('5k', '10k', 'half_marathon', 'marathon', 'longest_run'));
+
+DROP TABLE IF EXISTS activity_best_efforts;
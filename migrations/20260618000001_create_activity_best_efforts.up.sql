@@ -0,0 +1,14 @@
+-- Fastest stretch of each standard distance inside a run's track, so a fast
+-- 5K inside a 12 km run can hold the 5K record.
+CREATE TABLE activity_best_efforts (
+    activity_id          UUID             NOT NULL REFERENCES activities (id) ON DELETE CASCADE,
+    category             VARCHAR(16)      NOT NULL,
+    distance_m           DOUBLE PRECISION NOT NULL,
+    duration_seconds     DOUBLE PRECISION NOT NULL,
+    start_offset_seconds DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (activity_id, category)
+);
+
+ALTER TABLE personal_records DROP CONSTRAINT personal_records_category_check;
+ALTER TABLE personal_records ADD CONSTRAINT personal_records_category_check
+    CHECK (category IN ('400m', '1k', '1_mile', '5k', '10k', 'half_marathon', 'marathon', 'longest_run'));
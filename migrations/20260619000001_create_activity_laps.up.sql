@@ -0,0 +1,15 @@
+-- Laps derived from an activity's cleaned track: fixed-distance auto-laps and,
+-- for interval sessions, the detected work and recovery intervals.  `lap` is
+-- 1-based and counted per kind.
+CREATE TABLE activity_laps (
+    activity_id          UUID             NOT NULL REFERENCES activities (id) ON DELETE CASCADE,
+    kind                 TEXT             NOT NULL CHECK (kind IN ('auto', 'work', 'recovery')),
+    lap                  INTEGER          NOT NULL,
+    start_offset_seconds DOUBLE PRECISION NOT NULL,
+    duration_seconds     DOUBLE PRECISION NOT NULL,
+    distance_m           DOUBLE PRECISION NOT NULL,
+    pace                 REAL             NOT NULL,
+    elevation_gain       REAL             NOT NULL,
+    elevation_loss       REAL             NOT NULL,
+    PRIMARY KEY (activity_id, kind, lap)
+);
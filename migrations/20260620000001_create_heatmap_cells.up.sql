@@ -0,0 +1,28 @@
+-- Precomputed heatmap grid: the cleaned track of each activity counted into
+-- Web Mercator pixel cells at a few zoom levels (see activities::heatmap).
+-- Rewritten whenever an activity's track is stored or re-cleaned; a heatmap
+-- request sums these instead of scanning `trackpoints`.
+CREATE TABLE heatmap_cells (
+    activity_id UUID     NOT NULL REFERENCES activities (id) ON DELETE CASCADE,
+    level       SMALLINT NOT NULL,
+    x           INTEGER  NOT NULL,
+    y           INTEGER  NOT NULL,
+    weight      INTEGER  NOT NULL,
+    PRIMARY KEY (activity_id, level, x, y)
+);
+
+-- Backfill from the tracks stored so far.
+INSERT INTO heatmap_cells (activity_id, level, x, y, weight)
+SELECT activity_id, level, x, y, COUNT(*)
+FROM (
+    SELECT t.activity_id,
+           l.level,
+           LEAST(GREATEST(FLOOR((t.lon + 180) / 360 * l.size), 0), l.size - 1)::int AS x,
+           LEAST(GREATEST(FLOOR(
+               (1 - ASINH(TAN(RADIANS(LEAST(GREATEST(t.lat, -85.05112878), 85.05112878)))) / PI())
+               / 2 * l.size), 0), l.size - 1)::int AS y
+    FROM   trackpoints t
+    CROSS  JOIN (SELECT level, POWER(2, level + 8) AS size
+                 FROM   UNNEST(ARRAY[5, 8, 11, 14]::smallint[]) AS level) l
+) cells
+GROUP BY activity_id, level, x, y;
@@ -0,0 +1,4 @@
+ALTER TABLE users DROP COLUMN IF EXISTS segment_leaderboards;
+DROP INDEX IF EXISTS idx_heatmap_cells_level_xy;
+DROP TABLE IF EXISTS segment_efforts;
+DROP TABLE IF EXISTS segments;
@@ -0,0 +1,39 @@
+-- User-defined segments: a stretch of road every matching activity is timed
+-- on.  The geometry is kept as parallel coordinate arrays; the bounding box
+-- finds the segments a new track may cross.
+CREATE TABLE segments (
+    id            UUID PRIMARY KEY,
+    user_id       UUID             NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name          TEXT             NOT NULL,
+    activity_type TEXT             NOT NULL,
+    distance_m    DOUBLE PRECISION NOT NULL,
+    latitudes     DOUBLE PRECISION[] NOT NULL,
+    longitudes    DOUBLE PRECISION[] NOT NULL,
+    min_lat       DOUBLE PRECISION NOT NULL,
+    max_lat       DOUBLE PRECISION NOT NULL,
+    min_lon       DOUBLE PRECISION NOT NULL,
+    max_lon       DOUBLE PRECISION NOT NULL,
+    created_at    TIMESTAMPTZ      NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_segments_type_bbox ON segments (activity_type, min_lat, max_lat);
+
+-- One timed pass of an activity over a segment.  An activity can pass a
+-- segment more than once.
+CREATE TABLE segment_efforts (
+    id              UUID PRIMARY KEY,
+    segment_id      UUID             NOT NULL REFERENCES segments (id) ON DELETE CASCADE,
+    activity_id     UUID             NOT NULL REFERENCES activities (id) ON DELETE CASCADE,
+    start_time      TIMESTAMPTZ      NOT NULL,
+    elapsed_seconds DOUBLE PRECISION NOT NULL,
+    pace            REAL             NOT NULL,
+    UNIQUE (segment_id, activity_id, start_time)
+);
+
+CREATE INDEX idx_segment_efforts_activity ON segment_efforts (activity_id);
+
+-- Finds the activities passing a segment's start when the segment is created.
+CREATE INDEX idx_heatmap_cells_level_xy ON heatmap_cells (level, x, y);
+
+-- Segment leaderboards only list users who opted in.
+ALTER TABLE users ADD COLUMN segment_leaderboards BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,29 @@
+-- Routes: clusters of a user's activities that follow the same course.  The
+-- geometry is the simplified track of the activity that founded the route,
+-- kept as parallel coordinate arrays; the bounding box finds the routes a new
+-- track may follow.
+CREATE TABLE routes (
+    id            UUID PRIMARY KEY,
+    user_id       UUID             NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name          TEXT             NOT NULL,
+    activity_type TEXT             NOT NULL,
+    distance_m    DOUBLE PRECISION NOT NULL,
+    latitudes     DOUBLE PRECISION[] NOT NULL,
+    longitudes    DOUBLE PRECISION[] NOT NULL,
+    min_lat       DOUBLE PRECISION NOT NULL,
+    max_lat       DOUBLE PRECISION NOT NULL,
+    min_lon       DOUBLE PRECISION NOT NULL,
+    max_lon       DOUBLE PRECISION NOT NULL,
+    created_at    TIMESTAMPTZ      NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_routes_user_type ON routes (user_id, activity_type);
+
+-- The route each activity with a track belongs to.  A route outlives its
+-- activities, so its id and name survive re-imports.
+CREATE TABLE route_activities (
+    activity_id UUID PRIMARY KEY REFERENCES activities (id) ON DELETE CASCADE,
+    route_id    UUID NOT NULL    REFERENCES routes (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_route_activities_route ON route_activities (route_id);
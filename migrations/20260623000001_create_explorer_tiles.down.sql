@@ -0,0 +1,6 @@
+DELETE FROM user_achievements
+WHERE achievement_id IN (SELECT id FROM achievement_definitions
+                         WHERE slug IN ('tiles_100', 'tiles_1000', 'square_10', 'cluster_100'));
+DELETE FROM achievement_definitions
+WHERE slug IN ('tiles_100', 'tiles_1000', 'square_10', 'cluster_100');
+DROP TABLE IF EXISTS explorer_tiles;
@@ -0,0 +1,29 @@
+-- Explorer tiles: every zoom-14 map tile a user's tracks have ever touched
+-- (see explorer::tiles), with the activity that reached it first.  Updated
+-- whenever a track is stored, rebuilt from `heatmap_cells` when a track is
+-- re-cleaned or an activity deleted.
+CREATE TABLE explorer_tiles (
+    user_id           UUID        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    x                 INTEGER     NOT NULL,
+    y                 INTEGER     NOT NULL,
+    first_activity_id UUID        REFERENCES activities (id) ON DELETE SET NULL,
+    first_visited_at  TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (user_id, x, y)
+);
+
+CREATE INDEX idx_explorer_tiles_first_activity ON explorer_tiles (first_activity_id);
+
+-- Backfill from the level-8 heatmap cells: four cells per tile side.
+INSERT INTO explorer_tiles (user_id, x, y, first_activity_id, first_visited_at)
+SELECT DISTINCT ON (a.user_id, c.x >> 2, c.y >> 2)
+       a.user_id, c.x >> 2, c.y >> 2, a.id, a.date
+FROM   heatmap_cells c
+JOIN   activities a ON a.id = c.activity_id
+WHERE  c.level = 8
+ORDER  BY a.user_id, c.x >> 2, c.y >> 2, a.date, a.id;
+
+INSERT INTO achievement_definitions (slug, name, description, icon, xp_reward, rarity, category, is_secret, sort_order) VALUES
+('tiles_100',   'Tile Hunter',   'Visit 100 explorer tiles',                          'Map',     100, 'rare',      'exploration', false, 31),
+('tiles_1000',  'Cartographer',  'Visit 1,000 explorer tiles',                        'Map',     200, 'legendary', 'exploration', false, 32),
+('square_10',   'Square Deal',   'Fill a 10×10 square of explorer tiles',             'Grid',    150, 'epic',      'exploration', false, 33),
+('cluster_100', 'Home Turf',     'Grow a cluster of 100 fully surrounded tiles',      'Compass', 150, 'epic',      'exploration', false, 34);
@@ -0,0 +1,2 @@
+DELETE FROM route_activities WHERE route_id IS NULL;
+ALTER TABLE route_activities ALTER COLUMN route_id SET NOT NULL;
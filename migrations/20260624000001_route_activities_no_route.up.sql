@@ -0,0 +1,3 @@
+-- A NULL route_id records that an activity's track was checked and follows
+-- no route (it is too short), so it is not clustered again.
+ALTER TABLE route_activities ALTER COLUMN route_id DROP NOT NULL;
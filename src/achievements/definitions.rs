@@ -1,27 +1,30 @@
 /// Achievement evaluation rules — pure logic, no DB access.
 use std::collections::HashSet;
 
-use chrono::{Datelike, DateTime, Timelike, Utc};
+use chrono::{Datelike, DateTime, FixedOffset, Timelike};
 use uuid::Uuid;
 
+use crate::activities::pace::Pace;
+
 pub struct CheckContext {
     #[allow(dead_code)]
     pub user_id: Uuid,
     #[allow(dead_code)]
     pub activity_id: Uuid,
-    pub activity_start: DateTime<Utc>,
+    /// Start of THIS activity on the clock where it was run.
+    pub activity_start: DateTime<FixedOffset>,
     /// Distance of THIS activity in metres.
     pub activity_distance_m: f64,
-    /// Average pace of THIS activity in min/km (as stored in DB).
-    pub activity_pace_min_per_km: f64,
+    /// Average pace of THIS activity (unset for non-running activities).
+    pub activity_pace: Pace,
     /// Total runs including this one.
     pub total_runs: i64,
     /// Total distance in metres across all runs including this one.
     pub total_distance_m: f64,
     /// Consecutive-days streak ending at today, including this run.
     pub current_streak: i32,
-    /// Recent activities' average_pace values (min/km), last 10, newest first.
-    pub recent_paces: Vec<f64>,
+    /// Recent activities' average_pace values, last 10, newest first.
+    pub recent_paces: Vec<Pace>,
     /// Already-unlocked achievement slugs for this user.
     pub already_unlocked: HashSet<String>,
     /// Distinct (year, month) tuples for all runs including this one.
@@ -32,6 +35,12 @@ pub struct CheckContext {
     pub monday_run_count: i64,
     /// Whether there was a 30+ day gap before this run.
     pub had_long_gap: bool,
+    /// Explorer tiles ever visited.
+    pub explorer_tiles: i64,
+    /// Side of the largest square block of explorer tiles.
+    pub explorer_max_square: i32,
+    /// Size of the largest cluster of surrounded explorer tiles.
+    pub explorer_max_cluster: i64,
 }
 
 pub fn evaluate_all(ctx: &CheckContext) -> Vec<&'static str> {
@@ -65,6 +74,10 @@ pub fn evaluate_all(ctx: &CheckContext) -> Vec<&'static str> {
         check_explorer,
         check_monday_10,
         check_pr_machine,
+        check_tiles_100,
+        check_tiles_1000,
+        check_square_10,
+        check_cluster_100,
     ];
 
     checks
@@ -115,7 +128,7 @@ fn check_run_marathon_once(ctx: &CheckContext) -> Option<&'static str> {
 }
 
 fn check_pace_sub6(ctx: &CheckContext) -> Option<&'static str> {
-    if ctx.activity_pace_min_per_km > 0.0 && ctx.activity_pace_min_per_km < 6.0 {
+    if faster_than(ctx.activity_pace, 360.0) {
         Some("pace_sub6")
     } else {
         None
@@ -123,7 +136,7 @@ fn check_pace_sub6(ctx: &CheckContext) -> Option<&'static str> {
 }
 
 fn check_pace_sub5(ctx: &CheckContext) -> Option<&'static str> {
-    if ctx.activity_pace_min_per_km > 0.0 && ctx.activity_pace_min_per_km < 5.0 {
+    if faster_than(ctx.activity_pace, 300.0) {
         Some("pace_sub5")
     } else {
         None
@@ -131,22 +144,30 @@ fn check_pace_sub5(ctx: &CheckContext) -> Option<&'static str> {
 }
 
 fn check_pace_sub430(ctx: &CheckContext) -> Option<&'static str> {
-    if ctx.activity_pace_min_per_km > 0.0 && ctx.activity_pace_min_per_km < 4.5 {
+    if faster_than(ctx.activity_pace, 270.0) {
         Some("pace_sub430")
     } else {
         None
     }
 }
 
-/// 3 consecutive runs with pace within 0.167 min/km (10 sec/km) of each other.
+/// Whether `pace` is known and under `secs_per_km`.
+fn faster_than(pace: Pace, secs_per_km: f64) -> bool {
+    pace.is_set() && pace.secs_per_km() < secs_per_km
+}
+
+/// 3 consecutive runs with pace within 10 sec/km of each other.
 fn check_consistent_pace(ctx: &CheckContext) -> Option<&'static str> {
     if ctx.recent_paces.len() < 3 {
         return None;
     }
     let latest = &ctx.recent_paces[..3];
-    let max = latest.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    let min = latest.iter().cloned().fold(f64::INFINITY, f64::min);
-    if max - min <= 10.0 / 60.0 {
+    if !latest.iter().all(|p| p.is_set()) {
+        return None;
+    }
+    let max = latest.iter().map(|p| p.secs_per_km()).fold(f64::NEG_INFINITY, f64::max);
+    let min = latest.iter().map(|p| p.secs_per_km()).fold(f64::INFINITY, f64::min);
+    if max - min <= 10.0 {
         Some("consistent_pace")
     } else {
         None
@@ -199,8 +220,8 @@ fn check_new_years_runner(ctx: &CheckContext) -> Option<&'static str> {
 }
 
 fn check_speedy_upload(ctx: &CheckContext) -> Option<&'static str> {
-    // 3:30/km = 3.5 min/km
-    if ctx.activity_pace_min_per_km > 0.0 && ctx.activity_pace_min_per_km < 3.5 {
+    // 3:30/km
+    if faster_than(ctx.activity_pace, 210.0) {
         Some("speedy_upload")
     } else {
         None
@@ -218,3 +239,19 @@ fn check_monday_10(ctx: &CheckContext) -> Option<&'static str> {
 fn check_pr_machine(ctx: &CheckContext) -> Option<&'static str> {
     if ctx.pr_count >= 3 { Some("personal_best_streak") } else { None }
 }
+
+fn check_tiles_100(ctx: &CheckContext) -> Option<&'static str> {
+    if ctx.explorer_tiles >= 100 { Some("tiles_100") } else { None }
+}
+
+fn check_tiles_1000(ctx: &CheckContext) -> Option<&'static str> {
+    if ctx.explorer_tiles >= 1000 { Some("tiles_1000") } else { None }
+}
+
+fn check_square_10(ctx: &CheckContext) -> Option<&'static str> {
+    if ctx.explorer_max_square >= 10 { Some("square_10") } else { None }
+}
+
+fn check_cluster_100(ctx: &CheckContext) -> Option<&'static str> {
+    if ctx.explorer_max_cluster >= 100 { Some("cluster_100") } else { None }
+}
@@ -1,9 +1,10 @@
 use std::collections::HashSet;
 
+use chrono_tz::Tz;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::error::AppError;
+use crate::{activities::pace::Pace, error::AppError, users::timezone};
 
 use super::models::{AchievementDefinition, AchievementWithStatus};
 
@@ -145,6 +146,32 @@ pub async fn unlock_achievements(
     Ok(defs)
 }
 
+/// Remove the achievements a user unlocked with one activity.
+/// Returns the definitions of the deleted unlocks.
+pub async fn delete_achievements_for_activity<'c, E>(
+    db: E,
+    user_id: Uuid,
+    activity_id: Uuid,
+) -> Result<Vec<AchievementDefinition>, AppError>
+where
+    E: sqlx::Executor<'c, Database = sqlx::Postgres>,
+{
+    sqlx::query_as::<_, AchievementDefinition>(
+        "WITH removed AS ( \
+             DELETE FROM user_achievements \
+             WHERE user_id = $1 AND activity_id = $2 \
+             RETURNING achievement_id \
+         ) \
+         SELECT ad.* FROM achievement_definitions ad \
+         JOIN removed ON removed.achievement_id = ad.id",
+    )
+    .bind(user_id)
+    .bind(activity_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
 // ── Aggregate helpers needed for CheckContext ─────────────────────────────────
 
 pub async fn count_total_runs(db: &PgPool, user_id: Uuid) -> Result<i64, AppError> {
@@ -174,26 +201,26 @@ pub async fn get_recent_paces(
     db: &PgPool,
     user_id: Uuid,
     limit: i64,
-) -> Result<Vec<f64>, AppError> {
-    let rows: Vec<Option<f64>> = sqlx::query_scalar(
-        "SELECT average_pace::double precision FROM activities \
+) -> Result<Vec<Pace>, AppError> {
+    sqlx::query_scalar(
+        "SELECT average_pace FROM activities \
          WHERE user_id = $1 ORDER BY date DESC LIMIT $2",
     )
     .bind(user_id)
     .bind(limit)
     .fetch_all(db)
     .await
-    .map_err(AppError::from)?;
-
-    Ok(rows.into_iter().flatten().collect())
+    .map_err(AppError::from)
 }
 
-/// Returns the current consecutive-day run streak for the user.
-pub async fn get_current_streak(db: &PgPool, user_id: Uuid) -> Result<i32, AppError> {
+/// Returns the current consecutive-day run streak for the user, counting
+/// days in `tz`.
+pub async fn get_current_streak(db: &PgPool, user_id: Uuid, tz: Tz) -> Result<i32, AppError> {
     let dates: Vec<Option<chrono::NaiveDate>> = sqlx::query_scalar(
-        "SELECT DISTINCT DATE(date) FROM activities WHERE user_id = $1 ORDER BY 1 DESC",
+        "SELECT DISTINCT DATE(date AT TIME ZONE $2) FROM activities WHERE user_id = $1 ORDER BY 1 DESC",
     )
     .bind(user_id)
+    .bind(tz.name())
     .fetch_all(db)
     .await
     .map_err(AppError::from)?;
@@ -201,7 +228,7 @@ pub async fn get_current_streak(db: &PgPool, user_id: Uuid) -> Result<i32, AppEr
     let dates: Vec<chrono::NaiveDate> = dates.into_iter().flatten().collect();
 
     let mut streak = 0i32;
-    let today = chrono::Utc::now().date_naive();
+    let today = timezone::today(tz);
     let mut expected = today;
 
     for date in &dates {
@@ -223,11 +250,13 @@ pub async fn get_current_streak(db: &PgPool, user_id: Uuid) -> Result<i32, AppEr
 pub async fn had_long_gap_before_latest(
     db: &PgPool,
     user_id: Uuid,
+    tz: Tz,
 ) -> Result<bool, AppError> {
     let dates: Vec<Option<chrono::NaiveDate>> = sqlx::query_scalar(
-        "SELECT DISTINCT DATE(date) FROM activities WHERE user_id = $1 ORDER BY 1 DESC LIMIT 2",
+        "SELECT DISTINCT DATE(date AT TIME ZONE $2) FROM activities WHERE user_id = $1 ORDER BY 1 DESC LIMIT 2",
     )
     .bind(user_id)
+    .bind(tz.name())
     .fetch_all(db)
     .await
     .map_err(AppError::from)?;
@@ -244,6 +273,7 @@ pub async fn had_long_gap_before_latest(
 pub async fn get_months_with_runs(
     db: &PgPool,
     user_id: Uuid,
+    tz: Tz,
 ) -> Result<HashSet<(i32, u32)>, AppError> {
     struct YearMonth { year: Option<f64>, month: Option<f64> }
 
@@ -258,11 +288,13 @@ pub async fn get_months_with_runs(
     }
 
     let rows = sqlx::query_as::<_, YearMonth>(
-        "SELECT EXTRACT(YEAR FROM date)::FLOAT8 AS year, EXTRACT(MONTH FROM date)::FLOAT8 AS month \
+        "SELECT EXTRACT(YEAR FROM date AT TIME ZONE $2)::FLOAT8 AS year, \
+                EXTRACT(MONTH FROM date AT TIME ZONE $2)::FLOAT8 AS month \
          FROM activities WHERE user_id = $1 \
          GROUP BY year, month",
     )
     .bind(user_id)
+    .bind(tz.name())
     .fetch_all(db)
     .await
     .map_err(AppError::from)?;
@@ -276,12 +308,13 @@ pub async fn get_months_with_runs(
         .collect())
 }
 
-pub async fn count_monday_runs(db: &PgPool, user_id: Uuid) -> Result<i64, AppError> {
+pub async fn count_monday_runs(db: &PgPool, user_id: Uuid, tz: Tz) -> Result<i64, AppError> {
     let count: Option<i64> = sqlx::query_scalar(
         "SELECT COUNT(*) FROM activities \
-         WHERE user_id = $1 AND EXTRACT(DOW FROM date) = 1",
+         WHERE user_id = $1 AND EXTRACT(DOW FROM date AT TIME ZONE $2) = 1",
     )
     .bind(user_id)
+    .bind(tz.name())
     .fetch_one(db)
     .await
     .map_err(AppError::from)?;
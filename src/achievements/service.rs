@@ -1,9 +1,12 @@
-use chrono::{DateTime, Utc};
-use sqlx::PgPool;
+use chrono::{DateTime, FixedOffset};
+use chrono_tz::Tz;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
 use crate::{
+    activities::{models::Activity, pace::Pace},
     error::AppError,
+    explorer,
     xp::{models::AwardXpInput, service as xp_service},
 };
 
@@ -20,19 +23,67 @@ pub async fn get_user_achievements(
     repository::get_achievements_for_user(db, user_id).await
 }
 
-/// Called after every activity upload.
+/// Called after activities are uploaded, and after one is deleted, edited or
+/// merged away with the user's remaining activities.
 ///
-/// Builds context from the DB, evaluates rules, persists new unlocks, awards XP,
-/// and returns compact summaries of any newly-unlocked achievements.
+/// Evaluates the rules for each of `activities` in order, persists new
+/// unlocks, awards XP, and returns compact summaries of any newly-unlocked
+/// achievements.  The history-wide context, explorer tiles included, is
+/// loaded once for the whole batch; given the remaining history oldest first,
+/// an achievement revoked with a removed activity is unlocked again by the
+/// earliest activity that still earns it.  Streaks and calendar counts are
+/// cut in the user's zone `tz`.  A failed unlock is logged and only skips
+/// that activity.
 pub async fn check_and_unlock_achievements(
+    db: &PgPool,
+    user_id: Uuid,
+    activities: &[Activity],
+    tz: Tz,
+) -> Vec<UnlockedAchievementSummary> {
+    let Some(first) = activities.first() else {
+        return vec![];
+    };
+    let mut ctx = load_context(
+        db,
+        user_id,
+        first.id,
+        first.distance as f64 * 1000.0, // km → m
+        first.average_pace,
+        first.local_start(tz),
+        tz,
+    )
+    .await;
+
+    let mut unlocked = Vec::new();
+    for activity in activities {
+        ctx.activity_id = activity.id;
+        ctx.activity_distance_m = activity.distance as f64 * 1000.0; // km → m
+        ctx.activity_pace = activity.average_pace;
+        ctx.activity_start = activity.local_start(tz);
+
+        let earned = evaluate_all(&ctx);
+        let slugs: Vec<String> = earned.iter().map(|slug| slug.to_string()).collect();
+        match unlock_earned(db, user_id, activity.id, earned).await {
+            Ok(summaries) => {
+                ctx.already_unlocked.extend(slugs);
+                unlocked.extend(summaries);
+            }
+            Err(e) => tracing::warn!("Achievement check failed for activity {}: {e}", activity.id),
+        }
+    }
+    unlocked
+}
+
+/// Gather the user's history into a `CheckContext` for one activity.
+async fn load_context(
     db: &PgPool,
     user_id: Uuid,
     activity_id: Uuid,
     distance_m: f64,
-    pace_min_per_km: f64,
-    activity_start: DateTime<Utc>,
-) -> Result<Vec<UnlockedAchievementSummary>, AppError> {
-    // Gather context.
+    pace: Pace,
+    activity_start: DateTime<FixedOffset>,
+    tz: Tz,
+) -> CheckContext {
     let (
         total_runs,
         total_distance_m,
@@ -43,24 +94,27 @@ pub async fn check_and_unlock_achievements(
         pr_count,
         monday_run_count,
         had_long_gap,
+        explorer,
     ) = tokio::join!(
         repository::count_total_runs(db, user_id),
         repository::sum_total_distance(db, user_id),
-        repository::get_current_streak(db, user_id),
+        repository::get_current_streak(db, user_id, tz),
         repository::get_recent_paces(db, user_id, 10),
         repository::get_unlocked_slugs(db, user_id),
-        repository::get_months_with_runs(db, user_id),
+        repository::get_months_with_runs(db, user_id, tz),
         repository::count_personal_records(db, user_id),
-        repository::count_monday_runs(db, user_id),
-        repository::had_long_gap_before_latest(db, user_id),
+        repository::count_monday_runs(db, user_id, tz),
+        repository::had_long_gap_before_latest(db, user_id, tz),
+        explorer::service::get_summary(db, user_id),
     );
+    let explorer = explorer.ok();
 
-    let ctx = CheckContext {
+    CheckContext {
         user_id,
         activity_id,
         activity_start,
         activity_distance_m: distance_m,
-        activity_pace_min_per_km: pace_min_per_km,
+        activity_pace: pace,
         total_runs: total_runs.unwrap_or(0),
         total_distance_m: total_distance_m.unwrap_or(0.0),
         current_streak: current_streak.unwrap_or(0),
@@ -70,9 +124,19 @@ pub async fn check_and_unlock_achievements(
         pr_count: pr_count.unwrap_or(0),
         monday_run_count: monday_run_count.unwrap_or(0),
         had_long_gap: had_long_gap.unwrap_or(false),
-    };
+        explorer_tiles: explorer.as_ref().map_or(0, |e| e.total_tiles),
+        explorer_max_square: explorer.as_ref().map_or(0, |e| e.max_square),
+        explorer_max_cluster: explorer.as_ref().map_or(0, |e| e.max_cluster),
+    }
+}
 
-    let newly_earned_slugs = evaluate_all(&ctx);
+/// Persist the earned `slugs` against `activity_id` and award their XP.
+async fn unlock_earned(
+    db: &PgPool,
+    user_id: Uuid,
+    activity_id: Uuid,
+    newly_earned_slugs: Vec<&'static str>,
+) -> Result<Vec<UnlockedAchievementSummary>, AppError> {
     if newly_earned_slugs.is_empty() {
         return Ok(vec![]);
     }
@@ -107,3 +171,26 @@ pub async fn check_and_unlock_achievements(
 
     Ok(summaries)
 }
+
+/// Called before an activity is edited or deleted.
+///
+/// Removes the achievements that activity unlocked and takes back their XP.
+/// Returns the revoked slugs and the XP taken back; re-running the checks
+/// unlocks them again if they still apply.  Runs on the caller's connection,
+/// typically inside its transaction.
+pub async fn revoke_for_activity(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    activity_id: Uuid,
+) -> Result<(Vec<String>, i64), AppError> {
+    let defs = repository::delete_achievements_for_activity(&mut *conn, user_id, activity_id).await?;
+
+    let mut xp_revoked = 0;
+    for def in &defs {
+        let description = format!("Achievement revoked: {}", def.name);
+        xp_revoked +=
+            xp_service::revoke_xp_on(&mut *conn, user_id, "achievement", def.id, description).await?;
+    }
+
+    Ok((defs.into_iter().map(|d| d.slug).collect(), xp_revoked))
+}
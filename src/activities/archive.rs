@@ -0,0 +1,75 @@
+/// Extraction of uploaded ZIP archives (e.g. the Runkeeper data export).
+///
+/// The upload handler streams each archive to a temporary file; entries are
+/// then read one at a time through a bounded reader, so the declared sizes in
+/// the archive are never trusted: a zip bomb hits
+/// `MAX_TOTAL_UNCOMPRESSED_BYTES` and is rejected instead of exhausting
+/// memory.  Synchronous, blocking reads — run it on a blocking thread.
+use std::io::{Read, Seek};
+
+use super::models::UploadFiles;
+
+/// Maximum number of entries (files and directories) in one archive.
+/// A large Runkeeper export holds a few thousand GPX files.
+pub const MAX_ENTRIES: usize = 20_000;
+
+/// Maximum total uncompressed size of all extracted entries.  The extracted
+/// files are held in memory until the upload is parsed.
+pub const MAX_TOTAL_UNCOMPRESSED_BYTES: u64 = 128 * 1024 * 1024;
+
+/// Extract a ZIP archive into `files`, routing each entry by its file name
+/// exactly like a multipart field (see `UploadFiles::insert`).
+///
+/// Directory prefixes are dropped so `export-2024/cardioActivities.csv`
+/// and GPX entries line up with the bare `gps_file` names in the CSV.
+/// Directories, macOS resource forks and nested archives are skipped.
+pub fn extract_zip<R: Read + Seek>(archive: R, files: &mut UploadFiles) -> Result<(), String> {
+    let mut archive =
+        zip::ZipArchive::new(archive).map_err(|e| format!("Invalid ZIP archive: {}", e))?;
+
+    if archive.len() > MAX_ENTRIES {
+        return Err(format!(
+            "ZIP archive has {} entries (limit {})",
+            archive.len(),
+            MAX_ENTRIES
+        ));
+    }
+
+    let mut remaining = MAX_TOTAL_UNCOMPRESSED_BYTES;
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Invalid ZIP entry #{}: {}", i, e))?;
+
+        if entry.is_dir() || entry.name().starts_with("__MACOSX/") {
+            continue;
+        }
+        let Some(name) = entry
+            .enclosed_name()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        else {
+            continue;
+        };
+        if name.starts_with('.') || name.to_lowercase().ends_with(".zip") {
+            continue;
+        }
+
+        // Read at most one byte past the remaining budget to detect overflow.
+        let mut content = Vec::new();
+        entry
+            .take(remaining + 1)
+            .read_to_end(&mut content)
+            .map_err(|e| format!("Error extracting {}: {}", name, e))?;
+        if content.len() as u64 > remaining {
+            return Err(format!(
+                "ZIP archive exceeds the {} MiB uncompressed size limit",
+                MAX_TOTAL_UNCOMPRESSED_BYTES / (1024 * 1024)
+            ));
+        }
+        remaining -= content.len() as u64;
+
+        files.insert(name, content)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,259 @@
+/// GPS track cleaning, run on every track at ingest.
+///
+/// Devices and phones occasionally report fixes hundreds of metres off, which
+/// the naive pairwise speed turns into 200 m/s "sprints".  Cleaning:
+///
+/// 1. drops fixes with impossible coordinates and repeated timestamps;
+/// 2. rejects fixes that could only be reached faster than the activity
+///    allows and moves them onto the line between their good neighbours
+///    (their time and sensor readings are kept);
+/// 3. smooths elevation (median, then mean) and speed (mean) over a centred
+///    window.
+///
+/// The points as received are kept in `raw_trackpoints`, so the cleaning can
+/// be re-run with other thresholds.  Pure functions — no I/O.
+use std::env;
+
+use super::{models::TrackPoint, parser::haversine_distance_m};
+
+/// Thresholds for [`clean_track`] and for what is derived from the cleaned
+/// track (`metrics`, `laps`).  [`CleaningConfig::from_env`]
+/// reads the `TRACK_*` variables and falls back to [`Default`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CleaningConfig {
+    /// Fastest plausible speed on foot (running, walking, hiking), m/s.
+    pub max_foot_speed_ms: f64,
+    /// Fastest plausible speed for every other activity type, m/s.
+    pub max_speed_ms: f64,
+    /// Points in the centred smoothing window; 1 disables smoothing.
+    pub smoothing_window: usize,
+    /// After this many consecutive rejected fixes the track is taken to have
+    /// really moved (e.g. a lost signal re-acquired elsewhere).
+    pub max_outlier_run: usize,
+    /// Below this speed (m/s) the athlete counts as paused.
+    pub pause_speed_ms: f64,
+    /// Elevation changes smaller than this many metres are treated as noise.
+    pub elevation_hysteresis_m: f64,
+    /// Auto-lap length in metres.
+    pub auto_lap_m: f64,
+    /// Shortest work or recovery interval, in seconds.
+    pub interval_min_secs: f64,
+    /// How much faster (as a ratio) the fast fifth of a track must be than
+    /// the slow fifth before it is split into intervals.
+    pub interval_speed_ratio: f64,
+}
+
+impl Default for CleaningConfig {
+    fn default() -> Self {
+        Self {
+            max_foot_speed_ms: 12.5,
+            max_speed_ms: 40.0,
+            smoothing_window: 5,
+            max_outlier_run: 10,
+            pause_speed_ms: 0.8,
+            elevation_hysteresis_m: 3.0,
+            auto_lap_m: 1000.0,
+            interval_min_secs: 30.0,
+            interval_speed_ratio: 1.25,
+        }
+    }
+}
+
+impl CleaningConfig {
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(key: &str) -> Option<T> {
+            env::var(key).ok().and_then(|v| v.parse().ok())
+        }
+        let default = Self::default();
+        Self {
+            max_foot_speed_ms: var("TRACK_MAX_FOOT_SPEED_MS").unwrap_or(default.max_foot_speed_ms),
+            max_speed_ms: var("TRACK_MAX_SPEED_MS").unwrap_or(default.max_speed_ms),
+            smoothing_window: var("TRACK_SMOOTHING_WINDOW").unwrap_or(default.smoothing_window),
+            max_outlier_run: var("TRACK_MAX_OUTLIER_RUN").unwrap_or(default.max_outlier_run),
+            pause_speed_ms: var("TRACK_PAUSE_SPEED_MS").unwrap_or(default.pause_speed_ms),
+            elevation_hysteresis_m: var("TRACK_ELEVATION_HYSTERESIS_M")
+                .unwrap_or(default.elevation_hysteresis_m),
+            auto_lap_m: var("TRACK_AUTO_LAP_M").unwrap_or(default.auto_lap_m),
+            interval_min_secs: var("TRACK_INTERVAL_MIN_SECS").unwrap_or(default.interval_min_secs),
+            interval_speed_ratio: var("TRACK_INTERVAL_SPEED_RATIO")
+                .unwrap_or(default.interval_speed_ratio),
+        }
+    }
+
+    /// Speed limit in m/s for an activity type.
+    pub fn speed_limit(&self, activity_type: &str) -> f64 {
+        match activity_type.to_ascii_lowercase().as_str() {
+            "running" | "walking" | "hiking" => self.max_foot_speed_ms,
+            _ => self.max_speed_ms,
+        }
+    }
+}
+
+/// Clean a track (see the module docs).  Returns the points in time order.
+pub fn clean_track(
+    points: &[TrackPoint],
+    activity_type: &str,
+    config: &CleaningConfig,
+) -> Vec<TrackPoint> {
+    let mut track: Vec<TrackPoint> = points
+        .iter()
+        .filter(|tp| valid_position(tp.latitude, tp.longitude))
+        .cloned()
+        .collect();
+    track.sort_by_key(|tp| tp.time);
+    track.dedup_by_key(|tp| tp.time);
+
+    let limit = config.speed_limit(activity_type);
+    let keep = plausible_fixes(&track, limit, config.max_outlier_run);
+    let mut track = interpolate_rejected(track, &keep);
+
+    let window = config.smoothing_window.max(1);
+    let elevations: Vec<f64> = track.iter().map(|tp| tp.elevation as f64).collect();
+    let elevations = moving_average(&moving_median(&elevations, window), window);
+    for (tp, elevation) in track.iter_mut().zip(elevations) {
+        tp.elevation = elevation as f32;
+    }
+
+    let speeds = fill_speeds(&track, limit);
+    for (tp, speed) in track.iter_mut().zip(smooth_optional(&speeds, window)) {
+        tp.speed = speed;
+    }
+    track
+}
+
+/// Rejects (0, 0) too: it is what a fix without a position often decodes to.
+fn valid_position(lat: f64, lon: f64) -> bool {
+    lat.is_finite()
+        && lon.is_finite()
+        && (-90.0..=90.0).contains(&lat)
+        && (-180.0..=180.0).contains(&lon)
+        && (lat, lon) != (0.0, 0.0)
+}
+
+fn speed_between(a: &TrackPoint, b: &TrackPoint) -> f64 {
+    let dt = (b.time - a.time).num_milliseconds() as f64 / 1000.0;
+    let dist = haversine_distance_m(a.latitude, a.longitude, b.latitude, b.longitude);
+    if dt > 0.0 {
+        dist / dt
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Mark each fix (sorted, unique times) as plausible or not.  A fix is
+/// rejected when it could only be reached from the last good fix above
+/// `limit`; the very first fix is rejected when it is that far from a
+/// second fix that agrees with the third.
+fn plausible_fixes(track: &[TrackPoint], limit: f64, max_outlier_run: usize) -> Vec<bool> {
+    let n = track.len();
+    let mut keep = vec![true; n];
+    let mut last_good: Option<usize> = None;
+    let mut run = 0;
+    for i in 0..n {
+        let reject = match last_good {
+            None => {
+                i + 2 < n
+                    && speed_between(&track[i], &track[i + 1]) > limit
+                    && speed_between(&track[i + 1], &track[i + 2]) <= limit
+            }
+            Some(j) => speed_between(&track[j], &track[i]) > limit && run < max_outlier_run,
+        };
+        if reject {
+            keep[i] = false;
+            run += 1;
+        } else {
+            last_good = Some(i);
+            run = 0;
+        }
+    }
+    keep
+}
+
+/// Move each rejected fix onto the straight line between the good fixes
+/// around it, by time.  Rejected fixes before the first or after the last
+/// good one have nothing to interpolate from and are dropped.
+fn interpolate_rejected(track: Vec<TrackPoint>, keep: &[bool]) -> Vec<TrackPoint> {
+    let good: Vec<usize> = (0..track.len()).filter(|&i| keep[i]).collect();
+    let (Some(&first), Some(&last)) = (good.first(), good.last()) else {
+        return Vec::new();
+    };
+    let mut next_good = good.iter().copied().peekable();
+    let mut prev = first;
+    let mut out = Vec::with_capacity(last - first + 1);
+    for i in first..=last {
+        if keep[i] {
+            prev = i;
+            next_good.next();
+            out.push(track[i].clone());
+            continue;
+        }
+        let Some(&next) = next_good.peek() else { break };
+        let (a, b) = (&track[prev], &track[next]);
+        let span = (b.time - a.time).num_milliseconds() as f64;
+        let f = (track[i].time - a.time).num_milliseconds() as f64 / span;
+        let mut tp = track[i].clone();
+        tp.latitude = a.latitude + (b.latitude - a.latitude) * f;
+        tp.longitude = a.longitude + (b.longitude - a.longitude) * f;
+        tp.elevation = a.elevation + (b.elevation - a.elevation) * f as f32;
+        tp.speed = None;
+        out.push(tp);
+    }
+    out
+}
+
+/// Recorded speeds within `limit` are kept; the rest, and points without
+/// one, get the speed to the next point (the last point copies its
+/// predecessor's).
+fn fill_speeds(track: &[TrackPoint], limit: f64) -> Vec<Option<f64>> {
+    let n = track.len();
+    (0..n)
+        .map(|i| {
+            track[i]
+                .speed
+                .filter(|s| s.is_finite() && (0.0..=limit).contains(s))
+                .or_else(|| {
+                    let (a, b) = if i + 1 < n { (i, i + 1) } else { (i.checked_sub(1)?, i) };
+                    Some(speed_between(&track[a], &track[b]).min(limit))
+                })
+        })
+        .collect()
+}
+
+fn window_bounds(i: usize, n: usize, window: usize) -> std::ops::Range<usize> {
+    let half = window / 2;
+    i.saturating_sub(half)..(i + half + 1).min(n)
+}
+
+fn moving_average(values: &[f64], window: usize) -> Vec<f64> {
+    (0..values.len())
+        .map(|i| {
+            let w = &values[window_bounds(i, values.len(), window)];
+            w.iter().sum::<f64>() / w.len() as f64
+        })
+        .collect()
+}
+
+fn moving_median(values: &[f64], window: usize) -> Vec<f64> {
+    (0..values.len())
+        .map(|i| {
+            let mut w = values[window_bounds(i, values.len(), window)].to_vec();
+            w.sort_by(|a, b| a.total_cmp(b));
+            w[w.len() / 2]
+        })
+        .collect()
+}
+
+/// Moving average over the values present; gaps stay gaps.
+fn smooth_optional(values: &[Option<f64>], window: usize) -> Vec<Option<f64>> {
+    (0..values.len())
+        .map(|i| {
+            values[i]?;
+            let w: Vec<f64> = values[window_bounds(i, values.len(), window)]
+                .iter()
+                .flatten()
+                .copied()
+                .collect();
+            Some(w.iter().sum::<f64>() / w.len() as f64)
+        })
+        .collect()
+}
@@ -0,0 +1,287 @@
+/// Minimal decoder for the Garmin FIT (Flexible and Interoperable Data Transfer)
+/// binary format.
+///
+/// Only the parts of the protocol needed for activity import are handled:
+/// normal and compressed-timestamp record headers, definition messages (with
+/// or without developer fields) and data messages.  Every data message is
+/// returned as a `FitMessage` holding its scalar numeric fields; arrays,
+/// strings and developer fields are skipped.
+///
+/// Pure CPU work — no I/O, no allocation beyond the returned messages.
+use std::collections::HashMap;
+
+/// Global message numbers used by the importer (FIT profile `mesg_num`).
+pub const MESG_SPORT: u16 = 12;
+pub const MESG_SESSION: u16 = 18;
+pub const MESG_RECORD: u16 = 20;
+pub const MESG_ACTIVITY: u16 = 34;
+
+/// Field number shared by every message type for its `timestamp` field.
+pub const FIELD_TIMESTAMP: u8 = 253;
+
+/// Seconds between the Unix epoch and the FIT epoch (1989-12-31T00:00:00Z).
+pub const FIT_EPOCH_OFFSET: i64 = 631_065_600;
+
+/// One decoded data message: its global message number plus every scalar
+/// field that held a valid (non-sentinel) value, keyed by field number.
+#[derive(Debug, Clone)]
+pub struct FitMessage {
+    pub global: u16,
+    pub fields: HashMap<u8, f64>,
+}
+
+impl FitMessage {
+    pub fn get(&self, field: u8) -> Option<f64> {
+        self.fields.get(&field).copied()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FieldDef {
+    number: u8,
+    size: usize,
+    base_type: u8,
+}
+
+#[derive(Debug, Clone)]
+struct Definition {
+    global: u16,
+    big_endian: bool,
+    fields: Vec<FieldDef>,
+    /// Total size of the developer fields, which are skipped.
+    dev_size: usize,
+}
+
+/// Decode a FIT file into its data messages, in file order.
+///
+/// Only the first FIT file of a chained file is read.  The trailing CRC is not
+/// verified — several consumer devices write a zero CRC.
+pub fn decode(data: &[u8]) -> Result<Vec<FitMessage>, String> {
+    if data.len() < 12 {
+        return Err("FIT file too short".into());
+    }
+    let header_size = data[0] as usize;
+    if header_size < 12 || data.len() < header_size {
+        return Err(format!("Invalid FIT header size {}", header_size));
+    }
+    if &data[8..12] != b".FIT" {
+        return Err("Missing .FIT signature".into());
+    }
+    let data_size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let end = header_size
+        .checked_add(data_size)
+        .filter(|&e| e <= data.len())
+        .ok_or_else(|| "FIT data size exceeds file length".to_string())?;
+
+    let mut definitions: HashMap<u8, Definition> = HashMap::new();
+    let mut messages = Vec::new();
+    let mut last_timestamp: Option<u32> = None;
+    let mut pos = header_size;
+
+    while pos < end {
+        let header = data[pos];
+        pos += 1;
+
+        if header & 0x80 != 0 {
+            // Compressed timestamp header: 2-bit local type, 5-bit time offset.
+            let local = (header >> 5) & 0x03;
+            let offset = (header & 0x1F) as u32;
+            let def = definitions
+                .get(&local)
+                .ok_or_else(|| format!("Data message for undefined local type {}", local))?;
+            let mut msg = read_data(data, &mut pos, end, def)?;
+            let timestamp = match last_timestamp {
+                Some(last) => {
+                    let mut ts = (last & !0x1F) | offset;
+                    if offset < (last & 0x1F) {
+                        ts += 0x20;
+                    }
+                    ts
+                }
+                None => offset,
+            };
+            last_timestamp = Some(timestamp);
+            msg.fields.insert(FIELD_TIMESTAMP, timestamp as f64);
+            messages.push(msg);
+            continue;
+        }
+
+        let local = header & 0x0F;
+        if header & 0x40 != 0 {
+            let has_dev_fields = header & 0x20 != 0;
+            let def = read_definition(data, &mut pos, end, has_dev_fields)?;
+            definitions.insert(local, def);
+        } else {
+            let def = definitions
+                .get(&local)
+                .ok_or_else(|| format!("Data message for undefined local type {}", local))?;
+            let msg = read_data(data, &mut pos, end, def)?;
+            if let Some(ts) = msg.get(FIELD_TIMESTAMP) {
+                last_timestamp = Some(ts as u32);
+            }
+            messages.push(msg);
+        }
+    }
+
+    Ok(messages)
+}
+
+fn take<'a>(data: &'a [u8], pos: &mut usize, end: usize, n: usize) -> Result<&'a [u8], String> {
+    if *pos + n > end {
+        return Err("Unexpected end of FIT data".into());
+    }
+    let slice = &data[*pos..*pos + n];
+    *pos += n;
+    Ok(slice)
+}
+
+fn read_definition(
+    data: &[u8],
+    pos: &mut usize,
+    end: usize,
+    has_dev_fields: bool,
+) -> Result<Definition, String> {
+    let fixed = take(data, pos, end, 5)?;
+    let big_endian = fixed[1] == 1;
+    let global = if big_endian {
+        u16::from_be_bytes([fixed[2], fixed[3]])
+    } else {
+        u16::from_le_bytes([fixed[2], fixed[3]])
+    };
+    let num_fields = fixed[4] as usize;
+
+    let raw = take(data, pos, end, num_fields * 3)?;
+    let fields = raw
+        .chunks_exact(3)
+        .map(|f| FieldDef {
+            number: f[0],
+            size: f[1] as usize,
+            base_type: f[2],
+        })
+        .collect();
+
+    let mut dev_size = 0;
+    if has_dev_fields {
+        let num_dev = take(data, pos, end, 1)?[0] as usize;
+        let raw = take(data, pos, end, num_dev * 3)?;
+        dev_size = raw.chunks_exact(3).map(|f| f[1] as usize).sum();
+    }
+
+    Ok(Definition {
+        global,
+        big_endian,
+        fields,
+        dev_size,
+    })
+}
+
+fn read_data(
+    data: &[u8],
+    pos: &mut usize,
+    end: usize,
+    def: &Definition,
+) -> Result<FitMessage, String> {
+    let mut fields = HashMap::new();
+    for field in &def.fields {
+        let bytes = take(data, pos, end, field.size)?;
+        if let Some(value) = decode_scalar(bytes, field.base_type, def.big_endian) {
+            fields.insert(field.number, value);
+        }
+    }
+    take(data, pos, end, def.dev_size)?;
+
+    Ok(FitMessage {
+        global: def.global,
+        fields,
+    })
+}
+
+/// Decode one scalar field.  Returns `None` for strings, arrays, unknown base
+/// types and the per-type "invalid" sentinel values.
+fn decode_scalar(bytes: &[u8], base_type: u8, big_endian: bool) -> Option<f64> {
+    macro_rules! num {
+        ($t:ty, $n:expr) => {{
+            let arr: [u8; $n] = bytes.try_into().ok()?;
+            if big_endian {
+                <$t>::from_be_bytes(arr)
+            } else {
+                <$t>::from_le_bytes(arr)
+            }
+        }};
+    }
+
+    match base_type & 0x1F {
+        // enum, uint8
+        0x00 | 0x02 => {
+            let v = num!(u8, 1);
+            (v != u8::MAX).then_some(v as f64)
+        }
+        // sint8
+        0x01 => {
+            let v = num!(i8, 1);
+            (v != i8::MAX).then_some(v as f64)
+        }
+        // sint16
+        0x03 => {
+            let v = num!(i16, 2);
+            (v != i16::MAX).then_some(v as f64)
+        }
+        // uint16
+        0x04 => {
+            let v = num!(u16, 2);
+            (v != u16::MAX).then_some(v as f64)
+        }
+        // sint32
+        0x05 => {
+            let v = num!(i32, 4);
+            (v != i32::MAX).then_some(v as f64)
+        }
+        // uint32
+        0x06 => {
+            let v = num!(u32, 4);
+            (v != u32::MAX).then_some(v as f64)
+        }
+        // float32
+        0x08 => {
+            let v = num!(f32, 4);
+            v.is_finite().then_some(v as f64)
+        }
+        // float64
+        0x09 => {
+            let v = num!(f64, 8);
+            v.is_finite().then_some(v)
+        }
+        // uint8z
+        0x0A => {
+            let v = num!(u8, 1);
+            (v != 0).then_some(v as f64)
+        }
+        // uint16z
+        0x0B => {
+            let v = num!(u16, 2);
+            (v != 0).then_some(v as f64)
+        }
+        // uint32z
+        0x0C => {
+            let v = num!(u32, 4);
+            (v != 0).then_some(v as f64)
+        }
+        // sint64
+        0x0E => {
+            let v = num!(i64, 8);
+            (v != i64::MAX).then_some(v as f64)
+        }
+        // uint64
+        0x0F => {
+            let v = num!(u64, 8);
+            (v != u64::MAX).then_some(v as f64)
+        }
+        // uint64z
+        0x10 => {
+            let v = num!(u64, 8);
+            (v != 0).then_some(v as f64)
+        }
+        // string (0x07), byte (0x0D) and anything unknown
+        _ => None,
+    }
+}
@@ -0,0 +1,120 @@
+/// Track geometry for map views: Douglas-Peucker simplification and the
+/// compact encodings (Google encoded polyline, GeoJSON) served instead of the
+/// full point list.
+///
+/// Distances are measured on a local equirectangular projection, which is
+/// accurate to well under a metre over the extent of a single activity.
+/// Pure functions — no I/O.
+use serde_json::{json, Value};
+
+use super::models::TrackPoint;
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// Local equirectangular projection to metres, centred on a latitude.
+#[derive(Debug, Clone, Copy)]
+pub struct Projection {
+    cos_lat: f64,
+}
+
+impl Projection {
+    pub fn new(latitude: f64) -> Self {
+        Self {
+            cos_lat: latitude.to_radians().cos(),
+        }
+    }
+
+    /// `(x, y)` in metres; x grows eastwards, y northwards.
+    pub fn xy(&self, latitude: f64, longitude: f64) -> (f64, f64) {
+        (
+            longitude.to_radians() * self.cos_lat * EARTH_RADIUS_M,
+            latitude.to_radians() * EARTH_RADIUS_M,
+        )
+    }
+}
+
+/// The points Douglas-Peucker keeps so that no dropped point lies more than
+/// `tolerance_m` metres from the simplified line.  The first and last
+/// points are always kept.
+pub fn simplify(points: &[TrackPoint], tolerance_m: f64) -> Vec<TrackPoint> {
+    if points.len() < 3 || tolerance_m <= 0.0 {
+        return points.to_vec();
+    }
+    let projection = Projection::new(points[0].latitude);
+    let xy: Vec<(f64, f64)> = points
+        .iter()
+        .map(|tp| projection.xy(tp.latitude, tp.longitude))
+        .collect();
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((a, b)) = stack.pop() {
+        let farthest = (a + 1..b)
+            .map(|i| (i, segment_distance(xy[i], xy[a], xy[b])))
+            .max_by(|x, y| x.1.total_cmp(&y.1));
+        if let Some((i, d)) = farthest {
+            if d > tolerance_m {
+                keep[i] = true;
+                stack.push((a, i));
+                stack.push((i, b));
+            }
+        }
+    }
+    points
+        .iter()
+        .zip(keep)
+        .filter(|(_, k)| *k)
+        .map(|(tp, _)| tp.clone())
+        .collect()
+}
+
+/// Distance from `p` to the segment `a`–`b`, in projected coordinates.
+pub fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (p.0 - a.0 - t * dx).hypot(p.1 - a.1 - t * dy)
+}
+
+/// Google encoded polyline (precision 5) of the track.
+pub fn encode_polyline(points: &[TrackPoint]) -> String {
+    let mut out = String::new();
+    let (mut prev_lat, mut prev_lon) = (0i64, 0i64);
+    for tp in points {
+        let lat = (tp.latitude * 1e5).round() as i64;
+        let lon = (tp.longitude * 1e5).round() as i64;
+        encode_value(lat - prev_lat, &mut out);
+        encode_value(lon - prev_lon, &mut out);
+        (prev_lat, prev_lon) = (lat, lon);
+    }
+    out
+}
+
+fn encode_value(delta: i64, out: &mut String) {
+    let mut v = if delta < 0 { !(delta << 1) } else { delta << 1 };
+    while v >= 0x20 {
+        out.push(char::from((((v & 0x1f) | 0x20) + 63) as u8));
+        v >>= 5;
+    }
+    out.push(char::from((v + 63) as u8));
+}
+
+/// GeoJSON `Feature` with a `LineString` of `[longitude, latitude,
+/// elevation]` positions.
+pub fn geojson(points: &[TrackPoint]) -> Value {
+    let coordinates: Vec<[f64; 3]> = points
+        .iter()
+        .map(|tp| [tp.longitude, tp.latitude, tp.elevation as f64])
+        .collect();
+    json!({
+        "type": "Feature",
+        "geometry": { "type": "LineString", "coordinates": coordinates },
+        "properties": {},
+    })
+}
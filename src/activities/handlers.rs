@@ -2,19 +2,25 @@
 ///
 /// Each handler parses the request, delegates to `service`, and maps results
 /// to HTTP responses.  No SQL and no file-parsing logic here.
-use std::collections::HashMap;
-
 use actix_multipart::Multipart;
-use actix_web::{get, post, web, HttpResponse};
+use actix_web::{delete, get, patch, post, web, HttpResponse};
+use chrono::NaiveDate;
 use futures_util::stream::StreamExt as _;
 use sanitize_filename::sanitize;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::error::AppError;
+use crate::{
+    error::AppError,
+    uploads::{self, spool::SpooledUpload},
+};
 
 use super::{
-    models::{ActivityDetailQuery, HeatmapQuery, UploadForm},
+    heatmap::Bbox,
+    models::{
+        ActivityDetailQuery, ActivityListQuery, ActivitySort, CreateActivityRequest, HeatmapQuery, HeatmapTileQuery, SplitUnit, SplitsQuery, TrackFormat, UpdateActivityRequest,
+        UploadForm, UploadQuery,
+    },
     service,
 };
 
@@ -22,23 +28,122 @@ use super::{
     get,
     path = "/users/{user_id}/activities",
     params(
-        ("user_id" = String, description = "User ID (UUID v4)", example = "123e4567-e89b-12d3-a456-426614174000")
+        ("user_id"              = String,               Path,  description = "User ID (UUID v4)", example = "123e4567-e89b-12d3-a456-426614174000"),
+        ("activity_type"        = Option<String>,       Query, description = "Filter by activity type (e.g. 'Running')"),
+        ("date_from"            = Option<String>,       Query, description = "Start date inclusive (YYYY-MM-DD)"),
+        ("date_to"              = Option<String>,       Query, description = "End date inclusive (YYYY-MM-DD)"),
+        ("min_distance"         = Option<f32>,          Query, description = "Minimum distance in km, inclusive"),
+        ("max_distance"         = Option<f32>,          Query, description = "Maximum distance in km, inclusive"),
+        ("source"               = Option<String>,       Query, description = "Filter by source (runkeeper, strava, gpx, fit, tcx, manual)"),
+        ("q"                    = Option<String>,       Query, description = "Case-insensitive text search in the activity name"),
+        ("sort"                 = Option<ActivitySort>, Query, description = "Sort order (default date_desc)"),
+        ("limit"                = Option<i64>,          Query, description = "Page size (1–1000); omit to return every match"),
+        ("cursor"               = Option<String>,       Query, description = "`next_cursor` from the previous page"),
+        ("include_aggregations" = Option<bool>,         Query, description = "Compute aggregation and time_aggregations over all matches (default true without limit and cursor, false with either)"),
     ),
     responses(
-        (status = 200, description = "List of activities with aggregations", body = super::models::ActivitiesResponse, content_type = "application/json"),
-        (status = 400, description = "Invalid UUID"),
+        (status = 200, description = "Page of activities, with aggregations over all matches", body = super::models::ActivitiesResponse, content_type = "application/json"),
+        (status = 400, description = "Invalid UUID, filter range, limit or cursor"),
         (status = 500, description = "Internal Server Error")
     )
 )]
 #[get("/users/{user_id}/activities")]
 pub async fn get_activities(
     path: web::Path<String>,
+    query: web::Query<ActivityListQuery>,
     db: web::Data<PgPool>,
 ) -> Result<HttpResponse, AppError> {
     let user_id = Uuid::parse_str(&path.into_inner())
         .map_err(|_| AppError::BadRequest("Invalid UUID".into()))?;
 
-    let result = service::get_activities(db.get_ref(), user_id).await?;
+    let result = service::get_activities(db.get_ref(), user_id, query.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Parse the `(user_id, activity_id)` path of the single-activity endpoints.
+fn parse_activity_path(path: web::Path<(String, String)>) -> Result<(Uuid, Uuid), AppError> {
+    let (user_id, activity_id) = path.into_inner();
+    let parse = |s: &str| Uuid::parse_str(s).map_err(|_| AppError::BadRequest("Invalid UUID".into()));
+    Ok((parse(&user_id)?, parse(&activity_id)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{user_id}/activities",
+    params(
+        ("user_id" = String, Path, description = "User ID (UUID v4)", example = "123e4567-e89b-12d3-a456-426614174000")
+    ),
+    request_body = CreateActivityRequest,
+    responses(
+        (status = 201, description = "Activity logged; `effects` holds the XP, PRs, achievements, missions and goals it earned", body = super::models::ActivityChangeResponse, content_type = "application/json"),
+        (status = 400, description = "Invalid UUID or field, or another activity starts at the same time"),
+        (status = 404, description = "Unknown user"),
+        (status = 500, description = "Internal Server Error")
+    )
+)]
+#[post("/users/{user_id}/activities")]
+pub async fn create_activity(
+    path: web::Path<String>,
+    body: web::Json<CreateActivityRequest>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&path.into_inner())
+        .map_err(|_| AppError::BadRequest("Invalid UUID".into()))?;
+
+    let result = service::create_activity(db.get_ref(), user_id, body.into_inner()).await?;
+    Ok(HttpResponse::Created().json(result))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/users/{user_id}/activities/{activity_id}",
+    params(
+        ("user_id" = String, Path, description = "User ID (UUID v4)"),
+        ("activity_id" = String, Path, description = "Activity ID (UUID v4)")
+    ),
+    request_body = UpdateActivityRequest,
+    responses(
+        (status = 200, description = "Activity updated; its previous effects were reversed and the pipeline re-run", body = super::models::ActivityChangeResponse, content_type = "application/json"),
+        (status = 400, description = "Invalid UUID or field, or another activity starts at the new time"),
+        (status = 404, description = "Not found"),
+        (status = 500, description = "Internal Server Error")
+    )
+)]
+#[patch("/users/{user_id}/activities/{activity_id}")]
+pub async fn update_activity(
+    path: web::Path<(String, String)>,
+    body: web::Json<UpdateActivityRequest>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, activity_id) = parse_activity_path(path)?;
+
+    let result =
+        service::update_activity(db.get_ref(), user_id, activity_id, body.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}/activities/{activity_id}",
+    params(
+        ("user_id" = String, Path, description = "User ID (UUID v4)"),
+        ("activity_id" = String, Path, description = "Activity ID (UUID v4)")
+    ),
+    responses(
+        (status = 200, description = "Activity deleted; its effects were reversed and missions, goals and challenges recomputed", body = super::models::ActivityChangeResponse, content_type = "application/json"),
+        (status = 400, description = "Invalid UUID"),
+        (status = 404, description = "Not found"),
+        (status = 500, description = "Internal Server Error")
+    )
+)]
+#[delete("/users/{user_id}/activities/{activity_id}")]
+pub async fn delete_activity(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, activity_id) = parse_activity_path(path)?;
+
+    let result = service::delete_activity(db.get_ref(), user_id, activity_id).await?;
     Ok(HttpResponse::Ok().json(result))
 }
 
@@ -46,7 +151,10 @@ pub async fn get_activities(
     get,
     path = "/activities/{activity_id}",
     params(
-        ("activity_id" = String, description = "Activity ID (UUID v4)", example = "123e4567-e89b-12d3-a456-426614174000")
+        ("activity_id" = String, Path, description = "Activity ID (UUID v4)", example = "123e4567-e89b-12d3-a456-426614174000"),
+        ("user_id" = String, Query, description = "Owner of the activity (UUID v4)"),
+        ("format" = Option<TrackFormat>, Query, description = "Track encoding: points (default), polyline or geojson"),
+        ("tolerance" = Option<f64>, Query, description = "Douglas-Peucker simplification tolerance in metres")
     ),
     responses(
         (status = 200, description = "Activity detail with GPS track", body = super::models::ActivityDetailResponse, content_type = "application/json"),
@@ -64,7 +172,14 @@ pub async fn get_activity_detail(
     let activity_id = Uuid::parse_str(&path.into_inner())
         .map_err(|_| AppError::BadRequest("Invalid UUID".into()))?;
 
-    let result = service::get_activity_detail(db.get_ref(), activity_id, query.user_id).await?;
+    let result = service::get_activity_detail(
+        db.get_ref(),
+        activity_id,
+        query.user_id,
+        query.format,
+        query.tolerance,
+    )
+    .await?;
     Ok(HttpResponse::Ok().json(result))
 }
 
@@ -72,10 +187,13 @@ pub async fn get_activity_detail(
     get,
     path = "/trackpoints/{activity_id}",
     params(
-        ("activity_id" = String, description = "Activity ID (UUID v4)", example = "123e4567-e89b-12d3-a456-426614174000")
+        ("activity_id" = String, Path, description = "Activity ID (UUID v4)", example = "123e4567-e89b-12d3-a456-426614174000"),
+        ("user_id" = String, Query, description = "Owner of the activity (UUID v4)"),
+        ("format" = Option<TrackFormat>, Query, description = "Track encoding: points (default), polyline or geojson"),
+        ("tolerance" = Option<f64>, Query, description = "Douglas-Peucker simplification tolerance in metres")
     ),
     responses(
-        (status = 200, description = "Track points for an activity", body = Vec<super::models::TrackPoint>, content_type = "application/json"),
+        (status = 200, description = "Track of an activity in the requested format", body = super::models::Track, content_type = "application/json"),
         (status = 400, description = "Invalid UUID"),
         (status = 500, description = "Internal Server Error")
     )
@@ -89,7 +207,68 @@ pub async fn get_trackpoints(
     let activity_id = Uuid::parse_str(&path.into_inner())
         .map_err(|_| AppError::BadRequest("Invalid UUID".into()))?;
 
-    let tps = service::get_trackpoints(db.get_ref(), activity_id, query.user_id).await?;
+    let track = service::get_track(
+        db.get_ref(),
+        activity_id,
+        query.user_id,
+        query.format,
+        query.tolerance,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(track))
+}
+
+#[utoipa::path(
+    get,
+    path = "/activities/{activity_id}/splits",
+    params(
+        ("activity_id" = String, Path, description = "Activity ID (UUID v4)", example = "123e4567-e89b-12d3-a456-426614174000"),
+        ("user_id" = String, Query, description = "Owner of the activity (UUID v4)"),
+        ("unit" = Option<SplitUnit>, Query, description = "Split length: km (default) or mi")
+    ),
+    responses(
+        (status = 200, description = "Splits in track order; the last one may be partial", body = Vec<super::models::Split>, content_type = "application/json"),
+        (status = 400, description = "Invalid UUID or unit"),
+        (status = 404, description = "Not found"),
+        (status = 500, description = "Internal Server Error")
+    )
+)]
+#[get("/activities/{activity_id}/splits")]
+pub async fn get_splits(
+    path: web::Path<String>,
+    query: web::Query<SplitsQuery>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let activity_id = Uuid::parse_str(&path.into_inner())
+        .map_err(|_| AppError::BadRequest("Invalid UUID".into()))?;
+
+    let splits =
+        service::get_splits(db.get_ref(), activity_id, query.user_id, query.unit).await?;
+    Ok(HttpResponse::Ok().json(splits))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{user_id}/activities/{activity_id}/trackpoints/clean",
+    params(
+        ("user_id" = String, Path, description = "User ID (UUID v4)"),
+        ("activity_id" = String, Path, description = "Activity ID (UUID v4)")
+    ),
+    responses(
+        (status = 200, description = "Track re-cleaned from the raw points with the current thresholds", body = Vec<super::models::TrackPoint>, content_type = "application/json"),
+        (status = 400, description = "Invalid UUID"),
+        (status = 404, description = "Not found"),
+        (status = 500, description = "Internal Server Error")
+    )
+)]
+#[post("/users/{user_id}/activities/{activity_id}/trackpoints/clean")]
+pub async fn clean_trackpoints(
+    path: web::Path<(String, String)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, activity_id) = parse_activity_path(path)?;
+
+    let tps = service::clean_trackpoints(db.get_ref(), user_id, activity_id).await?;
     Ok(HttpResponse::Ok().json(tps))
 }
 
@@ -97,17 +276,21 @@ pub async fn get_trackpoints(
     post,
     path = "/activities/upload/{user_id}",
     params(
-        ("user_id" = String, Path, description = "User ID (UUID v4)")
+        ("user_id" = String, Path, description = "User ID (UUID v4)"),
+        ("dry_run" = Option<bool>, Query, description = "Preview only: parse and deduplicate, write nothing")
     ),
     request_body(content = UploadForm, content_type = "multipart/form-data"),
     responses(
-        (status = 200, description = "Upload processed successfully", body = super::models::UploadResponse, content_type = "application/json"),
-        (status = 400, description = "Bad request (missing/invalid user_id or multipart error)")
+        (status = 200, description = "Dry run: what the upload would insert, skip as duplicate and match to GPX files", body = super::models::UploadPreview, content_type = "application/json"),
+        (status = 202, description = "Upload accepted (cardioActivities.csv + GPX or the whole Runkeeper export .zip, and/or standalone .gpx/.fit/.tcx files); poll `status_url` for progress and the final UploadResponse. A ZIP over the entry/size limits fails the job", body = crate::uploads::models::UploadJobAccepted, content_type = "application/json"),
+        (status = 400, description = "Bad request (missing/invalid user_id, multipart error; for a dry run also a ZIP over the entry/size limits)"),
+        (status = 404, description = "Unknown user")
     )
 )]
 #[post("/activities/upload/{user_id}")]
 pub async fn upload_files(
     path: web::Path<String>,
+    query: web::Query<UploadQuery>,
     mut payload: Multipart,
     db: web::Data<PgPool>,
 ) -> Result<HttpResponse, AppError> {
@@ -116,8 +299,9 @@ pub async fn upload_files(
     let user_id = Uuid::parse_str(&user_id_str)
         .map_err(|_| AppError::BadRequest("Invalid UUID format".into()))?;
 
-    let mut csv_lines: Vec<String> = Vec::new();
-    let mut gpx_files: HashMap<String, Vec<u8>> = HashMap::new();
+    // Every file goes to a temporary file; extracting and parsing happen in
+    // the upload job (or the dry run) once the whole request has been read.
+    let mut files = SpooledUpload::default();
 
     while let Some(item) = payload.next().await {
         let mut field = item.map_err(|e| {
@@ -132,34 +316,16 @@ pub async fn upload_files(
         let Some(name) = filename else {
             continue;
         };
+        files.push(name, &mut field).await?;
+    }
 
-        let mut content = Vec::new();
-        while let Some(chunk) = field.next().await {
-            let bytes = chunk.map_err(|e| {
-                tracing::error!("Error reading chunk: {}", e);
-                AppError::BadRequest("Error reading upload chunk".into())
-            })?;
-            content.extend(bytes);
-        }
-
-        if name.to_lowercase().ends_with(".gpx") {
-            gpx_files.insert(name, content);
-        } else if name.to_lowercase() == "cardioactivities.csv" {
-            match std::str::from_utf8(&content) {
-                Ok(text) => {
-                    csv_lines = text.lines().map(|l| l.to_string()).collect();
-                }
-                Err(_) => {
-                    return Err(AppError::BadRequest(
-                        "Invalid UTF-8 in cardioActivities.csv".into(),
-                    ))
-                }
-            }
-        }
+    if query.dry_run {
+        let preview = service::preview_upload(db.get_ref(), user_id, files).await?;
+        return Ok(HttpResponse::Ok().json(preview));
     }
 
-    let response = service::upload(db.get_ref(), user_id, csv_lines, gpx_files).await;
-    Ok(HttpResponse::Ok().json(response))
+    let accepted = uploads::service::start_upload(db.get_ref(), user_id, files).await?;
+    Ok(HttpResponse::Accepted().json(accepted))
 }
 
 #[utoipa::path(
@@ -170,10 +336,12 @@ pub async fn upload_files(
         ("activity_type" = Option<String>,  Query, description = "Filter by activity type (e.g. 'Running')"),
         ("date_from"     = Option<String>,  Query, description = "Start date inclusive (YYYY-MM-DD)"),
         ("date_to"       = Option<String>,  Query, description = "End date inclusive (YYYY-MM-DD)"),
+        ("zoom"          = Option<u8>,      Query, description = "Map zoom level; picks the grid resolution (finest when absent)"),
+        ("bbox"          = Option<String>,  Query, description = "Only cells inside min_lon,min_lat,max_lon,max_lat"),
     ),
     responses(
-        (status = 200, description = "Heatmap grid points", body = Vec<super::models::HeatmapPoint>, content_type = "application/json"),
-        (status = 400, description = "Invalid UUID or date range"),
+        (status = 200, description = "Heatmap grid cells", body = Vec<super::models::HeatmapPoint>, content_type = "application/json"),
+        (status = 400, description = "Invalid UUID, date range or bbox"),
         (status = 500, description = "Internal Server Error"),
     )
 )]
@@ -187,18 +355,79 @@ pub async fn get_heatmap(
         .map_err(|_| AppError::BadRequest("Invalid UUID".into()))?;
 
     let q = query.into_inner();
+    check_date_range(q.date_from, q.date_to)?;
 
-    // Validate date range when both bounds are provided.
-    if let (Some(from), Some(to)) = (q.date_from, q.date_to) {
-        if from > to {
-            return Err(AppError::BadRequest(
-                "date_from must not be later than date_to".into(),
-            ));
-        }
-    }
+    let bbox = q
+        .bbox
+        .as_deref()
+        .map(str::parse::<Bbox>)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
 
-    let result =
-        service::get_heatmap(db.get_ref(), user_id, q.activity_type, q.date_from, q.date_to)
-            .await?;
+    let result = service::get_heatmap(
+        db.get_ref(),
+        user_id,
+        q.activity_type,
+        q.date_from,
+        q.date_to,
+        q.zoom,
+        bbox,
+    )
+    .await?;
     Ok(HttpResponse::Ok().json(result))
 }
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/heatmap/tiles/{z}/{x}/{y}.png",
+    params(
+        ("user_id"       = String,          Path,  description = "User ID (UUID v4)", example = "123e4567-e89b-12d3-a456-426614174000"),
+        ("z"             = u8,              Path,  description = "Zoom level (0-20)"),
+        ("x"             = u32,             Path,  description = "Tile column"),
+        ("y"             = u32,             Path,  description = "Tile row, counted from the north"),
+        ("activity_type" = Option<String>,  Query, description = "Filter by activity type (e.g. 'Running')"),
+        ("date_from"     = Option<String>,  Query, description = "Start date inclusive (YYYY-MM-DD)"),
+        ("date_to"       = Option<String>,  Query, description = "End date inclusive (YYYY-MM-DD)"),
+    ),
+    responses(
+        (status = 200, description = "256x256 heatmap tile", content_type = "image/png"),
+        (status = 400, description = "Invalid UUID, tile or date range"),
+        (status = 500, description = "Internal Server Error"),
+    )
+)]
+#[get("/users/{user_id}/heatmap/tiles/{z}/{x}/{y}.png")]
+pub async fn get_heatmap_tile(
+    path: web::Path<(String, u8, u32, u32)>,
+    query: web::Query<HeatmapTileQuery>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, z, x, y) = path.into_inner();
+    let user_id =
+        Uuid::parse_str(&user_id).map_err(|_| AppError::BadRequest("Invalid UUID".into()))?;
+
+    let q = query.into_inner();
+    check_date_range(q.date_from, q.date_to)?;
+
+    let png = service::get_heatmap_tile(
+        db.get_ref(),
+        user_id,
+        q.activity_type,
+        q.date_from,
+        q.date_to,
+        z,
+        x,
+        y,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().content_type("image/png").body(png))
+}
+
+/// Reject a date range whose bounds are the wrong way round.
+fn check_date_range(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<(), AppError> {
+    match (from, to) {
+        (Some(from), Some(to)) if from > to => Err(AppError::BadRequest(
+            "date_from must not be later than date_to".into(),
+        )),
+        _ => Ok(()),
+    }
+}
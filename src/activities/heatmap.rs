@@ -0,0 +1,135 @@
+/// Heatmap grid.
+///
+/// Cells are Web Mercator pixels: at level `L` the world is
+/// `2^(L + 8)` cells wide, i.e. one cell per pixel of a 256-pixel map tile at
+/// zoom `L`.  Each activity's cleaned track is counted into cells at every
+/// level in [`LEVELS`] when it is stored (`heatmap_cells`), so a heatmap
+/// request only sums precomputed cells.  Level 14 cells are ~10 m across at
+/// the equator and less towards the poles.  Pure functions — no I/O.
+use std::{collections::HashMap, f64::consts::PI, str::FromStr};
+
+use super::models::TrackPoint;
+
+/// Stored grid levels, coarsest first.
+pub const LEVELS: [i16; 4] = [5, 8, 11, 14];
+
+/// Web Mercator stops at this latitude.
+const MAX_LAT: f64 = 85.051_128_78;
+
+/// Point count of one grid cell of one activity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub level: i16,
+    pub x: i32,
+    pub y: i32,
+    pub weight: i32,
+}
+
+/// Finest stored level no finer than the map zoom; the finest level when no
+/// zoom is given.
+pub fn level_for_zoom(zoom: Option<u8>) -> i16 {
+    match zoom {
+        Some(z) => LEVELS
+            .iter()
+            .copied()
+            .rfind(|&l| l <= i16::from(z))
+            .unwrap_or(LEVELS[0]),
+        None => LEVELS[LEVELS.len() - 1],
+    }
+}
+
+fn world_size(level: i16) -> f64 {
+    f64::from(1u32 << (level + 8))
+}
+
+/// Cell containing a position, at `level`.
+pub fn cell_of(lat: f64, lon: f64, level: i16) -> (i32, i32) {
+    let size = world_size(level);
+    let lat = lat.clamp(-MAX_LAT, MAX_LAT).to_radians();
+    let x = (lon + 180.0) / 360.0 * size;
+    let y = (1.0 - lat.tan().asinh() / PI) / 2.0 * size;
+    let max = size - 1.0;
+    (
+        x.floor().clamp(0.0, max) as i32,
+        y.floor().clamp(0.0, max) as i32,
+    )
+}
+
+/// Latitude and longitude of a cell's centre.
+pub fn cell_centre(x: i32, y: i32, level: i16) -> (f64, f64) {
+    let size = world_size(level);
+    let lon = (f64::from(x) + 0.5) / size * 360.0 - 180.0;
+    let n = PI * (1.0 - 2.0 * (f64::from(y) + 0.5) / size);
+    (n.sinh().atan().to_degrees(), lon)
+}
+
+/// The cells a track falls into, at every stored level.
+pub fn cells(points: &[TrackPoint]) -> Vec<Cell> {
+    let mut counts: HashMap<(i16, i32, i32), i32> = HashMap::new();
+    for tp in points {
+        for level in LEVELS {
+            let (x, y) = cell_of(tp.latitude, tp.longitude, level);
+            *counts.entry((level, x, y)).or_default() += 1;
+        }
+    }
+    let mut cells: Vec<Cell> = counts
+        .into_iter()
+        .map(|((level, x, y), weight)| Cell {
+            level,
+            x,
+            y,
+            weight,
+        })
+        .collect();
+    cells.sort_by_key(|c| (c.level, c.x, c.y));
+    cells
+}
+
+/// `min_lon,min_lat,max_lon,max_lat`, as in GeoJSON.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bbox {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+}
+
+impl Bbox {
+    /// Inclusive cell ranges `(x_min, x_max, y_min, y_max)` covering the box.
+    pub fn cell_range(&self, level: i16) -> (i32, i32, i32, i32) {
+        // y grows southwards.
+        let (x_min, y_min) = cell_of(self.max_lat, self.min_lon, level);
+        let (x_max, y_max) = cell_of(self.min_lat, self.max_lon, level);
+        (x_min, x_max, y_min, y_max)
+    }
+}
+
+impl FromStr for Bbox {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("bbox must be min_lon,min_lat,max_lon,max_lat, got '{s}'");
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        let [min_lon, min_lat, max_lon, max_lat] = values[..] else {
+            return Err(invalid());
+        };
+        let lon_ok = |v: f64| (-180.0..=180.0).contains(&v);
+        let lat_ok = |v: f64| (-90.0..=90.0).contains(&v);
+        if !(lon_ok(min_lon) && lon_ok(max_lon) && lat_ok(min_lat) && lat_ok(max_lat))
+            || min_lon > max_lon
+            || min_lat > max_lat
+        {
+            return Err(invalid());
+        }
+        Ok(Self {
+            min_lon,
+            min_lat,
+            max_lon,
+            max_lat,
+        })
+    }
+}
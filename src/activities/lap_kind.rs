@@ -0,0 +1,77 @@
+/// Kind of an activity lap.
+///
+/// Stored as TEXT in the `activity_laps.kind` column, with the same
+/// TEXT-backed sqlx integration as `duplicates::status::DuplicateStatus`.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LapKind {
+    /// A fixed-distance auto-lap.
+    Auto,
+    /// A fast interval of an interval session.
+    Work,
+    /// The slower stretch between (or around) work intervals.
+    Recovery,
+}
+
+impl LapKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Work => "work",
+            Self::Recovery => "recovery",
+        }
+    }
+}
+
+impl fmt::Display for LapKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for LapKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "work" => Ok(Self::Work),
+            "recovery" => Ok(Self::Recovery),
+            other => Err(format!("unknown lap kind: {other}")),
+        }
+    }
+}
+
+// ─── sqlx TEXT-backed integration (same boilerplate as DuplicateStatus) ──────
+
+impl sqlx::Type<sqlx::Postgres> for LapKind {
+    fn type_info() -> PgTypeInfo {
+        <String as sqlx::Type<sqlx::Postgres>>::type_info()
+    }
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, sqlx::Postgres> for LapKind {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let s = <&str as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        s.parse().map_err(|e: String| e.into())
+    }
+}
+
+impl sqlx::Encode<'_, sqlx::Postgres> for LapKind {
+    fn encode_by_ref(
+        &self,
+        buf: &mut PgArgumentBuffer,
+    ) -> Result<sqlx::encode::IsNull, Box<dyn std::error::Error + Send + Sync>> {
+        let s = self.as_str();
+        <&str as sqlx::Encode<sqlx::Postgres>>::encode_by_ref(&s, buf)
+    }
+}
@@ -0,0 +1,148 @@
+/// Laps of a cleaned GPS track.
+///
+/// Auto-laps cut the track every `auto_lap_m` metres of moving distance, at
+/// the first point past each boundary.  Intervals are found from speed: the
+/// threshold sits halfway between the slow and the fast fifth of the track's
+/// speeds, points are classed fast or slow against it, and stretches shorter
+/// than `interval_min_secs` are absorbed by their neighbours, shortest first.
+/// A track whose speed barely varies, or with fewer than two fast stretches,
+/// is not an interval session and only gets auto-laps.
+///
+/// Pure functions — no I/O.
+use super::{
+    cleaning::CleaningConfig,
+    lap_kind::LapKind,
+    metrics,
+    models::{Lap, TrackPoint},
+    pace::Pace,
+};
+
+/// Auto-laps followed by work and recovery intervals, if any.
+pub fn detect_laps(points: &[TrackPoint], config: &CleaningConfig) -> Vec<Lap> {
+    let mut laps = auto_laps(points, config);
+    laps.extend(intervals(points, config));
+    laps
+}
+
+/// Fixed-distance laps; the last one holds whatever is left.
+pub fn auto_laps(points: &[TrackPoint], config: &CleaningConfig) -> Vec<Lap> {
+    if points.len() < 2 || config.auto_lap_m <= 0.0 {
+        return Vec::new();
+    }
+    let (dist, time) = metrics::cumulative(points, config);
+    let mut laps = Vec::new();
+    let mut start = 0;
+    for end in 1..points.len() {
+        if dist[end] - dist[start] >= config.auto_lap_m {
+            laps.push(lap(
+                points,
+                (&dist, &time),
+                start,
+                end,
+                LapKind::Auto,
+                laps.len(),
+                config,
+            ));
+            start = end;
+        }
+    }
+    let last = points.len() - 1;
+    if last > start && dist[last] - dist[start] >= 1.0 {
+        laps.push(lap(
+            points,
+            (&dist, &time),
+            start,
+            last,
+            LapKind::Auto,
+            laps.len(),
+            config,
+        ));
+    }
+    laps
+}
+
+/// Work and recovery intervals in track order; empty unless the track is an
+/// interval session.
+pub fn intervals(points: &[TrackPoint], config: &CleaningConfig) -> Vec<Lap> {
+    let n = points.len();
+    if n < 3 {
+        return Vec::new();
+    }
+    let speeds: Vec<f64> = points.iter().map(|tp| tp.speed.unwrap_or(0.0)).collect();
+    let mut sorted = speeds.clone();
+    sorted.sort_by(f64::total_cmp);
+    let (slow, fast) = (sorted[n / 5], sorted[n * 4 / 5]);
+    if fast <= 0.0 || fast < slow * config.interval_speed_ratio {
+        return Vec::new();
+    }
+    let threshold = (slow + fast) / 2.0;
+    let (dist, time) = metrics::cumulative(points, config);
+
+    // Stretches as (first point, fast); each runs to the next one's first point.
+    let mut stretches: Vec<(usize, bool)> = Vec::new();
+    for (i, &speed) in speeds.iter().enumerate() {
+        let is_fast = speed >= threshold;
+        if stretches.last().is_none_or(|&(_, f)| f != is_fast) {
+            stretches.push((i, is_fast));
+        }
+    }
+    while stretches.len() > 1 {
+        let secs = |k: usize| time[stretch_end(&stretches, k, n)] - time[stretches[k].0];
+        let shortest = (0..stretches.len())
+            .filter(|&k| secs(k) < config.interval_min_secs)
+            .min_by(|&a, &b| secs(a).total_cmp(&secs(b)));
+        let Some(k) = shortest else { break };
+        // Flipping a stretch merges it into its neighbours.
+        stretches[k].1 = !stretches[k].1;
+        stretches.dedup_by_key(|s| s.1);
+    }
+
+    if stretches.iter().filter(|s| s.1).count() < 2 {
+        return Vec::new();
+    }
+    let (mut work, mut recovery) = (0, 0);
+    (0..stretches.len())
+        .map(|k| {
+            let (start, is_fast) = stretches[k];
+            let end = stretch_end(&stretches, k, n);
+            let (kind, count) = if is_fast {
+                (LapKind::Work, &mut work)
+            } else {
+                (LapKind::Recovery, &mut recovery)
+            };
+            *count += 1;
+            lap(points, (&dist, &time), start, end, kind, *count - 1, config)
+        })
+        .collect()
+}
+
+fn stretch_end(stretches: &[(usize, bool)], k: usize, n: usize) -> usize {
+    stretches.get(k + 1).map_or(n - 1, |s| s.0)
+}
+
+/// The lap from point `start` to point `end`, given the cumulative moving
+/// distance and elapsed time of the track.
+fn lap(
+    points: &[TrackPoint],
+    (dist, time): (&[f64], &[f64]),
+    start: usize,
+    end: usize,
+    kind: LapKind,
+    index: usize,
+    config: &CleaningConfig,
+) -> Lap {
+    let distance_m = dist[end] - dist[start];
+    let duration = time[end] - time[start];
+    let (gain, loss) =
+        metrics::elevation_change(&points[start..=end], config.elevation_hysteresis_m);
+    Lap {
+        kind,
+        lap: index as i32 + 1,
+        start_offset_seconds: time[start],
+        duration_seconds: duration,
+        distance_m,
+        pace: Pace::from_duration(duration, distance_m / 1000.0),
+        elevation_gain: gain as f32,
+        elevation_loss: loss as f32,
+    }
+}
@@ -0,0 +1,229 @@
+/// Activity metrics derived from a cleaned GPS track.
+///
+/// Distance and moving time only count the stretches between consecutive
+/// points covered at or above the pause speed, so standing at a crossing
+/// neither adds GPS jitter to the distance nor time to the moving pace.
+/// Elevation gain and loss use a hysteresis band: the reference elevation
+/// only moves once the track has climbed or dropped by more than the band.
+///
+/// Splits and best efforts follow the same rule, so split distances add up
+/// to `track_distance`.
+///
+/// Pure functions — no I/O.
+use crate::personal_records::models::{BestEffort, BEST_EFFORT_DISTANCES};
+
+use super::{
+    cleaning::CleaningConfig,
+    models::{Split, TrackMetrics, TrackPoint},
+    pace::Pace,
+    parser::haversine_distance_m,
+};
+
+/// Metrics of a cleaned, time-ordered track; all `None` for fewer than two
+/// points.
+pub fn track_metrics(
+    points: &[TrackPoint],
+    activity_type: &str,
+    config: &CleaningConfig,
+) -> TrackMetrics {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return TrackMetrics::default();
+    };
+    if points.len() < 2 {
+        return TrackMetrics::default();
+    }
+
+    let mut distance_m = 0.0;
+    let mut moving_ms = 0;
+    for w in points.windows(2) {
+        let dist = haversine_distance_m(w[0].latitude, w[0].longitude, w[1].latitude, w[1].longitude);
+        let dt_ms = (w[1].time - w[0].time).num_milliseconds();
+        if dt_ms > 0 && dist / (dt_ms as f64 / 1000.0) >= config.pause_speed_ms {
+            distance_m += dist;
+            moving_ms += dt_ms;
+        }
+    }
+    let moving_secs = (moving_ms as f64 / 1000.0).round();
+    let distance_km = distance_m / 1000.0;
+
+    let (gain, loss) = elevation_change(points, config.elevation_hysteresis_m);
+    let moving_pace = (activity_type == "Running")
+        .then(|| Pace::from_duration(moving_secs, distance_km))
+        .filter(|p| p.is_set());
+
+    TrackMetrics {
+        track_distance: Some(distance_km as f32),
+        moving_time: Some(moving_secs as i32),
+        elapsed_time: Some((last.time - first.time).num_seconds() as i32),
+        moving_pace,
+        elevation_gain: Some(gain as f32),
+        elevation_loss: Some(loss as f32),
+    }
+}
+
+/// Cumulative moving distance (m) and elapsed time (s) at each point of a
+/// non-empty track.
+pub fn cumulative(points: &[TrackPoint], config: &CleaningConfig) -> (Vec<f64>, Vec<f64>) {
+    let first = points[0].time;
+    let mut dist = Vec::with_capacity(points.len());
+    let mut time = Vec::with_capacity(points.len());
+    let mut total = 0.0;
+    for (i, tp) in points.iter().enumerate() {
+        let elapsed = (tp.time - first).num_milliseconds() as f64 / 1000.0;
+        if i > 0 {
+            let prev = &points[i - 1];
+            let seg =
+                haversine_distance_m(prev.latitude, prev.longitude, tp.latitude, tp.longitude);
+            let dt = elapsed - time[i - 1];
+            if dt > 0.0 && seg / dt >= config.pause_speed_ms {
+                total += seg;
+            }
+        }
+        dist.push(total);
+        time.push(elapsed);
+    }
+    (dist, time)
+}
+
+/// Metres climbed and descended, ignoring changes within `hysteresis` of the
+/// last counted elevation.
+pub fn elevation_change(points: &[TrackPoint], hysteresis: f64) -> (f64, f64) {
+    let mut reference = points[0].elevation as f64;
+    let (mut gain, mut loss) = (0.0, 0.0);
+    for tp in &points[1..] {
+        let elevation = tp.elevation as f64;
+        if elevation > reference + hysteresis {
+            gain += elevation - reference;
+            reference = elevation;
+        } else if elevation < reference - hysteresis {
+            loss += reference - elevation;
+            reference = elevation;
+        }
+    }
+    (gain, loss)
+}
+
+/// Cut a cleaned, time-ordered track into splits of `unit_m` metres of
+/// moving distance.  Split boundaries fall between track points, so time and
+/// elevation are interpolated along the segment that crosses them.  A
+/// shorter final split holds whatever is left (under a metre is dropped).
+pub fn splits(points: &[TrackPoint], unit_m: f64, config: &CleaningConfig) -> Vec<Split> {
+    let Some(first) = points.first() else {
+        return Vec::new();
+    };
+    let mut splits = Vec::new();
+    let mut start_elevation = first.elevation as f64;
+    let (mut distance, mut elapsed, mut moving) = (0.0, 0.0, 0.0);
+
+    for w in points.windows(2) {
+        let seg =
+            haversine_distance_m(w[0].latitude, w[0].longitude, w[1].latitude, w[1].longitude);
+        let dt = (w[1].time - w[0].time).num_milliseconds() as f64 / 1000.0;
+        if dt <= 0.0 || seg / dt < config.pause_speed_ms {
+            elapsed += dt;
+            continue;
+        }
+        let (e0, e1) = (w[0].elevation as f64, w[1].elevation as f64);
+        let (mut seg_left, mut t_left) = (seg, dt);
+        while distance + seg_left >= unit_m {
+            let need = unit_m - distance;
+            let t = t_left * need / seg_left;
+            seg_left -= need;
+            t_left -= t;
+            let elevation = e0 + (e1 - e0) * (1.0 - seg_left / seg);
+            splits.push(split(
+                splits.len(),
+                unit_m,
+                unit_m,
+                elapsed + t,
+                moving + t,
+                elevation - start_elevation,
+            ));
+            start_elevation = elevation;
+            (distance, elapsed, moving) = (0.0, 0.0, 0.0);
+        }
+        distance += seg_left;
+        elapsed += t_left;
+        moving += t_left;
+    }
+
+    if distance >= 1.0 {
+        let end_elevation = points[points.len() - 1].elevation as f64;
+        splits.push(split(
+            splits.len(),
+            distance,
+            unit_m,
+            elapsed,
+            moving,
+            end_elevation - start_elevation,
+        ));
+    }
+    splits
+}
+
+fn split(
+    index: usize,
+    distance_m: f64,
+    unit_m: f64,
+    elapsed: f64,
+    moving: f64,
+    elevation_change: f64,
+) -> Split {
+    let speed_ms = if moving > 0.0 {
+        distance_m / moving
+    } else {
+        0.0
+    };
+    Split {
+        split: index as u32 + 1,
+        distance: (distance_m / unit_m) as f32,
+        elapsed_time: elapsed.round() as i32,
+        moving_time: moving.round() as i32,
+        pace: Pace::from_duration(moving, distance_m / 1000.0),
+        elevation_change: elevation_change as f32,
+        average_speed: (speed_ms * 3.6) as f32,
+    }
+}
+
+/// The fastest stretch of each `BEST_EFFORT_DISTANCES` distance inside a
+/// cleaned, time-ordered track, timed on elapsed time.  Each window ends on a
+/// track point; its start is interpolated inside the segment where exactly
+/// the distance remains.  Distances longer than the track are skipped.
+pub fn best_efforts(points: &[TrackPoint], config: &CleaningConfig) -> Vec<BestEffort> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let (dist, time) = cumulative(points, config);
+    let total = dist[dist.len() - 1];
+
+    BEST_EFFORT_DISTANCES
+        .iter()
+        .filter(|(_, target)| total >= *target)
+        .filter_map(|&(category, target)| {
+            let mut start = 0;
+            let mut best: Option<(f64, f64)> = None;
+            for end in 0..points.len() {
+                if dist[end] < target {
+                    continue;
+                }
+                while dist[end] - dist[start + 1] >= target {
+                    start += 1;
+                }
+                // dist[start] <= dist[end] - target < dist[start + 1]
+                let at = dist[end] - target;
+                let f = (at - dist[start]) / (dist[start + 1] - dist[start]);
+                let begins = time[start] + (time[start + 1] - time[start]) * f;
+                let duration = time[end] - begins;
+                if best.is_none_or(|(d, _)| duration < d) {
+                    best = Some((duration, begins));
+                }
+            }
+            best.map(|(duration, begins)| BestEffort {
+                category: category.to_string(),
+                distance_m: target,
+                duration_seconds: duration,
+                start_offset_seconds: begins,
+            })
+        })
+        .collect()
+}
@@ -1,15 +1,31 @@
+pub mod archive;
+pub mod cleaning;
+pub mod fit;
+pub mod geometry;
 pub mod handlers;
+pub mod heatmap;
+pub mod lap_kind;
+pub mod laps;
+pub mod metrics;
 pub mod models;
+pub mod pace;
 pub mod parser;
 pub mod repository;
 pub mod service;
+pub mod tiles;
 
 use actix_web::web;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(handlers::get_activities)
+        .service(handlers::create_activity)
+        .service(handlers::update_activity)
+        .service(handlers::delete_activity)
         .service(handlers::get_activity_detail)
         .service(handlers::get_trackpoints)
+        .service(handlers::get_splits)
+        .service(handlers::clean_trackpoints)
         .service(handlers::get_heatmap)
+        .service(handlers::get_heatmap_tile)
         .service(handlers::upload_files);
 }
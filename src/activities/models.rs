@@ -1,15 +1,46 @@
 use std::collections::HashMap;
 
-use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::aggregate::models::{ActivitiesAggregation, AggregationDTO};
+use crate::personal_records::models::BestEffort;
+use crate::users::timezone;
+
+use super::{lap_kind::LapKind, pace::Pace};
 
 #[derive(Debug, Deserialize)]
 pub struct ActivityDetailQuery {
     pub user_id: Uuid,
+    #[serde(default)]
+    pub format: TrackFormat,
+    /// Douglas-Peucker tolerance in metres; full resolution when absent.
+    pub tolerance: Option<f64>,
+}
+
+/// How a GPS track is returned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackFormat {
+    /// Every point as a `TrackPoint` object.
+    #[default]
+    Points,
+    /// Google encoded polyline (precision 5).
+    Polyline,
+    /// GeoJSON `Feature` with a `LineString` geometry.
+    Geojson,
+}
+
+/// A GPS track in the requested `TrackFormat`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum Track {
+    Points(Vec<TrackPoint>),
+    Polyline { polyline: String },
+    GeoJson(serde_json::Value),
 }
 
 #[derive(Debug, ToSchema, Deserialize)]
@@ -19,50 +50,287 @@ pub struct UploadForm {
     pub files: Vec<String>, // Represented as `format: binary` in OpenAPI
 }
 
+/// Files collected from an upload request, grouped by kind.
+#[derive(Debug, Default)]
+pub struct UploadFiles {
+    /// Contents of the Runkeeper `cardioActivities.csv`.
+    pub csv_text: String,
+    /// Runkeeper GPX tracks, keyed by filename.
+    pub gpx_files: HashMap<String, Vec<u8>>,
+    /// Standalone `.fit` files, keyed by filename.
+    pub fit_files: HashMap<String, Vec<u8>>,
+    /// Standalone `.tcx` files, keyed by filename.
+    pub tcx_files: HashMap<String, Vec<u8>>,
+}
+
+impl UploadFiles {
+    /// Route one uploaded file by its name.  Unknown file types are ignored;
+    /// the only error is a `cardioActivities.csv` that is not valid UTF-8.
+    pub fn insert(&mut self, name: String, content: Vec<u8>) -> Result<(), String> {
+        let lower = name.to_lowercase();
+        if lower.ends_with(".gpx") {
+            self.gpx_files.insert(name, content);
+        } else if lower.ends_with(".fit") {
+            self.fit_files.insert(name, content);
+        } else if lower.ends_with(".tcx") {
+            self.tcx_files.insert(name, content);
+        } else if lower == "cardioactivities.csv" {
+            self.csv_text = String::from_utf8(content)
+                .map_err(|_| "Invalid UTF-8 in cardioActivities.csv".to_string())?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, utoipa::ToSchema)]
 pub struct ActivitiesResponse {
     pub activities: Vec<Activity>,
+    /// Computed over every activity matching the filters, not just this page;
+    /// `None` unless aggregations were included (see `ActivityListQuery`).
     pub aggregation: Option<HashMap<String, AggregationDTO>>,
     pub time_aggregations: Option<HashMap<String, HashMap<String, ActivitiesAggregation>>>,
+    /// Pass as `cursor` to fetch the next page; `None` on the last page.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// Sort order of `GET /users/{user_id}/activities`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivitySort {
+    #[default]
+    DateDesc,
+    DateAsc,
+    DistanceDesc,
+    DistanceAsc,
+}
+
+/// Query parameters of `GET /users/{user_id}/activities`.  Every filter is
+/// optional; without `limit` the whole filtered list is returned.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ActivityListQuery {
+    pub activity_type: Option<String>,
+    /// Start date inclusive.
+    pub date_from: Option<NaiveDate>,
+    /// End date inclusive.
+    pub date_to: Option<NaiveDate>,
+    /// Kilometres, inclusive.
+    pub min_distance: Option<f32>,
+    /// Kilometres, inclusive.
+    pub max_distance: Option<f32>,
+    pub source: Option<String>,
+    /// Case-insensitive substring match on the activity name.
+    pub q: Option<String>,
+    #[serde(default)]
+    pub sort: ActivitySort,
+    /// Page size (1–1000).
+    pub limit: Option<i64>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+    /// Whether to compute `aggregation` and `time_aggregations`.  Defaults
+    /// to `true` for an unpaged list and `false` once `limit` or `cursor` is
+    /// given, since a page would otherwise re-read the whole filtered history.
+    pub include_aggregations: Option<bool>,
+}
+
+/// The filter part of an `ActivityListQuery`, as handed to the repository.
+#[derive(Debug, Default)]
+pub struct ActivityFilter {
+    pub activity_type: Option<String>,
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+    pub min_distance: Option<f32>,
+    pub max_distance: Option<f32>,
+    pub source: Option<String>,
+    pub name_contains: Option<String>,
+    /// Zone the `date_from` / `date_to` days are cut in.
+    pub tz: Tz,
+}
+
+/// Keyset position after the last activity of a page: its sort key plus its
+/// ID as the tie-breaker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActivityCursor {
+    Date(DateTime<Utc>, Uuid),
+    Distance(f32, Uuid),
+}
+
+impl ActivityCursor {
+    /// Cursor pointing just past `activity` in the given sort order.
+    pub fn after(activity: &Activity, sort: ActivitySort) -> Self {
+        match sort {
+            ActivitySort::DateDesc | ActivitySort::DateAsc => {
+                ActivityCursor::Date(activity.date, activity.id)
+            }
+            ActivitySort::DistanceDesc | ActivitySort::DistanceAsc => {
+                ActivityCursor::Distance(activity.distance, activity.id)
+            }
+        }
+    }
+
+    /// Opaque string form handed to clients.
+    pub fn encode(&self) -> String {
+        let raw = match self {
+            ActivityCursor::Date(date, id) => {
+                format!("d|{}|{id}", date.to_rfc3339())
+            }
+            ActivityCursor::Distance(km, id) => format!("k|{km}|{id}"),
+        };
+        hex::encode(raw)
+    }
+
+    /// Parse a cursor, checking it belongs to `sort`.
+    pub fn decode(cursor: &str, sort: ActivitySort) -> Result<Self, String> {
+        let invalid = || "Invalid cursor".to_string();
+        let raw = hex::decode(cursor).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let mut parts = raw.splitn(3, '|');
+        let (Some(kind), Some(key), Some(id)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+        let cursor = match kind {
+            "d" => ActivityCursor::Date(
+                DateTime::parse_from_rfc3339(key).map_err(|_| invalid())?.to_utc(),
+                id,
+            ),
+            "k" => ActivityCursor::Distance(key.parse().map_err(|_| invalid())?, id),
+            _ => return Err(invalid()),
+        };
+        if cursor.matches_sort(sort) {
+            Ok(cursor)
+        } else {
+            Err("Cursor does not match the requested sort".to_string())
+        }
+    }
+
+    fn matches_sort(&self, sort: ActivitySort) -> bool {
+        matches!(
+            (self, sort),
+            (ActivityCursor::Date(..), ActivitySort::DateDesc | ActivitySort::DateAsc)
+                | (ActivityCursor::Distance(..), ActivitySort::DistanceDesc | ActivitySort::DistanceAsc)
+        )
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ActivityDetailResponse {
     pub activity: Activity,
-    pub track_points: Vec<TrackPoint>,
+    pub track_points: Track,
+    /// Fastest stretches of the standard distances inside the run.
+    #[serde(default)]
+    pub best_efforts: Vec<BestEffort>,
+    /// Auto-laps, then work and recovery intervals when detected.
+    #[serde(default)]
+    pub laps: Vec<Lap>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, utoipa::ToSchema)]
 pub struct Activity {
     pub id: Uuid,
     pub user_id: Uuid,
-    pub date: NaiveDateTime,
+    /// Start instant.
+    pub date: DateTime<Utc>,
+    /// Offset of the clock the activity was recorded on, in seconds east of
+    /// UTC; `None` when the source did not report one.
+    #[serde(default)]
+    pub utc_offset: Option<i32>,
     pub name: String,
     pub activity_type: String,
     pub distance: f32,
     pub duration: String,
-    pub average_pace: f32,
+    /// Seconds per km, whatever the source.
+    pub average_pace: Pace,
     pub average_speed: f32,
     pub calories: f32,
     pub climb: f32,
     pub gps_file: String,
-    /// Data source: `"runkeeper"` or `"strava"`.
+    /// Data source: `"runkeeper"`, `"strava"`, `"gpx"`, `"fit"`, `"tcx"` or `"manual"`.
     #[serde(default = "default_source")]
     pub source: String,
     /// Source-specific stable ID for deduplication (None for legacy Runkeeper rows).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub external_id: Option<String>,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub track_metrics: TrackMetrics,
+}
+
+/// Metrics derived from the cleaned GPS track, stored next to the summary
+/// the source reported (`distance`, `duration`, `climb`), which often
+/// disagrees with the map.  All `None` without a track.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct TrackMetrics {
+    /// Kilometres covered while moving.
+    #[serde(default)]
+    pub track_distance: Option<f32>,
+    /// Seconds spent moving, pauses excluded.
+    #[serde(default)]
+    pub moving_time: Option<i32>,
+    /// Seconds from the first to the last track point.
+    #[serde(default)]
+    pub elapsed_time: Option<i32>,
+    /// Seconds per km over the moving time (running only).
+    #[serde(default)]
+    pub moving_pace: Option<Pace>,
+    /// Metres climbed, barometric noise ignored.
+    #[serde(default)]
+    pub elevation_gain: Option<f32>,
+    /// Metres descended, barometric noise ignored.
+    #[serde(default)]
+    pub elevation_loss: Option<f32>,
 }
 
 fn default_source() -> String {
     "runkeeper".to_string()
 }
 
+impl Activity {
+    /// Start time on the clock the activity was recorded on, or in `tz` when
+    /// the source gave no offset.  Use for time of day, not for calendar
+    /// periods — those are always cut in the user's zone.
+    pub fn local_start(&self, tz: Tz) -> DateTime<FixedOffset> {
+        let offset = self.utc_offset.unwrap_or_else(|| timezone::offset_seconds(tz, self.date));
+        let offset = FixedOffset::east_opt(offset).unwrap_or(FixedOffset::east_opt(0).unwrap());
+        self.date.with_timezone(&offset)
+    }
+
+    /// The day the activity started on in the user's zone `tz`.
+    pub fn local_date(&self, tz: Tz) -> NaiveDate {
+        timezone::local_date(tz, self.date)
+    }
+}
+
+/// A start time sent by a client: RFC 3339 with an offset
+/// (`2025-06-01T07:00:00+10:00`), or a local time without one
+/// (`2025-06-01T07:00:00`) read in the user's timezone.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum StartTime {
+    Offset(DateTime<FixedOffset>),
+    Local(NaiveDateTime),
+}
+
+impl StartTime {
+    /// The instant and its offset in seconds east of UTC.
+    pub fn resolve(self, tz: Tz) -> (DateTime<Utc>, i32) {
+        match self {
+            StartTime::Offset(dt) => (dt.to_utc(), dt.offset().local_minus_utc()),
+            StartTime::Local(local) => {
+                let instant = timezone::localize(tz, local).to_utc();
+                (instant, timezone::offset_seconds(tz, instant))
+            }
+        }
+    }
+}
+
 /// A GPS track point.
 ///
 /// `latitude` and `longitude` are stored as DOUBLE PRECISION in the DB
 /// (migration 20250522000001).  `time` is TIMESTAMPTZ.
 /// `speed` is DOUBLE PRECISION (m/s), nullable — populated on new uploads only.
+/// The sensor channels are nullable too and only set when the source
+/// recorded them.
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, utoipa::ToSchema)]
 pub struct TrackPoint {
     pub id: Option<Uuid>,
@@ -72,11 +340,24 @@ pub struct TrackPoint {
     pub elevation: f32,
     pub time: DateTime<Utc>,
     pub speed: Option<f64>,
+    /// Beats per minute.
+    #[serde(default)]
+    pub heart_rate: Option<i16>,
+    /// Steps (or revolutions) per minute.
+    #[serde(default)]
+    pub cadence: Option<i16>,
+    /// Watts.
+    #[serde(default)]
+    pub power: Option<i16>,
+    /// Degrees Celsius.
+    #[serde(default)]
+    pub temperature: Option<f32>,
 }
 
-/// A single point in the geographic heatmap grid.
+/// A single cell of the geographic heatmap grid.
 ///
-/// Coordinates are rounded to 4 decimal places (~11 m grid cells).
+/// `lat`/`lon` is the cell centre; `weight` is the number of track points in
+/// the cell.  Cell size follows the requested zoom (see `activities::heatmap`).
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, utoipa::ToSchema)]
 pub struct HeatmapPoint {
     pub lat: f64,
@@ -84,18 +365,276 @@ pub struct HeatmapPoint {
     pub weight: i64,
 }
 
+/// One lap of an activity: a fixed-distance auto-lap or a detected work or
+/// recovery interval.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Lap {
+    pub kind: LapKind,
+    /// 1-based, counted per kind.
+    pub lap: i32,
+    /// Seconds from the activity start.
+    pub start_offset_seconds: f64,
+    /// Elapsed seconds, pauses included.
+    pub duration_seconds: f64,
+    /// Metres covered while moving.
+    pub distance_m: f64,
+    /// Seconds per km over the elapsed time.
+    pub pace: Pace,
+    pub elevation_gain: f32,
+    pub elevation_loss: f32,
+}
+
+/// Split length of `GET /activities/{activity_id}/splits`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitUnit {
+    #[default]
+    Km,
+    Mi,
+}
+
+impl SplitUnit {
+    pub fn metres(self) -> f64 {
+        match self {
+            SplitUnit::Km => 1000.0,
+            SplitUnit::Mi => 1609.344,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SplitsQuery {
+    pub user_id: Uuid,
+    #[serde(default)]
+    pub unit: SplitUnit,
+}
+
+/// One kilometre or mile of an activity's track.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Split {
+    /// 1-based.
+    pub split: u32,
+    /// Length in the requested unit: 1, except for a shorter final split.
+    pub distance: f32,
+    /// Seconds from the start to the end of the split, pauses included.
+    pub elapsed_time: i32,
+    /// Seconds spent moving.
+    pub moving_time: i32,
+    /// Seconds per km over the moving time, whatever the split unit.
+    pub pace: Pace,
+    /// Metres gained (positive) or lost over the split.
+    pub elevation_change: f32,
+    /// Kilometres per hour over the moving time.
+    pub average_speed: f32,
+}
+
 /// Optional query parameters for the heatmap endpoint.
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct HeatmapQuery {
     pub activity_type: Option<String>,
     pub date_from: Option<NaiveDate>,
     pub date_to: Option<NaiveDate>,
+    /// Map zoom level; picks the grid resolution.  Finest grid when absent.
+    pub zoom: Option<u8>,
+    /// `min_lon,min_lat,max_lon,max_lat`; only cells inside are returned.
+    pub bbox: Option<String>,
 }
 
-/// Response returned after a successful upload.
+/// Optional query parameters for the heatmap tile endpoint.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct HeatmapTileQuery {
+    pub activity_type: Option<String>,
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+}
+
+/// What happened to one uploaded file or CSV row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestOutcome {
+    Inserted,
+    Duplicate,
+    Skipped,
+    /// Parsed, but could not be stored; uploading it again may succeed.
+    Failed,
+}
+
+/// Machine-readable reason attached to an `IngestReportEntry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestReason {
+    /// The CSV row could not be parsed (see `message`).
+    InvalidRow,
+    /// The GPX/FIT/TCX file could not be parsed (see `message`).
+    InvalidFile,
+    /// An activity with the same start time or source ID already exists.
+    Duplicate,
+    /// A GPX file not referenced by any CSV row, imported on its own.
+    UnreferencedGpx,
+    /// The CSV row names a GPX file that was not uploaded; imported without a track.
+    MissingGpx,
+    /// The same run already exists from another source with equal or higher
+    /// priority; this record was linked to it instead of being imported.
+    CrossSourceDuplicate,
+    /// The same run existed from a lower-priority source; this record replaced
+    /// its data, keeping the activity ID.
+    ReplacedDuplicate,
+    /// Imported, but resembles an activity from another source; listed under
+    /// `GET /users/{user_id}/duplicates` for review.
+    SuspectedDuplicate,
+    /// The database rejected the insert.
+    InsertFailed,
+}
+
+/// One line of the per-file / per-row ingestion report.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IngestReportEntry {
+    /// File the entry refers to (`cardioActivities.csv` for CSV rows).
+    pub file: String,
+    /// 1-based line of the CSV row; `None` for files.
+    pub line: Option<u32>,
+    pub outcome: IngestOutcome,
+    pub reason: Option<IngestReason>,
+    /// Human-readable detail, e.g. the parse error.
+    pub message: Option<String>,
+    /// ID of the inserted activity.
+    pub activity_id: Option<Uuid>,
+}
+
+impl IngestReportEntry {
+    pub fn file(file: impl Into<String>, outcome: IngestOutcome) -> Self {
+        IngestReportEntry {
+            file: file.into(),
+            line: None,
+            outcome,
+            reason: None,
+            message: None,
+            activity_id: None,
+        }
+    }
+
+    pub fn skipped(file: impl Into<String>, reason: IngestReason, message: impl Into<String>) -> Self {
+        IngestReportEntry {
+            reason: Some(reason),
+            message: Some(message.into()),
+            ..Self::file(file, IngestOutcome::Skipped)
+        }
+    }
+}
+
+/// Query parameters of `POST /activities/upload/{user_id}`.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct UploadQuery {
+    /// Parse and deduplicate only; write nothing and return an `UploadPreview`.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// One activity as the importer would see it, in an `UploadPreview`.
 #[derive(Debug, Serialize, ToSchema)]
+pub struct PreviewActivity {
+    /// File the activity comes from (`cardioActivities.csv` for CSV rows).
+    pub file: String,
+    /// 1-based line of the CSV row; `None` for standalone files.
+    pub line: Option<u32>,
+    pub source: String,
+    pub date: DateTime<Utc>,
+    pub name: String,
+    pub activity_type: String,
+    /// Kilometres.
+    pub distance: f32,
+    pub duration: String,
+    /// Number of GPS points that would be stored.
+    pub track_points: usize,
+}
+
+/// How a CSV row's `GPX File` column resolved against the uploaded files.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GpxMatch {
+    /// 1-based line of the CSV row.
+    pub line: u32,
+    pub gps_file: String,
+    /// The GPX file was part of the upload.
+    pub found: bool,
+    /// Points parsed from the file; `None` if missing or unreadable.
+    pub track_points: Option<usize>,
+}
+
+/// Result of a dry-run upload: what the importer would do, with nothing written.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UploadPreview {
+    /// Activities that would be inserted.
+    pub would_insert: Vec<PreviewActivity>,
+    /// Activities that would be skipped as duplicates of existing (or earlier
+    /// uploaded) activities.
+    pub duplicates: Vec<PreviewActivity>,
+    /// GPX file resolution for every CSV row that names one.
+    pub gpx_matches: Vec<GpxMatch>,
+    /// CSV rows and files that could not be parsed.
+    pub skipped: Vec<IngestReportEntry>,
+}
+
+/// Body of `POST /users/{user_id}/activities`: an activity logged by hand,
+/// e.g. a treadmill run.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateActivityRequest {
+    /// RFC 3339; without an offset it is read in the user's timezone.
+    #[schema(value_type = String, format = "date-time")]
+    pub date: StartTime,
+    /// Defaults to the activity type.
+    pub name: Option<String>,
+    pub activity_type: String,
+    /// Kilometres.
+    pub distance: f32,
+    /// `H:MM:SS` or `MM:SS`.
+    pub duration: String,
+    #[serde(default)]
+    pub calories: f32,
+    /// Elevation gain in metres.
+    #[serde(default)]
+    pub climb: f32,
+}
+
+/// Body of `PATCH /users/{user_id}/activities/{activity_id}`.
+/// Absent fields are left unchanged.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct UpdateActivityRequest {
+    /// RFC 3339; without an offset it is read in the user's timezone.
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub date: Option<StartTime>,
+    pub name: Option<String>,
+    pub activity_type: Option<String>,
+    /// Kilometres.
+    pub distance: Option<f32>,
+    /// `H:MM:SS` or `MM:SS`.
+    pub duration: Option<String>,
+    pub calories: Option<f32>,
+    /// Elevation gain in metres.
+    pub climb: Option<f32>,
+}
+
+/// Result of creating, editing or deleting an activity.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ActivityChangeResponse {
+    /// The activity as stored; `None` after a delete.
+    pub activity: Option<Activity>,
+    /// XP taken back from the previous version of the activity: its distance
+    /// XP, PR XP and the XP of the achievements it unlocked.
+    pub xp_revoked: i64,
+    /// PR categories the previous version held; each now belongs to the best
+    /// remaining activity, or to this one again if it still qualifies.
+    pub released_prs: Vec<String>,
+    /// Slugs of achievements the previous version had unlocked.
+    pub revoked_achievements: Vec<String>,
+    /// XP, PRs, achievements, missions and goals earned by re-running the
+    /// pipeline on the new version.
+    pub effects: UploadResponse,
+}
+
+/// Response returned after a successful upload.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
 pub struct UploadResponse {
-    /// Number of GPX files processed.
+    /// Number of activities processed (CSV rows and standalone GPX/FIT/TCX activities).
     pub processed: u32,
     /// Total XP earned from this upload batch.
     pub xp_earned: i64,
@@ -110,4 +649,27 @@ pub struct UploadResponse {
     /// User-defined goals completed during this upload batch.
     #[serde(default)]
     pub completed_goals: Vec<crate::goals::models::CompletedGoalSummary>,
+    /// What happened to every uploaded file and CSV row.
+    #[serde(default)]
+    pub report: Vec<IngestReportEntry>,
+}
+
+impl UploadResponse {
+    /// Fold the result of another ingest batch into this one.
+    ///
+    /// Counters are summed and lists concatenated; `new_level` keeps the most
+    /// recent level-up since batches are merged in processing order.
+    pub fn merge(&mut self, other: UploadResponse) {
+        self.processed += other.processed;
+        self.xp_earned += other.xp_earned;
+        if other.new_level.is_some() {
+            self.new_level = other.new_level;
+        }
+        self.newly_unlocked_achievements
+            .extend(other.newly_unlocked_achievements);
+        self.new_prs.extend(other.new_prs);
+        self.completed_missions.extend(other.completed_missions);
+        self.completed_goals.extend(other.completed_goals);
+        self.report.extend(other.report);
+    }
 }
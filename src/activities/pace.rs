@@ -0,0 +1,80 @@
+/// Running pace, always in **seconds per kilometre**.
+///
+/// Stored as REAL in `activities.average_pace` (migration 20260613000001
+/// converted the older per-source M.SS / decimal-minute values).  Sources
+/// convert into this type at the boundary with the constructor matching
+/// their unit; consumers never need to know where an activity came from.
+///
+/// `Pace::default()` (0 s/km) means "no pace" — non-running activities or
+/// activities without distance.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+const METRES_PER_MILE: f64 = 1_609.344;
+
+/// Seconds per kilometre; lower is faster, 0 when unknown.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize, ToSchema, sqlx::Type,
+)]
+#[serde(transparent)]
+#[sqlx(transparent)]
+#[schema(value_type = f32, example = 330.0)]
+pub struct Pace(f32);
+
+impl Pace {
+    pub fn from_secs_per_km(secs: f64) -> Self {
+        Self(secs.max(0.0) as f32)
+    }
+
+    /// Runkeeper's `M.SS` float, e.g. `6.56` = 6:56/km.
+    pub fn from_mss(mss: f64) -> Self {
+        let minutes = mss.trunc();
+        Self::from_secs_per_km(minutes * 60.0 + ((mss - minutes) * 100.0).round())
+    }
+
+    /// Pace from a speed in m/s; no pace below 0.01 m/s.
+    pub fn from_speed_ms(speed: f64) -> Self {
+        if speed > 0.01 {
+            Self::from_secs_per_km(1000.0 / speed)
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Pace of `duration_secs` over `distance_km`; no pace without both.
+    pub fn from_duration(duration_secs: f64, distance_km: f64) -> Self {
+        if distance_km > 0.0 && duration_secs > 0.0 {
+            Self::from_secs_per_km(duration_secs / distance_km)
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Convert a per-mile pace to per-km.
+    pub fn from_per_mile(per_mile: Pace) -> Self {
+        Self::from_secs_per_km(per_mile.secs_per_km() * 1000.0 / METRES_PER_MILE)
+    }
+
+    pub fn secs_per_km(self) -> f64 {
+        self.0 as f64
+    }
+
+    pub fn min_per_km(self) -> f64 {
+        self.secs_per_km() / 60.0
+    }
+
+    /// Whether a pace is known.
+    pub fn is_set(self) -> bool {
+        self.0 > 0.0
+    }
+}
+
+/// `M:SS`, e.g. `5:30`.
+impl fmt::Display for Pace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.secs_per_km().round() as u64;
+        write!(f, "{}:{:02}", total / 60, total % 60)
+    }
+}
@@ -1,14 +1,24 @@
 /// File parsing for the activities domain.
 ///
-/// Both parsing functions are **synchronous** — they do only CPU work (no I/O).
+/// All parsing functions are **synchronous** — they do only CPU work (no I/O).
 /// Callers must not `.await` them.  The `#[allow(dead_code)]` on `clean_gpx_data`
 /// is intentional: the hack is kept in one place and documented here.
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use gpx::Waypoint;
-use std::num::ParseFloatError;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-use super::models::{Activity, TrackPoint};
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::sync::normalized::{NormalizedActivity, NormalizedTrackPoint};
+use crate::users::timezone;
+
+use super::{
+    fit::{self, FitMessage},
+    models::{Activity, TrackMetrics, TrackPoint},
+    pace::Pace,
+};
 
 /// Compute the great-circle distance in metres between two WGS-84 points
 /// using the Haversine formula.  Pure math — no I/O, no allocation.
@@ -22,46 +32,70 @@ pub fn haversine_distance_m(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     R * c
 }
 
-/// Parse one CSV row from `cardioActivities.csv` into an `Activity`.
-///
-/// Expected column order (14 fields, 0-indexed):
-///  0=id, 1=date, 2=activity_type, 3=name, 4=distance, 5=duration,
-///  6=average_pace, 7=average_speed, 8=calories, 9=climb, …, 13=gps_file
-pub fn parse_csv_row(row: &str, user_id: Uuid) -> Result<Activity, String> {
-    let parts: Vec<&str> = row.split(',').collect();
+/// A `cardioActivities.csv` row that could not be turned into an `Activity`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRowError {
+    /// 1-based line in the file where the record starts.
+    pub line: usize,
+    /// Header name of the offending column, when the error is field-specific.
+    pub field: Option<String>,
+    pub message: String,
+}
 
-    if parts.len() != 14 {
-        return Err(format!("Expected 14 columns, got {}", parts.len()));
+impl std::fmt::Display for CsvRowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "line {}, field '{}': {}", self.line, field, self.message),
+            None => write!(f, "line {}: {}", self.line, self.message),
+        }
     }
+}
 
-    Ok(Activity {
-        id: Uuid::parse_str(parts[0]).map_err(|e| e.to_string())?,
-        user_id,
-        date: chrono::NaiveDateTime::parse_from_str(parts[1], "%Y-%m-%d %H:%M:%S")
-            .map_err(|e| e.to_string())?,
-        activity_type: parts[2].to_string(),
-        name: parts[3].to_string(),
-        distance: parts[4]
-            .parse()
-            .map_err(|e: ParseFloatError| e.to_string())?,
-        duration: parts[5].to_string(),
-        average_pace: parts[6]
-            .replace(":", ".")
-            .parse()
-            .map_err(|e: ParseFloatError| e.to_string())?,
-        average_speed: parts[7]
-            .parse()
-            .map_err(|e: ParseFloatError| e.to_string())?,
-        calories: parts[8]
-            .parse()
-            .map_err(|e: ParseFloatError| e.to_string())?,
-        climb: parts[9]
-            .parse()
-            .map_err(|e: ParseFloatError| e.to_string())?,
-        gps_file: parts[13].to_string(),
-        source: "runkeeper".to_string(),
-        external_id: None,
-    })
+/// Parse a whole `cardioActivities.csv` export (RFC 4180: quoted fields may
+/// contain commas, doubled quotes and line breaks).
+///
+/// Columns are mapped by header name, so extra columns and reordered exports
+/// are fine; only `Activity Id`, `Date`, `Type`, `Distance` and `Duration`
+/// are required.  Exports in miles (`Distance (mi)`) are converted to km.
+/// A file without a header row is read with the legacy 14-column layout.
+///
+/// Runkeeper writes local wall-clock times without an offset; they are read
+/// in the user's zone `tz`.
+///
+/// Returns one result per data row, in file order; parsed rows come with the
+/// 1-based line they start on.
+pub fn parse_csv(
+    text: &str,
+    user_id: Uuid,
+    tz: Tz,
+) -> Vec<Result<(usize, Activity), CsvRowError>> {
+    let mut records = read_csv_records(text).into_iter().peekable();
+
+    let columns = match records.peek() {
+        Some((line, first)) if Uuid::parse_str(first[0].trim()).is_err() => {
+            let (line, header) = (*line, first.clone());
+            records.next();
+            match CsvColumns::from_header(header) {
+                Ok(columns) => columns,
+                Err(message) => {
+                    return vec![Err(CsvRowError {
+                        line,
+                        field: None,
+                        message,
+                    })]
+                }
+            }
+        }
+        _ => CsvColumns::legacy(),
+    };
+
+    records
+        .map(|(line, fields)| {
+            columns
+                .to_activity(&fields, line, user_id, tz)
+                .map(|activity| (line, activity))
+        })
+        .collect()
 }
 
 /// Parse GPX bytes for a single activity into a list of `TrackPoint`s.
@@ -69,6 +103,8 @@ pub fn parse_csv_row(row: &str, user_id: Uuid) -> Result<Activity, String> {
 /// Each point's `speed` (m/s) is computed from the great-circle distance and
 /// time delta between consecutive points.  The last point copies the speed of
 /// its predecessor.  Points with zero time delta get `speed = None`.
+/// Sensor channels come from each point's `<extensions>`, as for
+/// [`parse_gpx_activity`].
 ///
 /// # GPX quirk
 /// Some Garmin exports include a non-standard creator attribute on line 11
@@ -76,47 +112,937 @@ pub fn parse_csv_row(row: &str, user_id: Uuid) -> Result<Activity, String> {
 /// strips that line before parsing — this is a known workaround and must be
 /// kept until the upstream import source is changed.
 pub fn parse_gpx(data: &[u8], activity_id: Uuid) -> Result<Vec<TrackPoint>, String> {
-    let cursor = clean_gpx_data(data)?;
+    let cleaned = clean_gpx_data(data)?.into_inner();
 
-    let gpx = gpx::read(cursor).map_err(|e| format!("Error reading GPX data: {}", e))?;
+    let gpx = gpx::read(cleaned.as_bytes()).map_err(|e| format!("Error reading GPX data: {}", e))?;
+    let mut sensors = gpx_sensor_readings(cleaned.as_bytes()).into_iter();
     let mut track_points = Vec::new();
 
     for track in gpx.tracks {
         for segment in track.segments {
             for waypoint in segment.points {
-                let tp = waypoint_to_trackpoint(waypoint, activity_id)?;
+                let readings = sensors.next().unwrap_or_default();
+                let tp = waypoint_to_trackpoint(waypoint, readings, activity_id)?;
                 track_points.push(tp);
             }
         }
     }
 
-    // Compute pairwise speed (forward: point i → point i+1).
-    let n = track_points.len();
-    if n >= 2 {
-        for i in 0..n - 1 {
-            let dist = haversine_distance_m(
-                track_points[i].latitude,
-                track_points[i].longitude,
-                track_points[i + 1].latitude,
-                track_points[i + 1].longitude,
-            );
-            let dt = (track_points[i + 1].time - track_points[i].time)
-                .num_milliseconds() as f64
-                / 1000.0;
-            track_points[i].speed = if dt > 0.0 { Some(dist / dt) } else { None };
+    compute_speeds(&mut track_points);
+
+    Ok(track_points)
+}
+
+/// Parse a standalone GPX file (one not referenced by a Runkeeper CSV row)
+/// into an `Activity` plus its `TrackPoint`s.
+///
+/// The summary is derived from the track: distance is the Haversine length of
+/// each segment, duration runs from the first to the last timestamp and
+/// elevation gain is the sum of positive elevation steps.  Activity type and
+/// name come from the track's `<type>` / `<name>` (falling back to the file
+/// `<metadata>`).  Points without a timestamp are dropped.  Heart rate,
+/// cadence, power and temperature are read from the point's `<extensions>`
+/// (Garmin `TrackPointExtension` and the common `<power>` element).
+///
+/// The activity gets `source = "gpx"` and a SHA-256 of the file contents as
+/// `external_id`, so uploading the same file twice is a no-op.
+pub fn parse_gpx_activity(
+    data: &[u8],
+    file_name: &str,
+    user_id: Uuid,
+) -> Result<(Activity, Vec<TrackPoint>), String> {
+    // Files from other apps rarely have the Runkeeper quirk, so try the raw
+    // bytes first and only fall back to `clean_gpx_data` if that fails.
+    let text = std::str::from_utf8(data).map_err(|e| e.to_string())?;
+    let (gpx, xml) = match gpx::read(text.as_bytes()) {
+        Ok(gpx) => (gpx, text.to_string()),
+        Err(_) => {
+            let cleaned = clean_gpx_data(data)?.into_inner();
+            let gpx = gpx::read(cleaned.as_bytes())
+                .map_err(|e| format!("Error reading GPX data: {}", e))?;
+            (gpx, cleaned)
+        }
+    };
+    let mut sensors = gpx_sensor_readings(xml.as_bytes()).into_iter();
+
+    let activity_id = Uuid::new_v4();
+    let first_track = gpx.tracks.first();
+    let gpx_type = first_track.and_then(|t| t.type_.clone());
+    let gpx_name = first_track
+        .and_then(|t| t.name.clone())
+        .or_else(|| gpx.metadata.as_ref().and_then(|m| m.name.clone()))
+        .filter(|n| !n.trim().is_empty());
+
+    let mut track_points = Vec::new();
+    let mut distance_m = 0.0;
+    for track in gpx.tracks {
+        for segment in track.segments {
+            let mut segment_points = Vec::new();
+            for waypoint in segment.points {
+                // Readings are in document order, so consume one per point.
+                let readings = sensors.next().unwrap_or_default();
+                if waypoint.time.is_some() {
+                    segment_points.push(waypoint_to_trackpoint(waypoint, readings, activity_id)?);
+                }
+            }
+            distance_m += track_distance_m(&segment_points);
+            track_points.extend(segment_points);
         }
-        // Last point copies speed from its predecessor.
-        let prev_speed = track_points[n - 2].speed;
-        track_points[n - 1].speed = prev_speed;
     }
+    compute_speeds(&mut track_points);
 
-    Ok(track_points)
+    let (first, last) = match (track_points.first(), track_points.last()) {
+        (Some(f), Some(l)) => (f, l),
+        _ => return Err("GPX file contains no timestamped track points".into()),
+    };
+    let start = first.time;
+    let elapsed_secs = (last.time - start).num_milliseconds() as f64 / 1000.0;
+
+    let climb: f64 = track_points
+        .windows(2)
+        .map(|w| ((w[1].elevation - w[0].elevation) as f64).max(0.0))
+        .sum();
+
+    let avg_speed_ms = if elapsed_secs > 0.0 {
+        distance_m / elapsed_secs
+    } else {
+        0.0
+    };
+    let activity_type = gpx_activity_type(gpx_type.as_deref(), gpx_name.as_deref());
+    let average_pace = if activity_type == "Running" {
+        Pace::from_speed_ms(avg_speed_ms)
+    } else {
+        Pace::default()
+    };
+
+    let activity = Activity {
+        id: activity_id,
+        user_id,
+        date: start,
+        utc_offset: None,
+        name: gpx_name.unwrap_or_else(|| activity_type.to_string()),
+        activity_type: activity_type.to_string(),
+        distance: (distance_m / 1000.0) as f32,
+        duration: seconds_to_hms(elapsed_secs.round() as i64),
+        average_pace,
+        average_speed: (avg_speed_ms * 3.6) as f32,
+        calories: 0.0,
+        climb: climb as f32,
+        gps_file: file_name.to_string(),
+        source: "gpx".to_string(),
+        external_id: Some(content_hash(data)),
+        track_metrics: TrackMetrics::default(),
+    };
+
+    Ok((activity, track_points))
+}
+
+/// Parse a Training Center XML (`.tcx`) export into `NormalizedActivity`s —
+/// one per `<Activity>` element (Garmin Connect and Polar Flow usually write
+/// exactly one).
+///
+/// Summary values are summed over the activity's `<Lap>`s (time, distance,
+/// calories); elevation gain is the sum of positive altitude steps between
+/// track points.  Each `<Trackpoint>` with a position becomes a
+/// `NormalizedTrackPoint` carrying heart rate, cadence and power when recorded.
+/// Speed comes from the Garmin `TPX` extension when present, otherwise it is
+/// computed pairwise as for GPX.
+///
+/// Activities get `source = "tcx"` and a SHA-256 of their `<Id>` (the start
+/// timestamp) as `external_id`.
+pub fn parse_tcx(data: &[u8], file_name: &str) -> Result<Vec<NormalizedActivity>, String> {
+    let mut activities: Vec<TcxActivity> = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+
+    for event in EventReader::new(data) {
+        match event.map_err(|e| format!("Error reading TCX data: {}", e))? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let attr = |key: &str| {
+                    attributes
+                        .iter()
+                        .find(|a| a.name.local_name == key)
+                        .map(|a| a.value.clone())
+                };
+                match name.local_name.as_str() {
+                    "Activity" => activities.push(TcxActivity {
+                        sport: attr("Sport").unwrap_or_default(),
+                        ..Default::default()
+                    }),
+                    "Lap" => {
+                        if let Some(a) = activities.last_mut() {
+                            a.laps.push(TcxLap {
+                                start: attr("StartTime").and_then(|t| parse_xml_time(&t)),
+                                ..Default::default()
+                            });
+                        }
+                    }
+                    "Trackpoint" => {
+                        if let Some(a) = activities.last_mut() {
+                            a.current = Some(TcxPoint::default());
+                        }
+                    }
+                    _ => {}
+                }
+                path.push(name.local_name);
+            }
+            XmlEvent::Characters(text) => {
+                if let Some(a) = activities.last_mut() {
+                    a.apply_text(&path, text.trim());
+                }
+            }
+            XmlEvent::EndElement { name } => {
+                if name.local_name == "Trackpoint" {
+                    if let Some(a) = activities.last_mut() {
+                        if let Some(p) = a.current.take() {
+                            a.points.push(p);
+                        }
+                    }
+                }
+                path.pop();
+            }
+            _ => {}
+        }
+    }
+
+    if activities.is_empty() {
+        return Err("TCX file contains no activities".into());
+    }
+
+    activities
+        .into_iter()
+        .enumerate()
+        .map(|(i, a)| a.into_normalized(data, i, file_name))
+        .collect()
+}
+
+/// Parse a Garmin/Coros `.fit` activity file into an `Activity` summary and
+/// its `TrackPoint`s.
+///
+/// Summary values come from the FIT `session` message when present and fall
+/// back to values derived from the `record` messages otherwise.  Recorded
+/// speeds and sensor channels (heart rate, cadence, power, temperature) are
+/// used as-is; when the device did not log speed it is computed pairwise the
+/// same way as for GPX.
+///
+/// The activity gets `source = "fit"` and a SHA-256 of the file contents as
+/// `external_id`, so re-uploading the same file is deduplicated.
+pub fn parse_fit(
+    data: &[u8],
+    file_name: &str,
+    user_id: Uuid,
+) -> Result<(Activity, Vec<TrackPoint>), String> {
+    let messages = fit::decode(data)?;
+    let activity_id = Uuid::new_v4();
+
+    let session = messages.iter().find(|m| m.global == fit::MESG_SESSION);
+    let sport = session
+        .and_then(|s| s.get(FIT_SESSION_SPORT))
+        .or_else(|| {
+            messages
+                .iter()
+                .find(|m| m.global == fit::MESG_SPORT)
+                .and_then(|m| m.get(FIT_SPORT_SPORT))
+        });
+
+    let mut track_points = Vec::new();
+    let mut record_times = Vec::new();
+    let mut last_record_distance_m = None;
+    for record in messages.iter().filter(|m| m.global == fit::MESG_RECORD) {
+        let Some(time) = record.get(fit::FIELD_TIMESTAMP).and_then(fit_time) else {
+            continue;
+        };
+        record_times.push(time);
+        if let Some(d) = record.get(FIT_RECORD_DISTANCE) {
+            last_record_distance_m = Some(d / 100.0);
+        }
+        if let Some(tp) = record_to_trackpoint(record, time, activity_id) {
+            track_points.push(tp);
+        }
+    }
+
+    if !track_points.iter().any(|tp| tp.speed.is_some()) {
+        compute_speeds(&mut track_points);
+    }
+
+    let start = session
+        .and_then(|s| s.get(FIT_SESSION_START_TIME))
+        .and_then(fit_time)
+        .or_else(|| record_times.first().copied())
+        .ok_or_else(|| "FIT file has no session or timestamped records".to_string())?;
+
+    let elapsed_secs = session
+        .and_then(|s| s.get(FIT_SESSION_TOTAL_ELAPSED_TIME))
+        .map(|t| t / 1000.0)
+        .or_else(|| {
+            record_times
+                .last()
+                .map(|last| (*last - start).num_milliseconds() as f64 / 1000.0)
+        })
+        .unwrap_or(0.0);
+
+    let distance_m = session
+        .and_then(|s| s.get(FIT_SESSION_TOTAL_DISTANCE))
+        .map(|d| d / 100.0)
+        .or(last_record_distance_m)
+        .unwrap_or_else(|| track_distance_m(&track_points));
+
+    let avg_speed_ms = session
+        .and_then(|s| {
+            s.get(FIT_SESSION_ENHANCED_AVG_SPEED)
+                .or_else(|| s.get(FIT_SESSION_AVG_SPEED))
+        })
+        .map(|v| v / 1000.0)
+        .unwrap_or_else(|| {
+            if elapsed_secs > 0.0 {
+                distance_m / elapsed_secs
+            } else {
+                0.0
+            }
+        });
+
+    let activity_type = fit_sport_to_activity_type(sport);
+    let average_pace = if activity_type == "Running" {
+        Pace::from_speed_ms(avg_speed_ms)
+    } else {
+        Pace::default()
+    };
+
+    let activity = Activity {
+        id: activity_id,
+        user_id,
+        date: start,
+        utc_offset: fit_utc_offset(&messages),
+        name: activity_type.to_string(),
+        activity_type: activity_type.to_string(),
+        distance: (distance_m / 1000.0) as f32,
+        duration: seconds_to_hms(elapsed_secs.round() as i64),
+        average_pace,
+        average_speed: (avg_speed_ms * 3.6) as f32,
+        calories: session
+            .and_then(|s| s.get(FIT_SESSION_TOTAL_CALORIES))
+            .unwrap_or(0.0) as f32,
+        climb: session
+            .and_then(|s| s.get(FIT_SESSION_TOTAL_ASCENT))
+            .unwrap_or(0.0) as f32,
+        gps_file: file_name.to_string(),
+        source: "fit".to_string(),
+        external_id: Some(content_hash(data)),
+        track_metrics: TrackMetrics::default(),
+    };
+
+    Ok((activity, track_points))
 }
 
 // ---------------------------------------------------------------------------
 // Private helpers
 // ---------------------------------------------------------------------------
 
+/// Split CSV text into records per RFC 4180, returning each record with the
+/// 1-based line it starts on.  Blank lines are skipped; an unterminated quote
+/// runs to the end of the input.
+fn read_csv_records(text: &str) -> Vec<(usize, Vec<String>)> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                fields.push(std::mem::take(&mut field));
+                if fields.iter().any(|f| !f.is_empty()) {
+                    records.push((record_line, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                record_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    if fields.iter().any(|f| !f.is_empty()) {
+        records.push((record_line, fields));
+    }
+    records
+}
+
+/// Column positions of the `cardioActivities.csv` fields we import.
+struct CsvColumns {
+    /// Header name per position, used in error messages.
+    names: Vec<String>,
+    id: usize,
+    date: usize,
+    activity_type: usize,
+    distance: usize,
+    duration: usize,
+    name: Option<usize>,
+    pace: Option<usize>,
+    speed: Option<usize>,
+    calories: Option<usize>,
+    climb: Option<usize>,
+    gps_file: Option<usize>,
+    /// Distance, pace and speed are in miles (`Distance (mi)` header).
+    miles: bool,
+}
+
+impl CsvColumns {
+    /// Positional layout of exports without a header row.
+    fn legacy() -> Self {
+        let names = [
+            "Activity Id",
+            "Date",
+            "Type",
+            "Route Name",
+            "Distance (km)",
+            "Duration",
+            "Average Pace",
+            "Average Speed (km/h)",
+            "Calories Burned",
+            "Climb (m)",
+            "Average Heart Rate (bpm)",
+            "Friend's Tagged",
+            "Notes",
+            "GPX File",
+        ];
+        CsvColumns {
+            names: names.iter().map(|n| n.to_string()).collect(),
+            id: 0,
+            date: 1,
+            activity_type: 2,
+            distance: 4,
+            duration: 5,
+            name: Some(3),
+            pace: Some(6),
+            speed: Some(7),
+            calories: Some(8),
+            climb: Some(9),
+            gps_file: Some(13),
+            miles: false,
+        }
+    }
+
+    /// Map columns by header name, ignoring case and any `(unit)` suffix.
+    fn from_header(header: Vec<String>) -> Result<Self, String> {
+        let key = |h: &str| {
+            h.split('(')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_lowercase()
+        };
+        let find = |name: &str| header.iter().position(|h| key(h) == name);
+        let require = |name: &str, display: &str| {
+            find(name).ok_or_else(|| format!("Missing required column '{}'", display))
+        };
+
+        Ok(CsvColumns {
+            id: require("activity id", "Activity Id")?,
+            date: require("date", "Date")?,
+            activity_type: require("type", "Type")?,
+            distance: require("distance", "Distance")?,
+            duration: require("duration", "Duration")?,
+            name: find("route name"),
+            pace: find("average pace"),
+            speed: find("average speed"),
+            calories: find("calories burned"),
+            climb: find("climb"),
+            gps_file: find("gpx file"),
+            miles: header
+                .iter()
+                .any(|h| key(h) == "distance" && h.to_lowercase().contains("(mi)")),
+            names: header,
+        })
+    }
+
+    fn to_activity(
+        &self,
+        fields: &[String],
+        line: usize,
+        user_id: Uuid,
+        tz: Tz,
+    ) -> Result<Activity, CsvRowError> {
+        let err = |idx: usize, message: String| CsvRowError {
+            line,
+            field: self.names.get(idx).cloned(),
+            message,
+        };
+        let get = |idx: usize| fields.get(idx).map(|f| f.trim()).unwrap_or_default();
+        let required = |idx: usize| match get(idx) {
+            "" => Err(err(idx, "missing value".into())),
+            v => Ok(v),
+        };
+        let number = |idx: Option<usize>| -> Result<f32, CsvRowError> {
+            match idx.map(get) {
+                None | Some("") => Ok(0.0),
+                Some(v) => v
+                    .parse::<f32>()
+                    .map_err(|_| err(idx.unwrap_or_default(), format!("invalid number '{}'", v))),
+            }
+        };
+        let km_factor = if self.miles { 1.609_344 } else { 1.0 };
+
+        let id_str = required(self.id)?;
+        let id = Uuid::parse_str(id_str)
+            .map_err(|_| err(self.id, format!("invalid UUID '{}'", id_str)))?;
+        let date_str = required(self.date)?;
+        let local = chrono::NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S")
+            .map_err(|_| err(self.date, format!("invalid date '{}'", date_str)))?;
+        let start = timezone::localize(tz, local);
+        let activity_type = required(self.activity_type)?.to_string();
+        let distance_str = required(self.distance)?;
+        let distance = distance_str
+            .parse::<f32>()
+            .map_err(|_| err(self.distance, format!("invalid number '{}'", distance_str)))?;
+        let duration = required(self.duration)?.to_string();
+
+        // Runkeeper writes pace as `M:SS` (older exports `M.SS`), per mile in
+        // imperial exports.
+        let pace_raw = self.pace.map(get).unwrap_or_default().replace(':', ".");
+        let mut average_pace = match pace_raw.as_str() {
+            "" => Pace::default(),
+            v => v.parse::<f64>().map(Pace::from_mss).map_err(|_| {
+                err(self.pace.unwrap_or_default(), format!("invalid pace '{}'", v))
+            })?,
+        };
+        if self.miles {
+            average_pace = Pace::from_per_mile(average_pace);
+        }
+
+        Ok(Activity {
+            id,
+            user_id,
+            date: start.to_utc(),
+            utc_offset: Some(timezone::offset_seconds(tz, start.to_utc())),
+            name: self
+                .name
+                .map(get)
+                .filter(|n| !n.is_empty())
+                .unwrap_or(&activity_type)
+                .to_string(),
+            activity_type,
+            distance: distance * km_factor,
+            duration,
+            average_pace,
+            average_speed: number(self.speed)? * km_factor,
+            calories: number(self.calories)?,
+            climb: number(self.climb)?,
+            gps_file: self.gps_file.map(get).unwrap_or_default().to_string(),
+            source: "runkeeper".to_string(),
+            external_id: None,
+            track_metrics: TrackMetrics::default(),
+        })
+    }
+}
+
+// FIT profile field numbers used by `parse_fit`.
+const FIT_SPORT_SPORT: u8 = 0;
+const FIT_SESSION_START_TIME: u8 = 2;
+const FIT_SESSION_SPORT: u8 = 5;
+const FIT_SESSION_TOTAL_ELAPSED_TIME: u8 = 7;
+const FIT_SESSION_TOTAL_DISTANCE: u8 = 9;
+const FIT_SESSION_TOTAL_CALORIES: u8 = 11;
+const FIT_SESSION_AVG_SPEED: u8 = 14;
+const FIT_SESSION_TOTAL_ASCENT: u8 = 22;
+const FIT_SESSION_ENHANCED_AVG_SPEED: u8 = 124;
+const FIT_ACTIVITY_LOCAL_TIMESTAMP: u8 = 5;
+const FIT_RECORD_POSITION_LAT: u8 = 0;
+const FIT_RECORD_POSITION_LONG: u8 = 1;
+const FIT_RECORD_ALTITUDE: u8 = 2;
+const FIT_RECORD_HEART_RATE: u8 = 3;
+const FIT_RECORD_CADENCE: u8 = 4;
+const FIT_RECORD_DISTANCE: u8 = 5;
+const FIT_RECORD_SPEED: u8 = 6;
+const FIT_RECORD_POWER: u8 = 7;
+const FIT_RECORD_TEMPERATURE: u8 = 13;
+const FIT_RECORD_ENHANCED_SPEED: u8 = 73;
+const FIT_RECORD_ENHANCED_ALTITUDE: u8 = 78;
+
+/// Compute pairwise speed (forward: point i → point i+1) in m/s for a list of
+/// `(lat, lon, time)` fixes.
+///
+/// The last point copies the speed of its predecessor.  Points with zero time
+/// delta get `None`.
+fn pairwise_speeds(fixes: &[(f64, f64, DateTime<Utc>)]) -> Vec<Option<f64>> {
+    let n = fixes.len();
+    if n < 2 {
+        return vec![None; n];
+    }
+    let mut speeds: Vec<Option<f64>> = fixes
+        .windows(2)
+        .map(|w| {
+            let dist = haversine_distance_m(w[0].0, w[0].1, w[1].0, w[1].1);
+            let dt = (w[1].2 - w[0].2).num_milliseconds() as f64 / 1000.0;
+            if dt > 0.0 {
+                Some(dist / dt)
+            } else {
+                None
+            }
+        })
+        .collect();
+    speeds.push(speeds[n - 2]);
+    speeds
+}
+
+fn compute_speeds(track_points: &mut [TrackPoint]) {
+    let fixes: Vec<_> = track_points
+        .iter()
+        .map(|tp| (tp.latitude, tp.longitude, tp.time))
+        .collect();
+    for (tp, speed) in track_points.iter_mut().zip(pairwise_speeds(&fixes)) {
+        tp.speed = speed;
+    }
+}
+
+/// Sum of the great-circle distances between consecutive points, in metres.
+fn track_distance_m(track_points: &[TrackPoint]) -> f64 {
+    track_points
+        .windows(2)
+        .map(|w| haversine_distance_m(w[0].latitude, w[0].longitude, w[1].latitude, w[1].longitude))
+        .sum()
+}
+
+/// Hex-encoded SHA-256 of some content, used as a stable `external_id`.
+fn content_hash(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn seconds_to_hms(seconds: i64) -> String {
+    let h = seconds / 3600;
+    let m = (seconds % 3600) / 60;
+    let s = seconds % 60;
+    format!("{:02}:{:02}:{:02}", h, m, s)
+}
+
+fn parse_xml_time(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Map the TCX `Sport` attribute onto the activity types used across the app.
+fn tcx_sport_to_activity_type(sport: &str) -> &'static str {
+    match sport {
+        "Running" => "Running",
+        "Biking" => "Cycling",
+        _ => "Other",
+    }
+}
+
+/// Infer the activity type from a GPX `<type>` value, falling back to the
+/// activity name ("Morning Run").  Strava writes its numeric activity type
+/// codes (`9` = run, `1` = ride, `10` = walk) into `<type>`.
+fn gpx_activity_type(gpx_type: Option<&str>, name: Option<&str>) -> &'static str {
+    let classify = |s: &str| {
+        let s = s.trim().to_lowercase();
+        match s.as_str() {
+            "9" => return Some("Running"),
+            "1" => return Some("Cycling"),
+            "10" | "11" => return Some("Walking"),
+            _ => {}
+        }
+        if s.contains("run") || s.contains("jog") {
+            Some("Running")
+        } else if s.contains("rid") || s.contains("bik") || s.contains("cycl") {
+            Some("Cycling")
+        } else if s.contains("walk") || s.contains("hik") {
+            Some("Walking")
+        } else if s.contains("swim") {
+            Some("Swimming")
+        } else {
+            None
+        }
+    };
+
+    gpx_type
+        .and_then(classify)
+        .or_else(|| name.and_then(classify))
+        .unwrap_or("Other")
+}
+
+#[derive(Debug, Default)]
+struct TcxLap {
+    start: Option<DateTime<Utc>>,
+    total_time_s: f64,
+    distance_m: f64,
+    calories: f64,
+}
+
+#[derive(Debug, Default)]
+struct TcxPoint {
+    time: Option<DateTime<Utc>>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    altitude: Option<f64>,
+    distance_m: Option<f64>,
+    heart_rate: Option<i16>,
+    cadence: Option<i16>,
+    power: Option<i16>,
+    speed: Option<f64>,
+}
+
+/// Accumulator for one `<Activity>` while the XML is streamed.
+#[derive(Debug, Default)]
+struct TcxActivity {
+    sport: String,
+    id: Option<String>,
+    notes: Option<String>,
+    laps: Vec<TcxLap>,
+    points: Vec<TcxPoint>,
+    /// Trackpoint currently being read (between its start and end tags).
+    current: Option<TcxPoint>,
+}
+
+impl TcxActivity {
+    /// Store the text content of the innermost element at `path`.
+    fn apply_text(&mut self, path: &[String], text: &str) {
+        let tail: Vec<&str> = path.iter().rev().take(3).map(String::as_str).collect();
+        let num = || text.parse::<f64>().ok();
+
+        if let Some(p) = self.current.as_mut() {
+            match tail.as_slice() {
+                ["Time", "Trackpoint", ..] => p.time = parse_xml_time(text),
+                ["LatitudeDegrees", "Position", ..] => p.latitude = num(),
+                ["LongitudeDegrees", "Position", ..] => p.longitude = num(),
+                ["AltitudeMeters", "Trackpoint", ..] => p.altitude = num(),
+                ["DistanceMeters", "Trackpoint", ..] => p.distance_m = num(),
+                ["Value", "HeartRateBpm", "Trackpoint"] => p.heart_rate = num().map(|v| v as i16),
+                ["Cadence", "Trackpoint", ..] => p.cadence = num().map(|v| v as i16),
+                ["RunCadence", "TPX", ..] => {
+                    p.cadence = p.cadence.or(num().map(|v| v as i16));
+                }
+                ["Speed", "TPX", ..] => p.speed = num(),
+                ["Watts", "TPX", ..] => p.power = num().map(|v| v as i16),
+                _ => {}
+            }
+            return;
+        }
+
+        match tail.as_slice() {
+            ["Id", "Activity", ..] => self.id = Some(text.to_string()),
+            ["Notes", "Activity", ..] => self.notes = Some(text.to_string()),
+            [field, "Lap", ..] => {
+                if let Some(lap) = self.laps.last_mut() {
+                    match *field {
+                        "TotalTimeSeconds" => lap.total_time_s = num().unwrap_or(0.0),
+                        "DistanceMeters" => lap.distance_m = num().unwrap_or(0.0),
+                        "Calories" => lap.calories = num().unwrap_or(0.0),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn into_normalized(
+        self,
+        data: &[u8],
+        index: usize,
+        file_name: &str,
+    ) -> Result<NormalizedActivity, String> {
+        let fixes: Vec<(f64, f64, DateTime<Utc>, &TcxPoint)> = self
+            .points
+            .iter()
+            .filter_map(|p| Some((p.latitude?, p.longitude?, p.time?, p)))
+            .collect();
+        let computed = pairwise_speeds(
+            &fixes
+                .iter()
+                .map(|(lat, lon, t, _)| (*lat, *lon, *t))
+                .collect::<Vec<_>>(),
+        );
+        let track_points: Vec<NormalizedTrackPoint> = fixes
+            .iter()
+            .zip(computed)
+            .map(|((lat, lon, time, p), computed_speed)| NormalizedTrackPoint {
+                latitude: *lat,
+                longitude: *lon,
+                elevation: p.altitude.unwrap_or(0.0) as f32,
+                time: *time,
+                speed: p.speed.or(computed_speed),
+                heart_rate: p.heart_rate,
+                cadence: p.cadence,
+                power: p.power,
+                temperature: None,
+            })
+            .collect();
+
+        let start = self
+            .laps
+            .first()
+            .and_then(|l| l.start)
+            .or_else(|| self.id.as_deref().and_then(parse_xml_time))
+            .or_else(|| self.points.iter().find_map(|p| p.time))
+            .ok_or_else(|| "TCX activity has no start time".to_string())?;
+
+        let lap_time: f64 = self.laps.iter().map(|l| l.total_time_s).sum();
+        let elapsed_secs = if lap_time > 0.0 {
+            lap_time
+        } else {
+            self.points
+                .iter()
+                .filter_map(|p| p.time)
+                .next_back()
+                .map(|last| (last - start).num_milliseconds() as f64 / 1000.0)
+                .unwrap_or(0.0)
+        };
+
+        let lap_distance: f64 = self.laps.iter().map(|l| l.distance_m).sum();
+        let distance_m = if lap_distance > 0.0 {
+            lap_distance
+        } else {
+            self.points
+                .iter()
+                .filter_map(|p| p.distance_m)
+                .next_back()
+                .unwrap_or_else(|| {
+                    fixes
+                        .windows(2)
+                        .map(|w| haversine_distance_m(w[0].0, w[0].1, w[1].0, w[1].1))
+                        .sum()
+                })
+        };
+
+        let climb: f64 = self
+            .points
+            .iter()
+            .filter_map(|p| p.altitude)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|w| (w[1] - w[0]).max(0.0))
+            .sum();
+
+        let avg_speed_ms = if elapsed_secs > 0.0 {
+            distance_m / elapsed_secs
+        } else {
+            0.0
+        };
+        let activity_type = tcx_sport_to_activity_type(&self.sport);
+        let average_pace = if activity_type == "Running" {
+            Pace::from_speed_ms(avg_speed_ms)
+        } else {
+            Pace::default()
+        };
+
+        let external_id = match &self.id {
+            Some(id) => content_hash(id.as_bytes()),
+            None => content_hash(&[data, &index.to_le_bytes()].concat()),
+        };
+
+        Ok(NormalizedActivity {
+            source: "tcx".to_string(),
+            external_id: Some(external_id),
+            date: start,
+            utc_offset: None,
+            name: self
+                .notes
+                .filter(|n| !n.is_empty())
+                .unwrap_or_else(|| activity_type.to_string()),
+            activity_type: activity_type.to_string(),
+            distance: (distance_m / 1000.0) as f32,
+            duration: seconds_to_hms(elapsed_secs.round() as i64),
+            average_pace,
+            average_speed: (avg_speed_ms * 3.6) as f32,
+            calories: self.laps.iter().map(|l| l.calories).sum::<f64>() as f32,
+            climb: climb as f32,
+            gps_file: file_name.to_string(),
+            track_points,
+        })
+    }
+}
+
+/// Convert a FIT timestamp (seconds since 1989-12-31T00:00:00Z) to UTC.
+fn fit_time(value: f64) -> Option<DateTime<Utc>> {
+    DateTime::<Utc>::from_timestamp(value as i64 + fit::FIT_EPOCH_OFFSET, 0)
+}
+
+/// The device's UTC offset, in seconds, from the `activity` message's
+/// `local_timestamp` minus its `timestamp`.  `None` when either is missing or
+/// the difference is not a plausible offset.
+fn fit_utc_offset(messages: &[fit::FitMessage]) -> Option<i32> {
+    let activity = messages.iter().find(|m| m.global == fit::MESG_ACTIVITY)?;
+    let utc = activity.get(fit::FIELD_TIMESTAMP)?;
+    let local = activity.get(FIT_ACTIVITY_LOCAL_TIMESTAMP)?;
+    let offset = (local - utc) as i64;
+    (offset.abs() <= 14 * 3600).then_some(offset as i32)
+}
+
+/// Convert FIT semicircles to degrees.
+fn semicircles_to_degrees(value: f64) -> f64 {
+    value * (180.0 / 2_147_483_648.0)
+}
+
+/// Map the FIT `sport` enum onto the activity types used across the app.
+fn fit_sport_to_activity_type(sport: Option<f64>) -> &'static str {
+    match sport.map(|s| s as u8) {
+        Some(1) => "Running",
+        Some(2) => "Cycling",
+        Some(5) => "Swimming",
+        Some(11) | Some(17) => "Walking",
+        _ => "Other",
+    }
+}
+
+/// Build a `TrackPoint` from a FIT `record` message.  Records without a GPS
+/// fix (indoor runs, signal loss) are skipped.
+fn record_to_trackpoint(
+    record: &FitMessage,
+    time: DateTime<Utc>,
+    activity_id: Uuid,
+) -> Option<TrackPoint> {
+    let latitude = semicircles_to_degrees(record.get(FIT_RECORD_POSITION_LAT)?);
+    let longitude = semicircles_to_degrees(record.get(FIT_RECORD_POSITION_LONG)?);
+    let elevation = record
+        .get(FIT_RECORD_ENHANCED_ALTITUDE)
+        .or_else(|| record.get(FIT_RECORD_ALTITUDE))
+        .map(|a| a / 5.0 - 500.0)
+        .unwrap_or(0.0);
+    let speed = record
+        .get(FIT_RECORD_ENHANCED_SPEED)
+        .or_else(|| record.get(FIT_RECORD_SPEED))
+        .map(|v| v / 1000.0);
+
+    Some(TrackPoint {
+        id: Some(Uuid::new_v4()),
+        activity_id,
+        latitude,
+        longitude,
+        elevation: elevation as f32,
+        time,
+        speed,
+        heart_rate: record.get(FIT_RECORD_HEART_RATE).map(|v| v as i16),
+        cadence: record.get(FIT_RECORD_CADENCE).map(|v| v as i16),
+        power: record.get(FIT_RECORD_POWER).map(|v| v as i16),
+        temperature: record.get(FIT_RECORD_TEMPERATURE).map(|v| v as f32),
+    })
+}
+
 fn clean_gpx_data(data: &[u8]) -> Result<std::io::Cursor<String>, String> {
     let content = std::str::from_utf8(data).map_err(|e| e.to_string())?;
     let cleaned: String = content
@@ -128,7 +1054,11 @@ fn clean_gpx_data(data: &[u8]) -> Result<std::io::Cursor<String>, String> {
     Ok(std::io::Cursor::new(cleaned))
 }
 
-fn waypoint_to_trackpoint(waypoint: Waypoint, activity_id: Uuid) -> Result<TrackPoint, String> {
+fn waypoint_to_trackpoint(
+    waypoint: Waypoint,
+    sensors: SensorReadings,
+    activity_id: Uuid,
+) -> Result<TrackPoint, String> {
     // gpx crate exposes time as `time::OffsetDateTime`; convert to chrono.
     let time: DateTime<Utc> = waypoint
         .time
@@ -147,5 +1077,63 @@ fn waypoint_to_trackpoint(waypoint: Waypoint, activity_id: Uuid) -> Result<Track
         elevation: waypoint.elevation.unwrap_or(0.0) as f32,
         time,
         speed: None,
+        heart_rate: sensors.heart_rate,
+        cadence: sensors.cadence,
+        power: sensors.power,
+        temperature: sensors.temperature,
     })
 }
+
+/// Sensor channels recorded alongside one track point.
+#[derive(Debug, Default, Clone, Copy)]
+struct SensorReadings {
+    heart_rate: Option<i16>,
+    cadence: Option<i16>,
+    power: Option<i16>,
+    temperature: Option<f32>,
+}
+
+/// Sensor readings of every `<trkpt>` in a GPX file, in document order.
+///
+/// The `gpx` crate skips `<extensions>`, so they are read in a second pass.
+/// Elements are matched by local name, whatever their namespace prefix:
+/// `hr`, `cad`, `atemp` (Garmin `TrackPointExtension` v1/v2) and `power`
+/// (written by most cycling apps).  A file that cannot be read yields no
+/// readings; the track itself is still imported.
+fn gpx_sensor_readings(data: &[u8]) -> Vec<SensorReadings> {
+    let mut readings = Vec::new();
+    let mut in_extensions = false;
+    let mut element = String::new();
+
+    for event in EventReader::new(data) {
+        match event {
+            Ok(XmlEvent::StartElement { name, .. }) => match name.local_name.as_str() {
+                "trkpt" => readings.push(SensorReadings::default()),
+                "extensions" => in_extensions = true,
+                local => element = local.to_string(),
+            },
+            Ok(XmlEvent::EndElement { name }) => {
+                if name.local_name == "extensions" {
+                    in_extensions = false;
+                }
+                element.clear();
+            }
+            Ok(XmlEvent::Characters(text)) if in_extensions => {
+                let (Some(r), Ok(value)) = (readings.last_mut(), text.trim().parse::<f64>())
+                else {
+                    continue;
+                };
+                match element.as_str() {
+                    "hr" => r.heart_rate = Some(value as i16),
+                    "cad" => r.cadence = Some(value as i16),
+                    "power" | "PowerInWatts" => r.power = Some(value as i16),
+                    "atemp" => r.temperature = Some(value as f32),
+                    _ => {}
+                }
+            }
+            Ok(_) => {}
+            Err(_) => return Vec::new(),
+        }
+    }
+    readings
+}
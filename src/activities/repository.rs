@@ -1,20 +1,33 @@
 /// SQL layer for the activities domain.
 ///
 /// All queries live here — no SQL in services or handlers.
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use chrono::NaiveDate;
-use sqlx::{PgPool, QueryBuilder};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use sqlx::{Connection, PgConnection, PgPool, Postgres, QueryBuilder};
 use tracing::{error, info};
 use uuid::Uuid;
 
-use crate::sync::normalized::NormalizedTrackPoint;
-
 use crate::error::AppError;
+use crate::users::timezone;
+
+use super::heatmap::Cell;
+use super::models::{
+    Activity, ActivityCursor, ActivityFilter, ActivitySort, Lap, TrackMetrics, TrackPoint,
+};
+
+/// Rows per track point `INSERT`, keeping the 11 binds per row under
+/// Postgres' 65 535 bind-parameter limit.
+const TRACKPOINT_INSERT_CHUNK: usize = 5_000;
 
-use super::models::{Activity, HeatmapPoint, TrackPoint};
+/// Rows per heatmap cell `INSERT` (5 binds per row).
+const HEATMAP_CELL_INSERT_CHUNK: usize = 10_000;
 
-pub async fn find_all_by_user(db: &PgPool, user_id: Uuid) -> Result<Vec<Activity>, AppError> {
+pub async fn find_all_by_user<'c, E>(db: E, user_id: Uuid) -> Result<Vec<Activity>, AppError>
+where
+    E: sqlx::Executor<'c, Database = sqlx::Postgres>,
+{
     sqlx::query_as::<_, Activity>("SELECT * FROM activities WHERE user_id = $1 ORDER BY date DESC")
         .bind(user_id)
         .fetch_all(db)
@@ -22,6 +35,78 @@ pub async fn find_all_by_user(db: &PgPool, user_id: Uuid) -> Result<Vec<Activity
         .map_err(AppError::from)
 }
 
+/// One page of a user's activities matching `filter`, in `sort` order,
+/// starting after `cursor`.  `limit = None` returns every match.
+pub async fn find_filtered(
+    db: &PgPool,
+    user_id: Uuid,
+    filter: &ActivityFilter,
+    sort: ActivitySort,
+    cursor: Option<&ActivityCursor>,
+    limit: Option<i64>,
+) -> Result<Vec<Activity>, AppError> {
+    let mut builder = QueryBuilder::new("SELECT * FROM activities WHERE user_id = ");
+    builder.push_bind(user_id);
+
+    if let Some(activity_type) = &filter.activity_type {
+        builder.push(" AND activity_type = ").push_bind(activity_type.clone());
+    }
+    if let Some(from) = filter.date_from {
+        builder.push(" AND date >= ").push_bind(timezone::start_of_day(filter.tz, from));
+    }
+    if let Some(to) = filter.date_to {
+        // Inclusive end date: everything before the following local midnight.
+        let end = timezone::start_of_day(filter.tz, to.succ_opt().unwrap_or(to));
+        builder.push(" AND date < ").push_bind(end);
+    }
+    if let Some(min) = filter.min_distance {
+        builder.push(" AND distance >= ").push_bind(min);
+    }
+    if let Some(max) = filter.max_distance {
+        builder.push(" AND distance <= ").push_bind(max);
+    }
+    if let Some(source) = &filter.source {
+        builder.push(" AND source = ").push_bind(source.clone());
+    }
+    if let Some(text) = &filter.name_contains {
+        let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        builder.push(" AND name ILIKE ").push_bind(format!("%{escaped}%"));
+    }
+
+    // Keyset pagination: (key, id) strictly after the cursor in sort order.
+    let op = match sort {
+        ActivitySort::DateDesc | ActivitySort::DistanceDesc => " < ",
+        ActivitySort::DateAsc | ActivitySort::DistanceAsc => " > ",
+    };
+    match cursor {
+        Some(ActivityCursor::Date(date, id)) => {
+            builder.push(" AND (date, id)").push(op).push("(").push_bind(*date);
+            builder.push(", ").push_bind(*id).push(")");
+        }
+        Some(ActivityCursor::Distance(km, id)) => {
+            builder.push(" AND (distance, id)").push(op).push("(").push_bind(*km);
+            builder.push(", ").push_bind(*id).push(")");
+        }
+        None => {}
+    }
+
+    builder.push(match sort {
+        ActivitySort::DateDesc => " ORDER BY date DESC, id DESC",
+        ActivitySort::DateAsc => " ORDER BY date ASC, id ASC",
+        ActivitySort::DistanceDesc => " ORDER BY distance DESC, id DESC",
+        ActivitySort::DistanceAsc => " ORDER BY distance ASC, id ASC",
+    });
+    if let Some(limit) = limit {
+        builder.push(" LIMIT ").push_bind(limit);
+    }
+
+    builder
+        .build_query_as::<Activity>()
+        .fetch_all(db)
+        .await
+        .map_err(AppError::from)
+}
+
 pub async fn find_by_id(db: &PgPool, activity_id: Uuid) -> Result<Option<Activity>, AppError> {
     sqlx::query_as::<_, Activity>("SELECT * FROM activities WHERE id = $1")
         .bind(activity_id)
@@ -53,8 +138,8 @@ pub async fn find_activities_by_ids(
 pub async fn find_activities_by_user_from(
     db: &PgPool,
     user_id: Uuid,
-    from: chrono::DateTime<chrono::Utc>,
-    until: Option<chrono::DateTime<chrono::Utc>>,
+    from: DateTime<Utc>,
+    until: Option<DateTime<Utc>>,
 ) -> Result<Vec<Activity>, AppError> {
     sqlx::query_as::<_, Activity>(
         "SELECT * FROM activities
@@ -64,8 +149,8 @@ pub async fn find_activities_by_user_from(
          ORDER BY date ASC",
     )
     .bind(user_id)
-    .bind(from.naive_utc())
-    .bind(until.map(|u| u.naive_utc()))
+    .bind(from)
+    .bind(until)
     .fetch_all(db)
     .await
     .map_err(AppError::from)
@@ -73,7 +158,8 @@ pub async fn find_activities_by_user_from(
 
 pub async fn find_trackpoints(db: &PgPool, activity_id: Uuid) -> Result<Vec<TrackPoint>, AppError> {
     sqlx::query_as::<_, TrackPoint>(
-        "SELECT id, activity_id, lat AS latitude, lon AS longitude, elevation, time, speed \
+        "SELECT id, activity_id, lat AS latitude, lon AS longitude, elevation, time, speed, \
+                heart_rate, cadence, power, temperature \
          FROM trackpoints WHERE activity_id = $1 ORDER BY time ASC",
     )
     .bind(activity_id)
@@ -83,15 +169,16 @@ pub async fn find_trackpoints(db: &PgPool, activity_id: Uuid) -> Result<Vec<Trac
 }
 
 /// Bulk-insert activities, ignoring rows that violate the unique constraint
-/// `uq_activities_user_date` (same user + same date).
-pub async fn insert_activities(db: &PgPool, activities: &[Activity]) {
+/// `uq_activities_user_date` (same user + same date).  Returns the IDs of the
+/// rows actually inserted; a database error fails the whole batch.
+pub async fn insert_activities(db: &PgPool, activities: &[Activity]) -> Result<Vec<Uuid>, AppError> {
     if activities.is_empty() {
-        return;
+        return Ok(vec![]);
     }
 
     let mut builder = QueryBuilder::new(
         "INSERT INTO activities \
-         (id, user_id, date, name, activity_type, distance, duration, \
+         (id, user_id, date, utc_offset, name, activity_type, distance, duration, \
           average_pace, average_speed, calories, climb, gps_file, source, external_id) ",
     );
 
@@ -99,6 +186,7 @@ pub async fn insert_activities(db: &PgPool, activities: &[Activity]) {
         b.push_bind(a.id)
             .push_bind(a.user_id)
             .push_bind(a.date)
+            .push_bind(a.utc_offset)
             .push_bind(&a.name)
             .push_bind(&a.activity_type)
             .push_bind(a.distance)
@@ -113,15 +201,11 @@ pub async fn insert_activities(db: &PgPool, activities: &[Activity]) {
     });
     // Runkeeper rows: dedup by (user_id, date) — legacy behaviour preserved.
     // Strava rows: dedup by the partial unique index on (user_id, source, external_id).
-    builder.push(" ON CONFLICT (user_id, date) DO NOTHING");
-
-    match builder.build().execute(db).await {
-        Ok(r) => info!(
-            "Inserted {} activities (rest were duplicates)",
-            r.rows_affected()
-        ),
-        Err(e) => error!("Error inserting activities: {}", e),
-    }
+    builder.push(" ON CONFLICT (user_id, date) DO NOTHING RETURNING id");
+
+    let ids = builder.build_query_scalar::<Uuid>().fetch_all(db).await?;
+    info!("Inserted {} activities (rest were duplicates)", ids.len());
+    Ok(ids)
 }
 
 /// Insert a batch of activities coming from a remote source (e.g. Strava).
@@ -130,28 +214,29 @@ pub async fn insert_activities(db: &PgPool, activities: &[Activity]) {
 /// unique index: `(user_id, source, external_id) WHERE external_id IS NOT NULL`.
 /// Re-syncing the same activities is therefore fully idempotent.
 ///
-/// Returns the IDs of activities that were actually inserted (not duplicates),
-/// so the caller can run the XP/achievement/PR pipelines only on new rows.
+/// Returns one entry per input activity, in order: the new row's ID, `None`
+/// if it was a duplicate, or the error it failed to insert with, so the
+/// caller can run the XP/achievement/PR pipelines only on new rows.
 pub async fn insert_activities_from_source(
     db: &PgPool,
     user_id: Uuid,
     activities: &[crate::sync::normalized::NormalizedActivity],
-) -> Vec<Uuid> {
+) -> Vec<Result<Option<Uuid>, AppError>> {
     if activities.is_empty() {
         return vec![];
     }
 
-    let mut inserted_ids = Vec::new();
+    let mut inserted_ids = Vec::with_capacity(activities.len());
 
     for a in activities {
         let new_id = Uuid::new_v4();
         let result = sqlx::query_scalar::<_, Uuid>(
             r#"
             INSERT INTO activities
-                (id, user_id, date, name, activity_type, distance, duration,
+                (id, user_id, date, utc_offset, name, activity_type, distance, duration,
                  average_pace, average_speed, calories, climb, gps_file,
                  source, external_id)
-            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14)
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15)
             ON CONFLICT DO NOTHING
             RETURNING id
             "#,
@@ -159,6 +244,7 @@ pub async fn insert_activities_from_source(
         .bind(new_id)
         .bind(user_id)
         .bind(a.date)
+        .bind(a.utc_offset)
         .bind(&a.name)
         .bind(&a.activity_type)
         .bind(a.distance)
@@ -173,21 +259,215 @@ pub async fn insert_activities_from_source(
         .fetch_optional(db)
         .await;
 
-        match result {
-            Ok(Some(id)) => inserted_ids.push(id),
-            Ok(None) => {} // duplicate — skip silently
-            Err(e) => error!("Error inserting activity (source={}, ext_id={:?}): {}", a.source, a.external_id, e),
+        if let Err(e) = &result {
+            error!("Error inserting activity (source={}, ext_id={:?}): {}", a.source, a.external_id, e);
         }
+        inserted_ids.push(result.map_err(AppError::from)); // `Ok(None)` — duplicate
     }
 
     info!(
         "Inserted {}/{} activities from source",
-        inserted_ids.len(),
+        inserted_ids.iter().filter(|id| matches!(id, Ok(Some(_)))).count(),
         activities.len()
     );
     inserted_ids
 }
 
+/// Return which of the given start dates and `(source, external_id)` pairs
+/// already exist for the user — the two keys the inserts deduplicate on.
+pub async fn find_existing_dedup_keys(
+    db: &PgPool,
+    user_id: Uuid,
+    dates: &[DateTime<Utc>],
+    external_ids: &[String],
+) -> Result<(HashSet<DateTime<Utc>>, HashSet<(String, String)>), AppError> {
+    let rows = sqlx::query_as::<_, (DateTime<Utc>, String, Option<String>)>(
+        "SELECT date, source, external_id FROM activities
+         WHERE user_id = $1 AND (date = ANY($2) OR external_id = ANY($3))",
+    )
+    .bind(user_id)
+    .bind(dates)
+    .bind(external_ids)
+    .fetch_all(db)
+    .await?;
+
+    let mut existing_dates = HashSet::new();
+    let mut existing_external = HashSet::new();
+    for (date, source, external_id) in rows {
+        existing_dates.insert(date);
+        if let Some(external_id) = external_id {
+            existing_external.insert((source, external_id));
+        }
+    }
+    Ok((existing_dates, existing_external))
+}
+
+/// Insert one activity.  Returns `None` if the user already has an activity
+/// starting at the same time.
+pub async fn insert_activity(db: &PgPool, a: &Activity) -> Result<Option<Uuid>, AppError> {
+    sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO activities
+            (id, user_id, date, utc_offset, name, activity_type, distance, duration,
+             average_pace, average_speed, calories, climb, gps_file,
+             source, external_id)
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15)
+        ON CONFLICT DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(a.id)
+    .bind(a.user_id)
+    .bind(a.date)
+    .bind(a.utc_offset)
+    .bind(&a.name)
+    .bind(&a.activity_type)
+    .bind(a.distance)
+    .bind(&a.duration)
+    .bind(a.average_pace)
+    .bind(a.average_speed)
+    .bind(a.calories)
+    .bind(a.climb)
+    .bind(&a.gps_file)
+    .bind(&a.source)
+    .bind(&a.external_id)
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Overwrite the editable summary fields of an activity.
+///
+/// Moving it onto the start time of another of the user's activities
+/// violates `uq_activities_user_date` and is reported as a bad request.
+pub async fn update_activity<'c, E>(db: E, a: &Activity) -> Result<(), AppError>
+where
+    E: sqlx::Executor<'c, Database = sqlx::Postgres>,
+{
+    sqlx::query(
+        r#"
+        UPDATE activities
+        SET date = $3, utc_offset = $4, name = $5, activity_type = $6, distance = $7,
+            duration = $8, average_pace = $9, average_speed = $10, calories = $11, climb = $12
+        WHERE id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(a.id)
+    .bind(a.user_id)
+    .bind(a.date)
+    .bind(a.utc_offset)
+    .bind(&a.name)
+    .bind(&a.activity_type)
+    .bind(a.distance)
+    .bind(&a.duration)
+    .bind(a.average_pace)
+    .bind(a.average_speed)
+    .bind(a.calories)
+    .bind(a.climb)
+    .execute(db)
+    .await
+    .map_err(|e| match e.as_database_error() {
+        Some(d) if d.is_unique_violation() => {
+            AppError::BadRequest(format!("Another activity already starts at {}", a.date))
+        }
+        _ => AppError::from(e),
+    })?;
+    Ok(())
+}
+
+/// Overwrite an activity with the record of a higher-priority source that
+/// describes the same run.  The ID — and everything linked to it — stays.
+pub async fn replace_with_source_record(
+    db: &PgPool,
+    activity_id: Uuid,
+    a: &crate::sync::normalized::NormalizedActivity,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE activities
+        SET date = $2, utc_offset = $3, name = $4, activity_type = $5, distance = $6,
+            duration = $7, average_pace = $8, average_speed = $9, calories = $10, climb = $11,
+            gps_file = $12, source = $13, external_id = $14
+        WHERE id = $1
+        "#,
+    )
+    .bind(activity_id)
+    .bind(a.date)
+    .bind(a.utc_offset)
+    .bind(&a.name)
+    .bind(&a.activity_type)
+    .bind(a.distance)
+    .bind(&a.duration)
+    .bind(a.average_pace)
+    .bind(a.average_speed)
+    .bind(a.calories)
+    .bind(a.climb)
+    .bind(&a.gps_file)
+    .bind(&a.source)
+    .bind(&a.external_id)
+    .execute(db)
+    .await
+    .map_err(AppError::from)?;
+    Ok(())
+}
+
+/// Store the metrics derived from an activity's cleaned track.
+pub async fn update_track_metrics(
+    db: &PgPool,
+    activity_id: Uuid,
+    m: &TrackMetrics,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE activities \
+         SET track_distance = $2, moving_time = $3, elapsed_time = $4, moving_pace = $5, \
+             elevation_gain = $6, elevation_loss = $7 \
+         WHERE id = $1",
+    )
+    .bind(activity_id)
+    .bind(m.track_distance)
+    .bind(m.moving_time)
+    .bind(m.elapsed_time)
+    .bind(m.moving_pace)
+    .bind(m.elevation_gain)
+    .bind(m.elevation_loss)
+    .execute(db)
+    .await
+    .map_err(AppError::from)?;
+    Ok(())
+}
+
+/// Remove all track points of an activity, cleaned and raw, the heatmap
+/// cells counted from them and its place on a route.
+pub async fn delete_trackpoints(db: &PgPool, activity_id: Uuid) -> Result<(), AppError> {
+    for table in ["trackpoints", "raw_trackpoints", "heatmap_cells", "route_activities"] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE activity_id = $1"))
+            .bind(activity_id)
+            .execute(db)
+            .await
+            .map_err(AppError::from)?;
+    }
+    Ok(())
+}
+
+/// Delete one activity; its track points cascade.  Returns `true` if a row
+/// was deleted.
+pub async fn delete_activity<'c, E>(
+    db: E,
+    user_id: Uuid,
+    activity_id: Uuid,
+) -> Result<bool, AppError>
+where
+    E: sqlx::Executor<'c, Database = sqlx::Postgres>,
+{
+    let result = sqlx::query("DELETE FROM activities WHERE id = $1 AND user_id = $2")
+        .bind(activity_id)
+        .bind(user_id)
+        .execute(db)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected() > 0)
+}
+
 /// Delete an activity by its source + external_id pair.
 ///
 /// Used when a Strava webhook delivers an `aspect_type = "delete"` event.
@@ -210,24 +490,6 @@ pub async fn delete_by_external_id(
     Ok(result.rows_affected() > 0)
 }
 
-/// Look up the internal UUID of an activity by its source + external_id.
-pub async fn find_id_by_external(
-    db: &PgPool,
-    user_id: Uuid,
-    source: &str,
-    external_id: &str,
-) -> Result<Option<Uuid>, AppError> {
-    sqlx::query_scalar::<_, Uuid>(
-        "SELECT id FROM activities WHERE user_id = $1 AND source = $2 AND external_id = $3",
-    )
-    .bind(user_id)
-    .bind(source)
-    .bind(external_id)
-    .fetch_optional(db)
-    .await
-    .map_err(AppError::from)
-}
-
 /// Bulk-insert track points for multiple activities.
 ///
 /// **D5 fix**: the existing-ID check is scoped to the relevant `activity_id`s
@@ -276,30 +538,38 @@ pub async fn insert_trackpoints(db: &PgPool, trackpoints_map: &HashMap<Uuid, Vec
             continue;
         }
 
-        let mut builder = QueryBuilder::new(
-            "INSERT INTO trackpoints (id, activity_id, lat, lon, elevation, time, speed) ",
-        );
-
-        builder.push_values(&new_tps, |mut b, tp| {
-            b.push_bind(tp.id.unwrap_or_else(uuid::Uuid::new_v4))
-                .push_bind(tp.activity_id)
-                .push_bind(tp.latitude)
-                .push_bind(tp.longitude)
-                .push_bind(tp.elevation)
-                .push_bind(tp.time)
-                .push_bind(tp.speed);
-        });
-        builder.push(" ON CONFLICT (activity_id, time) DO NOTHING");
-
-        match builder.build().execute(&mut *tx).await {
-            Ok(r) => total_inserted += r.rows_affected(),
-            Err(e) => {
-                error!(
-                    "Error inserting trackpoints for activity {}: {}",
-                    activity_id, e
-                );
-                let _ = tx.rollback().await;
-                return;
+        for chunk in new_tps.chunks(TRACKPOINT_INSERT_CHUNK) {
+            let mut builder = QueryBuilder::new(
+                "INSERT INTO trackpoints \
+                 (id, activity_id, lat, lon, elevation, time, speed, \
+                  heart_rate, cadence, power, temperature) ",
+            );
+
+            builder.push_values(chunk, |mut b, tp| {
+                b.push_bind(tp.id.unwrap_or_else(uuid::Uuid::new_v4))
+                    .push_bind(tp.activity_id)
+                    .push_bind(tp.latitude)
+                    .push_bind(tp.longitude)
+                    .push_bind(tp.elevation)
+                    .push_bind(tp.time)
+                    .push_bind(tp.speed)
+                    .push_bind(tp.heart_rate)
+                    .push_bind(tp.cadence)
+                    .push_bind(tp.power)
+                    .push_bind(tp.temperature);
+            });
+            builder.push(" ON CONFLICT (activity_id, time) DO NOTHING");
+
+            match builder.build().execute(&mut *tx).await {
+                Ok(r) => total_inserted += r.rows_affected(),
+                Err(e) => {
+                    error!(
+                        "Error inserting trackpoints for activity {}: {}",
+                        activity_id, e
+                    );
+                    let _ = tx.rollback().await;
+                    return;
+                }
             }
         }
     }
@@ -310,67 +580,196 @@ pub async fn insert_trackpoints(db: &PgPool, trackpoints_map: &HashMap<Uuid, Vec
     }
 }
 
-/// Return aggregated heatmap grid points for a user.
+/// Heatmap cells `(x, y, weight)` of a user's activities at one grid
+/// `level`, optionally limited to the inclusive cell ranges
+/// `(x_min, x_max, y_min, y_max)`.
 ///
-/// Coordinates are rounded to 4 decimal places (~11 m grid cells).
-/// All filter parameters are optional; `None` means "no filter".
-pub async fn find_heatmap_points(
+/// All filter parameters are optional; `None` means "no filter".  Dates are
+/// compared as calendar days in `tz`.
+#[allow(clippy::too_many_arguments)]
+pub async fn find_heatmap_cells(
     db: &PgPool,
     user_id: Uuid,
     activity_type: Option<String>,
     date_from: Option<NaiveDate>,
     date_to: Option<NaiveDate>,
-) -> Result<Vec<HeatmapPoint>, AppError> {
-    sqlx::query_as::<_, HeatmapPoint>(
-        "SELECT ROUND(t.lat::numeric, 4)::float8 AS lat, \
-                ROUND(t.lon::numeric, 4)::float8 AS lon, \
-                COUNT(*) AS weight \
-         FROM   trackpoints t \
-         JOIN   activities a ON a.id = t.activity_id \
+    tz: Tz,
+    level: i16,
+    range: Option<(i32, i32, i32, i32)>,
+) -> Result<Vec<(i32, i32, i64)>, AppError> {
+    let (x_min, x_max, y_min, y_max) = match range {
+        Some((x0, x1, y0, y1)) => (Some(x0), Some(x1), Some(y0), Some(y1)),
+        None => (None, None, None, None),
+    };
+    sqlx::query_as::<_, (i32, i32, i64)>(
+        "SELECT c.x, c.y, SUM(c.weight)::int8 \
+         FROM   heatmap_cells c \
+         JOIN   activities a ON a.id = c.activity_id \
          WHERE  a.user_id = $1 \
            AND  ($2::text IS NULL OR a.activity_type = $2) \
-           AND  ($3::date IS NULL OR a.date::date >= $3) \
-           AND  ($4::date IS NULL OR a.date::date <= $4) \
-         GROUP  BY ROUND(t.lat::numeric, 4), ROUND(t.lon::numeric, 4)",
+           AND  ($3::date IS NULL OR (a.date AT TIME ZONE $5)::date >= $3) \
+           AND  ($4::date IS NULL OR (a.date AT TIME ZONE $5)::date <= $4) \
+           AND  c.level = $6 \
+           AND  ($7::int IS NULL OR c.x BETWEEN $7 AND $8) \
+           AND  ($9::int IS NULL OR c.y BETWEEN $9 AND $10) \
+         GROUP  BY c.x, c.y",
     )
     .bind(user_id)
     .bind(activity_type)
     .bind(date_from)
     .bind(date_to)
+    .bind(tz.name())
+    .bind(level)
+    .bind(x_min)
+    .bind(x_max)
+    .bind(y_min)
+    .bind(y_max)
     .fetch_all(db)
     .await
     .map_err(AppError::from)
 }
 
-/// Bulk-insert `NormalizedTrackPoint` values for a single activity.
-///
-/// Uses `ON CONFLICT (activity_id, time) DO NOTHING` so re-syncing is safe.
-pub async fn insert_normalized_trackpoints(
+/// Replace the heatmap cells of one activity.
+pub async fn replace_heatmap_cells(
     db: &PgPool,
     activity_id: Uuid,
-    points: &[NormalizedTrackPoint],
-) {
-    if points.is_empty() {
-        return;
+    cells: &[Cell],
+) -> Result<(), AppError> {
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM heatmap_cells WHERE activity_id = $1")
+        .bind(activity_id)
+        .execute(&mut *tx)
+        .await?;
+    for chunk in cells.chunks(HEATMAP_CELL_INSERT_CHUNK) {
+        let mut builder =
+            QueryBuilder::new("INSERT INTO heatmap_cells (activity_id, level, x, y, weight) ");
+        builder.push_values(chunk, |mut b, c| {
+            b.push_bind(activity_id)
+                .push_bind(c.level)
+                .push_bind(c.x)
+                .push_bind(c.y)
+                .push_bind(c.weight);
+        });
+        builder.build().execute(&mut *tx).await?;
     }
+    tx.commit().await?;
+    Ok(())
+}
 
-    let mut builder = QueryBuilder::new(
-        "INSERT INTO trackpoints (id, activity_id, lat, lon, elevation, time, speed) ",
-    );
+/// Track points as the source delivered them, in time order.
+pub async fn find_raw_trackpoints(
+    db: &PgPool,
+    activity_id: Uuid,
+) -> Result<Vec<TrackPoint>, AppError> {
+    sqlx::query_as::<_, TrackPoint>(
+        "SELECT id, activity_id, lat AS latitude, lon AS longitude, elevation, time, speed, \
+                heart_rate, cadence, power, temperature \
+         FROM raw_trackpoints WHERE activity_id = $1 ORDER BY time ASC",
+    )
+    .bind(activity_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
 
+/// `INSERT` of `points` into `table` (`trackpoints` or `raw_trackpoints`).
+fn trackpoint_insert<'a>(table: &str, points: &'a [TrackPoint]) -> QueryBuilder<'a, Postgres> {
+    let mut builder = QueryBuilder::new(format!(
+        "INSERT INTO {table} \
+         (id, activity_id, lat, lon, elevation, time, speed, \
+          heart_rate, cadence, power, temperature) "
+    ));
     builder.push_values(points, |mut b, tp| {
-        b.push_bind(Uuid::new_v4())
-            .push_bind(activity_id)
+        b.push_bind(tp.id.unwrap_or_else(Uuid::new_v4))
+            .push_bind(tp.activity_id)
             .push_bind(tp.latitude)
             .push_bind(tp.longitude)
             .push_bind(tp.elevation)
             .push_bind(tp.time)
-            .push_bind(tp.speed);
+            .push_bind(tp.speed)
+            .push_bind(tp.heart_rate)
+            .push_bind(tp.cadence)
+            .push_bind(tp.power)
+            .push_bind(tp.temperature);
     });
-    builder.push(" ON CONFLICT (activity_id, time) DO NOTHING");
+    builder
+}
+
+/// Keep the uncleaned track points of one or more activities (each point
+/// carries its `activity_id`).
+pub async fn insert_raw_trackpoints(
+    conn: &mut PgConnection,
+    points: &[TrackPoint],
+) -> Result<(), AppError> {
+    for chunk in points.chunks(TRACKPOINT_INSERT_CHUNK) {
+        trackpoint_insert("raw_trackpoints", chunk)
+            .build()
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
 
-    match builder.build().execute(db).await {
-        Ok(r) => info!("Inserted {} trackpoints for activity {}", r.rows_affected(), activity_id),
-        Err(e) => error!("Error inserting trackpoints for activity {}: {}", activity_id, e),
+/// Swap an activity's stored track for `points` in one transaction, or in
+/// a savepoint of the caller's.
+pub async fn replace_trackpoints(
+    conn: &mut PgConnection,
+    activity_id: Uuid,
+    points: &[TrackPoint],
+) -> Result<(), AppError> {
+    let mut tx = conn.begin().await?;
+    sqlx::query("DELETE FROM trackpoints WHERE activity_id = $1")
+        .bind(activity_id)
+        .execute(&mut *tx)
+        .await?;
+    for chunk in points.chunks(TRACKPOINT_INSERT_CHUNK) {
+        let mut builder = trackpoint_insert("trackpoints", chunk);
+        builder.push(" ON CONFLICT (activity_id, time) DO NOTHING");
+        builder.build().execute(&mut *tx).await?;
     }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn replace_laps(db: &PgPool, activity_id: Uuid, laps: &[Lap]) -> Result<(), AppError> {
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM activity_laps WHERE activity_id = $1")
+        .bind(activity_id)
+        .execute(&mut *tx)
+        .await?;
+    for l in laps {
+        sqlx::query(
+            "INSERT INTO activity_laps \
+                (activity_id, kind, lap, start_offset_seconds, duration_seconds, distance_m, \
+                 pace, elevation_gain, elevation_loss) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .bind(activity_id)
+        .bind(l.kind)
+        .bind(l.lap)
+        .bind(l.start_offset_seconds)
+        .bind(l.duration_seconds)
+        .bind(l.distance_m)
+        .bind(l.pace)
+        .bind(l.elevation_gain)
+        .bind(l.elevation_loss)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Auto-laps first, then the intervals in track order.
+pub async fn find_laps(db: &PgPool, activity_id: Uuid) -> Result<Vec<Lap>, AppError> {
+    sqlx::query_as::<_, Lap>(
+        "SELECT kind, lap, start_offset_seconds, duration_seconds, distance_m, pace, \
+                elevation_gain, elevation_loss \
+         FROM activity_laps WHERE activity_id = $1 \
+         ORDER BY kind = 'auto' DESC, start_offset_seconds, kind",
+    )
+    .bind(activity_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
 }
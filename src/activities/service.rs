@@ -2,19 +2,31 @@
 ///
 /// Orchestrates between repository (SQL) and parser (file parsing).
 /// No SQL and no HTTP here.
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
     achievements,
     aggregate::aggregate_activities,
+    challenges::progression::ProgressionTrigger,
+    duplicates::{
+        self,
+        matcher::Fingerprint,
+        models::MergedRecord,
+        service::{DuplicateMatcher, Match},
+    },
     error::AppError,
+    explorer,
     monthly_missions,
-    personal_records,
-    sync::normalized::NormalizedActivity,
+    uploads::{progress::JobProgress, spool::SpooledUpload},
+    personal_records::{self, models::BestEffort},
+    routes, segments,
+    sync::{file_adapter, normalized::NormalizedActivity},
+    users::{self, timezone},
     weekly_missions,
     xp::{
         models::AwardXpInput,
@@ -23,18 +35,110 @@ use crate::{
 };
 
 use super::{
-    models::{ActivitiesResponse, Activity, ActivityDetailResponse, HeatmapPoint, TrackPoint, UploadResponse},
-    parser, repository,
+    models::{
+        ActivitiesResponse, Activity, ActivityChangeResponse, ActivityCursor,
+        ActivityDetailResponse, ActivityFilter, ActivityListQuery, CreateActivityRequest, GpxMatch, HeatmapPoint, IngestOutcome, IngestReason,
+        IngestReportEntry, PreviewActivity, Split, SplitUnit, Track, TrackFormat, TrackMetrics, TrackPoint, UpdateActivityRequest, UploadFiles,
+        UploadPreview, UploadResponse,
+    },
+    cleaning::{self, CleaningConfig},
+    geometry,
+    heatmap::{self, Bbox},
+    laps, metrics,
+    pace::Pace,
+    parser, repository, tiles,
 };
 
-pub async fn get_activities(db: &PgPool, user_id: Uuid) -> Result<ActivitiesResponse, AppError> {
-    let activities = repository::find_all_by_user(db, user_id).await?;
-    let (aggregation, time_aggregations) = aggregate_activities(&activities);
+const MAX_PAGE_SIZE: i64 = 1000;
+
+/// List a user's activities with filters, sorting and cursor pagination.
+///
+/// Aggregations cover every activity matching the filters, so paging through
+/// a list keeps the same totals; they cost a second query unless the whole
+/// list fits on one page.
+pub async fn get_activities(
+    db: &PgPool,
+    user_id: Uuid,
+    query: ActivityListQuery,
+) -> Result<ActivitiesResponse, AppError> {
+    if let (Some(from), Some(to)) = (query.date_from, query.date_to) {
+        if from > to {
+            return Err(AppError::BadRequest(
+                "date_from must not be later than date_to".into(),
+            ));
+        }
+    }
+    if let (Some(min), Some(max)) = (query.min_distance, query.max_distance) {
+        if min > max {
+            return Err(AppError::BadRequest(
+                "min_distance must not be greater than max_distance".into(),
+            ));
+        }
+    }
+    if query.limit.is_some_and(|l| !(1..=MAX_PAGE_SIZE).contains(&l)) {
+        return Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {MAX_PAGE_SIZE}"
+        )));
+    }
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(|c| ActivityCursor::decode(c, query.sort))
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+
+    let filter = ActivityFilter {
+        activity_type: query.activity_type,
+        date_from: query.date_from,
+        date_to: query.date_to,
+        min_distance: query.min_distance,
+        max_distance: query.max_distance,
+        source: query.source,
+        name_contains: query.q.filter(|q| !q.trim().is_empty()),
+        tz: users::service::timezone(db, user_id).await,
+    };
+
+    // Fetch one extra row to learn whether another page follows.
+    let mut activities = repository::find_filtered(
+        db,
+        user_id,
+        &filter,
+        query.sort,
+        cursor.as_ref(),
+        query.limit.map(|l| l + 1),
+    )
+    .await?;
+    let next_cursor = match query.limit {
+        Some(limit) if activities.len() as i64 > limit => {
+            activities.truncate(limit as usize);
+            activities
+                .last()
+                .map(|a| ActivityCursor::after(a, query.sort).encode())
+        }
+        _ => None,
+    };
+
+    // Paging clients fetch aggregations once, not on every page.
+    let paged = query.limit.is_some() || cursor.is_some();
+    let include_aggregations = query.include_aggregations.unwrap_or(!paged);
+
+    let (aggregation, time_aggregations) = if !include_aggregations {
+        (None, None)
+    } else if cursor.is_none() && next_cursor.is_none() {
+        let (a, t) = aggregate_activities(&activities, filter.tz);
+        (Some(a), Some(t))
+    } else {
+        let all =
+            repository::find_filtered(db, user_id, &filter, query.sort, None, None).await?;
+        let (a, t) = aggregate_activities(&all, filter.tz);
+        (Some(a), Some(t))
+    };
 
     Ok(ActivitiesResponse {
         activities,
-        aggregation: Some(aggregation),
-        time_aggregations: Some(time_aggregations),
+        aggregation,
+        time_aggregations,
+        next_cursor,
     })
 }
 
@@ -42,7 +146,10 @@ pub async fn get_activity_detail(
     db: &PgPool,
     activity_id: Uuid,
     user_id: Uuid,
+    format: TrackFormat,
+    tolerance: Option<f64>,
 ) -> Result<ActivityDetailResponse, AppError> {
+    check_tolerance(tolerance)?;
     let activity = repository::find_by_id(db, activity_id)
         .await?
         .ok_or(AppError::NotFound)?;
@@ -52,10 +159,15 @@ pub async fn get_activity_detail(
     }
 
     let track_points = repository::find_trackpoints(db, activity_id).await?;
+    let track_points = shape_track(&track_points, format, tolerance);
+    let best_efforts = personal_records::service::get_best_efforts(db, activity_id).await?;
+    let laps = repository::find_laps(db, activity_id).await?;
 
     Ok(ActivityDetailResponse {
         activity,
         track_points,
+        best_efforts,
+        laps,
     })
 }
 
@@ -75,128 +187,1134 @@ pub async fn get_trackpoints(
     repository::find_trackpoints(db, activity_id).await
 }
 
-/// Process an upload: parse CSV rows and GPX files, then persist.
-pub async fn upload(
+/// An activity's track, optionally simplified, in the requested format.
+pub async fn get_track(
     db: &PgPool,
+    activity_id: Uuid,
     user_id: Uuid,
-    csv_lines: Vec<String>,
-    gpx_files: HashMap<String, Vec<u8>>,
-) -> UploadResponse {
-    // Parse activities from CSV rows (synchronous — CPU only, no I/O).
-    let activities: Vec<Activity> = csv_lines
-        .iter()
-        .filter_map(|row| match parser::parse_csv_row(row, user_id) {
-            Ok(a) => Some(a),
+    format: TrackFormat,
+    tolerance: Option<f64>,
+) -> Result<Track, AppError> {
+    check_tolerance(tolerance)?;
+    let tps = get_trackpoints(db, activity_id, user_id).await?;
+    Ok(shape_track(&tps, format, tolerance))
+}
+
+fn check_tolerance(tolerance: Option<f64>) -> Result<(), AppError> {
+    match tolerance {
+        Some(t) if !t.is_finite() || t < 0.0 => Err(AppError::BadRequest(
+            "tolerance must be a non-negative number of metres".into(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn shape_track(points: &[TrackPoint], format: TrackFormat, tolerance: Option<f64>) -> Track {
+    let points = match tolerance {
+        Some(t) => geometry::simplify(points, t),
+        None => points.to_vec(),
+    };
+    match format {
+        TrackFormat::Points => Track::Points(points),
+        TrackFormat::Polyline => Track::Polyline {
+            polyline: geometry::encode_polyline(&points),
+        },
+        TrackFormat::Geojson => Track::GeoJson(geometry::geojson(&points)),
+    }
+}
+
+/// Kilometre or mile splits of an activity's track.
+pub async fn get_splits(
+    db: &PgPool,
+    activity_id: Uuid,
+    user_id: Uuid,
+    unit: SplitUnit,
+) -> Result<Vec<Split>, AppError> {
+    let tps = get_trackpoints(db, activity_id, user_id).await?;
+    Ok(metrics::splits(&tps, unit.metres(), &CleaningConfig::from_env()))
+}
+
+/// Re-run track cleaning from the raw points with the current thresholds,
+/// recompute everything derived from the track (metrics, best efforts,
+/// personal records, laps, heatmap cells, segment efforts, route, explorer
+/// tiles) and return the new track.
+///
+/// Activities imported before raw points were kept have only their stored
+/// track; it becomes the raw copy on the first run.
+pub async fn clean_trackpoints(
+    db: &PgPool,
+    user_id: Uuid,
+    activity_id: Uuid,
+) -> Result<Vec<TrackPoint>, AppError> {
+    let activity = find_owned(db, user_id, activity_id).await?;
+
+    let mut raw = repository::find_raw_trackpoints(db, activity_id).await?;
+    let first_run = raw.is_empty();
+    if first_run {
+        raw = repository::find_trackpoints(db, activity_id).await?;
+    }
+    let config = CleaningConfig::from_env();
+    let cleaned = cleaning::clean_track(&raw, &activity.activity_type, &config);
+    // The stored track is only overwritten once its raw copy is safe.
+    let mut tx = db.begin().await?;
+    if first_run {
+        repository::insert_raw_trackpoints(&mut tx, &raw).await?;
+    }
+    repository::replace_trackpoints(&mut tx, activity_id, &cleaned).await?;
+    tx.commit().await?;
+    let track_metrics = metrics::track_metrics(&cleaned, &activity.activity_type, &config);
+    repository::update_track_metrics(db, activity_id, &track_metrics).await?;
+    let efforts = best_efforts(&cleaned, &activity.activity_type, &config);
+    personal_records::service::save_best_efforts(db, activity_id, &efforts).await?;
+    // A record set by a GPS jump the cleaning removed goes back to the best
+    // other activity; this one then competes again on its new efforts.
+    let mut tx = db.begin().await?;
+    let description = format!("Personal records re-evaluated: {}", activity.name);
+    xp_service::revoke_xp_on(&mut tx, user_id, "pr", activity_id, description).await?;
+    personal_records::service::release_activity_prs(&mut tx, user_id, activity_id).await?;
+    tx.commit().await?;
+    personal_records::service::check_activity_for_prs(
+        db,
+        user_id,
+        activity_id,
+        activity.distance as f64 * 1000.0, // km → m
+        &activity.duration,
+        activity.date,
+    )
+    .await?;
+    repository::replace_laps(db, activity_id, &laps::detect_laps(&cleaned, &config)).await?;
+    repository::replace_heatmap_cells(db, activity_id, &heatmap::cells(&cleaned)).await?;
+    segments::service::match_activity(db, activity_id, &activity.activity_type, &cleaned).await?;
+    routes::service::assign_activity(db, activity_id, &cleaned).await?;
+    explorer::service::rebuild(db, user_id).await?;
+
+    repository::find_trackpoints(db, activity_id).await
+}
+
+/// Keep the uncleaned points of newly stored tracks.  Failures are logged:
+/// the cleaned track is stored regardless, it just cannot be re-cleaned from
+/// the source's points later.
+async fn keep_raw_trackpoints(db: &PgPool, points: &[TrackPoint]) {
+    let result = match db.acquire().await {
+        Ok(mut conn) => repository::insert_raw_trackpoints(&mut conn, points).await,
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = result {
+        tracing::error!("Error inserting raw trackpoints: {e}");
+    }
+}
+
+/// Check the hand-editable summary fields and recompute pace and speed.
+fn apply_summary(activity: &mut Activity) -> Result<(), AppError> {
+    if activity.activity_type.trim().is_empty() {
+        return Err(AppError::BadRequest("activity_type must not be empty".into()));
+    }
+    if !activity.distance.is_finite() || activity.distance < 0.0 {
+        return Err(AppError::BadRequest("distance must be a non-negative number of km".into()));
+    }
+    let duration_secs = personal_records::models::parse_duration_to_secs(&activity.duration);
+    if duration_secs <= 0 {
+        return Err(AppError::BadRequest("duration must be H:MM:SS or MM:SS".into()));
+    }
+    activity.average_pace = Pace::from_duration(duration_secs as f64, activity.distance as f64);
+    activity.average_speed = if activity.average_pace.is_set() {
+        activity.distance / (duration_secs as f32 / 3600.0)
+    } else {
+        0.0
+    };
+    Ok(())
+}
+
+/// Load an activity, treating another user's activity as missing.
+async fn find_owned(db: &PgPool, user_id: Uuid, activity_id: Uuid) -> Result<Activity, AppError> {
+    repository::find_by_id(db, activity_id)
+        .await?
+        .filter(|a| a.user_id == user_id)
+        .ok_or(AppError::NotFound)
+}
+
+/// What reversing an activity's derived effects took back.
+struct Reversal {
+    xp_revoked: i64,
+    released_prs: Vec<String>,
+    revoked_achievements: Vec<String>,
+}
+
+/// Undo what the pipeline credited to one activity: its distance and PR XP,
+/// the PRs it holds and the achievements it unlocked.
+///
+/// Mission, goal and challenge progress are recomputed from the activities
+/// table by the pipeline, so they need no per-activity bookkeeping here.
+///
+/// Runs inside the caller's transaction so the reversal commits together with
+/// the edit or delete that required it.
+async fn reverse_activity_effects(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    activity: &Activity,
+) -> Result<Reversal, AppError> {
+    let description = format!("Activity changed: {}", activity.name);
+    let mut xp_revoked =
+        xp_service::revoke_xp_on(tx, user_id, "activity", activity.id, description).await?;
+    let description = format!("Personal records re-evaluated: {}", activity.name);
+    xp_revoked += xp_service::revoke_xp_on(tx, user_id, "pr", activity.id, description).await?;
+
+    let released_prs =
+        personal_records::service::release_activity_prs(tx, user_id, activity.id).await?;
+    let (revoked_achievements, achievement_xp) =
+        achievements::service::revoke_for_activity(tx, user_id, activity.id).await?;
+
+    Ok(Reversal {
+        xp_revoked: xp_revoked + achievement_xp,
+        released_prs,
+        revoked_achievements,
+    })
+}
+
+/// Unlock again the revoked achievements that the user's remaining history
+/// still earns.  Failures are logged: the change itself has been committed.
+async fn restore_achievements(
+    db: &PgPool,
+    user_id: Uuid,
+    revoked: &[String],
+) -> Vec<achievements::models::UnlockedAchievementSummary> {
+    if revoked.is_empty() {
+        return vec![];
+    }
+    let tz = users::service::timezone(db, user_id).await;
+    match repository::find_all_by_user(db, user_id).await {
+        Ok(mut remaining) => {
+            remaining.reverse(); // oldest first
+            achievements::service::check_and_unlock_achievements(db, user_id, &remaining, tz)
+                .await
+        }
+        Err(e) => {
+            tracing::warn!("Could not re-check achievements of {user_id}: {e}");
+            vec![]
+        }
+    }
+}
+
+/// Log an activity by hand (`source = "manual"`) and run the usual pipeline.
+pub async fn create_activity(
+    db: &PgPool,
+    user_id: Uuid,
+    req: CreateActivityRequest,
+) -> Result<ActivityChangeResponse, AppError> {
+    let user = users::service::get_user(db, user_id).await?;
+    let tz = timezone::parse(&user.timezone).unwrap_or_default();
+    let (date, utc_offset) = req.date.resolve(tz);
+
+    let mut activity = Activity {
+        id: Uuid::new_v4(),
+        user_id,
+        date,
+        utc_offset: Some(utc_offset),
+        name: req
+            .name
+            .filter(|n| !n.trim().is_empty())
+            .unwrap_or_else(|| req.activity_type.clone()),
+        activity_type: req.activity_type,
+        distance: req.distance,
+        duration: req.duration,
+        average_pace: Pace::default(),
+        average_speed: 0.0,
+        calories: req.calories,
+        climb: req.climb,
+        gps_file: String::new(),
+        source: "manual".to_string(),
+        external_id: None,
+        track_metrics: TrackMetrics::default(),
+    };
+    apply_summary(&mut activity)?;
+
+    repository::insert_activity(db, &activity)
+        .await?
+        .ok_or_else(|| {
+            AppError::BadRequest(format!("Another activity already starts at {}", activity.date))
+        })?;
+
+    let effects =
+        run_post_ingest_pipeline(db, user_id, &[activity.id], std::slice::from_ref(&activity), false)
+            .await;
+
+    Ok(ActivityChangeResponse {
+        activity: Some(activity),
+        xp_revoked: 0,
+        released_prs: vec![],
+        revoked_achievements: vec![],
+        effects,
+    })
+}
+
+/// Edit an activity's summary, then reverse and re-run its derived effects.
+pub async fn update_activity(
+    db: &PgPool,
+    user_id: Uuid,
+    activity_id: Uuid,
+    req: UpdateActivityRequest,
+) -> Result<ActivityChangeResponse, AppError> {
+    let mut activity = find_owned(db, user_id, activity_id).await?;
+
+    if let Some(start) = req.date {
+        let tz = users::service::timezone(db, user_id).await;
+        let (date, utc_offset) = start.resolve(tz);
+        activity.date = date;
+        activity.utc_offset = Some(utc_offset);
+    }
+    if let Some(name) = req.name {
+        activity.name = name;
+    }
+    if let Some(activity_type) = req.activity_type {
+        activity.activity_type = activity_type;
+    }
+    if let Some(distance) = req.distance {
+        activity.distance = distance;
+    }
+    if let Some(duration) = req.duration {
+        activity.duration = duration;
+    }
+    if let Some(calories) = req.calories {
+        activity.calories = calories;
+    }
+    if let Some(climb) = req.climb {
+        activity.climb = climb;
+    }
+    apply_summary(&mut activity)?;
+
+    // One transaction: the edit is saved only together with the reversal
+    // of what the old values earned.
+    let mut tx = db.begin().await?;
+    repository::update_activity(&mut *tx, &activity).await?;
+    let reversal = reverse_activity_effects(&mut tx, user_id, &activity).await?;
+    tx.commit().await?;
+
+    let mut effects =
+        run_post_ingest_pipeline(db, user_id, &[activity.id], std::slice::from_ref(&activity), true)
+            .await;
+    effects
+        .newly_unlocked_achievements
+        .extend(restore_achievements(db, user_id, &reversal.revoked_achievements).await);
+
+    Ok(ActivityChangeResponse {
+        activity: Some(activity),
+        xp_revoked: reversal.xp_revoked,
+        released_prs: reversal.released_prs,
+        revoked_achievements: reversal.revoked_achievements,
+        effects,
+    })
+}
+
+/// Delete an activity after reversing its derived effects, then re-run the
+/// period-based pipelines (missions, goals, challenges) without it and
+/// restore the achievements the remaining activities still earn.
+pub async fn delete_activity(
+    db: &PgPool,
+    user_id: Uuid,
+    activity_id: Uuid,
+) -> Result<ActivityChangeResponse, AppError> {
+    let activity = find_owned(db, user_id, activity_id).await?;
+    delete_in_transaction(db, db.begin().await?, user_id, &activity).await
+}
+
+/// `delete_activity` for an activity already checked to belong to the user,
+/// finishing the caller's transaction `tx`: the caller's own writes commit
+/// together with the reversal and the delete, or not at all.
+pub async fn delete_in_transaction(
+    db: &PgPool,
+    mut tx: sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    activity: &Activity,
+) -> Result<ActivityChangeResponse, AppError> {
+    // Reverse while the row exists: deleting it nulls the PR and
+    // achievement references we need to find.  One transaction, so a failed
+    // delete does not leave the activity without its XP and records.
+    let reversal = reverse_activity_effects(&mut tx, user_id, activity).await?;
+    repository::delete_activity(&mut *tx, user_id, activity.id).await?;
+    tx.commit().await?;
+
+    if let Err(e) = explorer::service::rebuild(db, user_id).await {
+        tracing::warn!("Could not rebuild explorer tiles of {user_id}: {e}");
+    }
+
+    let mut effects = run_post_ingest_pipeline(db, user_id, &[], &[], true).await;
+    effects
+        .newly_unlocked_achievements
+        .extend(restore_achievements(db, user_id, &reversal.revoked_achievements).await);
+
+    Ok(ActivityChangeResponse {
+        activity: None,
+        xp_revoked: reversal.xp_revoked,
+        released_prs: reversal.released_prs,
+        revoked_achievements: reversal.revoked_achievements,
+        effects,
+    })
+}
+
+const CSV_FILE: &str = "cardioActivities.csv";
+
+/// An upload parsed into the two ingest paths, with nothing persisted yet.
+struct ParsedUpload {
+    /// Parsed `cardioActivities.csv` rows with their line numbers.
+    csv_rows: Vec<(u32, Activity)>,
+    /// GPX files referenced by a CSV row, keyed by filename.
+    referenced_gpx: HashMap<String, Vec<u8>>,
+    /// Standalone GPX, FIT and TCX activities.
+    file_activities: Vec<NormalizedActivity>,
+    /// CSV rows and files that could not be parsed.
+    skipped: Vec<IngestReportEntry>,
+    /// CSV rows plus uploaded files, for job progress.
+    total_items: usize,
+}
+
+/// Parse every row and file of an upload (synchronous — CPU only, no I/O).
+///
+/// GPX files named by a CSV row's `gps_file` belong to that row; any other
+/// GPX file is parsed as a standalone activity.  CSV times are read in `tz`.
+fn parse_upload(files: UploadFiles, user_id: Uuid, tz: Tz) -> ParsedUpload {
+    let mut parsed = ParsedUpload {
+        csv_rows: Vec::new(),
+        referenced_gpx: HashMap::new(),
+        file_activities: Vec::new(),
+        skipped: Vec::new(),
+        total_items: files.gpx_files.len() + files.fit_files.len() + files.tcx_files.len(),
+    };
+
+    for row in parser::parse_csv(&files.csv_text, user_id, tz) {
+        parsed.total_items += 1;
+        match row {
+            Ok((line, activity)) => parsed.csv_rows.push((line as u32, activity)),
             Err(e) => {
                 tracing::warn!("Skipping CSV row: {}", e);
-                None
+                parsed.skipped.push(IngestReportEntry {
+                    line: Some(e.line as u32),
+                    ..IngestReportEntry::skipped(CSV_FILE, IngestReason::InvalidRow, e.to_string())
+                });
             }
+        }
+    }
+
+    let referenced: HashSet<&str> =
+        parsed.csv_rows.iter().map(|(_, a)| a.gps_file.as_str()).collect();
+    let mut file_errors = Vec::new();
+    for (name, data) in files.gpx_files {
+        if referenced.contains(name.as_str()) {
+            parsed.referenced_gpx.insert(name, data);
+            continue;
+        }
+        match parser::parse_gpx_activity(&data, &name, user_id) {
+            Ok((activity, tps)) => parsed.file_activities.push(file_adapter::from_parsed(activity, tps)),
+            Err(e) => file_errors.push((name, e)),
+        }
+    }
+    for (name, data) in files.fit_files {
+        match parser::parse_fit(&data, &name, user_id) {
+            Ok((activity, tps)) => parsed.file_activities.push(file_adapter::from_parsed(activity, tps)),
+            Err(e) => file_errors.push((name, e)),
+        }
+    }
+    for (name, data) in files.tcx_files {
+        match parser::parse_tcx(&data, &name) {
+            Ok(activities) => parsed.file_activities.extend(activities),
+            Err(e) => file_errors.push((name, e)),
+        }
+    }
+    for (name, e) in file_errors {
+        tracing::warn!("Skipping file {}: {}", name, e);
+        parsed.skipped.push(IngestReportEntry::skipped(name, IngestReason::InvalidFile, e));
+    }
+
+    parsed
+}
+
+/// `parse_upload` on a blocking thread.
+async fn parse_upload_blocking(
+    files: UploadFiles,
+    user_id: Uuid,
+    tz: Tz,
+) -> Result<ParsedUpload, AppError> {
+    tokio::task::spawn_blocking(move || parse_upload(files, user_id, tz))
+        .await
+        .map_err(|e| {
+            tracing::error!("Parsing the upload of {user_id} failed: {e}");
+            AppError::Internal
         })
+}
+
+/// Process an upload: parse CSV rows, GPX, FIT and TCX files, then persist.
+///
+/// The Runkeeper CSV + GPX pair keeps its own insert path.  Standalone GPX
+/// files and all FIT and TCX files are self-contained and go through
+/// `ingest_activities`.  The two results are merged into a single
+/// `UploadResponse`, whose `report` lists the outcome of every row and file.
+///
+/// When run as a background upload job (`job_id`), progress is recorded as
+/// rows and files are processed.
+pub async fn upload(
+    db: &PgPool,
+    user_id: Uuid,
+    files: UploadFiles,
+    job_id: Option<Uuid>,
+) -> Result<UploadResponse, AppError> {
+    let tz = users::service::timezone(db, user_id).await;
+    let parsed = parse_upload_blocking(files, user_id, tz).await?;
+    let mut progress = JobProgress::new(job_id, parsed.total_items);
+    // Rows and files that failed to parse are done already.
+    progress.advance(db, parsed.skipped.len()).await;
+
+    let mut response = upload_runkeeper(
+        db,
+        user_id,
+        &parsed.csv_rows,
+        &parsed.referenced_gpx,
+        &mut progress,
+    )
+    .await;
+    if !parsed.file_activities.is_empty() {
+        response.merge(ingest(db, user_id, &parsed.file_activities, &mut progress).await);
+    }
+    response.report.extend(parsed.skipped);
+    Ok(response)
+}
+
+/// Dry run of `upload`: parse everything and deduplicate against the user's
+/// existing activities (and earlier items of the same upload), but write
+/// nothing and run no XP / achievement / PR pipeline.
+pub async fn preview_upload(
+    db: &PgPool,
+    user_id: Uuid,
+    files: SpooledUpload,
+) -> Result<UploadPreview, AppError> {
+    let files = tokio::task::spawn_blocking(move || files.load())
+        .await
+        .map_err(|e| {
+            tracing::error!("Reading the upload of {user_id} failed: {e}");
+            AppError::Internal
+        })?
+        .map_err(AppError::BadRequest)?;
+    let tz = users::service::timezone(db, user_id).await;
+    let parsed = parse_upload_blocking(files, user_id, tz).await?;
+
+    let dates: Vec<DateTime<Utc>> = parsed
+        .csv_rows
+        .iter()
+        .map(|(_, a)| a.date)
+        .chain(parsed.file_activities.iter().map(|a| a.date))
         .collect();
+    let external_ids: Vec<String> = parsed
+        .file_activities
+        .iter()
+        .filter_map(|a| a.external_id.clone())
+        .collect();
+    let (mut seen_dates, mut seen_external) =
+        repository::find_existing_dedup_keys(db, user_id, &dates, &external_ids).await?;
+
+    let mut preview = UploadPreview {
+        would_insert: Vec::new(),
+        duplicates: Vec::new(),
+        gpx_matches: Vec::new(),
+        skipped: parsed.skipped,
+    };
+
+    // Same order as `upload`: Runkeeper rows first, then standalone files.
+    for (line, activity) in &parsed.csv_rows {
+        let mut track_points = 0;
+        if !activity.gps_file.is_empty() {
+            let gpx = parsed.referenced_gpx.get(&activity.gps_file);
+            let points = gpx.and_then(|data| parser::parse_gpx(data, activity.id).ok()).map(|tps| tps.len());
+            track_points = points.unwrap_or(0);
+            preview.gpx_matches.push(GpxMatch {
+                line: *line,
+                gps_file: activity.gps_file.clone(),
+                found: gpx.is_some(),
+                track_points: points,
+            });
+        }
+
+        let entry = PreviewActivity {
+            file: CSV_FILE.to_string(),
+            line: Some(*line),
+            source: activity.source.clone(),
+            date: activity.date,
+            name: activity.name.clone(),
+            activity_type: activity.activity_type.clone(),
+            distance: activity.distance,
+            duration: activity.duration.clone(),
+            track_points,
+        };
+        if seen_dates.insert(activity.date) {
+            preview.would_insert.push(entry);
+        } else {
+            preview.duplicates.push(entry);
+        }
+    }
+
+    for activity in &parsed.file_activities {
+        let entry = PreviewActivity {
+            file: activity.gps_file.clone(),
+            line: None,
+            source: activity.source.clone(),
+            date: activity.date,
+            name: activity.name.clone(),
+            activity_type: activity.activity_type.clone(),
+            distance: activity.distance,
+            duration: activity.duration.clone(),
+            track_points: activity.track_points.len(),
+        };
+        let new_external = match &activity.external_id {
+            Some(ext) => !seen_external.contains(&(activity.source.clone(), ext.clone())),
+            None => true,
+        };
+        if new_external && seen_dates.insert(activity.date) {
+            if let Some(ext) = &activity.external_id {
+                seen_external.insert((activity.source.clone(), ext.clone()));
+            }
+            preview.would_insert.push(entry);
+        } else {
+            preview.duplicates.push(entry);
+        }
+    }
+
+    Ok(preview)
+}
+
+/// Rows of a Runkeeper CSV inserted with one statement.
+const CSV_INSERT_CHUNK: usize = 1_000;
 
-    // Persist activities (ON CONFLICT DO NOTHING for duplicates).
-    repository::insert_activities(db, &activities).await;
+/// A CSV row that matched no activity from another source, waiting for the
+/// batch insert.
+struct PendingRow<'a> {
+    line: u32,
+    activity: &'a Activity,
+    gpx_data: Option<&'a Vec<u8>>,
+    tps: Vec<TrackPoint>,
+    gpx_error: Option<String>,
+    track: Vec<(f64, f64)>,
+    found: Match,
+}
 
-    // Parse GPX files for each activity (synchronous — CPU only, no I/O).
+/// Runkeeper export path: CSV rows carry the summary, GPX files the tracks.
+///
+/// Rows are matched against other sources first, then the remaining ones are
+/// inserted in batches of `CSV_INSERT_CHUNK` before their tracks are stored.
+async fn upload_runkeeper(
+    db: &PgPool,
+    user_id: Uuid,
+    activities: &[(u32, Activity)],
+    gpx_files: &HashMap<String, Vec<u8>>,
+    progress: &mut JobProgress,
+) -> UploadResponse {
+    let mut report = Vec::new();
+    let mut matcher = load_matcher(db, user_id, activities.iter().map(|(_, a)| a.date)).await;
+    let mut inserted = Vec::new();
+    let mut replaced = Vec::new();
     let mut trackpoints_map: HashMap<Uuid, Vec<TrackPoint>> = HashMap::new();
-    for activity in &activities {
-        if let Some(gpx_data) = gpx_files.get(&activity.gps_file) {
-            match parser::parse_gpx(gpx_data, activity.id) {
-                Ok(tps) => {
-                    trackpoints_map.insert(activity.id, tps);
-                }
-                Err(e) => {
-                    tracing::warn!("Skipping GPX for {}: {}", activity.gps_file, e);
+    let mut raw_points = Vec::new();
+    let cleaning = CleaningConfig::from_env();
+    let csv_entry = |line: u32| IngestReportEntry {
+        line: Some(line),
+        ..IngestReportEntry::file(CSV_FILE, IngestOutcome::Duplicate)
+    };
+
+    let mut pending = Vec::with_capacity(activities.len());
+    for (line, activity) in activities {
+        // Parse the GPX file first (CPU only, no I/O): the duplicate matcher
+        // compares tracks.
+        let gpx_data = gpx_files.get(&activity.gps_file);
+        let (tps, gpx_error) = match gpx_data.map(|data| parser::parse_gpx(data, activity.id)) {
+            Some(Ok(tps)) => (tps, None),
+            Some(Err(e)) => (vec![], Some(e)),
+            None => (vec![], None),
+        };
+
+        let track: Vec<(f64, f64)> = tps.iter().map(|tp| (tp.latitude, tp.longitude)).collect();
+        let found =
+            find_duplicate(db, &mut matcher, &activity.source, activity, track.clone()).await;
+        if let Match::Duplicate { existing, score } = found {
+            let record = file_adapter::from_parsed(activity.clone(), tps);
+            let (reason, message, id) =
+                absorb_duplicate(db, user_id, &record, *existing, score, &mut matcher, &mut replaced)
+                    .await;
+            report.push(IngestReportEntry {
+                reason: Some(reason),
+                message: Some(message),
+                activity_id: Some(id),
+                ..csv_entry(*line)
+            });
+            progress.advance(db, 1 + usize::from(gpx_data.is_some())).await;
+            continue;
+        }
+        pending.push(PendingRow {
+            line: *line,
+            activity,
+            gpx_data,
+            tps,
+            gpx_error,
+            track,
+            found,
+        });
+    }
+
+    // Persist the rows (ON CONFLICT DO NOTHING for duplicates).  A failed
+    // statement fails only the rows of its chunk.
+    let mut stored: HashSet<Uuid> = HashSet::new();
+    let mut failed: HashSet<Uuid> = HashSet::new();
+    for chunk in pending.chunks(CSV_INSERT_CHUNK) {
+        let rows: Vec<Activity> = chunk.iter().map(|row| row.activity.clone()).collect();
+        match repository::insert_activities(db, &rows).await {
+            Ok(ids) => stored.extend(ids),
+            Err(_) => failed.extend(rows.iter().map(|a| a.id)),
+        }
+    }
+
+    for row in pending {
+        let PendingRow { line, activity, gpx_data, tps, gpx_error, track, found } = row;
+        let mut entry = csv_entry(line);
+        let items = 1 + usize::from(gpx_data.is_some());
+        if failed.contains(&activity.id) {
+            report.push(insert_failed(entry));
+            progress.advance(db, items).await;
+            continue;
+        }
+        if !stored.contains(&activity.id) {
+            entry.reason = Some(IngestReason::Duplicate);
+            report.push(entry);
+            progress.advance(db, items).await;
+            continue;
+        }
+        entry.outcome = IngestOutcome::Inserted;
+        entry.activity_id = Some(activity.id);
+        inserted.push(activity.clone());
+
+        match (gpx_data, gpx_error) {
+            (Some(_), Some(e)) => {
+                tracing::warn!("Skipping GPX for {}: {}", activity.gps_file, e);
+                report.push(IngestReportEntry::skipped(
+                    &activity.gps_file,
+                    IngestReason::InvalidFile,
+                    e,
+                ));
+            }
+            (Some(_), None) => {
+                let cleaned = cleaning::clean_track(&tps, &activity.activity_type, &cleaning);
+                let track_metrics =
+                    analyse_track(db, activity.id, &activity.activity_type, &cleaned, &cleaning)
+                        .await;
+                if let Some(stored) = inserted.last_mut() {
+                    stored.track_metrics = track_metrics;
                 }
+                trackpoints_map.insert(activity.id, cleaned);
+                raw_points.extend(tps);
+            }
+            (None, _) if !activity.gps_file.is_empty() => {
+                entry.reason = Some(IngestReason::MissingGpx);
+                entry.message = Some(format!("{} was not uploaded", activity.gps_file));
             }
+            (None, _) => {}
+        }
+        if let Match::Suspected { activity_id, score } = found {
+            duplicates::service::record_suspected(db, user_id, activity_id, activity.id, score)
+                .await;
+            let suspect = suspected_message(activity_id, score);
+            entry.message = Some(match entry.message {
+                Some(m) => format!("{m}; {suspect}"),
+                None => suspect,
+            });
+            entry.reason = Some(IngestReason::SuspectedDuplicate);
         }
+        if let Some(m) = matcher.as_mut() {
+            m.remember(activity.clone(), track);
+        }
+        report.push(entry);
+        progress.advance(db, items).await;
     }
 
-    let _gpx_count = trackpoints_map.len() as u32;
+    keep_raw_trackpoints(db, &raw_points).await;
     repository::insert_trackpoints(db, &trackpoints_map).await;
 
-    // Build activity IDs list for the pipeline step.
-    let activity_ids: Vec<Uuid> = activities.iter().map(|a| a.id).collect();
-
-    // Run the XP / achievement / PR / mission pipeline on the newly inserted rows.
-    run_post_ingest_pipeline(db, user_id, &activity_ids, &activities).await
+    let mut response = finish_ingest(db, user_id, inserted, replaced).await;
+    response.report = report;
+    response
 }
 
 /// Core ingestion pipeline for activities arriving from any data source.
 ///
-/// Called by `upload()` (Runkeeper) and by `strava::sync` (Strava).
+/// Called by `upload()` (standalone files) and by `strava::sync` (Strava).
 /// Inserts activities that are not already present in the DB, then runs the
 /// XP / achievement / PR / mission pipelines on only the *newly* inserted rows.
 ///
-/// Returns an `UploadResponse` summarising what was earned/unlocked.
+/// Before inserting, each activity is compared with the user's activities
+/// from other sources: the same run is linked to (or, from a higher-priority
+/// source, replaces) the existing activity instead of counting twice.
+///
+/// Returns an `UploadResponse` summarising what was earned/unlocked, with one
+/// report entry per input activity.
 pub async fn ingest_activities(
     db: &PgPool,
     user_id: Uuid,
     activities: &[NormalizedActivity],
 ) -> UploadResponse {
-    // Insert activities, collecting the IDs of rows that were actually new.
-    let inserted_ids = repository::insert_activities_from_source(db, user_id, activities).await;
-
-    if inserted_ids.is_empty() {
-        return UploadResponse {
-            processed: 0,
-            xp_earned: 0,
-            new_level: None,
-            newly_unlocked_achievements: vec![],
-            new_prs: vec![],
-            completed_missions: vec![],
-            completed_goals: vec![],
+    ingest(db, user_id, activities, &mut JobProgress::untracked()).await
+}
+
+/// `ingest_activities`, counting each activity as processed in `progress`.
+async fn ingest(
+    db: &PgPool,
+    user_id: Uuid,
+    activities: &[NormalizedActivity],
+    progress: &mut JobProgress,
+) -> UploadResponse {
+    let mut report = Vec::with_capacity(activities.len());
+    let mut matcher = load_matcher(db, user_id, activities.iter().map(|a| a.date)).await;
+    let merged = duplicates::service::merged_into(db, user_id, activities).await;
+    let mut inserted = Vec::new();
+    let mut replaced = Vec::new();
+
+    for (i, activity) in activities.iter().enumerate() {
+        progress.advance(db, usize::from(i > 0)).await;
+        let file = if activity.gps_file.is_empty() {
+            activity.external_id.clone().unwrap_or_default()
+        } else {
+            activity.gps_file.clone()
+        };
+        let entry = IngestReportEntry::file(file, IngestOutcome::Duplicate);
+
+        // A record merged into another activity earlier (e.g. on a previous
+        // Strava sync) is recognised by its source ID.
+        let merged_into = activity
+            .external_id
+            .as_ref()
+            .and_then(|ext| merged.get(&(activity.source.clone(), ext.clone())).copied());
+        if let Some(canonical_id) = merged_into {
+            report.push(IngestReportEntry {
+                reason: Some(IngestReason::CrossSourceDuplicate),
+                message: Some(format!("Already merged into activity {canonical_id}")),
+                activity_id: Some(canonical_id),
+                ..entry
+            });
+            continue;
+        }
+
+        let new_activity = to_activity(Uuid::nil(), user_id, activity);
+        let track: Vec<(f64, f64)> =
+            activity.track_points.iter().map(|tp| (tp.latitude, tp.longitude)).collect();
+        let found =
+            find_duplicate(db, &mut matcher, &activity.source, &new_activity, track.clone()).await;
+        if let Match::Duplicate { existing, score } = found {
+            let (reason, message, id) =
+                absorb_duplicate(db, user_id, activity, *existing, score, &mut matcher, &mut replaced)
+                    .await;
+            report.push(IngestReportEntry {
+                reason: Some(reason),
+                message: Some(message),
+                activity_id: Some(id),
+                ..entry
+            });
+            continue;
+        }
+
+        // Insert the activity; `None` marks a duplicate.
+        let outcome =
+            repository::insert_activities_from_source(db, user_id, std::slice::from_ref(activity))
+                .await;
+        let id = match outcome.into_iter().next() {
+            Some(Ok(Some(id))) => id,
+            Some(Err(_)) => {
+                report.push(insert_failed(entry));
+                continue;
+            }
+            _ => {
+                report.push(IngestReportEntry {
+                    reason: Some(IngestReason::Duplicate),
+                    ..entry
+                });
+                continue;
+            }
+        };
+
+        let track_metrics = store_track(db, id, activity).await;
+        let mut entry = IngestReportEntry {
+            outcome: IngestOutcome::Inserted,
+            activity_id: Some(id),
+            reason: (activity.source == "gpx").then_some(IngestReason::UnreferencedGpx),
+            ..entry
         };
+        if let Match::Suspected { activity_id, score } = found {
+            duplicates::service::record_suspected(db, user_id, activity_id, id, score).await;
+            entry.reason = Some(IngestReason::SuspectedDuplicate);
+            entry.message = Some(suspected_message(activity_id, score));
+        }
+        let stored = Activity {
+            track_metrics,
+            ..to_activity(id, user_id, activity)
+        };
+        if let Some(m) = matcher.as_mut() {
+            m.remember(stored.clone(), track);
+        }
+        inserted.push(stored);
+        report.push(entry);
     }
+    progress.advance(db, usize::from(!activities.is_empty())).await;
 
-    // Insert track points for every newly inserted activity.
-    let id_set: std::collections::HashSet<Uuid> = inserted_ids.iter().copied().collect();
-    for activity in activities {
-        if let Some(ext_id) = &activity.external_id {
-            // Look up the DB-assigned UUID for this activity.
-            if let Ok(Some(db_id)) = repository::find_id_by_external(db, user_id, &activity.source, ext_id).await {
-                if id_set.contains(&db_id) {
-                    repository::insert_normalized_trackpoints(db, db_id, &activity.track_points).await;
+    let mut response = finish_ingest(db, user_id, inserted, replaced).await;
+    response.report = report;
+    response
+}
+
+/// Report entry for a row or file whose insert the database rejected.
+/// The database error itself is logged, not reported.
+fn insert_failed(entry: IngestReportEntry) -> IngestReportEntry {
+    IngestReportEntry {
+        outcome: IngestOutcome::Failed,
+        reason: Some(IngestReason::InsertFailed),
+        message: Some("Could not store the activity".to_string()),
+        ..entry
+    }
+}
+
+/// Store a normalized activity's track under `activity_id`: the points as
+/// received go to `raw_trackpoints`, the cleaned track to `trackpoints`, and
+/// the metrics and best efforts derived from it next to the activity.
+async fn store_track(
+    db: &PgPool,
+    activity_id: Uuid,
+    activity: &NormalizedActivity,
+) -> TrackMetrics {
+    if activity.track_points.is_empty() {
+        return TrackMetrics::default();
+    }
+    let config = CleaningConfig::from_env();
+    let raw: Vec<TrackPoint> =
+        activity.track_points.iter().map(|tp| tp.to_track_point(activity_id)).collect();
+    let cleaned = cleaning::clean_track(&raw, &activity.activity_type, &config);
+    keep_raw_trackpoints(db, &raw).await;
+    let track_metrics =
+        analyse_track(db, activity_id, &activity.activity_type, &cleaned, &config).await;
+    repository::insert_trackpoints(db, &HashMap::from([(activity_id, cleaned)])).await;
+    track_metrics
+}
+
+/// Derive the metrics, best efforts, laps, heatmap cells, segment efforts,
+/// route and explorer tiles of a cleaned track and store them.
+/// Failures are logged; the activity then just lacks them (and competes for
+/// personal records with its whole distance only).
+async fn analyse_track(
+    db: &PgPool,
+    activity_id: Uuid,
+    activity_type: &str,
+    cleaned: &[TrackPoint],
+    config: &CleaningConfig,
+) -> TrackMetrics {
+    let efforts = best_efforts(cleaned, activity_type, config);
+    if let Err(e) = personal_records::service::save_best_efforts(db, activity_id, &efforts).await {
+        tracing::warn!("Could not store best efforts of {activity_id}: {e}");
+    }
+    if let Err(e) = repository::replace_laps(db, activity_id, &laps::detect_laps(cleaned, config)).await {
+        tracing::warn!("Could not store laps of {activity_id}: {e}");
+    }
+    if let Err(e) = repository::replace_heatmap_cells(db, activity_id, &heatmap::cells(cleaned)).await {
+        tracing::warn!("Could not store heatmap cells of {activity_id}: {e}");
+    }
+    if let Err(e) = segments::service::match_activity(db, activity_id, activity_type, cleaned).await {
+        tracing::warn!("Could not match {activity_id} against segments: {e}");
+    }
+    if let Err(e) = routes::service::assign_activity(db, activity_id, cleaned).await {
+        tracing::warn!("Could not put {activity_id} on a route: {e}");
+    }
+    if let Err(e) = explorer::service::record_activity(db, activity_id, cleaned).await {
+        tracing::warn!("Could not record explorer tiles of {activity_id}: {e}");
+    }
+    let track_metrics = metrics::track_metrics(cleaned, activity_type, config);
+    if let Err(e) = repository::update_track_metrics(db, activity_id, &track_metrics).await {
+        tracing::warn!("Could not store track metrics of {activity_id}: {e}");
+        return TrackMetrics::default();
+    }
+    track_metrics
+}
+
+/// Best efforts only count for runs.
+fn best_efforts(cleaned: &[TrackPoint], activity_type: &str, config: &CleaningConfig) -> Vec<BestEffort> {
+    if activity_type == "Running" {
+        metrics::best_efforts(cleaned, config)
+    } else {
+        Vec::new()
+    }
+}
+
+/// The `Activity` row a normalized activity is stored as.
+fn to_activity(id: Uuid, user_id: Uuid, a: &NormalizedActivity) -> Activity {
+    Activity {
+        id,
+        user_id,
+        date: a.date,
+        utc_offset: a.utc_offset,
+        name: a.name.clone(),
+        activity_type: a.activity_type.clone(),
+        distance: a.distance,
+        duration: a.duration.clone(),
+        average_pace: a.average_pace,
+        average_speed: a.average_speed,
+        calories: a.calories,
+        climb: a.climb,
+        gps_file: a.gps_file.clone(),
+        source: a.source.clone(),
+        external_id: a.external_id.clone(),
+        track_metrics: TrackMetrics::default(),
+    }
+}
+
+/// Load the duplicate matcher for one import batch.  Without it the import
+/// still works, just without cross-source matching.
+async fn load_matcher(
+    db: &PgPool,
+    user_id: Uuid,
+    dates: impl IntoIterator<Item = DateTime<Utc>>,
+) -> Option<DuplicateMatcher> {
+    DuplicateMatcher::load(db, user_id, dates)
+        .await
+        .inspect_err(|e| tracing::warn!("Duplicate matching disabled for this import: {e}"))
+        .ok()
+}
+
+async fn find_duplicate(
+    db: &PgPool,
+    matcher: &mut Option<DuplicateMatcher>,
+    source: &str,
+    activity: &Activity,
+    track: Vec<(f64, f64)>,
+) -> Match {
+    let Some(matcher) = matcher.as_mut() else {
+        return Match::None;
+    };
+    let fingerprint = Fingerprint {
+        date: activity.date,
+        distance_km: activity.distance,
+        duration_secs: personal_records::models::parse_duration_to_secs(&activity.duration),
+        track,
+    };
+    matcher.find_match(db, source, &fingerprint).await
+}
+
+fn suspected_message(activity_id: Uuid, score: f32) -> String {
+    format!("Possible duplicate of activity {activity_id} (match score {score:.2})")
+}
+
+/// Handle an automatic cross-source match.
+///
+/// If `record` comes from a higher-priority source it replaces the existing
+/// activity's data and track in place (the old version is pushed onto
+/// `replaced` so its effects can be reversed); otherwise it is only linked.
+/// Either way the record that loses is kept in `activity_duplicates`.
+///
+/// Returns the report reason, message and the canonical activity ID.
+async fn absorb_duplicate(
+    db: &PgPool,
+    user_id: Uuid,
+    record: &NormalizedActivity,
+    existing: Activity,
+    score: f32,
+    matcher: &mut Option<DuplicateMatcher>,
+    replaced: &mut Vec<Activity>,
+) -> (IngestReason, String, Uuid) {
+    if duplicates::service::supersedes(&record.source, &existing) {
+        match repository::replace_with_source_record(db, existing.id, record).await {
+            Ok(()) => {
+                if !record.track_points.is_empty() {
+                    if let Err(e) = repository::delete_trackpoints(db, existing.id).await {
+                        tracing::warn!("Could not drop old track of {}: {e}", existing.id);
+                    }
+                    store_track(db, existing.id, record).await;
+                }
+                duplicates::service::record_merged(
+                    db,
+                    user_id,
+                    existing.id,
+                    score,
+                    &MergedRecord::from(&existing),
+                )
+                .await;
+                if let Some(m) = matcher.as_mut() {
+                    let track =
+                        record.track_points.iter().map(|tp| (tp.latitude, tp.longitude)).collect();
+                    m.replace(to_activity(existing.id, user_id, record), track);
                 }
+                let message = format!(
+                    "Replaced the {} record of activity {} (match score {score:.2})",
+                    existing.source, existing.id
+                );
+                let id = existing.id;
+                replaced.push(existing);
+                return (IngestReason::ReplacedDuplicate, message, id);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Could not replace activity {} with {} record: {e}",
+                    existing.id,
+                    record.source
+                );
             }
-        } else {
-            // For source without external_id (legacy Runkeeper), skip track points here —
-            // the existing upload() path already handles them.
         }
     }
 
-    // Re-fetch the Activity structs for the newly inserted rows so we can
-    // pass typed data into the downstream pipelines.
-    let db_activities = match repository::find_activities_by_ids(db, &inserted_ids).await {
-        Ok(map) => {
-            inserted_ids.iter().filter_map(|id| map.get(id).cloned()).collect::<Vec<_>>()
-        }
-        Err(e) => {
-            tracing::warn!("Could not fetch inserted activities for pipeline: {e}");
-            return UploadResponse {
-                processed: inserted_ids.len() as u32,
-                xp_earned: 0,
-                new_level: None,
-                newly_unlocked_achievements: vec![],
-                new_prs: vec![],
-                completed_missions: vec![],
-                completed_goals: vec![],
-            };
+    duplicates::service::record_merged(db, user_id, existing.id, score, &MergedRecord::from(record))
+        .await;
+    let message = format!(
+        "Same run as {} activity {} (match score {score:.2})",
+        existing.source, existing.id
+    );
+    (IngestReason::CrossSourceDuplicate, message, existing.id)
+}
+
+/// Run the pipeline on an import's new activities and on the existing ones
+/// that a higher-priority record replaced, after reversing what their old
+/// versions earned.
+async fn finish_ingest(
+    db: &PgPool,
+    user_id: Uuid,
+    inserted: Vec<Activity>,
+    replaced: Vec<Activity>,
+) -> UploadResponse {
+    for old in &replaced {
+        let reversal = match db.begin().await {
+            Ok(mut tx) => match reverse_activity_effects(&mut tx, user_id, old).await {
+                Ok(_) => tx.commit().await.map_err(AppError::from),
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(AppError::from(e)),
+        };
+        if let Err(e) = reversal {
+            tracing::warn!("Could not reverse effects of replaced activity {}: {e}", old.id);
         }
-    };
+    }
+
+    let replaced_ids: Vec<Uuid> = replaced.iter().map(|a| a.id).collect();
+    let mut activities = inserted;
+    match repository::find_activities_by_ids(db, &replaced_ids).await {
+        Ok(mut map) => activities.extend(replaced_ids.iter().filter_map(|id| map.remove(id))),
+        Err(e) => tracing::warn!("Could not fetch replaced activities for pipeline: {e}"),
+    }
+    if activities.is_empty() {
+        return UploadResponse::default();
+    }
 
-    run_post_ingest_pipeline(db, user_id, &inserted_ids, &db_activities).await
+    // Run the XP / achievement / PR / mission pipeline on the new and replaced rows.
+    let activity_ids: Vec<Uuid> = activities.iter().map(|a| a.id).collect();
+    run_post_ingest_pipeline(db, user_id, &activity_ids, &activities, !replaced.is_empty()).await
 }
 
 /// Shared XP / achievement / PR / mission pipeline.
 ///
 /// Runs after activities have been persisted. Takes the already-fetched
 /// `Activity` structs to avoid an extra DB round-trip.
+///
+/// `edited` is set when an existing activity changed or disappeared: mission
+/// and goal completions that no longer hold are then revoked as well.
 async fn run_post_ingest_pipeline(
     db: &PgPool,
     user_id: Uuid,
     _activity_ids: &[Uuid],
     activities: &[Activity],
+    edited: bool,
 ) -> UploadResponse {
     // Record XP level before awarding so we can detect level-up.
     let level_before = xp_service::get_user_xp_summary(db, user_id)
@@ -241,32 +1359,16 @@ async fn run_post_ingest_pipeline(
         None
     };
 
-    // Check & unlock achievements for each activity.
-    let mut all_unlocked = Vec::new();
-    for activity in activities {
-        let distance_m = activity.distance as f64 * 1000.0; // km → m
-        let pace = activity.average_pace as f64;
-        let start = activity.date.and_utc();
-        match achievements::service::check_and_unlock_achievements(
-            db,
-            user_id,
-            activity.id,
-            distance_m,
-            pace,
-            start,
-        )
-        .await
-        {
-            Ok(unlocked) => all_unlocked.extend(unlocked),
-            Err(e) => tracing::warn!("Achievement check failed for activity {}: {e}", activity.id),
-        }
-    }
+    // Check & unlock achievements for the whole batch.
+    let tz = users::service::timezone(db, user_id).await;
+    let all_unlocked =
+        achievements::service::check_and_unlock_achievements(db, user_id, activities, tz).await;
 
     // Check personal records for each activity.
     let mut all_new_prs = Vec::new();
     for activity in activities {
         let distance_m = activity.distance as f64 * 1000.0; // km → m
-        let start = activity.date.and_utc();
+        let start = activity.date;
         match personal_records::service::check_activity_for_prs(
             db,
             user_id,
@@ -283,40 +1385,49 @@ async fn run_post_ingest_pipeline(
     }
 
     // Update weekly mission progress and detect completions.
-    let mut completed_missions = weekly_missions::service::update_progress_after_upload(db, user_id)
-        .await
-        .unwrap_or_else(|e| {
-            tracing::warn!("Weekly mission progress update failed: {e}");
-            vec![]
-        });
+    let weekly = if edited {
+        weekly_missions::service::update_progress_after_edit(db, user_id).await
+    } else {
+        weekly_missions::service::update_progress_after_upload(db, user_id).await
+    };
+    let mut completed_missions = weekly.unwrap_or_else(|e| {
+        tracing::warn!("Weekly mission progress update failed: {e}");
+        vec![]
+    });
 
     // Update monthly mission progress and detect completions.
-    let completed_monthly = monthly_missions::service::update_progress_after_upload(db, user_id)
-        .await
-        .unwrap_or_else(|e| {
-            tracing::warn!("Monthly mission progress update failed: {e}");
-            vec![]
-        });
+    let monthly = if edited {
+        monthly_missions::service::update_progress_after_edit(db, user_id).await
+    } else {
+        monthly_missions::service::update_progress_after_upload(db, user_id).await
+    };
+    let completed_monthly = monthly.unwrap_or_else(|e| {
+        tracing::warn!("Monthly mission progress update failed: {e}");
+        vec![]
+    });
     completed_missions.extend(completed_monthly);
 
     // Trigger challenge progression for all active challenges of this user.
     // Failure is non-fatal — log and continue so the upload response is unaffected.
-    if let Err(e) = crate::challenges::progression::handle(
-        db,
-        crate::challenges::progression::ProgressionTrigger::ActivitiesUploaded { user_id },
-    )
-    .await
-    {
+    let trigger = if edited {
+        ProgressionTrigger::ActivitiesChanged { user_id }
+    } else {
+        ProgressionTrigger::ActivitiesUploaded { user_id }
+    };
+    if let Err(e) = crate::challenges::progression::handle(db, trigger).await {
         tracing::warn!("Challenge progression failed after activity upload: {e}");
     }
 
     // Update user-defined goal progress and detect completions.
-    let completed_goals = crate::goals::service::update_progress_after_upload(db, user_id)
-        .await
-        .unwrap_or_else(|e| {
-            tracing::warn!("Goals progress update failed: {e}");
-            vec![]
-        });
+    let goals = if edited {
+        crate::goals::service::update_progress_after_edit(db, user_id).await
+    } else {
+        crate::goals::service::update_progress_after_upload(db, user_id).await
+    };
+    let completed_goals = goals.unwrap_or_else(|e| {
+        tracing::warn!("Goals progress update failed: {e}");
+        vec![]
+    });
 
     UploadResponse {
         processed: activities.len() as u32,
@@ -326,16 +1437,83 @@ async fn run_post_ingest_pipeline(
         new_prs: all_new_prs,
         completed_missions,
         completed_goals,
+        report: vec![],
     }
 }
 
-/// Return heatmap grid points for a user, with optional filters.
+/// Render heatmap tile `z/x/y` (XYZ scheme) of a user's activities as a PNG,
+/// with the same filters as [`get_heatmap`].
+#[allow(clippy::too_many_arguments)]
+pub async fn get_heatmap_tile(
+    db: &PgPool,
+    user_id: Uuid,
+    activity_type: Option<String>,
+    date_from: Option<NaiveDate>,
+    date_to: Option<NaiveDate>,
+    z: u8,
+    x: u32,
+    y: u32,
+) -> Result<Vec<u8>, AppError> {
+    if z > tiles::MAX_ZOOM {
+        return Err(AppError::BadRequest(format!(
+            "zoom must be at most {}",
+            tiles::MAX_ZOOM
+        )));
+    }
+    if x >= 1 << z || y >= 1 << z {
+        return Err(AppError::BadRequest(format!("no tile {z}/{x}/{y}")));
+    }
+    let tz = users::service::timezone(db, user_id).await;
+    let level = tiles::tile_level(z);
+    let range = tiles::tile_cell_range(level, z, x, y);
+    let cells = repository::find_heatmap_cells(
+        db,
+        user_id,
+        activity_type,
+        date_from,
+        date_to,
+        tz,
+        level,
+        Some(range),
+    )
+    .await?;
+    tiles::render_tile(&cells, level, z, x, y).map_err(|e| {
+        tracing::error!("Could not encode heatmap tile {z}/{x}/{y}: {e}");
+        AppError::Internal
+    })
+}
+
+/// Return heatmap grid cells for a user, with optional filters, at the grid
+/// resolution for `zoom` and clipped to `bbox`.  The date range is read in
+/// the user's timezone.
 pub async fn get_heatmap(
     db: &PgPool,
     user_id: Uuid,
     activity_type: Option<String>,
     date_from: Option<NaiveDate>,
     date_to: Option<NaiveDate>,
+    zoom: Option<u8>,
+    bbox: Option<Bbox>,
 ) -> Result<Vec<HeatmapPoint>, AppError> {
-    repository::find_heatmap_points(db, user_id, activity_type, date_from, date_to).await
+    let tz = users::service::timezone(db, user_id).await;
+    let level = heatmap::level_for_zoom(zoom);
+    let range = bbox.map(|b| b.cell_range(level));
+    let cells = repository::find_heatmap_cells(
+        db,
+        user_id,
+        activity_type,
+        date_from,
+        date_to,
+        tz,
+        level,
+        range,
+    )
+    .await?;
+    Ok(cells
+        .into_iter()
+        .map(|(x, y, weight)| {
+            let (lat, lon) = heatmap::cell_centre(x, y, level);
+            HeatmapPoint { lat, lon, weight }
+        })
+        .collect())
 }
@@ -0,0 +1,104 @@
+/// Heatmap raster tiles for XYZ ("slippy map") clients.
+///
+/// A 256×256 tile at zoom `z` is drawn from the heatmap cells of the stored
+/// level for `z` (see [`super::heatmap`]).  Tile pixels at zoom `z` are
+/// exactly the grid cells of level `z`, so above the finest stored level a
+/// cell is drawn as a square of pixels and below the coarsest one several
+/// cells add up into one pixel.  Colour runs from transparent through red and
+/// yellow to white on a log scale that saturates at [`SATURATION`] points per
+/// pixel, the same for every tile so neighbouring tiles join up.
+/// Pure functions — no I/O.
+use super::heatmap;
+
+pub const TILE_SIZE: u32 = 256;
+
+/// Highest tile zoom served.
+pub const MAX_ZOOM: u8 = 20;
+
+/// Points per pixel drawn at full intensity.
+const SATURATION: f64 = 64.0;
+
+/// The stored level a tile at zoom `z` is drawn from.
+pub fn tile_level(z: u8) -> i16 {
+    heatmap::level_for_zoom(Some(z))
+}
+
+/// Inclusive cell ranges `(x_min, x_max, y_min, y_max)` at `level` that
+/// overlap tile `z/x/y`.
+pub fn tile_cell_range(level: i16, z: u8, x: u32, y: u32) -> (i32, i32, i32, i32) {
+    let (x_min, x_max) = cell_span(x, z, level);
+    let (y_min, y_max) = cell_span(y, z, level);
+    (x_min, x_max, y_min, y_max)
+}
+
+/// First and last cell at `level` under tile column (or row) `t` at zoom `z`.
+fn cell_span(t: u32, z: u8, level: i16) -> (i32, i32) {
+    let start = i64::from(t) * i64::from(TILE_SIZE);
+    let end = start + i64::from(TILE_SIZE);
+    let shift = i16::from(z) - level;
+    let (first, last) = if shift >= 0 {
+        (start >> shift, (end - 1) >> shift)
+    } else {
+        (start << -shift, (end << -shift) - 1)
+    };
+    (first as i32, last as i32)
+}
+
+/// Render tile `z/x/y` from cells `(x, y, weight)` at `level` as an RGBA PNG.
+pub fn render_tile(
+    cells: &[(i32, i32, i64)],
+    level: i16,
+    z: u8,
+    x: u32,
+    y: u32,
+) -> Result<Vec<u8>, png::EncodingError> {
+    let size = TILE_SIZE as usize;
+    let mut density = vec![0i64; size * size];
+    let shift = i16::from(z) - level;
+    let origin = (
+        i64::from(x) * i64::from(TILE_SIZE),
+        i64::from(y) * i64::from(TILE_SIZE),
+    );
+    for &(cx, cy, weight) in cells {
+        // Pixels of this cell, relative to the tile origin.
+        let span = |c: i32, o: i64| {
+            let c = i64::from(c);
+            let (a, b) = if shift >= 0 {
+                (c << shift, (c + 1) << shift)
+            } else {
+                (c >> -shift, (c >> -shift) + 1)
+            };
+            let clip = |v: i64| (v - o).clamp(0, size as i64) as usize;
+            clip(a)..clip(b)
+        };
+        for py in span(cy, origin.1) {
+            for px in span(cx, origin.0) {
+                density[py * size + px] += weight;
+            }
+        }
+    }
+
+    let pixels: Vec<u8> = density.into_iter().flat_map(colour).collect();
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, TILE_SIZE, TILE_SIZE);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+    Ok(out)
+}
+
+/// RGBA colour of a pixel with `weight` track points.
+fn colour(weight: i64) -> [u8; 4] {
+    if weight <= 0 {
+        return [0, 0, 0, 0];
+    }
+    let t = ((weight as f64).ln_1p() / SATURATION.ln_1p()).min(1.0);
+    // Red, then yellow, then white, each over a third of the range.
+    let ramp = |from: f64| (((t - from) * 3.0).clamp(0.0, 1.0) * 255.0) as u8;
+    [
+        (160.0 + 95.0 * (t * 3.0).min(1.0)) as u8,
+        ramp(1.0 / 3.0),
+        ramp(2.0 / 3.0),
+        (96.0 + 159.0 * t) as u8,
+    ]
+}
@@ -2,14 +2,17 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::Datelike;
 
+use crate::activities::pace::Pace;
+
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ActivitiesAggregation {
     pub total_activities: u32,
     pub total_distance: f32,
-    pub average_pace: f32,
+    /// Distance-weighted over the activities that have a pace.
+    pub average_pace: Pace,
     pub average_distance: f32,
     pub best_distance: f32,
-    pub best_pace: f32,
+    pub best_pace: Pace,
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema, Clone)]
@@ -17,17 +20,19 @@ pub struct AdvancedAggregation {
     pub longest_streak_days: u32,
     pub longest_streak_weeks: u32,
     pub current_weekly_streak: u32,
+    /// Mean pace per weekday in seconds per km, fastest first.
     pub top_3_fastest_weekdays: Vec<(String, f32)>,
     pub most_consistent_week: Option<String>,
     pub max_daily_calories: f32,
     pub top_speeds: Vec<f32>,
     pub max_climb: f32,
     pub most_frequent_weekday: Option<String>,
-    pub slowest_pace: f32,
+    pub slowest_pace: Pace,
     pub speed_demon_hour: Option<String>,
     pub sweatiest_week: Option<String>,
     pub most_skipped_weekday: Option<String>,
     pub weekend_ratio: f32,
+    /// Seconds per km.
     pub pace_std_dev: f32,
     pub max_effort_cal_per_min: f32,
     // ── Streak detail ────────────────────────────────────────────────────
@@ -62,7 +67,7 @@ impl Default for AdvancedAggregation {
             top_speeds: vec![],
             max_climb: 0.0,
             most_frequent_weekday: None,
-            slowest_pace: 0.0,
+            slowest_pace: Pace::default(),
             speed_demon_hour: None,
             sweatiest_week: None,
             most_skipped_weekday: None,
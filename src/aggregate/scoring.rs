@@ -111,14 +111,14 @@ pub fn calculate_scores(
     for (key, rule) in &config.rules {
         let score = match key.as_str() {
             "average_pace" => {
-                let pace_diff = 6.0 - basic.average_pace;
+                let pace_diff = 6.0 - basic.average_pace.min_per_km() as f32;
                 rule.base as f32 + (pace_diff * rule.multiplier)
             }
             "total_distance" => basic.total_distance * rule.multiplier + rule.base as f32,
             "average_distance" => basic.average_distance * rule.multiplier + rule.base as f32,
             "best_distance" => basic.best_distance * rule.multiplier + rule.base as f32,
             "best_pace" => {
-                let pace_diff = 6.0 - basic.best_pace;
+                let pace_diff = 6.0 - basic.best_pace.min_per_km() as f32;
                 rule.base as f32 + (pace_diff * rule.multiplier)
             }
             "max_climb" => advanced
@@ -137,7 +137,8 @@ pub fn calculate_scores(
                 a.max_effort_cal_per_min * rule.multiplier + rule.base as f32
             }),
             "pace_std_dev" => advanced.as_ref().map_or(0.0, |a| {
-                let inverse_dev = (3.0 - a.pace_std_dev).max(0.0);
+                // pace_std_dev is in seconds; the rule is scaled for minutes.
+                let inverse_dev = (3.0 - a.pace_std_dev / 60.0).max(0.0);
                 rule.base as f32 + (inverse_dev * rule.multiplier)
             }),
             "max_daily_calories" => advanced.as_ref().map_or(0.0, |a| {
@@ -2,8 +2,10 @@
 ///
 /// All public functions take slices of Activity and return aggregation structs.
 /// Safe to call from any context (handler, test, background job).
-use crate::activities::models::Activity;
+use crate::activities::{models::Activity, pace::Pace};
+use crate::users::timezone;
 use chrono::{Datelike, IsoWeek, NaiveDate, NaiveTime, Timelike};
+use chrono_tz::Tz;
 use std::collections::HashMap;
 
 use super::{
@@ -15,12 +17,14 @@ use super::{
 };
 
 /// Top-level entry point: splits activities by type then aggregates each group.
+/// Days, weeks and months are cut in the user's zone `tz`.
 ///
 /// Returns:
 /// - A map of activity_type → AggregationDTO (basic + advanced stats + scores)
 /// - A map of activity_type → month_key → ActivitiesAggregation (for time-series charts)
 pub fn aggregate_activities(
     activities: &[Activity],
+    tz: Tz,
 ) -> (
     HashMap<String, AggregationDTO>,
     HashMap<String, HashMap<String, ActivitiesAggregation>>,
@@ -30,7 +34,7 @@ pub fn aggregate_activities(
 
     for activity in activities {
         let activity_type = activity.activity_type.clone();
-        let month_key = activity.date.format("%Y-%m").to_string();
+        let month_key = activity.local_date(tz).format("%Y-%m").to_string();
 
         activity_types
             .entry(activity_type.clone())
@@ -50,7 +54,7 @@ pub fn aggregate_activities(
     let mut aggregation_map = HashMap::new();
     for (activity_type, acts) in &activity_types {
         let basic = compute_basic_aggregation(acts);
-        let advanced = compute_advanced_aggregation(acts);
+        let advanced = compute_advanced_aggregation(acts, tz);
         let scores = calculate_score_summary(&basic, &Some(advanced.clone()), &config);
 
         aggregation_map.insert(
@@ -79,18 +83,17 @@ pub fn compute_basic_aggregation(activities: &[Activity]) -> ActivitiesAggregati
     let total_activities = activities.len() as u32;
     let total_distance: f32 = activities.iter().map(|a| a.distance).sum();
 
-    let total_seconds: f32 = activities
+    // Distance-weighted mean over the activities that have a pace.
+    let (paced_seconds, paced_distance) = activities
         .iter()
-        .map(|a| a.average_pace * a.distance * 60.0)
-        .sum();
-
-    let average_pace = if total_distance > 0.0 {
-        let pace_seconds_per_km = total_seconds / total_distance;
-        let minutes = (pace_seconds_per_km / 60.0).floor();
-        let seconds = (pace_seconds_per_km % 60.0) / 100.0;
-        minutes + seconds
+        .filter(|a| a.average_pace.is_set())
+        .fold((0.0_f64, 0.0_f64), |(secs, km), a| {
+            (secs + a.average_pace.secs_per_km() * a.distance as f64, km + a.distance as f64)
+        });
+    let average_pace = if paced_distance > 0.0 {
+        Pace::from_secs_per_km(paced_seconds / paced_distance)
     } else {
-        0.0
+        Pace::default()
     };
 
     let average_distance = if total_activities > 0 {
@@ -106,16 +109,12 @@ pub fn compute_basic_aggregation(activities: &[Activity]) -> ActivitiesAggregati
 
     // Lower pace = faster. best_pace is the minimum pace value observed.
     // Q8 fix: do NOT cap best_pace at average_pace — best_pace can legitimately be better.
-    let raw_best_pace = activities
+    let best_pace = activities
         .iter()
         .map(|a| a.average_pace)
-        .fold(f32::INFINITY, f32::min);
-
-    let best_pace = if raw_best_pace.is_infinite() {
-        0.0
-    } else {
-        raw_best_pace
-    };
+        .filter(|p| p.is_set())
+        .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .unwrap_or_default();
 
     ActivitiesAggregation {
         total_activities,
@@ -132,12 +131,17 @@ pub fn compute_basic_aggregation(activities: &[Activity]) -> ActivitiesAggregati
 /// All metrics — streaks, weekday patterns, calories, effort, etc. — are derived
 /// in one iteration. The only secondary pass is deduplication of sorted date/week
 /// vectors which is O(n log n) and kept separate for clarity.
-pub fn compute_advanced_aggregation(activities: &[Activity]) -> AdvancedAggregation {
+pub fn compute_advanced_aggregation(activities: &[Activity], tz: Tz) -> AdvancedAggregation {
+    let today = timezone::today(tz);
     if activities.is_empty() {
-        return AdvancedAggregation::default();
+        return AdvancedAggregation {
+            days_until_week_end: 6u32.saturating_sub(today.weekday().num_days_from_monday()),
+            ..AdvancedAggregation::default()
+        };
     }
 
     // ── Single-pass collection ─────────────────────────────────────────────
+    // Pace statistics are in seconds per km, over activities that have a pace.
     let mut weekday_pace_acc: HashMap<String, (f32, u32)> = HashMap::new();
     let mut weekday_counts: HashMap<String, u32> = HashMap::new();
     let mut week_pace_map: HashMap<IsoWeek, Vec<f32>> = HashMap::new();
@@ -148,7 +152,7 @@ pub fn compute_advanced_aggregation(activities: &[Activity]) -> AdvancedAggregat
     let mut speed_list: Vec<f32> = Vec::new();
     let mut max_climb: f32 = 0.0;
     let mut max_effort_cal_per_min: f32 = 0.0;
-    let mut slowest_pace: f32 = 0.0;
+    let mut slowest_pace = Pace::default();
     let mut total_weekend_sessions: u32 = 0;
     let total_sessions = activities.len() as u32;
 
@@ -157,26 +161,34 @@ pub fn compute_advanced_aggregation(activities: &[Activity]) -> AdvancedAggregat
     let mut raw_weeks: Vec<IsoWeek> = Vec::new();
 
     for a in activities {
-        let weekday = a.date.weekday().to_string();
+        let day = a.local_date(tz);
+        let weekday = day.weekday().to_string();
 
         *weekday_counts.entry(weekday.clone()).or_default() += 1;
 
-        let pace_acc = weekday_pace_acc.entry(weekday.clone()).or_default();
-        pace_acc.0 += a.average_pace;
-        pace_acc.1 += 1;
+        let week = day.iso_week();
+        if a.average_pace.is_set() {
+            let pace = a.average_pace.secs_per_km() as f32;
 
-        let week = a.date.iso_week();
-        week_pace_map.entry(week).or_default().push(a.average_pace);
-        *day_calories.entry(a.date.date()).or_default() += a.calories;
-        *calories_per_week.entry(week).or_default() += a.calories;
+            let pace_acc = weekday_pace_acc.entry(weekday.clone()).or_default();
+            pace_acc.0 += pace;
+            pace_acc.1 += 1;
+
+            week_pace_map.entry(week).or_default().push(pace);
+
+            let h_entry = hour_buckets.entry(a.local_start(tz).hour()).or_default();
+            h_entry.0 += pace;
+            h_entry.1 += 1;
 
-        let h_entry = hour_buckets.entry(a.date.hour()).or_default();
-        h_entry.0 += a.average_pace;
-        h_entry.1 += 1;
+            pace_list.push(pace);
+            if a.average_pace > slowest_pace {
+                slowest_pace = a.average_pace;
+            }
+        }
+        *day_calories.entry(day).or_default() += a.calories;
+        *calories_per_week.entry(week).or_default() += a.calories;
 
-        pace_list.push(a.average_pace);
         speed_list.push(a.average_speed);
-        slowest_pace = f32::max(slowest_pace, a.average_pace);
         max_climb = f32::max(max_climb, a.climb);
 
         if let Ok(time) = NaiveTime::parse_from_str(&a.duration, "%H:%M:%S") {
@@ -188,13 +200,13 @@ pub fn compute_advanced_aggregation(activities: &[Activity]) -> AdvancedAggregat
         }
 
         if matches!(
-            a.date.weekday(),
+            day.weekday(),
             chrono::Weekday::Sat | chrono::Weekday::Sun
         ) {
             total_weekend_sessions += 1;
         }
 
-        raw_dates.push(a.date.date());
+        raw_dates.push(day);
         raw_weeks.push(week);
     }
 
@@ -229,7 +241,6 @@ pub fn compute_advanced_aggregation(activities: &[Activity]) -> AdvancedAggregat
         }
     }
 
-    let today = chrono::Utc::now().naive_utc().date();
     let week_set: std::collections::HashSet<IsoWeek> = raw_weeks.into_iter().collect();
     let mut current_weekly_streak: u32 = 0;
     let mut week_iter = today.iso_week();
@@ -270,7 +281,7 @@ pub fn compute_advanced_aggregation(activities: &[Activity]) -> AdvancedAggregat
 
     let (streak_runs_this_week, streak_distance_this_week) = activities
         .iter()
-        .filter(|a| a.date.date().iso_week() == this_week)
+        .filter(|a| a.local_date(tz).iso_week() == this_week)
         .fold((0u32, 0.0f32), |(runs, dist), a| {
             (runs + 1, dist + a.distance)
         });
@@ -309,7 +320,7 @@ pub fn compute_advanced_aggregation(activities: &[Activity]) -> AdvancedAggregat
 
     let (streak_total_runs, streak_total_km) = activities
         .iter()
-        .filter(|a| streak_weeks.contains(&a.date.date().iso_week()))
+        .filter(|a| streak_weeks.contains(&a.local_date(tz).iso_week()))
         .fold((0u32, 0.0f32), |(runs, dist), a| {
             (runs + 1, dist + a.distance)
         });
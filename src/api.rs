@@ -11,9 +11,14 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::achievements::models::{AchievementWithStatus, UnlockedAchievementSummary};
+use crate::activities::lap_kind::LapKind;
 use crate::activities::models::{
-    ActivitiesResponse, Activity, ActivityDetailResponse, HeatmapPoint, HeatmapQuery, TrackPoint,
-    UploadForm, UploadResponse,
+    ActivitiesResponse, Activity, ActivityChangeResponse, ActivityDetailResponse,
+    ActivityListQuery, ActivitySort, CreateActivityRequest, HeatmapPoint, HeatmapQuery, HeatmapTileQuery,
+    UpdateActivityRequest, GpxMatch,
+    IngestOutcome, IngestReason, IngestReportEntry, Lap, PreviewActivity, Split, SplitUnit,
+    Track, TrackFormat, TrackMetrics, TrackPoint,
+    UploadForm, UploadPreview, UploadResponse,
 };
 use crate::challenges::models::{
     ActivateChallengeRequest, AddRequirementRequest, Challenge, ChallengeDetail, ChallengeSummary,
@@ -23,8 +28,8 @@ use crate::challenges::models::{
     UpdateWorkoutRequest, WorkoutRequirement, WorkoutWithDetails, WorkoutLink,
 };
 use crate::challenges::ChallengeStatus;
-use crate::users::models::{CreateUser, User};
-use crate::personal_records::models::{PersonalRecordsResponse, PersonalRecordSummary, PrCategorySummary};
+use crate::users::models::{CreateUser, UpdateUser, User};
+use crate::personal_records::models::{BestEffort, PersonalRecordsResponse, PersonalRecordSummary, PrCategorySummary};
 use crate::missions::handler::{MissionHistoryEntry, MissionHistoryResponse};
 use crate::missions::common::CompletedMissionSummary;
 use crate::monthly_missions::models::{MonthlyMission, MonthlyMissionsResponse};
@@ -35,19 +40,52 @@ use crate::goals::models::{
     CreateGoalRequest, CreateGoalRequirementRequest,
 };
 use crate::goals::requirement_type::{GoalMetricType, GoalFilterType};
-use crate::{achievements, activities, challenges, goals, missions, monthly_missions, personal_records, strava, users, weekly_missions, xp};
+use crate::uploads::models::{UploadJob, UploadJobAccepted};
+use crate::uploads::status::UploadJobStatus;
+use crate::duplicates::models::{
+    ActivityDuplicate, DuplicateEntry, ResolveAction, ResolveDuplicateRequest,
+    ResolveDuplicateResponse,
+};
+use crate::duplicates::status::DuplicateStatus;
+use crate::explorer::models::{ActivityTiles, ExplorerSummary};
+use crate::routes::models::{Route, RouteRun, RouteSummary, RoutesQuery, UpdateRouteRequest};
+use crate::segments::models::{
+    CreateSegmentRequest, Segment, SegmentDetail, SegmentEffort, SegmentLeaderboard,
+    SegmentLeaderboardEntry, SegmentPoint, SegmentSummary,
+};
+use crate::{achievements, activities, challenges, duplicates, explorer, goals, missions, monthly_missions, personal_records, routes, segments, strava, uploads, users, weekly_missions, xp};
 use crate::strava::client::StravaClient;
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         activities::handlers::get_activities,
+        activities::handlers::create_activity,
+        activities::handlers::update_activity,
+        activities::handlers::delete_activity,
         activities::handlers::get_activity_detail,
         activities::handlers::get_trackpoints,
+        activities::handlers::get_splits,
+        activities::handlers::clean_trackpoints,
         activities::handlers::get_heatmap,
+        activities::handlers::get_heatmap_tile,
         activities::handlers::upload_files,
+        uploads::handlers::get_upload_job,
+        duplicates::handlers::list_duplicates,
+        duplicates::handlers::resolve_duplicate,
+        segments::handlers::create_segment,
+        segments::handlers::list_segments,
+        segments::handlers::get_segment,
+        segments::handlers::delete_segment,
+        segments::handlers::get_leaderboard,
+        routes::handlers::list_routes,
+        routes::handlers::get_route,
+        routes::handlers::update_route,
+        explorer::handlers::get_summary,
+        explorer::handlers::list_activity_tiles,
         users::handlers::get_user,
         users::handlers::create_user,
+        users::handlers::update_user,
         challenges::handlers::list_challenges,
         challenges::handlers::create_challenge,
         challenges::handlers::get_challenge,
@@ -83,12 +121,32 @@ use crate::strava::client::StravaClient;
         ActivitiesResponse,
         ActivityDetailResponse,
         TrackPoint,
+        Track,
+        TrackFormat,
+        TrackMetrics,
+        Split,
+        SplitUnit,
+        Lap,
+        LapKind,
         UploadForm,
         UploadResponse,
+        IngestReportEntry,
+        IngestOutcome,
+        IngestReason,
+        UploadPreview,
+        PreviewActivity,
+        CreateActivityRequest,
+        UpdateActivityRequest,
+        ActivityChangeResponse,
+        ActivityListQuery,
+        ActivitySort,
+        GpxMatch,
         HeatmapPoint,
         HeatmapQuery,
+        HeatmapTileQuery,
         User,
         CreateUser,
+        UpdateUser,
         Challenge,
         ChallengeSummary,
         ChallengeDetail,
@@ -116,6 +174,7 @@ use crate::strava::client::StravaClient;
         AchievementWithStatus,
         UnlockedAchievementSummary,
         PersonalRecordsResponse,
+        BestEffort,
         PersonalRecordSummary,
         PrCategorySummary,
         WeeklyMission,
@@ -134,6 +193,30 @@ use crate::strava::client::StravaClient;
         CreateGoalRequirementRequest,
         GoalMetricType,
         GoalFilterType,
+        UploadJob,
+        UploadJobAccepted,
+        UploadJobStatus,
+        ActivityDuplicate,
+        DuplicateEntry,
+        DuplicateStatus,
+        ResolveAction,
+        ResolveDuplicateRequest,
+        ResolveDuplicateResponse,
+        Segment,
+        SegmentEffort,
+        SegmentPoint,
+        CreateSegmentRequest,
+        SegmentDetail,
+        SegmentSummary,
+        SegmentLeaderboardEntry,
+        SegmentLeaderboard,
+        Route,
+        RouteRun,
+        RouteSummary,
+        RoutesQuery,
+        UpdateRouteRequest,
+        ExplorerSummary,
+        ActivityTiles,
     )),
     tags(
         (name = "Activities",       description = "Activity management"),
@@ -144,6 +227,11 @@ use crate::strava::client::StravaClient;
         (name = "personal_records", description = "Personal records"),
         (name = "missions",         description = "Weekly, monthly missions and history"),
         (name = "goals",            description = "User-defined goals"),
+        (name = "uploads",          description = "Background upload jobs"),
+        (name = "duplicates",       description = "Cross-source duplicate activities"),
+        (name = "segments",         description = "User-defined segments, efforts and leaderboards"),
+        (name = "routes",           description = "Repeated routes and their run history"),
+        (name = "explorer",         description = "Map tiles ever visited"),
     )
 )]
 struct ApiDoc;
@@ -195,6 +283,21 @@ pub async fn run_api(db_pool: PgPool) -> std::io::Result<()> {
 
     info!("Database migrations applied successfully.");
 
+    match uploads::repository::fail_interrupted_jobs(&db_pool).await {
+        Ok(0) => {}
+        Ok(n) => info!("Marked {} interrupted upload jobs as failed.", n),
+        Err(e) => tracing::warn!("Could not clean up interrupted upload jobs: {e}"),
+    }
+
+    let backfill_pool = db_pool.clone();
+    tokio::spawn(async move {
+        match routes::service::backfill(&backfill_pool).await {
+            Ok(0) => {}
+            Ok(n) => info!("Clustered {} activities stored before routes existed.", n),
+            Err(e) => tracing::warn!("Could not cluster activities into routes: {e}"),
+        }
+    });
+
     let port: u16 = env::var("PORT")
         .unwrap_or_else(|_| "8080".to_string())
         .parse()
@@ -244,6 +347,11 @@ pub async fn run_api(db_pool: PgPool) -> std::io::Result<()> {
             .configure(missions::handler::configure)
             .configure(strava::configure)
             .configure(goals::configure)
+            .configure(uploads::configure)
+            .configure(duplicates::configure)
+            .configure(segments::configure)
+            .configure(routes::configure)
+            .configure(explorer::configure)
             .service(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
     })
     .bind(("0.0.0.0", port))?
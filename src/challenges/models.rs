@@ -228,7 +228,8 @@ pub enum GoalType {
 pub struct GenerateChallengeRequest {
     pub user_id: Uuid,
     pub goal_type: GoalType,
-    /// Target pace in M.SS format (e.g. 5.41 = 5:41/km).
+    /// Target pace in M.SS format (e.g. 5.41 = 5:41/km), converted to
+    /// seconds per km for the generated requirements.
     /// Defaults to 5.41 for half marathon and 5.00 for 5 km.
     pub target_pace_mss: Option<f64>,
     /// Override the plan length in weeks.
@@ -1,4 +1,6 @@
 
+use crate::activities::pace::Pace;
+
 use super::models::{GenerateChallengeRequest, GoalType};
 use super::requirement_type::RequirementType;
 
@@ -20,7 +22,10 @@ pub struct GeneratedRequirement {
 // ─── Entry point ─────────────────────────────────────────────────────────────
 
 pub fn generate_plan(req: &GenerateChallengeRequest) -> (String, Vec<GeneratedWorkout>) {
-    let target = req.target_pace_mss.unwrap_or_else(|| default_pace(req.goal_type));
+    let target = req
+        .target_pace_mss
+        .map(Pace::from_mss)
+        .unwrap_or_else(|| default_pace(req.goal_type));
     let weeks = req.weeks.unwrap_or_else(|| default_weeks(req.goal_type));
     let description = format_description(req.goal_type, target, weeks);
 
@@ -34,36 +39,20 @@ pub fn generate_plan(req: &GenerateChallengeRequest) -> (String, Vec<GeneratedWo
 
 // ─── Pace helpers ────────────────────────────────────────────────────────────
 
-/// Convert M.SS float to total seconds.  5.41 → 5*60 + 41 = 341.
-fn mss_to_sec(mss: f64) -> f64 {
-    let mins = mss.floor();
-    let secs = (mss - mins) * 100.0;
-    mins * 60.0 + secs
-}
-
-/// Convert total seconds to M.SS float.  341 → 5.41.
-fn sec_to_mss(sec: f64) -> f64 {
-    let mins = (sec / 60.0).floor();
-    let secs = sec % 60.0;
-    mins + secs / 100.0
-}
-
-/// Format M.SS pace as "M:SS/km" for display in workout description.
-fn fmt_pace(mss: f64) -> String {
-    let mins = mss.floor() as u32;
-    let secs = ((mss - mss.floor()) * 100.0).round() as u32;
-    format!("{mins}:{secs:02}/km")
+/// Format a pace as "M:SS/km" for display in workout description.
+fn fmt_pace(pace: Pace) -> String {
+    format!("{pace}/km")
 }
 
-/// Add `offset_sec` seconds to a M.SS pace (slower = higher value).
-fn pace_plus(mss: f64, offset_sec: f64) -> f64 {
-    sec_to_mss(mss_to_sec(mss) + offset_sec)
+/// Add `offset_sec` seconds per km to a pace (slower = higher value).
+fn pace_plus(pace: Pace, offset_sec: f64) -> Pace {
+    Pace::from_secs_per_km(pace.secs_per_km() + offset_sec)
 }
 
-fn default_pace(goal_type: GoalType) -> f64 {
+fn default_pace(goal_type: GoalType) -> Pace {
     match goal_type {
-        GoalType::FiveKImprovement => 5.00,  // 5:00/km
-        GoalType::Sub2HalfMarathon => 5.41, // ~5:41/km → 2h half marathon
+        GoalType::FiveKImprovement => Pace::from_secs_per_km(300.0), // 5:00/km
+        GoalType::Sub2HalfMarathon => Pace::from_secs_per_km(341.0), // ~5:41/km → 2h half marathon
     }
 }
 
@@ -74,7 +63,7 @@ fn default_weeks(goal_type: GoalType) -> u32 {
     }
 }
 
-fn format_description(goal_type: GoalType, target: f64, weeks: u32) -> String {
+fn format_description(goal_type: GoalType, target: Pace, weeks: u32) -> String {
     let pace_str = fmt_pace(target);
     match goal_type {
         GoalType::FiveKImprovement => format!(
@@ -96,10 +85,10 @@ fn dist_req(km: f64) -> GeneratedRequirement {
     }
 }
 
-fn pace_req(mss: f64) -> GeneratedRequirement {
+fn pace_req(pace: Pace) -> GeneratedRequirement {
     GeneratedRequirement {
         requirement_type: RequirementType::PaceFasterThan,
-        value: Some(mss_to_sec(mss)), // store as seconds/km (same unit as seed data)
+        value: Some(pace.secs_per_km()), // requirement values are seconds/km
         params: serde_json::json!({}),
     }
 }
@@ -128,7 +117,7 @@ fn workout(
 
 // ─── Half Marathon plan ──────────────────────────────────────────────────────
 
-fn build_half_marathon_plan(target: f64, weeks: u32) -> Vec<GeneratedWorkout> {
+fn build_half_marathon_plan(target: Pace, weeks: u32) -> Vec<GeneratedWorkout> {
     let easy = pace_plus(target, 90.0);
     let long_run = pace_plus(target, 60.0);
     let tempo = pace_plus(target, 30.0);
@@ -269,7 +258,7 @@ fn build_half_marathon_plan(target: f64, weeks: u32) -> Vec<GeneratedWorkout> {
 
 // ─── 5 km improvement plan ───────────────────────────────────────────────────
 
-fn build_5k_plan(target: f64, weeks: u32) -> Vec<GeneratedWorkout> {
+fn build_5k_plan(target: Pace, weeks: u32) -> Vec<GeneratedWorkout> {
     let easy = pace_plus(target, 75.0); // +1:15/km
     let tempo = pace_plus(target, 15.0); // +15 sec/km
 
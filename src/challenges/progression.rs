@@ -5,11 +5,13 @@
 /// progression state (challenge created/updated, activities uploaded).
 use std::collections::{HashMap, HashSet};
 
+use chrono_tz::Tz;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::activities;
 use crate::error::AppError;
+use crate::users::{self, timezone};
 
 use super::models::{Challenge, WorkoutLink, WorkoutRequirement, WorkoutState};
 use super::requirement_type::RequirementType;
@@ -26,13 +28,15 @@ pub enum ProgressionTrigger {
     ChallengeStartDateChanged { challenge_id: Uuid },
     /// New activities were uploaded for a user.
     ActivitiesUploaded { user_id: Uuid },
+    /// One of the user's activities was edited or deleted.
+    ActivitiesChanged { user_id: Uuid },
     /// A challenge just transitioned from PendingActivation → Active (lazy).
     ChallengeActivated { challenge_id: Uuid },
 }
 
 /// Dispatch a trigger event to the appropriate recalculation path.
 ///
-/// For `ActivitiesUploaded`, activities are loaded **once** for the user
+/// For `ActivitiesUploaded` and `ActivitiesChanged`, activities are loaded **once** for the user
 /// and reused across all of the user's active challenges for efficiency.
 pub async fn handle(db: &PgPool, trigger: ProgressionTrigger) -> Result<(), AppError> {
     match trigger {
@@ -44,7 +48,8 @@ pub async fn handle(db: &PgPool, trigger: ProgressionTrigger) -> Result<(), AppE
             recalculate_one(db, challenge_id).await?;
         }
 
-        ProgressionTrigger::ActivitiesUploaded { user_id } => {
+        ProgressionTrigger::ActivitiesUploaded { user_id }
+        | ProgressionTrigger::ActivitiesChanged { user_id } => {
             // First, apply any pending lazy transitions for the user's challenges.
             let transitioning =
                 repository::find_transitioning_challenges_for_user(db, user_id).await?;
@@ -70,10 +75,11 @@ pub async fn handle(db: &PgPool, trigger: ProgressionTrigger) -> Result<(), AppE
                     db, user_id, earliest_from, None,
                 )
                 .await?;
+            let tz = users::service::timezone(db, user_id).await;
 
             for challenge in &challenges {
                 if let Err(e) =
-                    recalculate_with_activities(db, challenge, &all_activities).await
+                    recalculate_with_activities(db, challenge, &all_activities, tz).await
                 {
                     // Progression failure must not abort the upload response.
                     tracing::warn!(
@@ -108,14 +114,16 @@ async fn recalculate_one(db: &PgPool, challenge_id: Uuid) -> Result<usize, AppEr
         challenge.ends_at,
     )
     .await?;
+    let tz = users::service::timezone(db, challenge.user_id).await;
 
-    recalculate_with_activities(db, &challenge, &activities).await
+    recalculate_with_activities(db, &challenge, &activities, tz).await
 }
 
 /// Core algorithm.
 ///
 /// Activity list must already be sorted by date ASC and filtered to
-/// `[challenge.started_at, challenge.ends_at]`.
+/// `[challenge.started_at, challenge.ends_at]`.  Day-based requirements
+/// count calendar days in the owner's zone `tz`.
 ///
 /// Steps:
 ///  1. Acquire a per-challenge advisory lock to prevent concurrent runs.
@@ -130,6 +138,7 @@ async fn recalculate_with_activities(
     db: &PgPool,
     challenge: &Challenge,
     all_activities: &[crate::activities::models::Activity],
+    tz: Tz,
 ) -> Result<usize, AppError> {
     let Some(from) = challenge.started_at else {
         return Ok(0);
@@ -145,8 +154,7 @@ async fn recalculate_with_activities(
     let activities: Vec<_> = all_activities
         .iter()
         .filter(|a| {
-            let dt = a.date.and_utc();
-            dt >= from && challenge.ends_at.is_none_or(|end| dt <= end)
+            a.date >= from && challenge.ends_at.is_none_or(|end| a.date <= end)
         })
         .collect();
 
@@ -196,7 +204,7 @@ async fn recalculate_with_activities(
             if used.contains(&activity.id) {
                 continue;
             }
-            if evaluate_requirements(reqs, activity, challenge, prev_activity) {
+            if evaluate_requirements(reqs, activity, challenge, prev_activity, tz) {
                 links.push((workout.id, activity.id));
                 used.insert(activity.id);
                 prev_activity = Some(activity);
@@ -217,40 +225,13 @@ async fn recalculate_with_activities(
 
 // ─── Requirement evaluation ────────────────────────────────────────────────────
 
-/// Normalise `activity.average_pace` to **seconds per km**.
-///
-/// Two storage formats exist depending on the activity source:
-/// * Runkeeper (`source == "runkeeper"`): M.SS float, e.g. `6.56` = 6:56/km = 416 s/km.
-/// * Strava / others: decimal min/km, e.g. `6.9328895` ≈ 6:56/km = 415.97 s/km.
+/// `activity.average_pace` in seconds per km; an activity without a pace
+/// counts as infinitely slow.
 fn activity_pace_sec_per_km(activity: &crate::activities::models::Activity) -> f64 {
-    let p = activity.average_pace as f64;
-    if p <= 0.0 {
-        return f64::MAX; // no valid pace — treat as infinitely slow
-    }
-    if activity.source == "runkeeper" {
-        // M.SS → sec: 6.56 → 6 * 60 + 56 = 416 s/km
-        let mins = p.floor();
-        let secs = (p - mins) * 100.0;
-        mins * 60.0 + secs
-    } else {
-        // Strava / others store as decimal min/km → sec/km
-        p * 60.0
-    }
-}
-
-/// Normalise a pace requirement `value` to **seconds per km**.
-///
-/// Two formats are persisted in the DB:
-/// * Seed data / manually set: already in s/km (`value >= 60`), e.g. `420` = 7:00/km.
-/// * Plan-generator output: M.SS float (`value < 60`), e.g. `5.41` = 5:41/km = 341 s/km.
-fn req_threshold_sec_per_km(value: f64) -> f64 {
-    if value >= 60.0 {
-        value // already in seconds per km
+    if activity.average_pace.is_set() {
+        activity.average_pace.secs_per_km()
     } else {
-        // M.SS → sec
-        let mins = value.floor();
-        let secs = (value - mins) * 100.0;
-        mins * 60.0 + secs
+        f64::MAX
     }
 }
 
@@ -266,6 +247,7 @@ pub(crate) fn evaluate_workout_state(
     activity_map: &HashMap<Uuid, crate::activities::models::Activity>,
     challenge: &Challenge,
     previous_activity: Option<&crate::activities::models::Activity>,
+    tz: Tz,
 ) -> WorkoutState {
     let Some(link) = link else {
         return WorkoutState::NotStarted;
@@ -281,7 +263,7 @@ pub(crate) fn evaluate_workout_state(
         return WorkoutState::Completed;
     }
 
-    if evaluate_requirements(requirements, activity, challenge, previous_activity) {
+    if evaluate_requirements(requirements, activity, challenge, previous_activity, tz) {
         WorkoutState::Completed
     } else {
         WorkoutState::Failed
@@ -295,31 +277,32 @@ pub(crate) fn evaluate_requirements(
     activity: &crate::activities::models::Activity,
     challenge: &Challenge,
     previous_activity: Option<&crate::activities::models::Activity>,
+    tz: Tz,
 ) -> bool {
     if requirements.is_empty() {
         return true;
     }
     requirements
         .iter()
-        .all(|req| evaluate_single_requirement(req, activity, challenge, previous_activity))
+        .all(|req| evaluate_single_requirement(req, activity, challenge, previous_activity, tz))
 }
 
 /// Returns `true` if the activity satisfies one requirement.
 ///
-/// Activity numeric fields (`distance`, `average_pace`) are `f32`; we cast
+/// Activity numeric fields (`distance`, `climb`, …) are `f32`; we cast
 /// to `f64` for comparison against `requirement.value: Option<f64>`.
+/// Pace requirement values are seconds per km, like `Pace`.
 fn evaluate_single_requirement(
     req: &WorkoutRequirement,
     activity: &crate::activities::models::Activity,
     challenge: &Challenge,
     previous_activity: Option<&crate::activities::models::Activity>,
+    tz: Tz,
 ) -> bool {
     match req.requirement_type {
         RequirementType::PaceFasterThan => {
-            // Lower s/km = faster. Compare in a common unit: seconds per km.
-            let threshold = req.value
-                .map(req_threshold_sec_per_km)
-                .unwrap_or(f64::MAX);
+            // Lower s/km = faster; requirement values are seconds per km.
+            let threshold = req.value.unwrap_or(f64::MAX);
             activity_pace_sec_per_km(activity) < threshold
         }
 
@@ -332,8 +315,8 @@ fn evaluate_single_requirement(
             let Some(started_at) = challenge.started_at else {
                 return false;
             };
-            let start_date = started_at.date_naive();
-            let activity_date = activity.date.date();
+            let start_date = timezone::local_date(tz, started_at);
+            let activity_date = activity.local_date(tz);
             let days = (activity_date - start_date).num_days();
             let threshold = req.value.unwrap_or(0.0) as i64;
             days >= threshold
@@ -354,7 +337,7 @@ fn evaluate_single_requirement(
             else {
                 return false;
             };
-            let activity_date = activity.date.date();
+            let activity_date = activity.local_date(tz);
             let days = (activity_date - first_date).num_days();
             let threshold = req.value.unwrap_or(0.0) as i64;
             days >= threshold
@@ -383,9 +366,7 @@ fn evaluate_single_requirement(
         }
 
         RequirementType::PaceSlowerThan => {
-            let threshold = req.value
-                .map(req_threshold_sec_per_km)
-                .unwrap_or(0.0);
+            let threshold = req.value.unwrap_or(0.0);
             activity_pace_sec_per_km(activity) > threshold
         }
 
@@ -420,7 +401,7 @@ fn evaluate_single_requirement(
                 // No previous workout at position 1 → pass automatically.
                 return true;
             };
-            let days = (activity.date.date() - prev.date.date()).num_days();
+            let days = (activity.local_date(tz) - prev.local_date(tz)).num_days();
             days >= req.value.unwrap_or(0.0) as i64
         }
 
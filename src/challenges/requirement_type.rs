@@ -19,12 +19,14 @@ use utoipa::ToSchema;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum RequirementType {
+    /// `value` in seconds per km.
     PaceFasterThan,
     DistanceLongerThan,
     DaysSinceChallengeStart,
     DaysSinceFirstWorkout,
     FasterThanPrevious,
     DurationLongerThan,
+    /// `value` in seconds per km.
     PaceSlowerThan,
     ClimbAtLeast,
     CaloriesAtLeast,
@@ -9,6 +9,7 @@ use uuid::Uuid;
 
 use crate::activities;
 use crate::error::AppError;
+use crate::users;
 
 use super::models::{
     ActivateChallengeRequest, AddRequirementRequest, Challenge, ChallengeDetail,
@@ -75,6 +76,7 @@ pub async fn get_challenge_detail(
         .collect();
     let activity_map =
         activities::repository::find_activities_by_ids(db, &activity_ids).await?;
+    let tz = users::service::timezone(db, challenge.user_id).await;
 
     // Assemble WorkoutWithDetails in position order, tracking the previous
     // linked activity so faster_than_previous can be evaluated correctly.
@@ -94,6 +96,7 @@ pub async fn get_challenge_detail(
             &activity_map,
             &challenge,
             prev_act,
+            tz,
         );
 
         // Advance tracker only for completed workouts.
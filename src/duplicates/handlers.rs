@@ -0,0 +1,62 @@
+use actix_web::{get, post, web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+use super::{
+    models::{DuplicateListQuery, ResolveDuplicateRequest},
+    service,
+};
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/duplicates",
+    tag = "duplicates",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID"),
+        ("status" = Option<super::status::DuplicateStatus>, Query, description = "suspected (default), merged or dismissed")
+    ),
+    responses(
+        (status = 200, description = "Cross-source duplicate pairs with both activities", body = Vec<super::models::DuplicateEntry>),
+        (status = 400, description = "Invalid UUID or status")
+    )
+)]
+#[get("/users/{user_id}/duplicates")]
+pub async fn list_duplicates(
+    db: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    query: web::Query<DuplicateListQuery>,
+) -> Result<HttpResponse, AppError> {
+    let entries =
+        service::list_duplicates(db.get_ref(), path.into_inner(), query.into_inner().status)
+            .await?;
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{user_id}/duplicates/{duplicate_id}/resolve",
+    tag = "duplicates",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID"),
+        ("duplicate_id" = Uuid, Path, description = "Duplicate pair ID")
+    ),
+    request_body = ResolveDuplicateRequest,
+    responses(
+        (status = 200, description = "Pair merged (one activity deleted, its effects reversed) or dismissed", body = super::models::ResolveDuplicateResponse),
+        (status = 400, description = "Pair already resolved, or `keep` is not part of it"),
+        (status = 404, description = "Unknown pair")
+    )
+)]
+#[post("/users/{user_id}/duplicates/{duplicate_id}/resolve")]
+pub async fn resolve_duplicate(
+    db: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+    body: web::Json<ResolveDuplicateRequest>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, duplicate_id) = path.into_inner();
+    let result =
+        service::resolve_duplicate(db.get_ref(), user_id, duplicate_id, body.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
@@ -0,0 +1,108 @@
+/// Fuzzy matching of activities imported from different sources.
+///
+/// Pure scoring only — no I/O.  `service` loads the candidates and decides
+/// what to do with the score.
+use chrono::{DateTime, Utc};
+
+/// Score at or above which two activities are merged automatically.
+pub const AUTO_MERGE_SCORE: f32 = 0.85;
+/// Score at or above which a pair is kept for manual review.
+pub const SUSPECT_SCORE: f32 = 0.6;
+/// Only activities starting within this many minutes of each other are compared.
+pub const WINDOW_MINUTES: i64 = 15;
+
+/// Start-time difference that still scores 1.0, in seconds.
+const TIME_EXACT_SECS: f64 = 60.0;
+/// Relative distance/duration difference that still scores 1.0.
+const REL_EXACT: f64 = 0.02;
+/// Relative distance/duration difference that scores 0.
+const REL_ZERO: f64 = 0.20;
+/// A point overlaps the other track if it lies within this many metres of it.
+const OVERLAP_METRES: f64 = 50.0;
+/// Points sampled from each track for the overlap test.
+const OVERLAP_SAMPLES: usize = 50;
+
+/// What the matcher compares: the summary plus the GPS track (may be empty).
+#[derive(Debug, Clone)]
+pub struct Fingerprint {
+    pub date: DateTime<Utc>,
+    /// Kilometres.
+    pub distance_km: f32,
+    pub duration_secs: i64,
+    /// `(latitude, longitude)` pairs in track order.
+    pub track: Vec<(f64, f64)>,
+}
+
+/// Similarity of two activities in `0.0..=1.0`.
+///
+/// Weighted mean of start time (0.35), distance (0.3), duration (0.15) and
+/// track overlap (0.2).  When either activity has no track, the overlap term
+/// is dropped and the other weights are rescaled.
+pub fn score(a: &Fingerprint, b: &Fingerprint) -> f32 {
+    let dt = (a.date - b.date).num_seconds().unsigned_abs() as f64;
+    let window = (WINDOW_MINUTES * 60) as f64;
+    let time = if dt <= TIME_EXACT_SECS {
+        1.0
+    } else {
+        (1.0 - (dt - TIME_EXACT_SECS) / (window - TIME_EXACT_SECS)).max(0.0)
+    };
+    let distance = relative_similarity(a.distance_km as f64, b.distance_km as f64);
+    let duration = relative_similarity(a.duration_secs as f64, b.duration_secs as f64);
+
+    let mut total = 0.35 * time + 0.3 * distance + 0.15 * duration;
+    let mut weight = 0.8;
+    if !a.track.is_empty() && !b.track.is_empty() {
+        let overlap = (track_overlap(&a.track, &b.track) + track_overlap(&b.track, &a.track)) / 2.0;
+        total += 0.2 * overlap;
+        weight += 0.2;
+    }
+    (total / weight) as f32
+}
+
+/// 1.0 when the values agree within `REL_EXACT`, falling linearly to 0 at `REL_ZERO`.
+fn relative_similarity(a: f64, b: f64) -> f64 {
+    let larger = a.abs().max(b.abs());
+    if larger == 0.0 {
+        return 1.0;
+    }
+    let rel = (a - b).abs() / larger;
+    if rel <= REL_EXACT {
+        1.0
+    } else {
+        (1.0 - (rel - REL_EXACT) / (REL_ZERO - REL_EXACT)).max(0.0)
+    }
+}
+
+/// Fraction of sampled points of `a` lying within `OVERLAP_METRES` of `b`.
+fn track_overlap(a: &[(f64, f64)], b: &[(f64, f64)]) -> f64 {
+    let step = a.len().div_ceil(OVERLAP_SAMPLES).max(1);
+    let sampled: Vec<&(f64, f64)> = a.iter().step_by(step).collect();
+    let near = sampled
+        .iter()
+        .filter(|p| b.iter().any(|q| approx_distance_m(p, q) <= OVERLAP_METRES))
+        .count();
+    near as f64 / sampled.len() as f64
+}
+
+/// Equirectangular distance in metres — accurate enough at overlap scale.
+fn approx_distance_m(a: &(f64, f64), b: &(f64, f64)) -> f64 {
+    const EARTH_RADIUS_M: f64 = 6_371_000.0;
+    let mean_lat = ((a.0 + b.0) / 2.0).to_radians();
+    let x = (b.1 - a.1).to_radians() * mean_lat.cos();
+    let y = (b.0 - a.0).to_radians();
+    (x * x + y * y).sqrt() * EARTH_RADIUS_M
+}
+
+/// Which record becomes canonical when two sources describe the same run:
+/// device files with sensor data first, summaries without a track last.
+pub fn source_priority(source: &str) -> u8 {
+    match source {
+        "fit" => 6,
+        "tcx" => 5,
+        "strava" => 4,
+        "gpx" => 3,
+        "runkeeper" => 2,
+        "manual" => 1,
+        _ => 0,
+    }
+}
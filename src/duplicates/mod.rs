@@ -0,0 +1,13 @@
+pub mod handlers;
+pub mod matcher;
+pub mod models;
+pub mod repository;
+pub mod service;
+pub mod status;
+
+use actix_web::web;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers::list_duplicates)
+        .service(handlers::resolve_duplicate);
+}
@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::activities::models::{Activity, ActivityChangeResponse};
+use crate::sync::normalized::NormalizedActivity;
+
+use super::status::DuplicateStatus;
+
+/// Row of `activity_duplicates`.
+///
+/// The snapshot columns (`source` … `duration`) describe the record merged
+/// away and are only set once `status = "merged"`.
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ActivityDuplicate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// The canonical activity.
+    pub activity_id: Uuid,
+    /// The suspected duplicate, while the pair awaits review.
+    pub other_id: Option<Uuid>,
+    /// Match score in `0.0..=1.0`.
+    pub score: f32,
+    pub status: DuplicateStatus,
+    pub source: Option<String>,
+    pub external_id: Option<String>,
+    #[schema(value_type = Option<String>, format = "date-time")]
+    pub date: Option<DateTime<Utc>>,
+    pub name: Option<String>,
+    pub activity_type: Option<String>,
+    /// Kilometres.
+    pub distance: Option<f32>,
+    pub duration: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Summary of a record that was merged into a canonical activity.
+#[derive(Debug, Clone)]
+pub struct MergedRecord {
+    pub source: String,
+    pub external_id: Option<String>,
+    pub date: DateTime<Utc>,
+    pub name: String,
+    pub activity_type: String,
+    pub distance: f32,
+    pub duration: String,
+}
+
+impl From<&Activity> for MergedRecord {
+    fn from(a: &Activity) -> Self {
+        MergedRecord {
+            source: a.source.clone(),
+            external_id: a.external_id.clone(),
+            date: a.date,
+            name: a.name.clone(),
+            activity_type: a.activity_type.clone(),
+            distance: a.distance,
+            duration: a.duration.clone(),
+        }
+    }
+}
+
+impl From<&NormalizedActivity> for MergedRecord {
+    fn from(a: &NormalizedActivity) -> Self {
+        MergedRecord {
+            source: a.source.clone(),
+            external_id: a.external_id.clone(),
+            date: a.date,
+            name: a.name.clone(),
+            activity_type: a.activity_type.clone(),
+            distance: a.distance,
+            duration: a.duration.clone(),
+        }
+    }
+}
+
+/// One entry of `GET /users/{user_id}/duplicates`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DuplicateEntry {
+    #[serde(flatten)]
+    pub duplicate: ActivityDuplicate,
+    /// The canonical activity.
+    pub activity: Option<Activity>,
+    /// The suspected duplicate; `None` once resolved.
+    pub other: Option<Activity>,
+}
+
+/// Query parameters of `GET /users/{user_id}/duplicates`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DuplicateListQuery {
+    /// Defaults to `suspected`.
+    pub status: Option<DuplicateStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolveAction {
+    /// Keep one activity and delete the other, reversing its XP and rewards.
+    Merge,
+    /// The two activities are different runs; keep both.
+    Dismiss,
+}
+
+/// Body of `POST /users/{user_id}/duplicates/{duplicate_id}/resolve`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ResolveDuplicateRequest {
+    pub action: ResolveAction,
+    /// For `merge`: which of the two activities to keep.  Defaults to the one
+    /// from the higher-priority source (fit > tcx > strava > gpx > runkeeper > manual).
+    pub keep: Option<Uuid>,
+}
+
+/// Result of resolving a suspected duplicate.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ResolveDuplicateResponse {
+    pub duplicate: DuplicateEntry,
+    /// For `merge`: the reversed effects of the deleted activity.
+    pub removed: Option<ActivityChangeResponse>,
+}
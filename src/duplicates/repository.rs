@@ -0,0 +1,170 @@
+/// SQL layer for cross-source duplicates.
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+use super::{
+    models::{ActivityDuplicate, MergedRecord},
+    status::DuplicateStatus,
+};
+
+/// Record a pair for manual review.  A pair already under review is left as is.
+pub async fn insert_suspected(
+    db: &PgPool,
+    user_id: Uuid,
+    activity_id: Uuid,
+    other_id: Uuid,
+    score: f32,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO activity_duplicates (user_id, activity_id, other_id, score, status)
+         VALUES ($1, $2, $3, $4, 'suspected')
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(activity_id)
+    .bind(other_id)
+    .bind(score)
+    .execute(db)
+    .await
+    .map_err(AppError::from)?;
+    Ok(())
+}
+
+/// Record that `record` was merged into `activity_id`.  A record with the
+/// same source ID that was already merged is left as is.
+pub async fn insert_merged(
+    db: &PgPool,
+    user_id: Uuid,
+    activity_id: Uuid,
+    score: f32,
+    record: &MergedRecord,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO activity_duplicates
+            (user_id, activity_id, score, status, source, external_id, date, name,
+             activity_type, distance, duration, resolved_at)
+         VALUES ($1, $2, $3, 'merged', $4, $5, $6, $7, $8, $9, $10, NOW())
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(activity_id)
+    .bind(score)
+    .bind(&record.source)
+    .bind(&record.external_id)
+    .bind(record.date)
+    .bind(&record.name)
+    .bind(&record.activity_type)
+    .bind(record.distance)
+    .bind(&record.duration)
+    .execute(db)
+    .await
+    .map_err(AppError::from)?;
+    Ok(())
+}
+
+/// The merged records among `external_ids`, as `(source, external_id,
+/// canonical activity)`.
+pub async fn find_merged_into(
+    db: &PgPool,
+    user_id: Uuid,
+    external_ids: &[String],
+) -> Result<Vec<(String, String, Uuid)>, AppError> {
+    sqlx::query_as(
+        "SELECT source, external_id, activity_id FROM activity_duplicates
+         WHERE user_id = $1 AND external_id = ANY($2) AND status = 'merged'",
+    )
+    .bind(user_id)
+    .bind(external_ids)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn list(
+    db: &PgPool,
+    user_id: Uuid,
+    status: DuplicateStatus,
+) -> Result<Vec<ActivityDuplicate>, AppError> {
+    sqlx::query_as::<_, ActivityDuplicate>(
+        "SELECT * FROM activity_duplicates
+         WHERE user_id = $1 AND status = $2
+         ORDER BY created_at DESC",
+    )
+    .bind(user_id)
+    .bind(status)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn find(db: &PgPool, id: Uuid) -> Result<Option<ActivityDuplicate>, AppError> {
+    sqlx::query_as::<_, ActivityDuplicate>("SELECT * FROM activity_duplicates WHERE id = $1")
+        .bind(id)
+        .fetch_optional(db)
+        .await
+        .map_err(AppError::from)
+}
+
+pub async fn mark_dismissed(db: &PgPool, id: Uuid) -> Result<ActivityDuplicate, AppError> {
+    sqlx::query_as::<_, ActivityDuplicate>(
+        "UPDATE activity_duplicates
+         SET status = 'dismissed', resolved_at = NOW()
+         WHERE id = $1
+         RETURNING *",
+    )
+    .bind(id)
+    .fetch_one(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Turn a reviewed pair into a merge: `keep` becomes canonical and `record`
+/// is the summary of the activity about to be deleted.
+pub async fn mark_merged(
+    db: &mut PgConnection,
+    id: Uuid,
+    keep: Uuid,
+    record: &MergedRecord,
+) -> Result<ActivityDuplicate, AppError> {
+    sqlx::query_as::<_, ActivityDuplicate>(
+        "UPDATE activity_duplicates
+         SET status = 'merged', activity_id = $2, other_id = NULL,
+             source = $3, external_id = $4, date = $5, name = $6,
+             activity_type = $7, distance = $8, duration = $9, resolved_at = NOW()
+         WHERE id = $1
+         RETURNING *",
+    )
+    .bind(id)
+    .bind(keep)
+    .bind(&record.source)
+    .bind(&record.external_id)
+    .bind(record.date)
+    .bind(&record.name)
+    .bind(&record.activity_type)
+    .bind(record.distance)
+    .bind(&record.duration)
+    .fetch_one(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Move the merged records of `from` onto `to` before `from` is deleted,
+/// so they survive the cascade.
+pub async fn reassign_merged(
+    db: &mut PgConnection,
+    from: Uuid,
+    to: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE activity_duplicates SET activity_id = $2
+         WHERE activity_id = $1 AND status = 'merged'",
+    )
+    .bind(from)
+    .bind(to)
+    .execute(db)
+    .await
+    .map_err(AppError::from)?;
+    Ok(())
+}
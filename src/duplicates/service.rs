@@ -0,0 +1,303 @@
+/// Cross-source duplicate detection and manual review.
+///
+/// The ingest paths in `activities::service` ask a [`DuplicateMatcher`] about
+/// every new activity before inserting it and act on the returned [`Match`].
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    activities::{self, models::Activity},
+    error::AppError,
+    personal_records::models::parse_duration_to_secs,
+    sync::normalized::NormalizedActivity,
+};
+
+use super::{
+    matcher::{self, Fingerprint},
+    models::{
+        ActivityDuplicate, DuplicateEntry, MergedRecord, ResolveAction, ResolveDuplicateRequest,
+        ResolveDuplicateResponse,
+    },
+    repository,
+    status::DuplicateStatus,
+};
+
+/// How a new activity relates to the user's existing ones.
+#[derive(Debug)]
+pub enum Match {
+    /// Nothing similar from another source.
+    None,
+    /// Similar enough to review by hand; import it and record the pair.
+    Suspected { activity_id: Uuid, score: f32 },
+    /// The same run from another source; link it instead of double-counting.
+    Duplicate { existing: Box<Activity>, score: f32 },
+}
+
+/// Fingerprint of a stored activity, without its track.
+fn summary_fingerprint(a: &Activity) -> Fingerprint {
+    Fingerprint {
+        date: a.date,
+        distance_km: a.distance,
+        duration_secs: parse_duration_to_secs(&a.duration),
+        track: vec![],
+    }
+}
+
+/// The user's existing activities around the start times of one import batch.
+///
+/// Loaded once per batch; activities inserted during the batch are added with
+/// [`DuplicateMatcher::remember`] so one upload carrying the same run twice
+/// (e.g. its FIT and its GPX file) is caught as well.
+pub struct DuplicateMatcher {
+    existing: Vec<Activity>,
+    tracks: HashMap<Uuid, Vec<(f64, f64)>>,
+}
+
+impl DuplicateMatcher {
+    pub async fn load(
+        db: &PgPool,
+        user_id: Uuid,
+        dates: impl IntoIterator<Item = DateTime<Utc>>,
+    ) -> Result<Self, AppError> {
+        let window = Duration::minutes(matcher::WINDOW_MINUTES);
+        let (mut min, mut max) = (None::<DateTime<Utc>>, None::<DateTime<Utc>>);
+        for date in dates {
+            min = Some(min.map_or(date, |m| m.min(date)));
+            max = Some(max.map_or(date, |m| m.max(date)));
+        }
+        let existing = match (min, max) {
+            (Some(min), Some(max)) => {
+                activities::repository::find_activities_by_user_from(
+                    db,
+                    user_id,
+                    min - window,
+                    Some(max + window),
+                )
+                .await?
+            }
+            _ => vec![],
+        };
+        Ok(DuplicateMatcher {
+            existing,
+            tracks: HashMap::new(),
+        })
+    }
+
+    /// Add an activity inserted during this batch, with its track.
+    pub fn remember(&mut self, activity: Activity, track: Vec<(f64, f64)>) {
+        self.tracks.insert(activity.id, track);
+        self.existing.push(activity);
+    }
+
+    /// Replace a remembered activity after it absorbed a duplicate.
+    pub fn replace(&mut self, activity: Activity, track: Vec<(f64, f64)>) {
+        self.existing.retain(|a| a.id != activity.id);
+        self.remember(activity, track);
+    }
+
+    /// Compare a new activity from `source` with the loaded ones from other
+    /// sources.  Lookup failures are logged and treated as "no match" so they
+    /// never block an import.
+    pub async fn find_match(&mut self, db: &PgPool, source: &str, new: &Fingerprint) -> Match {
+        let window = Duration::minutes(matcher::WINDOW_MINUTES);
+        let candidates: Vec<Activity> = self
+            .existing
+            .iter()
+            .filter(|a| a.source != source && (a.date - new.date).abs() <= window)
+            .cloned()
+            .collect();
+
+        let mut best: Option<(Activity, f32)> = None;
+        for candidate in candidates {
+            let mut fingerprint = summary_fingerprint(&candidate);
+            if !new.track.is_empty() {
+                fingerprint.track = self.track_of(db, candidate.id).await;
+            }
+            let score = matcher::score(new, &fingerprint);
+            if best.as_ref().is_none_or(|(_, s)| score > *s) {
+                best = Some((candidate, score));
+            }
+        }
+
+        match best {
+            Some((existing, score)) if score >= matcher::AUTO_MERGE_SCORE => {
+                Match::Duplicate { existing: Box::new(existing), score }
+            }
+            Some((existing, score)) if score >= matcher::SUSPECT_SCORE => Match::Suspected {
+                activity_id: existing.id,
+                score,
+            },
+            _ => Match::None,
+        }
+    }
+
+    async fn track_of(&mut self, db: &PgPool, activity_id: Uuid) -> Vec<(f64, f64)> {
+        if let Some(track) = self.tracks.get(&activity_id) {
+            return track.clone();
+        }
+        let track: Vec<(f64, f64)> = activities::repository::find_trackpoints(db, activity_id)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Could not load track of {activity_id} for duplicate matching: {e}");
+                vec![]
+            })
+            .into_iter()
+            .map(|tp| (tp.latitude, tp.longitude))
+            .collect();
+        self.tracks.insert(activity_id, track.clone());
+        track
+    }
+}
+
+/// Whether a record from `new_source` should replace `existing` as canonical.
+pub fn supersedes(new_source: &str, existing: &Activity) -> bool {
+    matcher::source_priority(new_source) > matcher::source_priority(&existing.source)
+}
+
+/// The canonical activities that previously merged records of an import
+/// batch now live in, by `(source, external_id)`.  Loaded once per batch;
+/// lookup failures are logged and treated as "nothing merged".
+pub async fn merged_into(
+    db: &PgPool,
+    user_id: Uuid,
+    activities: &[NormalizedActivity],
+) -> HashMap<(String, String), Uuid> {
+    let external_ids: Vec<String> =
+        activities.iter().filter_map(|a| a.external_id.clone()).collect();
+    if external_ids.is_empty() {
+        return HashMap::new();
+    }
+    match repository::find_merged_into(db, user_id, &external_ids).await {
+        Ok(rows) => rows
+            .into_iter()
+            .map(|(source, external_id, id)| ((source, external_id), id))
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Merged-record lookup failed: {e}");
+            HashMap::new()
+        }
+    }
+}
+
+/// Record an automatic merge.  Failures are logged: the import itself is done.
+pub async fn record_merged(
+    db: &PgPool,
+    user_id: Uuid,
+    canonical_id: Uuid,
+    score: f32,
+    record: &MergedRecord,
+) {
+    if let Err(e) = repository::insert_merged(db, user_id, canonical_id, score, record).await {
+        tracing::warn!("Could not record merged duplicate of {canonical_id}: {e}");
+    }
+}
+
+/// Record a pair for manual review.  Failures are logged.
+pub async fn record_suspected(
+    db: &PgPool,
+    user_id: Uuid,
+    activity_id: Uuid,
+    other_id: Uuid,
+    score: f32,
+) {
+    if let Err(e) = repository::insert_suspected(db, user_id, activity_id, other_id, score).await {
+        tracing::warn!("Could not record suspected duplicate {activity_id}/{other_id}: {e}");
+    }
+}
+
+async fn to_entry(db: &PgPool, duplicate: ActivityDuplicate) -> Result<DuplicateEntry, AppError> {
+    let ids: Vec<Uuid> = std::iter::once(duplicate.activity_id)
+        .chain(duplicate.other_id)
+        .collect();
+    let mut found = activities::repository::find_activities_by_ids(db, &ids).await?;
+    Ok(DuplicateEntry {
+        activity: found.remove(&duplicate.activity_id),
+        other: duplicate.other_id.and_then(|id| found.remove(&id)),
+        duplicate,
+    })
+}
+
+pub async fn list_duplicates(
+    db: &PgPool,
+    user_id: Uuid,
+    status: Option<DuplicateStatus>,
+) -> Result<Vec<DuplicateEntry>, AppError> {
+    let rows =
+        repository::list(db, user_id, status.unwrap_or(DuplicateStatus::Suspected)).await?;
+    let mut entries = Vec::with_capacity(rows.len());
+    for row in rows {
+        entries.push(to_entry(db, row).await?);
+    }
+    Ok(entries)
+}
+
+/// Merge or dismiss a pair under review.
+///
+/// Merging deletes the activity that is not kept through the regular delete
+/// path, so its XP, PRs, achievements and mission/goal progress are reversed
+/// and the achievements the kept activity also earns are unlocked again.
+pub async fn resolve_duplicate(
+    db: &PgPool,
+    user_id: Uuid,
+    duplicate_id: Uuid,
+    req: ResolveDuplicateRequest,
+) -> Result<ResolveDuplicateResponse, AppError> {
+    let duplicate = repository::find(db, duplicate_id)
+        .await?
+        .filter(|d| d.user_id == user_id)
+        .ok_or(AppError::NotFound)?;
+    let other_id = match (duplicate.status, duplicate.other_id) {
+        (DuplicateStatus::Suspected, Some(other_id)) => other_id,
+        _ => {
+            return Err(AppError::BadRequest(format!(
+                "Duplicate is already {}",
+                duplicate.status
+            )))
+        }
+    };
+
+    if req.action == ResolveAction::Dismiss {
+        let duplicate = repository::mark_dismissed(db, duplicate_id).await?;
+        return Ok(ResolveDuplicateResponse {
+            duplicate: to_entry(db, duplicate).await?,
+            removed: None,
+        });
+    }
+
+    let mut pair =
+        activities::repository::find_activities_by_ids(db, &[duplicate.activity_id, other_id])
+            .await?;
+    let (Some(first), Some(second)) = (pair.remove(&duplicate.activity_id), pair.remove(&other_id))
+    else {
+        return Err(AppError::NotFound);
+    };
+
+    let (keep, drop) = match req.keep {
+        Some(id) if id == first.id => (first, second),
+        Some(id) if id == second.id => (second, first),
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "keep must be one of the two activities of the pair".into(),
+            ))
+        }
+        None if supersedes(&second.source, &first) => (second, first),
+        None => (first, second),
+    };
+
+    // Record the merge and delete the dropped activity in one transaction:
+    // a failed delete must not leave the pair marked as merged.
+    let mut tx = db.begin().await?;
+    let duplicate =
+        repository::mark_merged(&mut tx, duplicate_id, keep.id, &MergedRecord::from(&drop))
+            .await?;
+    repository::reassign_merged(&mut tx, drop.id, keep.id).await?;
+    let removed = activities::service::delete_in_transaction(db, tx, user_id, &drop).await?;
+
+    Ok(ResolveDuplicateResponse {
+        duplicate: to_entry(db, duplicate).await?,
+        removed: Some(removed),
+    })
+}
@@ -0,0 +1,76 @@
+/// Status of a cross-source duplicate pair.
+///
+/// Stored as TEXT in the `activity_duplicates.status` column, with the same
+/// TEXT-backed sqlx integration as `challenges::status::ChallengeStatus`.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateStatus {
+    Suspected,
+    Merged,
+    Dismissed,
+}
+
+impl DuplicateStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Suspected => "suspected",
+            Self::Merged    => "merged",
+            Self::Dismissed => "dismissed",
+        }
+    }
+}
+
+impl fmt::Display for DuplicateStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DuplicateStatus {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "suspected" => Ok(Self::Suspected),
+            "merged"    => Ok(Self::Merged),
+            "dismissed" => Ok(Self::Dismissed),
+            other       => Err(format!("unknown duplicate status: {other}")),
+        }
+    }
+}
+
+// ─── sqlx TEXT-backed integration (same boilerplate as ChallengeStatus) ──────
+
+impl sqlx::Type<sqlx::Postgres> for DuplicateStatus {
+    fn type_info() -> PgTypeInfo {
+        <String as sqlx::Type<sqlx::Postgres>>::type_info()
+    }
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, sqlx::Postgres> for DuplicateStatus {
+    fn decode(
+        value: PgValueRef<'r>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let s = <&str as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        s.parse().map_err(|e: String| e.into())
+    }
+}
+
+impl sqlx::Encode<'_, sqlx::Postgres> for DuplicateStatus {
+    fn encode_by_ref(
+        &self,
+        buf: &mut PgArgumentBuffer,
+    ) -> Result<sqlx::encode::IsNull, Box<dyn std::error::Error + Send + Sync>> {
+        let s = self.as_str();
+        <&str as sqlx::Encode<sqlx::Postgres>>::encode_by_ref(&s, buf)
+    }
+}
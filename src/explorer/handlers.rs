@@ -0,0 +1,49 @@
+use actix_web::{get, web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+use super::service;
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/explorer",
+    tag = "explorer",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Explorer tiles ever visited, max square and max cluster", body = super::models::ExplorerSummary),
+        (status = 400, description = "Invalid UUID")
+    )
+)]
+#[get("/users/{user_id}/explorer")]
+pub async fn get_summary(
+    db: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let summary = service::get_summary(db.get_ref(), path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/explorer/activities",
+    tag = "explorer",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Activities that visited new explorer tiles, with how many, newest first", body = Vec<super::models::ActivityTiles>),
+        (status = 400, description = "Invalid UUID")
+    )
+)]
+#[get("/users/{user_id}/explorer/activities")]
+pub async fn list_activity_tiles(
+    db: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let activities = service::list_activity_tiles(db.get_ref(), path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(activities))
+}
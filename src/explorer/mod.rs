@@ -0,0 +1,12 @@
+pub mod handlers;
+pub mod models;
+pub mod repository;
+pub mod service;
+pub mod tiles;
+
+use actix_web::web;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers::get_summary)
+        .service(handlers::list_activity_tiles);
+}
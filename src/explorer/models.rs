@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Response for `GET /users/{user_id}/explorer`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExplorerSummary {
+    /// Map zoom of the tiles.
+    pub zoom: i16,
+    /// Tiles ever visited.
+    pub total_tiles: i64,
+    /// Side of the largest square block of visited tiles.
+    pub max_square: i32,
+    /// Size of the largest group of connected tiles surrounded on all four
+    /// sides by visited tiles.
+    pub max_cluster: i64,
+}
+
+/// Tiles an activity visited first, in `GET /users/{user_id}/explorer/activities`.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ActivityTiles {
+    pub activity_id: Uuid,
+    pub date: DateTime<Utc>,
+    pub name: String,
+    pub new_tiles: i64,
+}
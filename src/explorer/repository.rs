@@ -0,0 +1,110 @@
+/// SQL layer for explorer tiles.
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+use super::{
+    models::ActivityTiles,
+    tiles::{HEATMAP_LEVEL, ZOOM},
+};
+
+/// Record the tiles an activity's track touches for its user.  A tile
+/// reached earlier by another activity keeps that one as its first visit.
+pub async fn record_tiles(
+    db: &PgPool,
+    activity_id: Uuid,
+    tiles: &[(i32, i32)],
+) -> Result<(), AppError> {
+    if tiles.is_empty() {
+        return Ok(());
+    }
+    let xs: Vec<i32> = tiles.iter().map(|t| t.0).collect();
+    let ys: Vec<i32> = tiles.iter().map(|t| t.1).collect();
+    sqlx::query(
+        "INSERT INTO explorer_tiles (user_id, x, y, first_activity_id, first_visited_at)
+         SELECT a.user_id, t.x, t.y, a.id, a.date
+         FROM   activities a, UNNEST($2::int[], $3::int[]) AS t (x, y)
+         WHERE  a.id = $1
+         ON CONFLICT (user_id, x, y) DO UPDATE
+            SET first_activity_id = EXCLUDED.first_activity_id,
+                first_visited_at  = EXCLUDED.first_visited_at
+          WHERE EXCLUDED.first_visited_at < explorer_tiles.first_visited_at",
+    )
+    .bind(activity_id)
+    .bind(&xs)
+    .bind(&ys)
+    .execute(db)
+    .await
+    .map_err(AppError::from)?;
+    Ok(())
+}
+
+/// Recount a user's tiles from the heatmap cells of the activities they
+/// still have.
+pub async fn rebuild_tiles(db: &PgPool, user_id: Uuid) -> Result<(), AppError> {
+    let shift = i32::from(HEATMAP_LEVEL + 8 - ZOOM);
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM explorer_tiles WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO explorer_tiles (user_id, x, y, first_activity_id, first_visited_at)
+         SELECT DISTINCT ON (c.x >> $3, c.y >> $3)
+                a.user_id, c.x >> $3, c.y >> $3, a.id, a.date
+         FROM   heatmap_cells c
+         JOIN   activities a ON a.id = c.activity_id
+         WHERE  a.user_id = $1 AND c.level = $2
+         ORDER  BY c.x >> $3, c.y >> $3, a.date, a.id",
+    )
+    .bind(user_id)
+    .bind(HEATMAP_LEVEL)
+    .bind(shift)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Every tile a user visited first in `[from, to)`, or ever without bounds.
+pub async fn find_tiles(
+    db: &PgPool,
+    user_id: Uuid,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<Vec<(i32, i32)>, AppError> {
+    sqlx::query_as(
+        "SELECT x, y FROM explorer_tiles
+         WHERE  user_id = $1
+           AND  ($2::timestamptz IS NULL OR first_visited_at >= $2)
+           AND  ($3::timestamptz IS NULL OR first_visited_at < $3)",
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// The user's activities that visited tiles first, with how many, newest
+/// first.
+pub async fn find_activity_tiles(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<ActivityTiles>, AppError> {
+    sqlx::query_as::<_, ActivityTiles>(
+        "SELECT a.id AS activity_id, a.date, a.name, COUNT(*) AS new_tiles
+         FROM   explorer_tiles t
+         JOIN   activities a ON a.id = t.first_activity_id
+         WHERE  t.user_id = $1
+         GROUP  BY a.id, a.date, a.name
+         ORDER  BY a.date DESC, a.id",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
@@ -0,0 +1,69 @@
+/// Explorer tiles: the map squares a user has ever covered.
+///
+/// `activities::service` records the tiles of each newly stored track and has
+/// them rebuilt from the heatmap cells when a track is re-cleaned or an
+/// activity deleted.  Achievements and
+/// goals read the totals from here.
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{activities::models::TrackPoint, error::AppError};
+
+use super::{
+    models::{ActivityTiles, ExplorerSummary},
+    repository,
+    tiles::{self, ZOOM},
+};
+
+/// Record the tiles an activity's cleaned track touches.
+pub async fn record_activity(
+    db: &PgPool,
+    activity_id: Uuid,
+    track: &[TrackPoint],
+) -> Result<(), AppError> {
+    repository::record_tiles(db, activity_id, &tiles::tiles(track)).await
+}
+
+/// Recount a user's tiles after tracks were re-cleaned or removed.
+pub async fn rebuild(db: &PgPool, user_id: Uuid) -> Result<(), AppError> {
+    repository::rebuild_tiles(db, user_id).await
+}
+
+/// Total tiles, max square and max cluster of a user.
+pub async fn get_summary(db: &PgPool, user_id: Uuid) -> Result<ExplorerSummary, AppError> {
+    let visited: HashSet<(i32, i32)> = repository::find_tiles(db, user_id, None, None)
+        .await?
+        .into_iter()
+        .collect();
+    Ok(ExplorerSummary {
+        zoom: ZOOM,
+        total_tiles: visited.len() as i64,
+        max_square: tiles::max_square(&visited),
+        max_cluster: tiles::max_cluster(&visited),
+    })
+}
+
+/// Tiles a user visited for the first time in `[from, to)`; `to = None`
+/// means up to now.
+pub async fn count_new_tiles(
+    db: &PgPool,
+    user_id: Uuid,
+    from: DateTime<Utc>,
+    to: Option<DateTime<Utc>>,
+) -> Result<i64, AppError> {
+    Ok(repository::find_tiles(db, user_id, Some(from), to)
+        .await?
+        .len() as i64)
+}
+
+/// The user's activities with the number of tiles each visited first,
+/// newest first.
+pub async fn list_activity_tiles(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<ActivityTiles>, AppError> {
+    repository::find_activity_tiles(db, user_id).await
+}
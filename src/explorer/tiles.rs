@@ -0,0 +1,88 @@
+/// Explorer tiles: the standard zoom-[`ZOOM`] Web Mercator map tiles
+/// (~1.5 km across at the equator, ~1 km at 50°).
+///
+/// A tile counts as visited once any point of a track falls in it.  Two
+/// numbers describe how thoroughly an area has been covered:
+///
+/// - the max square, the side of the largest square block of visited tiles;
+/// - the max cluster, the size of the largest group of 4-connected tiles
+///   that are visited and whose four neighbours are all visited too.
+///
+/// Pure functions — no I/O.
+use std::collections::{HashMap, HashSet};
+
+use crate::activities::{heatmap, models::TrackPoint};
+
+/// Map zoom of explorer tiles.
+pub const ZOOM: i16 = 14;
+
+/// Stored heatmap level the tiles are rebuilt from; each tile is
+/// `2^(HEATMAP_LEVEL + 8 - ZOOM)` of its cells across.
+pub const HEATMAP_LEVEL: i16 = 8;
+
+/// Tile containing a position.
+pub fn tile_of(lat: f64, lon: f64) -> (i32, i32) {
+    // Heatmap level `L` cells are the pixels of zoom-`L` tiles, so level
+    // `ZOOM - 8` cells are the zoom-`ZOOM` tiles themselves.
+    heatmap::cell_of(lat, lon, ZOOM - 8)
+}
+
+/// The distinct tiles a track touches, sorted.
+pub fn tiles(points: &[TrackPoint]) -> Vec<(i32, i32)> {
+    let mut tiles: Vec<(i32, i32)> = points
+        .iter()
+        .map(|tp| tile_of(tp.latitude, tp.longitude))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    tiles.sort_unstable();
+    tiles
+}
+
+/// Side of the largest square block of visited tiles.
+pub fn max_square(tiles: &HashSet<(i32, i32)>) -> i32 {
+    // Side of the largest square with its bottom-right corner at each tile;
+    // row by row, so the tiles above and to the left are done first.
+    let mut ordered: Vec<(i32, i32)> = tiles.iter().copied().collect();
+    ordered.sort_unstable_by_key(|&(x, y)| (y, x));
+    let mut side: HashMap<(i32, i32), i32> = HashMap::with_capacity(ordered.len());
+    let mut best = 0;
+    for (x, y) in ordered {
+        let at = |k| side.get(&k).copied().unwrap_or(0);
+        let s = 1 + at((x - 1, y)).min(at((x, y - 1))).min(at((x - 1, y - 1)));
+        side.insert((x, y), s);
+        best = best.max(s);
+    }
+    best
+}
+
+/// Size of the largest 4-connected group of visited tiles whose four
+/// neighbours are all visited.
+pub fn max_cluster(tiles: &HashSet<(i32, i32)>) -> i64 {
+    let neighbours = |(x, y): (i32, i32)| [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)];
+    let inner: HashSet<(i32, i32)> = tiles
+        .iter()
+        .copied()
+        .filter(|&t| neighbours(t).iter().all(|n| tiles.contains(n)))
+        .collect();
+
+    let mut seen: HashSet<(i32, i32)> = HashSet::with_capacity(inner.len());
+    let mut best = 0;
+    for &start in &inner {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut size = 0;
+        let mut stack = vec![start];
+        while let Some(t) = stack.pop() {
+            size += 1;
+            for n in neighbours(t) {
+                if inner.contains(&n) && seen.insert(n) {
+                    stack.push(n);
+                }
+            }
+        }
+        best = best.max(size);
+    }
+    best
+}
@@ -63,7 +63,7 @@ pub struct UserGoalResponse {
 }
 
 /// Minimal summary included in the UploadResponse when a goal is completed.
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CompletedGoalSummary {
     pub goal_id: Uuid,
     pub name: String,
@@ -54,6 +54,7 @@ pub async fn find_goals_for_user(
 
 /// Count "active" goal slots for a user.
 /// Forever goals that are permanently completed do NOT count toward the limit.
+#[allow(dead_code)]
 pub async fn count_active_slots(db: &PgPool, user_id: Uuid) -> Result<i64, AppError> {
     let row: (i64,) = sqlx::query_as(
         "SELECT COUNT(*) FROM goals
@@ -80,6 +81,8 @@ pub async fn find_goal_by_id(
 
 // ─── Write ────────────────────────────────────────────────────────────────────
 
+#[allow(dead_code)]
+#[allow(clippy::too_many_arguments)]
 pub async fn insert_goal(
     db: &PgPool,
     user_id: Uuid,
@@ -110,6 +113,7 @@ pub async fn insert_goal(
     .map_err(AppError::from)
 }
 
+#[allow(dead_code)]
 pub async fn insert_goal_requirements(
     db: &PgPool,
     goal_id: Uuid,
@@ -20,6 +20,9 @@ use utoipa::ToSchema;
 /// Completion direction:
 ///   - FastestPace / AveragePace: complete when current <= target (lower secs/km = faster)
 ///   - All others: complete when current >= target
+///
+/// The explorer metrics are read from the user's explorer tiles, not from
+/// activities, so filters do not apply to them.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum GoalMetricType {
@@ -31,6 +34,9 @@ pub enum GoalMetricType {
     LongestRun,       // km (single-activity max)
     FastestPace,      // secs/km (best single activity; LOWER = better)
     AveragePace,      // secs/km (mean across filtered activities; LOWER = better)
+    ExplorerTiles,      // count of tiles first visited in the period
+    ExplorerMaxSquare,  // side of the largest square of tiles ever visited
+    ExplorerMaxCluster, // size of the largest cluster of tiles ever visited
 }
 
 impl GoalMetricType {
@@ -44,9 +50,20 @@ impl GoalMetricType {
             Self::LongestRun => "longest_run",
             Self::FastestPace => "fastest_pace",
             Self::AveragePace => "average_pace",
+            Self::ExplorerTiles => "explorer_tiles",
+            Self::ExplorerMaxSquare => "explorer_max_square",
+            Self::ExplorerMaxCluster => "explorer_max_cluster",
         }
     }
 
+    /// Whether the metric is read from explorer tiles rather than activities.
+    pub fn is_explorer(self) -> bool {
+        matches!(
+            self,
+            Self::ExplorerTiles | Self::ExplorerMaxSquare | Self::ExplorerMaxCluster
+        )
+    }
+
     /// Returns true when the goal is met given the current and target values.
     /// Pace metrics are inverted: lower secs/km means a faster pace.
     pub fn is_met(self, current: f64, target: f64) -> bool {
@@ -76,6 +93,9 @@ impl FromStr for GoalMetricType {
             "longest_run" => Ok(Self::LongestRun),
             "fastest_pace" => Ok(Self::FastestPace),
             "average_pace" => Ok(Self::AveragePace),
+            "explorer_tiles" => Ok(Self::ExplorerTiles),
+            "explorer_max_square" => Ok(Self::ExplorerMaxSquare),
+            "explorer_max_cluster" => Ok(Self::ExplorerMaxCluster),
             other => Err(format!("unknown GoalMetricType: {other}")),
         }
     }
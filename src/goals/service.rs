@@ -1,11 +1,14 @@
 /// Business logic for user-defined goals.
-use chrono::{Datelike, Utc};
+use chrono::{Datelike, NaiveDate, Utc};
+use chrono_tz::Tz;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
     activities,
     error::AppError,
+    explorer,
+    users::{self, timezone},
     xp::{models::AwardXpInput, service as xp_service},
 };
 
@@ -19,18 +22,26 @@ use super::{
 
 // ─── Period helpers ───────────────────────────────────────────────────────────
 
-fn current_period_key(timeframe: &str) -> String {
-    let now = Utc::now();
+/// Key of the period containing `today`, e.g. `"2025-03"` for a monthly goal.
+fn period_key_for(timeframe: &str, today: NaiveDate) -> String {
     match timeframe {
-        "monthly" => format!("{}-{:02}", now.year(), now.month()),
-        "yearly" => format!("{}", now.year()),
+        "monthly" => format!("{}-{:02}", today.year(), today.month()),
+        "yearly" => format!("{}", today.year()),
         _ => String::new(), // "forever"
     }
 }
 
-fn period_window(timeframe: &str, period_key: &str) -> (chrono::DateTime<Utc>, Option<chrono::DateTime<Utc>>) {
-    use chrono::{NaiveDate, TimeZone};
+/// Key of the current period; periods roll over at midnight in `tz`.
+fn current_period_key(timeframe: &str, tz: Tz) -> String {
+    period_key_for(timeframe, timezone::today(tz))
+}
 
+/// The instants a period starts and ends, its days cut in `tz`.
+fn period_window(
+    timeframe: &str,
+    period_key: &str,
+    tz: Tz,
+) -> (chrono::DateTime<Utc>, Option<chrono::DateTime<Utc>>) {
     match timeframe {
         "monthly" => {
             // period_key = "YYYY-MM"
@@ -41,37 +52,26 @@ fn period_window(timeframe: &str, period_key: &str) -> (chrono::DateTime<Utc>, O
                     parts[1].parse::<u32>().unwrap_or(1),
                 )
             } else {
-                let now = Utc::now();
-                (now.year(), now.month())
+                let today = timezone::today(tz);
+                (today.year(), today.month())
             };
-            let start = NaiveDate::from_ymd_opt(year, month, 1)
-                .unwrap()
-                .and_hms_opt(0, 0, 0)
-                .unwrap();
+            let start = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
             // End = first day of next month
-            let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
-            let end = NaiveDate::from_ymd_opt(next_year, next_month, 1)
-                .unwrap()
-                .and_hms_opt(0, 0, 0)
-                .unwrap();
+            let end = timezone::next_month_start(start);
             (
-                Utc.from_utc_datetime(&start),
-                Some(Utc.from_utc_datetime(&end)),
+                timezone::start_of_day(tz, start),
+                Some(timezone::start_of_day(tz, end)),
             )
         }
         "yearly" => {
-            let year = period_key.parse::<i32>().unwrap_or_else(|_| Utc::now().year());
-            let start = NaiveDate::from_ymd_opt(year, 1, 1)
-                .unwrap()
-                .and_hms_opt(0, 0, 0)
-                .unwrap();
-            let end = NaiveDate::from_ymd_opt(year + 1, 1, 1)
-                .unwrap()
-                .and_hms_opt(0, 0, 0)
-                .unwrap();
+            let year = period_key
+                .parse::<i32>()
+                .unwrap_or_else(|_| timezone::today(tz).year());
+            let start = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+            let end = NaiveDate::from_ymd_opt(year + 1, 1, 1).unwrap();
             (
-                Utc.from_utc_datetime(&start),
-                Some(Utc.from_utc_datetime(&end)),
+                timezone::start_of_day(tz, start),
+                Some(timezone::start_of_day(tz, end)),
             )
         }
         _ => {
@@ -97,26 +97,30 @@ fn activity_passes_filters(
                 activity.activity_type.eq_ignore_ascii_case(expected)
             }
             GoalFilterType::MinDistance => {
-                value.map_or(true, |v| activity.distance as f64 >= v)
+                value.is_none_or(|v| activity.distance as f64 >= v)
             }
             GoalFilterType::MaxDistance => {
-                value.map_or(true, |v| activity.distance as f64 <= v)
+                value.is_none_or(|v| activity.distance as f64 <= v)
             }
             GoalFilterType::MinDuration => {
                 // activity.duration is stored as "HH:MM:SS"
                 let secs = parse_duration_secs(&activity.duration);
-                value.map_or(true, |v| secs as f64 >= v * 60.0)
+                value.is_none_or(|v| secs as f64 >= v * 60.0)
             }
             GoalFilterType::MinPace => {
                 // min_pace: only activities with pace <= value (faster than threshold)
-                value.map_or(true, |v| activity.average_pace as f64 <= v)
+                value.is_none_or(|v| {
+                    activity.average_pace.is_set() && activity.average_pace.secs_per_km() <= v
+                })
             }
             GoalFilterType::MaxPace => {
                 // max_pace: only activities with pace >= value (slower than threshold)
-                value.map_or(true, |v| activity.average_pace as f64 >= v)
+                value.is_none_or(|v| {
+                    activity.average_pace.is_set() && activity.average_pace.secs_per_km() >= v
+                })
             }
             GoalFilterType::MinElevation => {
-                value.map_or(true, |v| activity.climb as f64 >= v)
+                value.is_none_or(|v| activity.climb as f64 >= v)
             }
         };
         if !passes {
@@ -164,14 +168,14 @@ fn aggregate_metric(
             .fold(0.0_f64, f64::max),
         GoalMetricType::FastestPace => activities
             .iter()
-            .map(|a| a.average_pace as f64)
-            .filter(|&p| p > 0.0)
+            .filter(|a| a.average_pace.is_set())
+            .map(|a| a.average_pace.secs_per_km())
             .fold(f64::MAX, f64::min),
         GoalMetricType::AveragePace => {
             let paces: Vec<f64> = activities
                 .iter()
-                .map(|a| a.average_pace as f64)
-                .filter(|&p| p > 0.0)
+                .filter(|a| a.average_pace.is_set())
+                .map(|a| a.average_pace.secs_per_km())
                 .collect();
             if paces.is_empty() {
                 0.0
@@ -179,9 +183,33 @@ fn aggregate_metric(
                 paces.iter().sum::<f64>() / paces.len() as f64
             }
         }
+        // Read from explorer tiles; see `explorer_metric`.
+        GoalMetricType::ExplorerTiles
+        | GoalMetricType::ExplorerMaxSquare
+        | GoalMetricType::ExplorerMaxCluster => 0.0,
     }
 }
 
+/// Value of an explorer metric: tiles first visited in `[from, to)`, or the
+/// max square / cluster over every tile ever visited.
+async fn explorer_metric(
+    db: &PgPool,
+    user_id: Uuid,
+    metric: GoalMetricType,
+    from: chrono::DateTime<Utc>,
+    to: Option<chrono::DateTime<Utc>>,
+) -> Result<f64, AppError> {
+    Ok(match metric {
+        GoalMetricType::ExplorerTiles => {
+            explorer::service::count_new_tiles(db, user_id, from, to).await? as f64
+        }
+        GoalMetricType::ExplorerMaxSquare => {
+            explorer::service::get_summary(db, user_id).await?.max_square as f64
+        }
+        _ => explorer::service::get_summary(db, user_id).await?.max_cluster as f64,
+    })
+}
+
 // ─── Requirement parsing ──────────────────────────────────────────────────────
 
 fn parse_requirements(
@@ -196,20 +224,21 @@ fn parse_requirements(
     let metric = metric_reqs[0]
         .requirement_type
         .parse::<GoalMetricType>()
-        .map_err(|e| AppError::BadRequest(e))?;
+        .map_err(AppError::BadRequest)?;
 
     let mut filters = vec![];
     for f in reqs.iter().filter(|r| r.category == "filter") {
         let ft = f
             .requirement_type
             .parse::<GoalFilterType>()
-            .map_err(|e| AppError::BadRequest(e))?;
+            .map_err(AppError::BadRequest)?;
         filters.push((ft, f.value, f.params.clone()));
     }
 
     Ok(ParsedRequirements { metric, filters })
 }
 
+#[allow(clippy::type_complexity)]
 fn extract_requirements_from_db(
     reqs: &[super::models::GoalRequirement],
 ) -> Option<(GoalMetricType, Vec<(GoalFilterType, Option<f64>, serde_json::Value)>)> {
@@ -239,6 +268,7 @@ async fn compute_and_persist_goal(
     goal: &UserGoal,
     reqs: &[super::models::GoalRequirement],
     now: chrono::DateTime<Utc>,
+    tz: Tz,
 ) -> (f64, Option<chrono::DateTime<Utc>>) {
     let (metric, filters) = match extract_requirements_from_db(reqs) {
         Some(r) => r,
@@ -248,22 +278,32 @@ async fn compute_and_persist_goal(
         }
     };
 
-    let current_key = current_period_key(&goal.timeframe);
-    let (from, to) = period_window(&goal.timeframe, &current_key);
+    let current_key = current_period_key(&goal.timeframe, tz);
+    let (from, to) = period_window(&goal.timeframe, &current_key, tz);
 
-    let all_activities =
-        activities::repository::find_activities_by_user_from(db, user_id, from, to)
+    let new_value = if metric.is_explorer() {
+        explorer_metric(db, user_id, metric, from, to)
             .await
-            .unwrap_or_default();
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to read explorer tiles for goal {}: {e}", goal.id);
+                goal.current_value
+            })
+    } else {
+        let all_activities =
+            activities::repository::find_activities_by_user_from(db, user_id, from, to)
+                .await
+                .unwrap_or_default();
 
-    let filtered: Vec<&activities::models::Activity> = all_activities
-        .iter()
-        .filter(|a| activity_passes_filters(a, &filters))
-        .collect();
+        let filtered: Vec<&activities::models::Activity> = all_activities
+            .iter()
+            .filter(|a| activity_passes_filters(a, &filters))
+            .collect();
 
-    let new_value = aggregate_metric(metric, &filtered);
+        aggregate_metric(metric, &filtered)
+    };
+    // Keep the original completion time while the goal stays met.
     let new_completed_at = if metric.is_met(new_value, goal.target_value) {
-        Some(now)
+        goal.completed_at.or(Some(now))
     } else {
         None
     };
@@ -286,6 +326,14 @@ async fn compute_and_persist_goal(
         }
     }
 
+    // An edited or deleted activity can un-complete a goal; take its XP back.
+    if new_completed_at.is_none() && goal.completed_at.is_some() {
+        let description = format!("Goal no longer complete: {}", goal.name);
+        if let Err(e) = xp_service::revoke_xp(db, user_id, "goal", goal.id, description).await {
+            tracing::warn!("Failed to revoke XP for goal {}: {e}", goal.id);
+        }
+    }
+
     (new_value, new_completed_at)
 }
 
@@ -298,13 +346,13 @@ pub async fn list_goals(
     user_id: Uuid,
 ) -> Result<Vec<UserGoalResponse>, AppError> {
     let pairs = repository::find_goals_for_user(db, user_id).await?;
-    let current_now = Utc::now();
+    let today = timezone::today(users::service::timezone(db, user_id).await);
 
     Ok(pairs
         .into_iter()
         .map(|(goal, reqs)| {
             let (display_value, display_completed) =
-                apply_lazy_rollover(&goal, &current_now);
+                apply_lazy_rollover(&goal, today);
 
             UserGoalResponse {
                 id: goal.id,
@@ -329,13 +377,12 @@ pub async fn list_goals(
 /// Does NOT write to DB — purely for display purposes.
 fn apply_lazy_rollover(
     goal: &UserGoal,
-    now: &chrono::DateTime<Utc>,
+    today: NaiveDate,
 ) -> (f64, Option<chrono::DateTime<Utc>>) {
-    let current_key = match goal.timeframe.as_str() {
-        "monthly" => format!("{}-{:02}", now.year(), now.month()),
-        "yearly" => format!("{}", now.year()),
-        _ => return (goal.current_value, goal.completed_at), // forever — no rollover
-    };
+    if goal.timeframe == "forever" {
+        return (goal.current_value, goal.completed_at); // no rollover
+    }
+    let current_key = period_key_for(&goal.timeframe, today);
 
     if goal.period_key != current_key {
         // Period has rolled over; show zeroed state without a DB write
@@ -369,7 +416,8 @@ pub async fn create_goal(
     }
     let parsed = parse_requirements(&req.requirements)?;
 
-    let period_key = current_period_key(&req.timeframe);
+    let tz = users::service::timezone(db, user_id).await;
+    let period_key = current_period_key(&req.timeframe, tz);
     let xp_reward = req.xp_reward.unwrap_or(150);
 
     // Use an advisory lock per user to prevent concurrent slot-limit races.
@@ -467,7 +515,7 @@ pub async fn create_goal(
 
     // Compute initial progress from activities that already exist in this period.
     let (initial_value, initial_completed_at) =
-        compute_and_persist_goal(db, user_id, &goal, &reqs, Utc::now()).await;
+        compute_and_persist_goal(db, user_id, &goal, &reqs, Utc::now(), tz).await;
 
     Ok(UserGoalResponse {
         id: goal.id,
@@ -511,6 +559,24 @@ pub async fn delete_goal(
 pub async fn update_progress_after_upload(
     db: &PgPool,
     user_id: Uuid,
+) -> Result<Vec<CompletedGoalSummary>, AppError> {
+    recalculate_progress(db, user_id, false).await
+}
+
+/// Called after an activity is edited or deleted. Like
+/// [`update_progress_after_upload`], but also re-checks goals that are
+/// already complete and revokes the ones that no longer hold.
+pub async fn update_progress_after_edit(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<CompletedGoalSummary>, AppError> {
+    recalculate_progress(db, user_id, true).await
+}
+
+async fn recalculate_progress(
+    db: &PgPool,
+    user_id: Uuid,
+    recheck_completed: bool,
 ) -> Result<Vec<CompletedGoalSummary>, AppError> {
     let pairs = repository::find_goals_for_user(db, user_id).await?;
     if pairs.is_empty() {
@@ -518,15 +584,16 @@ pub async fn update_progress_after_upload(
     }
 
     let now = Utc::now();
+    let tz = users::service::timezone(db, user_id).await;
     let mut completed = vec![];
 
     for (mut goal, reqs) in pairs {
         // Skip permanently completed forever goals.
-        if goal.timeframe == "forever" && goal.completed_at.is_some() {
+        if goal.timeframe == "forever" && goal.completed_at.is_some() && !recheck_completed {
             continue;
         }
 
-        let current_key = current_period_key(&goal.timeframe);
+        let current_key = current_period_key(&goal.timeframe, tz);
 
         // Period rollover: reset DB-side values so the helper uses the fresh period.
         if goal.timeframe != "forever" && goal.period_key != current_key {
@@ -536,15 +603,15 @@ pub async fn update_progress_after_upload(
         }
 
         // Skip already-completed goals in this period.
-        if goal.completed_at.is_some() {
+        if goal.completed_at.is_some() && !recheck_completed {
             continue;
         }
 
         // Delegate computation, persistence, and XP award to the shared helper.
         let (new_value, new_completed_at) =
-            compute_and_persist_goal(db, user_id, &goal, &reqs, now).await;
+            compute_and_persist_goal(db, user_id, &goal, &reqs, now, tz).await;
 
-        if new_completed_at.is_some() {
+        if new_completed_at.is_some() && goal.completed_at.is_none() {
             completed.push(CompletedGoalSummary {
                 goal_id: goal.id,
                 name: goal.name.clone(),
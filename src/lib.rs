@@ -4,13 +4,18 @@ pub mod aggregate;
 pub mod api;
 pub mod challenges;
 pub mod db;
+pub mod duplicates;
 pub mod error;
+pub mod explorer;
 pub mod goals;
 pub mod missions;
 pub mod monthly_missions;
 pub mod personal_records;
+pub mod routes;
+pub mod segments;
 pub mod strava;
 pub mod sync;
+pub mod uploads;
 pub mod users;
 pub mod weekly_missions;
 pub mod xp;
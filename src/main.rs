@@ -4,13 +4,18 @@ mod aggregate;
 mod api;
 mod challenges;
 mod db;
+mod duplicates;
 mod error;
+mod explorer;
 mod goals;
 mod missions;
 mod monthly_missions;
 mod personal_records;
+mod routes;
+mod segments;
 mod strava;
 mod sync;
+mod uploads;
 mod users;
 mod weekly_missions;
 mod xp;
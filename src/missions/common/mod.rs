@@ -1,3 +1,3 @@
 pub mod shared;
 
-pub use shared::{CompletedMissionSummary, dow_name, format_pace_str, is_mission_complete};
+pub use shared::{CompletedMissionSummary, dow_name, is_mission_complete};
@@ -22,13 +22,6 @@ pub fn is_mission_complete(mission_type: &str, current: f64, target: f64) -> boo
     }
 }
 
-/// Format a pace in seconds/km as "M:SS".
-pub fn format_pace_str(secs: f64) -> String {
-    let mins = (secs / 60.0).floor() as u32;
-    let s = (secs % 60.0).round() as u32;
-    format!("{mins}:{s:02}")
-}
-
 /// Map a PostgreSQL day-of-week integer (0=Sunday … 6=Saturday) to a name.
 pub fn dow_name(dow: u32) -> &'static str {
     match dow {
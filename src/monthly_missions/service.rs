@@ -1,10 +1,14 @@
-use chrono::{Datelike, NaiveDate, Utc};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
+    activities::pace::Pace,
     error::AppError,
-    missions::common::{format_pace_str, is_mission_complete, CompletedMissionSummary},
+    missions::common::{is_mission_complete, CompletedMissionSummary},
+    users::{self, timezone},
+    weekly_missions::service::current_week_start,
     xp::{models::AwardXpInput, service as xp_service},
 };
 
@@ -17,10 +21,9 @@ type MissionFactory = (&'static str, Box<dyn Fn() -> MonthlyMission>);
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
-/// Returns the 1st of the current month (UTC).
-pub fn current_month_start() -> NaiveDate {
-    let today = Utc::now().date_naive();
-    NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap()
+/// Returns the 1st of the current month in `tz`.
+pub fn current_month_start(tz: Tz) -> NaiveDate {
+    timezone::month_start(timezone::today(tz))
 }
 
 // ── Stats ─────────────────────────────────────────────────────────────────────
@@ -32,11 +35,17 @@ struct UserMonthlyStats {
     avg_weekly_km: f64,
     avg_weekly_runs: f64,
     best_single_run_km: f64,
-    avg_pace_secs: f64,
-    best_pace_secs: f64, // all-time min pace converted to secs/km
+    avg_pace: Pace,
+    best_pace: Pace, // all-time best
 }
 
-async fn fetch_monthly_stats(pool: &PgPool, user_id: Uuid) -> UserMonthlyStats {
+/// `monthly_sub_pace_count` threshold, formatted: 15 secs/km under the
+/// user's average, but never under 3:00/km.
+fn sub_pace_target(avg_pace: Pace) -> String {
+    Pace::from_secs_per_km((avg_pace.secs_per_km() - 15.0).max(180.0)).to_string()
+}
+
+async fn fetch_monthly_stats(pool: &PgPool, user_id: Uuid, tz: Tz) -> UserMonthlyStats {
     // Per-month aggregates
     let monthly: (Option<f64>, Option<f64>, Option<f64>) =
         sqlx::query_as::<_, (Option<f64>, Option<f64>, Option<f64>)>(
@@ -47,7 +56,7 @@ async fn fetch_monthly_stats(pool: &PgPool, user_id: Uuid) -> UserMonthlyStats {
                 AVG(monthly_elevation)
             FROM (
                 SELECT
-                    DATE_TRUNC('month', date) AS m,
+                    DATE_TRUNC('month', date AT TIME ZONE $2) AS m,
                     SUM(distance::FLOAT8)     AS monthly_km,
                     COUNT(*)                  AS monthly_count,
                     SUM(COALESCE(climb, 0))   AS monthly_elevation
@@ -58,6 +67,7 @@ async fn fetch_monthly_stats(pool: &PgPool, user_id: Uuid) -> UserMonthlyStats {
             "#,
         )
         .bind(user_id)
+        .bind(tz.name())
         .fetch_one(pool)
         .await
         .unwrap_or((None, None, None));
@@ -69,7 +79,7 @@ async fn fetch_monthly_stats(pool: &PgPool, user_id: Uuid) -> UserMonthlyStats {
             SELECT AVG(weekly_km), AVG(weekly_count)
             FROM (
                 SELECT
-                    DATE_TRUNC('week', date) AS w,
+                    DATE_TRUNC('week', date AT TIME ZONE $2) AS w,
                     SUM(distance::FLOAT8)    AS weekly_km,
                     COUNT(*)                 AS weekly_count
                 FROM activities
@@ -79,6 +89,7 @@ async fn fetch_monthly_stats(pool: &PgPool, user_id: Uuid) -> UserMonthlyStats {
             "#,
         )
         .bind(user_id)
+        .bind(tz.name())
         .fetch_one(pool)
         .await
         .unwrap_or((None, None));
@@ -93,13 +104,13 @@ async fn fetch_monthly_stats(pool: &PgPool, user_id: Uuid) -> UserMonthlyStats {
     .unwrap_or(Some(0.0))
     .unwrap_or(0.0);
 
-    // Average and best (min) pace — stored as M.SS, convert to secs/km
+    // Average and best (min) pace
     let pace_stats: (Option<f64>, Option<f64>) =
         sqlx::query_as::<_, (Option<f64>, Option<f64>)>(
             r#"
             SELECT
-                AVG(FLOOR(average_pace) * 60.0 + ((average_pace - FLOOR(average_pace)) * 100.0)),
-                MIN(FLOOR(average_pace) * 60.0 + ((average_pace - FLOOR(average_pace)) * 100.0))
+                AVG(average_pace::FLOAT8),
+                MIN(average_pace::FLOAT8)
             FROM activities
             WHERE user_id = $1 AND average_pace > 0
             "#,
@@ -116,8 +127,8 @@ async fn fetch_monthly_stats(pool: &PgPool, user_id: Uuid) -> UserMonthlyStats {
         avg_weekly_km: weekly.0.unwrap_or(5.0),
         avg_weekly_runs: weekly.1.unwrap_or(2.0),
         best_single_run_km: best_run,
-        avg_pace_secs: pace_stats.0.unwrap_or(360.0),
-        best_pace_secs: pace_stats.1.unwrap_or(360.0),
+        avg_pace: Pace::from_secs_per_km(pace_stats.0.unwrap_or(360.0)),
+        best_pace: Pace::from_secs_per_km(pace_stats.1.unwrap_or(360.0)),
     }
 }
 
@@ -258,7 +269,7 @@ fn generate_missions(
         let uid = user_id;
         let ms = month_start;
         let n = now;
-        let pace_secs = stats.avg_pace_secs;
+        let avg_pace = stats.avg_pace;
         let wkly_km = stats.avg_weekly_km;
 
         vec![
@@ -305,8 +316,7 @@ fn generate_missions(
             (
                 "monthly_sub_pace_count",
                 Box::new(move || {
-                    let target_pace_secs = (pace_secs - 15.0).max(180.0);
-                    let pace_str = format_pace_str(target_pace_secs);
+                    let pace_str = sub_pace_target(avg_pace);
                     MonthlyMission {
                         id: Uuid::new_v4(),
                         user_id: uid,
@@ -464,8 +474,8 @@ fn build_boss(
         },
         "boss_speed_demon" => {
             // Personal best − 5s/km. Default to 355s/km if no data.
-            let target = (stats.best_pace_secs - 5.0).max(150.0);
-            let pace_str = format_pace_str(target);
+            let target = Pace::from_secs_per_km((stats.best_pace.secs_per_km() - 5.0).max(150.0));
+            let pace_str = target.to_string();
             MonthlyMission {
                 id: Uuid::new_v4(),
                 user_id,
@@ -475,7 +485,7 @@ fn build_boss(
                 description: format!(
                     "Smash your personal best — run 5km+ under {pace_str}/km",
                 ),
-                target_value: target,
+                target_value: target.secs_per_km(),
                 // Start high so lower-is-better logic works (inverted)
                 current_value: 9999.0,
                 xp_reward: 750,
@@ -549,14 +559,15 @@ pub async fn get_or_generate_missions(
     pool: &PgPool,
     user_id: Uuid,
 ) -> Result<MonthlyMissionsResponse, AppError> {
-    let month_start = current_month_start();
+    let tz = users::service::timezone(pool, user_id).await;
+    let month_start = current_month_start(tz);
     let existing = repository::get_missions_for_month(pool, user_id, month_start).await?;
 
     if existing.len() < 4 {
         let existing_types: std::collections::HashSet<String> =
             existing.iter().map(|m| m.mission_type.clone()).collect();
 
-        let stats = fetch_monthly_stats(pool, user_id).await;
+        let stats = fetch_monthly_stats(pool, user_id, tz).await;
         let generated = generate_missions(user_id, month_start, &stats);
 
         let to_insert: Vec<MonthlyMission> = generated
@@ -595,7 +606,8 @@ pub async fn reroll_mission(
     user_id: Uuid,
     mission_id: Uuid,
 ) -> Result<MonthlyMission, AppError> {
-    let month_start = current_month_start();
+    let tz = users::service::timezone(pool, user_id).await;
+    let month_start = current_month_start(tz);
 
     let mission = repository::get_mission_by_id(pool, mission_id)
         .await?
@@ -613,7 +625,7 @@ pub async fn reroll_mission(
         }
         let old_count = mission.boss_reroll_count;
         repository::delete_mission(pool, mission_id).await?;
-        let stats = fetch_monthly_stats(pool, user_id).await;
+        let stats = fetch_monthly_stats(pool, user_id, tz).await;
         let boss_type =
             select_boss_excluding(&stats, month_start.month(), &mission.mission_type);
         let mut new_boss = build_boss(user_id, month_start, boss_type, &stats, Utc::now());
@@ -638,11 +650,11 @@ pub async fn reroll_mission(
         .map(|m| m.mission_type.clone())
         .collect();
 
-    let stats = fetch_monthly_stats(pool, user_id).await;
+    let stats = fetch_monthly_stats(pool, user_id, tz).await;
     let replacement = find_replacement(user_id, month_start, &stats, &existing_types)
         .ok_or_else(|| AppError::BadRequest("No replacement mission available".to_string()))?;
 
-    repository::insert_missions(pool, &[replacement.clone()]).await?;
+    repository::insert_missions(pool, std::slice::from_ref(&replacement)).await?;
 
     Ok(replacement)
 }
@@ -679,7 +691,7 @@ fn find_replacement(
     }
 
     let pick = available[(month as usize) % available.len()];
-    let pace_secs = stats.avg_pace_secs;
+    let avg_pace = stats.avg_pace;
     let wkly_km = stats.avg_weekly_km;
 
     let m = match pick {
@@ -744,8 +756,7 @@ fn find_replacement(
             created_at: now, updated_at: now,
         },
         "monthly_sub_pace_count" => {
-            let target_pace = (pace_secs - 15.0).max(180.0);
-            let pace_str = format_pace_str(target_pace);
+            let pace_str = sub_pace_target(avg_pace);
             MonthlyMission {
                 id: Uuid::new_v4(), user_id, month_start,
                 mission_type: "monthly_sub_pace_count".to_string(),
@@ -791,21 +802,35 @@ pub async fn update_progress_after_upload(
     pool: &PgPool,
     user_id: Uuid,
 ) -> Result<Vec<CompletedMissionSummary>, AppError> {
-    let month_start = current_month_start();
+    recalculate_progress(pool, user_id, false).await
+}
+
+/// Recalculate progress after an activity was edited or deleted.
+///
+/// Unlike an upload, an edit can take progress away: missions that are no
+/// longer met lose their completion and the XP it awarded.
+pub async fn update_progress_after_edit(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<CompletedMissionSummary>, AppError> {
+    recalculate_progress(pool, user_id, true).await
+}
+
+async fn recalculate_progress(
+    pool: &PgPool,
+    user_id: Uuid,
+    revoke_unmet: bool,
+) -> Result<Vec<CompletedMissionSummary>, AppError> {
+    let tz = users::service::timezone(pool, user_id).await;
+    let month_start = current_month_start(tz);
     let missions = repository::get_missions_for_month(pool, user_id, month_start).await?;
 
     if missions.is_empty() {
         return Ok(vec![]);
     }
 
-    let month_start_dt = month_start.and_hms_opt(0, 0, 0).unwrap();
-    // End of current month
-    let next_month = if month_start.month() == 12 {
-        NaiveDate::from_ymd_opt(month_start.year() + 1, 1, 1).unwrap()
-    } else {
-        NaiveDate::from_ymd_opt(month_start.year(), month_start.month() + 1, 1).unwrap()
-    };
-    let month_end_dt = next_month.and_hms_opt(0, 0, 0).unwrap();
+    let month_start_dt = timezone::start_of_day(tz, month_start);
+    let month_end_dt = timezone::start_of_day(tz, timezone::next_month_start(month_start));
 
     // Basic monthly stats
     struct MonthStats {
@@ -869,7 +894,7 @@ pub async fn update_progress_after_upload(
             "monthly_consistency_weeks" => {
                 let v: Option<i64> = sqlx::query_scalar(
                     r#"
-                    SELECT COUNT(DISTINCT DATE_TRUNC('week', date))
+                    SELECT COUNT(DISTINCT DATE_TRUNC('week', date AT TIME ZONE $4))
                     FROM activities
                     WHERE user_id = $1 AND date >= $2 AND date < $3
                     "#,
@@ -877,6 +902,7 @@ pub async fn update_progress_after_upload(
                 .bind(user_id)
                 .bind(month_start_dt)
                 .bind(month_end_dt)
+                .bind(tz.name())
                 .fetch_optional(pool)
                 .await
                 .ok()
@@ -954,7 +980,7 @@ pub async fn update_progress_after_upload(
                 // threshold in the DB except derivatively, we use the description. For simplicity,
                 // we count all 5km+ runs with avg_pace < (avg - 15s), which matches generation.
                 // In practice, once generated, the pace threshold is implicit.
-                // We'll query for pace using the DB formula; below-average pace counts.
+                // Pace is stored in secs/km; below-average pace counts.
                 let v: Option<i64> = sqlx::query_scalar(
                     r#"
                     SELECT COUNT(*)
@@ -962,9 +988,8 @@ pub async fn update_progress_after_upload(
                     WHERE user_id = $1 AND date >= $2 AND date < $3
                       AND distance::FLOAT8 >= 5.0
                       AND average_pace > 0
-                      AND (FLOOR(average_pace) * 60.0 + ((average_pace - FLOOR(average_pace)) * 100.0))
-                          < (
-                              SELECT AVG(FLOOR(average_pace) * 60.0 + ((average_pace - FLOOR(average_pace)) * 100.0)) - 15.0
+                      AND average_pace::FLOAT8 < (
+                              SELECT AVG(average_pace::FLOAT8) - 15.0
                               FROM activities
                               WHERE user_id = $1 AND average_pace > 0
                           )
@@ -987,7 +1012,8 @@ pub async fn update_progress_after_upload(
                     r#"
                     SELECT COALESCE(MAX(weekly_km), 0)
                     FROM (
-                        SELECT DATE_TRUNC('week', date) AS w, SUM(distance::FLOAT8) AS weekly_km
+                        SELECT DATE_TRUNC('week', date AT TIME ZONE $4) AS w,
+                               SUM(distance::FLOAT8) AS weekly_km
                         FROM activities
                         WHERE user_id = $1 AND date >= $2 AND date < $3
                         GROUP BY w
@@ -997,6 +1023,7 @@ pub async fn update_progress_after_upload(
                 .bind(user_id)
                 .bind(month_start_dt)
                 .bind(month_end_dt)
+                .bind(tz.name())
                 .fetch_optional(pool)
                 .await
                 .ok()
@@ -1010,11 +1037,11 @@ pub async fn update_progress_after_upload(
                     r#"
                     WITH weekly_km AS (
                         SELECT
-                            DATE_TRUNC('week', date) AS week_start,
+                            DATE_TRUNC('week', date AT TIME ZONE $4) AS week_start,
                             SUM(distance::FLOAT8) AS km
                         FROM activities
                         WHERE user_id = $1 AND date >= $2 AND date < $3
-                        GROUP BY DATE_TRUNC('week', date)
+                        GROUP BY DATE_TRUNC('week', date AT TIME ZONE $4)
                         ORDER BY week_start
                     )
                     SELECT COUNT(*) AS improving_weeks
@@ -1029,6 +1056,7 @@ pub async fn update_progress_after_upload(
                 .bind(user_id)
                 .bind(month_start_dt)
                 .bind(month_end_dt)
+                .bind(tz.name())
                 .fetch_optional(pool)
                 .await
                 .ok()
@@ -1041,17 +1069,21 @@ pub async fn update_progress_after_upload(
                 // Count distinct running days in the current ISO week (Monday–Sunday).
                 // Progress reflects how you're doing *this* week, resetting each Monday.
                 // The mission completes if you reach 5 days in the current week.
+                let week_start = current_week_start(tz);
                 let v: Option<i64> = sqlx::query_scalar(
                     r#"
-                    SELECT COUNT(DISTINCT DATE_TRUNC('day', date))
+                    SELECT COUNT(DISTINCT DATE_TRUNC('day', date AT TIME ZONE $4))
                     FROM activities
                     WHERE user_id = $1
                       AND activity_type = 'Running'
-                      AND date >= DATE_TRUNC('week', NOW())
-                      AND date < DATE_TRUNC('week', NOW()) + INTERVAL '1 week'
+                      AND date >= $2
+                      AND date < $3
                     "#,
                 )
                 .bind(user_id)
+                .bind(timezone::start_of_day(tz, week_start))
+                .bind(timezone::start_of_day(tz, week_start + Duration::days(7)))
+                .bind(tz.name())
                 .fetch_optional(pool)
                 .await
                 .ok()
@@ -1061,12 +1093,10 @@ pub async fn update_progress_after_upload(
                 (v, v >= mission.target_value)
             }
             "boss_speed_demon" => {
-                // Min converted-pace for runs >= 5km this month (lower = faster)
+                // Min pace (secs/km) for runs >= 5km this month (lower = faster)
                 let v: Option<f64> = sqlx::query_scalar(
                     r#"
-                    SELECT MIN(
-                        FLOOR(average_pace) * 60.0 + ((average_pace - FLOOR(average_pace)) * 100.0)
-                    )
+                    SELECT MIN(average_pace::FLOAT8)
                     FROM activities
                     WHERE user_id = $1 AND date >= $2 AND date < $3
                       AND distance >= 5 AND average_pace > 0
@@ -1090,19 +1120,20 @@ pub async fn update_progress_after_upload(
                     r#"
                     SELECT COALESCE(MAX(weekday_count), 0)
                     FROM (
-                        SELECT DATE_TRUNC('week', date) AS week_start,
-                               COUNT(DISTINCT DATE_TRUNC('day', date)) FILTER (
-                                   WHERE EXTRACT(DOW FROM date) BETWEEN 1 AND 5
+                        SELECT DATE_TRUNC('week', date AT TIME ZONE $4) AS week_start,
+                               COUNT(DISTINCT DATE_TRUNC('day', date AT TIME ZONE $4)) FILTER (
+                                   WHERE EXTRACT(DOW FROM date AT TIME ZONE $4) BETWEEN 1 AND 5
                                ) AS weekday_count
                         FROM activities
                         WHERE user_id = $1 AND date >= $2 AND date < $3
-                        GROUP BY DATE_TRUNC('week', date)
+                        GROUP BY DATE_TRUNC('week', date AT TIME ZONE $4)
                     ) weeks
                     "#,
                 )
                 .bind(user_id)
                 .bind(month_start_dt)
                 .bind(month_end_dt)
+                .bind(tz.name())
                 .fetch_optional(pool)
                 .await
                 .ok()
@@ -1142,14 +1173,31 @@ pub async fn update_progress_after_upload(
             _ => continue,
         };
 
+        // `boss_iron_week` tracks the rolling current week, so falling short
+        // after Monday's reset does not undo a completion.
+        let revoke = revoke_unmet
+            && was_completed
+            && !is_done
+            && mission.mission_type != "boss_iron_week";
         let completed_at = if is_done && !was_completed {
             Some(Utc::now())
+        } else if revoke {
+            None
         } else {
             mission.completed_at
         };
 
         repository::update_mission_progress(pool, mission.id, new_value, completed_at).await?;
 
+        if revoke {
+            let description = format!("Monthly mission no longer complete: {}", mission.title);
+            if let Err(e) =
+                xp_service::revoke_xp(pool, user_id, "mission", mission.id, description).await
+            {
+                tracing::warn!("Failed to revoke XP for mission {}: {e}", mission.id);
+            }
+        }
+
         if is_done && !was_completed {
             let input = AwardXpInput {
                 user_id,
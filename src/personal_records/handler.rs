@@ -14,7 +14,7 @@ use super::{models::PersonalRecordsResponse, service};
         ("user_id" = Uuid, Path, description = "User UUID")
     ),
     responses(
-        (status = 200, description = "All 5 PR categories with best times", body = PersonalRecordsResponse),
+        (status = 200, description = "Every PR category with its best time", body = PersonalRecordsResponse),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -7,6 +7,9 @@ use uuid::Uuid;
 /// Distance categories with optional range bounds (in metres).
 /// `None` bounds for `longest_run` mean "any distance" — winner is the longest.
 pub const CATEGORIES: &[(&str, Option<f64>, Option<f64>)] = &[
+    ("400m",          Some(380.0),   Some(420.0)),
+    ("1k",            Some(950.0),   Some(1_050.0)),
+    ("1_mile",        Some(1_530.0), Some(1_690.0)),
     ("5k",            Some(4_750.0), Some(5_250.0)),
     ("10k",           Some(9_500.0), Some(10_500.0)),
     ("half_marathon", Some(20_600.0), Some(21_600.0)),
@@ -14,6 +17,29 @@ pub const CATEGORIES: &[(&str, Option<f64>, Option<f64>)] = &[
     ("longest_run",   None, None),
 ];
 
+/// Distances (in metres) searched for inside every run's track; a category
+/// with a best effort is judged on it rather than on the whole run.
+pub const BEST_EFFORT_DISTANCES: &[(&str, f64)] = &[
+    ("400m",          400.0),
+    ("1k",            1_000.0),
+    ("1_mile",        1_609.344),
+    ("5k",            5_000.0),
+    ("10k",           10_000.0),
+    ("half_marathon", 21_097.5),
+    ("marathon",      42_195.0),
+];
+
+/// The fastest stretch of one standard distance inside an activity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BestEffort {
+    pub category: String,
+    pub distance_m: f64,
+    /// Elapsed seconds over `distance_m`.
+    pub duration_seconds: f64,
+    /// Seconds from the activity start to the start of the effort.
+    pub start_offset_seconds: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct PersonalRecord {
     pub id: Uuid,
@@ -61,6 +87,9 @@ pub struct PrCategorySummary {
 
 pub fn category_display(slug: &str) -> &'static str {
     match slug {
+        "400m"          => "400 m",
+        "1k"            => "1K",
+        "1_mile"        => "1 Mile",
         "5k"            => "5K",
         "10k"           => "10K",
         "half_marathon" => "Half Marathon",
@@ -70,13 +99,6 @@ pub fn category_display(slug: &str) -> &'static str {
     }
 }
 
-#[allow(dead_code)]
-pub fn format_pace(pace_secs: f64) -> String {
-    let mins = (pace_secs / 60.0).floor() as u32;
-    let secs = (pace_secs % 60.0).round() as u32;
-    format!("{}:{:02}/km", mins, secs)
-}
-
 /// Parse "H:MM:SS" or "MM:SS" duration string → total seconds.
 pub fn parse_duration_to_secs(s: &str) -> i64 {
     let parts: Vec<&str> = s.split(':').collect();
@@ -4,7 +4,7 @@ use uuid::Uuid;
 
 use crate::error::AppError;
 
-use super::models::PersonalRecord;
+use super::models::{BestEffort, PersonalRecord};
 
 pub async fn get_all_prs(db: &PgPool, user_id: Uuid) -> Result<Vec<PersonalRecord>, AppError> {
     sqlx::query_as::<_, PersonalRecord>(
@@ -31,6 +31,103 @@ pub async fn get_pr(
     .map_err(AppError::from)
 }
 
+/// Replace the stored best efforts of one activity.
+pub async fn replace_best_efforts(
+    db: &PgPool,
+    activity_id: Uuid,
+    efforts: &[BestEffort],
+) -> Result<(), AppError> {
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM activity_best_efforts WHERE activity_id = $1")
+        .bind(activity_id)
+        .execute(&mut *tx)
+        .await?;
+    for e in efforts {
+        sqlx::query(
+            "INSERT INTO activity_best_efforts \
+                (activity_id, category, distance_m, duration_seconds, start_offset_seconds) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(activity_id)
+        .bind(&e.category)
+        .bind(e.distance_m)
+        .bind(e.duration_seconds)
+        .bind(e.start_offset_seconds)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn find_best_efforts(
+    db: &PgPool,
+    activity_id: Uuid,
+) -> Result<Vec<BestEffort>, AppError> {
+    sqlx::query_as::<_, BestEffort>(
+        "SELECT category, distance_m, duration_seconds, start_offset_seconds \
+         FROM activity_best_efforts WHERE activity_id = $1 ORDER BY distance_m",
+    )
+    .bind(activity_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Best efforts of a user's activities in one category, as
+/// `(activity_id, effort)` pairs.
+pub async fn find_user_best_efforts<'c, E>(
+    db: E,
+    user_id: Uuid,
+    category: &str,
+) -> Result<Vec<(Uuid, BestEffort)>, AppError>
+where
+    E: sqlx::Executor<'c, Database = sqlx::Postgres>,
+{
+    let rows = sqlx::query_as::<_, (Uuid, String, f64, f64, f64)>(
+        "SELECT e.activity_id, e.category, e.distance_m, e.duration_seconds, \
+                e.start_offset_seconds \
+         FROM activity_best_efforts e \
+         JOIN activities a ON a.id = e.activity_id \
+         WHERE a.user_id = $1 AND e.category = $2",
+    )
+    .bind(user_id)
+    .bind(category)
+    .fetch_all(db)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(activity_id, category, distance_m, duration_seconds, start_offset_seconds)| {
+            let effort = BestEffort {
+                category,
+                distance_m,
+                duration_seconds,
+                start_offset_seconds,
+            };
+            (activity_id, effort)
+        })
+        .collect())
+}
+
+/// Delete every PR held by one activity, returning the freed categories.
+pub async fn delete_prs_for_activity<'c, E>(
+    db: E,
+    user_id: Uuid,
+    activity_id: Uuid,
+) -> Result<Vec<String>, AppError>
+where
+    E: sqlx::Executor<'c, Database = sqlx::Postgres>,
+{
+    sqlx::query_scalar(
+        "DELETE FROM personal_records WHERE user_id = $1 AND activity_id = $2 RETURNING category",
+    )
+    .bind(user_id)
+    .bind(activity_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
 /// Upsert a PR.
 ///
 /// For categories other than `longest_run`: only updates if the new pace is
@@ -41,8 +138,8 @@ pub async fn get_pr(
 /// Returns `Some(record)` if the record was inserted or updated, `None` if the
 /// existing record was better.
 #[allow(clippy::too_many_arguments)]
-pub async fn upsert_pr(
-    db: &PgPool,
+pub async fn upsert_pr<'c, E>(
+    db: E,
     user_id: Uuid,
     category: &str,
     activity_id: Uuid,
@@ -50,7 +147,10 @@ pub async fn upsert_pr(
     duration_seconds: i64,
     pace_seconds_per_km: f64,
     achieved_at: DateTime<Utc>,
-) -> Result<Option<PersonalRecord>, AppError> {
+) -> Result<Option<PersonalRecord>, AppError>
+where
+    E: sqlx::Executor<'c, Database = sqlx::Postgres>,
+{
     let condition_column = if category == "longest_run" {
         // For longest_run, update when the new distance is greater.
         "distance_m"
@@ -1,16 +1,19 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
 use crate::{
+    activities::{self, pace::Pace},
     error::AppError,
     xp::{models::AwardXpInput, service as xp_service},
 };
 
 use super::{
     models::{
-        category_display, parse_duration_to_secs, PersonalRecordSummary, PersonalRecordsResponse,
-        PrCategorySummary, CATEGORIES,
+        category_display, parse_duration_to_secs, BestEffort, PersonalRecordSummary,
+        PersonalRecordsResponse, PrCategorySummary, CATEGORIES,
     },
     repository,
 };
@@ -40,8 +43,24 @@ pub async fn get_user_prs(
     Ok(PersonalRecordsResponse { records: summaries })
 }
 
-/// Checks an activity against all PR categories.  Persists any new/improved
-/// records, awards 150 XP per PR, and returns compact summaries.
+/// Store the best efforts found in an activity's track, replacing earlier ones.
+pub async fn save_best_efforts(
+    db: &PgPool,
+    activity_id: Uuid,
+    efforts: &[BestEffort],
+) -> Result<(), AppError> {
+    repository::replace_best_efforts(db, activity_id, efforts).await
+}
+
+pub async fn get_best_efforts(db: &PgPool, activity_id: Uuid) -> Result<Vec<BestEffort>, AppError> {
+    repository::find_best_efforts(db, activity_id).await
+}
+
+/// Checks an activity against all PR categories.  A category the activity has
+/// a stored best effort for is judged on that effort, so a fast 5K inside a
+/// longer run counts; otherwise the whole activity competes when its distance
+/// is in the category's range.  Persists any new/improved records, awards
+/// 150 XP per PR, and returns compact summaries.
 pub async fn check_activity_for_prs(
     db: &PgPool,
     user_id: Uuid,
@@ -50,31 +69,21 @@ pub async fn check_activity_for_prs(
     duration_str: &str,
     achieved_at: DateTime<Utc>,
 ) -> Result<Vec<PrCategorySummary>, AppError> {
-    if distance_m <= 0.0 {
-        return Ok(vec![]);
-    }
-
-    let duration_seconds = parse_duration_to_secs(duration_str);
-    if duration_seconds <= 0 {
+    let efforts = repository::find_best_efforts(db, activity_id).await?;
+    let whole = whole_activity(distance_m, parse_duration_to_secs(duration_str));
+    if whole.is_none() && efforts.is_empty() {
         return Ok(vec![]);
     }
 
-    let pace_seconds_per_km = duration_seconds as f64 / (distance_m / 1000.0);
     let mut new_prs = Vec::new();
 
-    for (slug, min_m, max_m) in CATEGORIES {
-        let qualifies = if *slug == "longest_run" {
-            // Longest run: no range, always eligible — the upsert handles the comparison.
-            true
-        } else {
-            let min = min_m.unwrap();
-            let max = max_m.unwrap();
-            distance_m >= min && distance_m <= max
-        };
-
-        if !qualifies {
+    for (slug, _, _) in CATEGORIES {
+        let Some((distance_m, duration)) = candidate(slug, whole, &efforts) else {
             continue;
-        }
+        };
+        let duration_seconds = duration.round() as i64;
+        let pace_seconds_per_km =
+            Pace::from_duration(duration, distance_m / 1000.0).secs_per_km();
 
         // Check if this is a new PR (to determine is_first_pr after upsert).
         let existing = repository::get_pr(db, user_id, slug).await?;
@@ -116,3 +125,94 @@ pub async fn check_activity_for_prs(
 
     Ok(new_prs)
 }
+
+/// `(distance_m, seconds)` of a whole activity, if it has both.
+fn whole_activity(distance_m: f64, duration_seconds: i64) -> Option<(f64, f64)> {
+    (distance_m > 0.0 && duration_seconds > 0).then_some((distance_m, duration_seconds as f64))
+}
+
+/// The `(distance_m, seconds)` an activity competes with in `category`: its
+/// best effort when it has one, else the whole activity if it qualifies.
+fn candidate(
+    category: &str,
+    whole: Option<(f64, f64)>,
+    efforts: &[BestEffort],
+) -> Option<(f64, f64)> {
+    efforts
+        .iter()
+        .find(|e| e.category == category)
+        .map(|e| (e.distance_m, e.duration_seconds))
+        .or(whole.filter(|(distance_m, _)| qualifies_for(category, *distance_m)))
+}
+
+/// Whether a run of `distance_m` metres can hold the PR for `category`.
+fn qualifies_for(category: &str, distance_m: f64) -> bool {
+    CATEGORIES
+        .iter()
+        .find(|(slug, _, _)| *slug == category)
+        .is_some_and(|(_, min_m, max_m)| match (min_m, max_m) {
+            (Some(min), Some(max)) => distance_m >= *min && distance_m <= *max,
+            // Longest run: no range, always eligible — the upsert handles the comparison.
+            _ => true,
+        })
+}
+
+/// Strip the PRs held by an activity that is being edited or deleted, then
+/// hand each freed category to the best of the user's *other* activities.
+///
+/// Restored records are history, not new achievements, so no XP is awarded
+/// for them. Returns the categories the activity held.  Runs on the caller's
+/// connection, typically inside its transaction.
+pub async fn release_activity_prs(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    activity_id: Uuid,
+) -> Result<Vec<String>, AppError> {
+    let categories = repository::delete_prs_for_activity(&mut *conn, user_id, activity_id).await?;
+    if categories.is_empty() {
+        return Ok(categories);
+    }
+
+    let others = activities::repository::find_all_by_user(&mut *conn, user_id).await?;
+    for category in &categories {
+        let efforts: HashMap<Uuid, BestEffort> =
+            repository::find_user_best_efforts(&mut *conn, user_id, category)
+                .await?
+                .into_iter()
+                .collect();
+        let best = others
+            .iter()
+            .filter(|a| a.id != activity_id)
+            .filter_map(|a| {
+                let distance_m = a.distance as f64 * 1000.0; // km → m
+                let whole = whole_activity(distance_m, parse_duration_to_secs(&a.duration));
+                let effort = efforts.get(&a.id).map(std::slice::from_ref).unwrap_or_default();
+                let (distance_m, duration) = candidate(category, whole, effort)?;
+                let pace = Pace::from_duration(duration, distance_m / 1000.0).secs_per_km();
+                Some((a, distance_m, duration.round() as i64, pace))
+            })
+            .min_by(|x, y| {
+                if category == "longest_run" {
+                    y.1.total_cmp(&x.1)
+                } else {
+                    x.3.total_cmp(&y.3)
+                }
+            });
+
+        if let Some((activity, distance_m, duration_seconds, pace)) = best {
+            repository::upsert_pr(
+                &mut *conn,
+                user_id,
+                category,
+                activity.id,
+                distance_m,
+                duration_seconds,
+                pace,
+                activity.date,
+            )
+            .await?;
+        }
+    }
+
+    Ok(categories)
+}
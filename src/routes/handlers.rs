@@ -0,0 +1,83 @@
+use actix_web::{get, patch, web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+use super::{
+    models::{RoutesQuery, UpdateRouteRequest},
+    service,
+};
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/routes",
+    tag = "routes",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID"),
+        ("min_runs" = Option<i64>, Query, description = "Only routes run at least this often (default 2)")
+    ),
+    responses(
+        (status = 200, description = "Routes the user ran repeatedly, most run first, with run history, best time and pace trend", body = Vec<super::models::RouteSummary>),
+        (status = 400, description = "Invalid UUID or min_runs below 1")
+    )
+)]
+#[get("/users/{user_id}/routes")]
+pub async fn list_routes(
+    db: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    query: web::Query<RoutesQuery>,
+) -> Result<HttpResponse, AppError> {
+    let routes =
+        service::list_routes(db.get_ref(), path.into_inner(), query.into_inner().min_runs).await?;
+    Ok(HttpResponse::Ok().json(routes))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/routes/{route_id}",
+    tag = "routes",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID"),
+        ("route_id" = Uuid, Path, description = "Route ID")
+    ),
+    responses(
+        (status = 200, description = "The route with its run history, best time and pace trend", body = super::models::RouteSummary),
+        (status = 404, description = "Unknown route")
+    )
+)]
+#[get("/users/{user_id}/routes/{route_id}")]
+pub async fn get_route(
+    db: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, route_id) = path.into_inner();
+    let route = service::get_route(db.get_ref(), user_id, route_id).await?;
+    Ok(HttpResponse::Ok().json(route))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/users/{user_id}/routes/{route_id}",
+    tag = "routes",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID"),
+        ("route_id" = Uuid, Path, description = "Route ID")
+    ),
+    request_body = UpdateRouteRequest,
+    responses(
+        (status = 200, description = "Route renamed", body = super::models::RouteSummary),
+        (status = 400, description = "Empty name"),
+        (status = 404, description = "Unknown route")
+    )
+)]
+#[patch("/users/{user_id}/routes/{route_id}")]
+pub async fn update_route(
+    db: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+    body: web::Json<UpdateRouteRequest>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, route_id) = path.into_inner();
+    let route = service::rename_route(db.get_ref(), user_id, route_id, &body.name).await?;
+    Ok(HttpResponse::Ok().json(route))
+}
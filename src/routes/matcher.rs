@@ -0,0 +1,98 @@
+/// Deciding whether two tracks follow the same route.
+///
+/// Tracks are compared by their simplified shape: they must start close
+/// together, be of similar length, and each must lie (for at least
+/// [`MIN_OVERLAP`] of its length) within [`MATCH_RADIUS_M`] of the other.
+/// Direction is ignored, so a loop run either way round is one route.
+///
+/// Pure functions — no I/O.
+use crate::activities::{
+    geometry::{self, segment_distance, Projection},
+    models::TrackPoint,
+    parser::haversine_distance_m,
+};
+use crate::segments::matcher::polyline_length;
+
+/// Tolerance tracks are simplified with before comparing, in metres.
+pub const SIMPLIFY_TOLERANCE_M: f64 = 10.0;
+
+/// Shortest track that founds a route, in metres.
+pub const MIN_ROUTE_M: f64 = 500.0;
+
+/// How far apart the starts of two runs of a route may be, in metres.
+const START_RADIUS_M: f64 = 200.0;
+
+/// Largest accepted length difference, relative to the longer track.
+const MAX_LENGTH_DIFF: f64 = 0.15;
+
+/// How far a track may stray from the other and still overlap it, in metres.
+const MATCH_RADIUS_M: f64 = 50.0;
+
+/// Share of each track that must overlap the other.
+pub const MIN_OVERLAP: f64 = 0.9;
+
+/// Spacing of the points a track is sampled at for the overlap, in metres.
+const SAMPLE_SPACING_M: f64 = 25.0;
+
+/// The simplified shape of a track as `(latitude, longitude)` pairs.
+pub fn signature(track: &[TrackPoint]) -> Vec<(f64, f64)> {
+    geometry::simplify(track, SIMPLIFY_TOLERANCE_M)
+        .iter()
+        .map(|tp| (tp.latitude, tp.longitude))
+        .collect()
+}
+
+/// How alike two signatures are, from 0 to 1: the smaller share of either
+/// that overlaps the other, or 0 when the starts or lengths are too far apart.
+/// Two tracks follow the same route from [`MIN_OVERLAP`] on.
+pub fn similarity(a: &[(f64, f64)], b: &[(f64, f64)]) -> f64 {
+    let (Some(&start_a), Some(&start_b)) = (a.first(), b.first()) else {
+        return 0.0;
+    };
+    if haversine_distance_m(start_a.0, start_a.1, start_b.0, start_b.1) > START_RADIUS_M {
+        return 0.0;
+    }
+    let (len_a, len_b) = (polyline_length(a), polyline_length(b));
+    if (len_a - len_b).abs() > MAX_LENGTH_DIFF * len_a.max(len_b) {
+        return 0.0;
+    }
+    let projection = Projection::new(start_a.0);
+    let project = |s: &[(f64, f64)]| -> Vec<(f64, f64)> {
+        s.iter()
+            .map(|&(lat, lon)| projection.xy(lat, lon))
+            .collect()
+    };
+    let (a, b) = (project(a), project(b));
+    overlap(&a, &b).min(overlap(&b, &a))
+}
+
+/// Share of the samples along `a` that lie within [`MATCH_RADIUS_M`] of `b`.
+fn overlap(a: &[(f64, f64)], b: &[(f64, f64)]) -> f64 {
+    let samples = resample(a);
+    let near = |p: (f64, f64)| match b {
+        [only] => (p.0 - only.0).hypot(p.1 - only.1) <= MATCH_RADIUS_M,
+        _ => b
+            .windows(2)
+            .any(|w| segment_distance(p, w[0], w[1]) <= MATCH_RADIUS_M),
+    };
+    let hits = samples.iter().filter(|&&p| near(p)).count();
+    hits as f64 / samples.len() as f64
+}
+
+/// Points along a projected polyline every [`SAMPLE_SPACING_M`], ends
+/// included.
+fn resample(line: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut out = vec![line[0]];
+    for w in line.windows(2) {
+        let len = (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1);
+        let steps = (len / SAMPLE_SPACING_M).ceil().max(1.0) as usize;
+        for s in 1..=steps {
+            let f = s as f64 / steps as f64;
+            out.push((
+                w[0].0 + (w[1].0 - w[0].0) * f,
+                w[0].1 + (w[1].1 - w[0].1) * f,
+            ));
+        }
+    }
+    out
+}
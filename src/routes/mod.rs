@@ -0,0 +1,13 @@
+pub mod handlers;
+pub mod matcher;
+pub mod models;
+pub mod repository;
+pub mod service;
+
+use actix_web::web;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers::list_routes)
+        .service(handlers::get_route)
+        .service(handlers::update_route);
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::activities::pace::Pace;
+
+/// Row of `routes`: a course a user has run, found by comparing tracks.
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct Route {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Generated from the distance and type until the user renames it.
+    pub name: String,
+    pub activity_type: String,
+    /// Metres, along the simplified track of the founding activity.
+    pub distance_m: f64,
+    /// The simplified track of the founding activity, start first; parallel
+    /// to `longitudes`.
+    pub latitudes: Vec<f64>,
+    pub longitudes: Vec<f64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Route {
+    /// `(latitude, longitude)` pairs, start first.
+    pub fn coordinates(&self) -> Vec<(f64, f64)> {
+        self.latitudes
+            .iter()
+            .copied()
+            .zip(self.longitudes.iter().copied())
+            .collect()
+    }
+}
+
+/// One activity on a route.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RouteRun {
+    pub activity_id: Uuid,
+    pub date: DateTime<Utc>,
+    pub name: String,
+    /// Kilometres, as reported by the source.
+    pub distance: f32,
+    pub duration_seconds: i64,
+    pub pace: Pace,
+    /// Seconds slower than the route's best time; 0 for the best run.
+    pub behind_best_seconds: i64,
+}
+
+/// A route with its run history, newest first.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RouteSummary {
+    pub route: Route,
+    pub run_count: i64,
+    /// The fastest run.
+    pub best: Option<RouteRun>,
+    /// Change of pace over time, in seconds per km per 30 days; negative
+    /// means getting faster.  `None` until runs on two different days.
+    pub pace_trend: Option<f64>,
+    pub runs: Vec<RouteRun>,
+}
+
+/// Optional query parameters for `GET /users/{user_id}/routes`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RoutesQuery {
+    /// Only routes with at least this many runs (default 2).
+    pub min_runs: Option<i64>,
+}
+
+/// Body of `PATCH /users/{user_id}/routes/{route_id}`.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateRouteRequest {
+    pub name: String,
+}
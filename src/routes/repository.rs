@@ -0,0 +1,165 @@
+/// SQL layer for routes and the activities on them.
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{activities::models::Activity, error::AppError};
+
+use super::models::Route;
+
+const ROUTE_COLUMNS: &str =
+    "id, user_id, name, activity_type, distance_m, latitudes, longitudes, created_at";
+
+pub async fn insert_route(db: &PgPool, r: &Route) -> Result<(), AppError> {
+    let lat = |f: fn(f64, f64) -> f64| r.latitudes.iter().copied().fold(r.latitudes[0], f);
+    let lon = |f: fn(f64, f64) -> f64| r.longitudes.iter().copied().fold(r.longitudes[0], f);
+    sqlx::query(
+        "INSERT INTO routes
+            (id, user_id, name, activity_type, distance_m, latitudes, longitudes,
+             min_lat, max_lat, min_lon, max_lon, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+    )
+    .bind(r.id)
+    .bind(r.user_id)
+    .bind(&r.name)
+    .bind(&r.activity_type)
+    .bind(r.distance_m)
+    .bind(&r.latitudes)
+    .bind(&r.longitudes)
+    .bind(lat(f64::min))
+    .bind(lat(f64::max))
+    .bind(lon(f64::min))
+    .bind(lon(f64::max))
+    .bind(r.created_at)
+    .execute(db)
+    .await
+    .map_err(AppError::from)?;
+    Ok(())
+}
+
+pub async fn find_route(
+    db: &PgPool,
+    user_id: Uuid,
+    route_id: Uuid,
+) -> Result<Option<Route>, AppError> {
+    sqlx::query_as::<_, Route>(&format!(
+        "SELECT {ROUTE_COLUMNS} FROM routes WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(route_id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Every route of a user, oldest first.
+pub async fn find_user_routes(db: &PgPool, user_id: Uuid) -> Result<Vec<Route>, AppError> {
+    sqlx::query_as::<_, Route>(&format!(
+        "SELECT {ROUTE_COLUMNS} FROM routes WHERE user_id = $1 ORDER BY created_at, id"
+    ))
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// A user's routes of `activity_type` whose bounding box overlaps the given
+/// one, oldest first.
+pub async fn find_routes_in_bbox(
+    db: &PgPool,
+    user_id: Uuid,
+    activity_type: &str,
+    (min_lat, max_lat, min_lon, max_lon): (f64, f64, f64, f64),
+) -> Result<Vec<Route>, AppError> {
+    sqlx::query_as::<_, Route>(&format!(
+        "SELECT {ROUTE_COLUMNS} FROM routes
+         WHERE user_id = $1 AND activity_type = $2
+           AND min_lat <= $4 AND max_lat >= $3
+           AND min_lon <= $6 AND max_lon >= $5
+         ORDER BY created_at, id"
+    ))
+    .bind(user_id)
+    .bind(activity_type)
+    .bind(min_lat)
+    .bind(max_lat)
+    .bind(min_lon)
+    .bind(max_lon)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Rename a route.  Returns `true` if the user has a route with that id.
+pub async fn rename_route(
+    db: &PgPool,
+    user_id: Uuid,
+    route_id: Uuid,
+    name: &str,
+) -> Result<bool, AppError> {
+    let result = sqlx::query("UPDATE routes SET name = $3 WHERE id = $1 AND user_id = $2")
+        .bind(route_id)
+        .bind(user_id)
+        .bind(name)
+        .execute(db)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Put an activity on a route, or record with `None` that it follows none.
+pub async fn set_activity_route(
+    db: &PgPool,
+    activity_id: Uuid,
+    route_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO route_activities (activity_id, route_id) VALUES ($1, $2)
+         ON CONFLICT (activity_id) DO UPDATE SET route_id = EXCLUDED.route_id",
+    )
+    .bind(activity_id)
+    .bind(route_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Up to `limit` activities, of any user, that have a track but were never
+/// checked against routes, oldest first.
+pub async fn find_unrouted_activities(db: &PgPool, limit: i64) -> Result<Vec<Uuid>, AppError> {
+    sqlx::query_scalar(
+        "SELECT a.id FROM activities a
+         WHERE NOT EXISTS (SELECT 1 FROM route_activities r WHERE r.activity_id = a.id)
+           AND EXISTS (SELECT 1 FROM trackpoints t WHERE t.activity_id = a.id)
+         ORDER BY a.date, a.id
+         LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// The activities on a user's routes, with their route, newest first.
+pub async fn find_route_activities(
+    db: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<(Uuid, Activity)>, AppError> {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        route_id: Uuid,
+        #[sqlx(flatten)]
+        activity: Activity,
+    }
+
+    let rows = sqlx::query_as::<_, Row>(
+        "SELECT r.route_id, a.*
+         FROM   route_activities r
+         JOIN   activities a ON a.id = r.activity_id
+         WHERE  a.user_id = $1 AND r.route_id IS NOT NULL
+         ORDER  BY a.date DESC, a.id",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)?;
+    Ok(rows.into_iter().map(|r| (r.route_id, r.activity)).collect())
+}
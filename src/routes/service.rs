@@ -0,0 +1,233 @@
+/// Routes: a user's activities clustered by the course they follow.
+///
+/// `activities::service` puts each newly stored or re-cleaned track on the
+/// user's route it matches best, or founds a new route with it.
+/// Activities stored before routes existed are clustered by [`backfill`],
+/// which the server runs in the background at startup.
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    activities::{self, models::Activity, models::TrackPoint, pace::Pace},
+    error::AppError,
+    personal_records::models::parse_duration_to_secs,
+    segments::matcher::polyline_length,
+};
+
+use super::{
+    matcher::{self, MIN_ROUTE_M},
+    models::{Route, RouteRun, RouteSummary},
+    repository,
+};
+
+/// Routes with fewer runs are left out of the list unless asked for.
+const DEFAULT_MIN_RUNS: i64 = 2;
+
+/// Activities [`backfill`] loads per query.
+const BACKFILL_BATCH: i64 = 100;
+
+/// Put an activity's cleaned track on the most similar of the user's routes,
+/// founding a new route when none matches.  Tracks shorter than
+/// [`MIN_ROUTE_M`] are on no route.
+pub async fn assign_activity(
+    db: &PgPool,
+    activity_id: Uuid,
+    track: &[TrackPoint],
+) -> Result<(), AppError> {
+    let Some(activity) = activities::repository::find_by_id(db, activity_id).await? else {
+        return Ok(());
+    };
+    let signature = matcher::signature(track);
+    let distance_m = polyline_length(&signature);
+    if signature.len() < 2 || distance_m < MIN_ROUTE_M {
+        return repository::set_activity_route(db, activity_id, None).await;
+    }
+
+    let bbox = signature.iter().fold(
+        (
+            signature[0].0,
+            signature[0].0,
+            signature[0].1,
+            signature[0].1,
+        ),
+        |b, &(lat, lon)| (b.0.min(lat), b.1.max(lat), b.2.min(lon), b.3.max(lon)),
+    );
+    let candidates =
+        repository::find_routes_in_bbox(db, activity.user_id, &activity.activity_type, bbox)
+            .await?;
+    let best = candidates
+        .iter()
+        .map(|r| (r.id, matcher::similarity(&signature, &r.coordinates())))
+        .filter(|&(_, score)| score >= matcher::MIN_OVERLAP)
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    let route_id = match best {
+        Some((route_id, _)) => route_id,
+        None => {
+            let route = Route {
+                id: Uuid::new_v4(),
+                user_id: activity.user_id,
+                name: format!("{:.1} km {}", distance_m / 1000.0, activity.activity_type),
+                activity_type: activity.activity_type,
+                distance_m,
+                latitudes: signature.iter().map(|c| c.0).collect(),
+                longitudes: signature.iter().map(|c| c.1).collect(),
+                created_at: Utc::now(),
+            };
+            repository::insert_route(db, &route).await?;
+            route.id
+        }
+    };
+    repository::set_activity_route(db, activity_id, Some(route_id)).await
+}
+
+/// Check every activity that has a track but was never checked against
+/// routes, of every user.  Returns the number of activities checked.
+pub async fn backfill(db: &PgPool) -> Result<usize, AppError> {
+    let mut checked = 0;
+    loop {
+        let batch = repository::find_unrouted_activities(db, BACKFILL_BATCH).await?;
+        if batch.is_empty() {
+            return Ok(checked);
+        }
+        for activity_id in batch {
+            let track = activities::repository::find_trackpoints(db, activity_id).await?;
+            assign_activity(db, activity_id, &track).await?;
+            checked += 1;
+        }
+    }
+}
+
+/// A user's routes with at least `min_runs` runs (default 2), most run
+/// first.
+pub async fn list_routes(
+    db: &PgPool,
+    user_id: Uuid,
+    min_runs: Option<i64>,
+) -> Result<Vec<RouteSummary>, AppError> {
+    let min_runs = min_runs.unwrap_or(DEFAULT_MIN_RUNS);
+    if min_runs < 1 {
+        return Err(AppError::BadRequest("min_runs must be at least 1".into()));
+    }
+    let mut runs: HashMap<Uuid, Vec<Activity>> = HashMap::new();
+    for (route_id, activity) in repository::find_route_activities(db, user_id).await? {
+        runs.entry(route_id).or_default().push(activity);
+    }
+    let mut summaries: Vec<RouteSummary> = repository::find_user_routes(db, user_id)
+        .await?
+        .into_iter()
+        .map(|route| {
+            let activities = runs.remove(&route.id).unwrap_or_default();
+            summarise(route, &activities)
+        })
+        .filter(|s| s.run_count >= min_runs)
+        .collect();
+    summaries.sort_by(|a, b| {
+        b.run_count
+            .cmp(&a.run_count)
+            .then_with(|| b.runs[0].date.cmp(&a.runs[0].date))
+    });
+    Ok(summaries)
+}
+
+/// One of the user's routes with its full history.
+pub async fn get_route(
+    db: &PgPool,
+    user_id: Uuid,
+    route_id: Uuid,
+) -> Result<RouteSummary, AppError> {
+    let route = repository::find_route(db, user_id, route_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let activities: Vec<Activity> = repository::find_route_activities(db, user_id)
+        .await?
+        .into_iter()
+        .filter(|(id, _)| *id == route_id)
+        .map(|(_, a)| a)
+        .collect();
+    Ok(summarise(route, &activities))
+}
+
+/// Rename one of the user's routes.
+pub async fn rename_route(
+    db: &PgPool,
+    user_id: Uuid,
+    route_id: Uuid,
+    name: &str,
+) -> Result<RouteSummary, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("name must not be empty".into()));
+    }
+    if !repository::rename_route(db, user_id, route_id, name).await? {
+        return Err(AppError::NotFound);
+    }
+    get_route(db, user_id, route_id).await
+}
+
+/// History, best run and pace trend of a route; `activities` newest first.
+fn summarise(route: Route, activities: &[Activity]) -> RouteSummary {
+    let mut runs: Vec<RouteRun> = activities
+        .iter()
+        .map(|a| {
+            let duration_seconds = match parse_duration_to_secs(&a.duration) {
+                0 => a.track_metrics.elapsed_time.unwrap_or(0) as i64,
+                secs => secs,
+            };
+            RouteRun {
+                activity_id: a.id,
+                date: a.date,
+                name: a.name.clone(),
+                distance: a.distance,
+                duration_seconds,
+                pace: Pace::from_duration(duration_seconds as f64, a.distance as f64),
+                behind_best_seconds: 0,
+            }
+        })
+        .collect();
+    let best_seconds = runs
+        .iter()
+        .map(|r| r.duration_seconds)
+        .filter(|&s| s > 0)
+        .min();
+    if let Some(best_seconds) = best_seconds {
+        for run in runs.iter_mut().filter(|r| r.duration_seconds > 0) {
+            run.behind_best_seconds = run.duration_seconds - best_seconds;
+        }
+    }
+    let pace_trend = pace_trend(&runs.iter().map(|r| (r.date, r.pace)).collect::<Vec<_>>());
+    RouteSummary {
+        route,
+        run_count: runs.len() as i64,
+        best: runs
+            .iter()
+            .filter(|r| r.duration_seconds > 0)
+            .min_by_key(|r| (r.duration_seconds, r.date))
+            .cloned(),
+        pace_trend,
+        runs,
+    }
+}
+
+/// Least-squares slope of pace over time, in seconds per km per 30 days.
+/// Runs without a pace are skipped; `None` until runs on two different days.
+pub fn pace_trend(runs: &[(DateTime<Utc>, Pace)]) -> Option<f64> {
+    let points: Vec<(f64, f64)> = runs
+        .iter()
+        .filter(|(_, pace)| pace.is_set())
+        .map(|(date, pace)| (date.timestamp() as f64 / 86_400.0, pace.secs_per_km()))
+        .collect();
+    let first_day = points.first()?.0.floor();
+    if points.iter().all(|p| p.0.floor() == first_day) {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    Some(sxy / sxx * 30.0)
+}
@@ -0,0 +1,120 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+use super::{models::CreateSegmentRequest, service};
+
+#[utoipa::path(
+    post,
+    path = "/users/{user_id}/segments",
+    tag = "segments",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID")
+    ),
+    request_body = CreateSegmentRequest,
+    responses(
+        (status = 201, description = "Segment created and every stored activity timed on it; the creator's efforts", body = super::models::SegmentDetail),
+        (status = 400, description = "Neither or both of activity_id and points, or a segment under 100 m"),
+        (status = 404, description = "Unknown activity")
+    )
+)]
+#[post("/users/{user_id}/segments")]
+pub async fn create_segment(
+    db: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+    body: web::Json<CreateSegmentRequest>,
+) -> Result<HttpResponse, AppError> {
+    let detail =
+        service::create_segment(db.get_ref(), path.into_inner(), body.into_inner()).await?;
+    Ok(HttpResponse::Created().json(detail))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/segments",
+    tag = "segments",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Segments the user created or has efforts on, with personal bests", body = Vec<super::models::SegmentSummary>),
+        (status = 400, description = "Invalid UUID")
+    )
+)]
+#[get("/users/{user_id}/segments")]
+pub async fn list_segments(
+    db: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let segments = service::list_segments(db.get_ref(), path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(segments))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/segments/{segment_id}",
+    tag = "segments",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID"),
+        ("segment_id" = Uuid, Path, description = "Segment ID")
+    ),
+    responses(
+        (status = 200, description = "The segment with the user's efforts, fastest first", body = super::models::SegmentDetail),
+        (status = 404, description = "Unknown segment")
+    )
+)]
+#[get("/users/{user_id}/segments/{segment_id}")]
+pub async fn get_segment(
+    db: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, segment_id) = path.into_inner();
+    let detail = service::get_segment(db.get_ref(), user_id, segment_id).await?;
+    Ok(HttpResponse::Ok().json(detail))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}/segments/{segment_id}",
+    tag = "segments",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID"),
+        ("segment_id" = Uuid, Path, description = "Segment ID")
+    ),
+    responses(
+        (status = 204, description = "Segment and all its efforts deleted"),
+        (status = 404, description = "Unknown segment, or created by another user")
+    )
+)]
+#[delete("/users/{user_id}/segments/{segment_id}")]
+pub async fn delete_segment(
+    db: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, segment_id) = path.into_inner();
+    service::delete_segment(db.get_ref(), user_id, segment_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/segments/{segment_id}/leaderboard",
+    tag = "segments",
+    params(
+        ("segment_id" = Uuid, Path, description = "Segment ID")
+    ),
+    responses(
+        (status = 200, description = "Best effort of every user who opted in to segment leaderboards", body = super::models::SegmentLeaderboard),
+        (status = 404, description = "Unknown segment")
+    )
+)]
+#[get("/segments/{segment_id}/leaderboard")]
+pub async fn get_leaderboard(
+    db: web::Data<PgPool>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let leaderboard = service::get_leaderboard(db.get_ref(), path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(leaderboard))
+}
@@ -0,0 +1,167 @@
+/// Finding the passes of a track over a segment.
+///
+/// A pass starts at the track point closest to the segment start (within
+/// [`ENDPOINT_RADIUS_M`]) and ends at the first later point closest to the
+/// segment end, once at least half the segment's length has been covered.
+/// It only counts when the track in between never leaves the
+/// [`CORRIDOR_M`] corridor around the segment, visits every part of the
+/// segment in order, and is at most [`MAX_DETOUR`] times the segment's
+/// length.  One track can pass a segment several times (laps of a loop);
+/// passes never overlap.
+///
+/// Pure functions — no I/O.
+use crate::activities::{
+    geometry::{segment_distance, Projection},
+    models::TrackPoint,
+};
+
+/// How close the track must come to the segment's start and end, in metres.
+pub const ENDPOINT_RADIUS_M: f64 = 25.0;
+
+/// How far the track may stray from the segment, in metres.
+pub const CORRIDOR_M: f64 = 30.0;
+
+/// Longest accepted pass, as a multiple of the segment's length.
+pub const MAX_DETOUR: f64 = 1.5;
+
+/// Spacing of the segment checkpoints every pass has to visit, in metres.
+const CHECKPOINT_SPACING_M: f64 = 25.0;
+
+/// A pass over a segment: indices of its first and last track point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pass {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Length in metres of a `(latitude, longitude)` polyline.
+pub fn polyline_length(segment: &[(f64, f64)]) -> f64 {
+    let Some(&(lat0, _)) = segment.first() else {
+        return 0.0;
+    };
+    let projection = Projection::new(lat0);
+    let xy: Vec<(f64, f64)> = segment
+        .iter()
+        .map(|&(lat, lon)| projection.xy(lat, lon))
+        .collect();
+    xy.windows(2)
+        .map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1))
+        .sum()
+}
+
+/// Every pass of `track` over `segment` (`(latitude, longitude)` pairs), in
+/// track order.
+pub fn find_passes(segment: &[(f64, f64)], track: &[TrackPoint]) -> Vec<Pass> {
+    if segment.len() < 2 || track.len() < 2 {
+        return Vec::new();
+    }
+    let projection = Projection::new(segment[0].0);
+    let seg: Vec<(f64, f64)> = segment
+        .iter()
+        .map(|&(lat, lon)| projection.xy(lat, lon))
+        .collect();
+    let pts: Vec<(f64, f64)> = track
+        .iter()
+        .map(|tp| projection.xy(tp.latitude, tp.longitude))
+        .collect();
+    let length = polyline_length(segment);
+    let mut along = vec![0.0; pts.len()];
+    for i in 1..pts.len() {
+        along[i] = along[i - 1] + (pts[i].0 - pts[i - 1].0).hypot(pts[i].1 - pts[i - 1].1);
+    }
+    let (first, last) = (seg[0], seg[seg.len() - 1]);
+    let near = |i: usize, p: (f64, f64)| distance(pts[i], p) <= ENDPOINT_RADIUS_M;
+    let checkpoints = checkpoints(&seg);
+
+    let mut passes = Vec::new();
+    let mut i = 0;
+    while i < pts.len() {
+        if !near(i, first) {
+            i += 1;
+            continue;
+        }
+        let start = closest_in_run(&pts, i, first, &near);
+        let mut found = None;
+        let mut j = start + 1;
+        while j < pts.len() && along[j] - along[start] <= length * MAX_DETOUR {
+            if along[j] - along[start] >= length / 2.0 && near(j, last) {
+                let end = closest_in_run(&pts, j, last, &near);
+                if along[end] - along[start] <= length * MAX_DETOUR
+                    && follows(&pts[start..=end], &seg, &checkpoints)
+                {
+                    found = Some(end);
+                    break;
+                }
+                j = end;
+            }
+            j += 1;
+        }
+        match found {
+            Some(end) => {
+                passes.push(Pass { start, end });
+                i = end + 1;
+            }
+            None => i = start + 1,
+        }
+    }
+    passes
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+/// The point closest to `target` among the consecutive points from `i` on
+/// that are `near` it.
+fn closest_in_run(
+    pts: &[(f64, f64)],
+    i: usize,
+    target: (f64, f64),
+    near: &impl Fn(usize, (f64, f64)) -> bool,
+) -> usize {
+    (i..pts.len())
+        .take_while(|&k| near(k, target))
+        .min_by(|&a, &b| distance(pts[a], target).total_cmp(&distance(pts[b], target)))
+        .unwrap_or(i)
+}
+
+/// Points along the segment every [`CHECKPOINT_SPACING_M`], ends included.
+fn checkpoints(seg: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut out = vec![seg[0]];
+    for w in seg.windows(2) {
+        let len = distance(w[0], w[1]);
+        let steps = (len / CHECKPOINT_SPACING_M).ceil().max(1.0) as usize;
+        for s in 1..=steps {
+            let f = s as f64 / steps as f64;
+            out.push((
+                w[0].0 + (w[1].0 - w[0].0) * f,
+                w[0].1 + (w[1].1 - w[0].1) * f,
+            ));
+        }
+    }
+    out
+}
+
+/// Whether a stretch of track stays in the corridor and reaches every
+/// checkpoint, in segment order.
+fn follows(stretch: &[(f64, f64)], seg: &[(f64, f64)], checkpoints: &[(f64, f64)]) -> bool {
+    let in_corridor = |p: (f64, f64)| {
+        seg.windows(2)
+            .any(|w| segment_distance(p, w[0], w[1]) <= CORRIDOR_M)
+    };
+    if !stretch.iter().all(|&p| in_corridor(p)) {
+        return false;
+    }
+    // Checkpoints are matched against the track's edges, so sparse recording
+    // (a point every few seconds on a bike) does not miss them.
+    let mut from = 0;
+    for &c in checkpoints {
+        let Some(k) = (from..stretch.len() - 1)
+            .find(|&k| segment_distance(c, stretch[k], stretch[k + 1]) <= CORRIDOR_M)
+        else {
+            return false;
+        };
+        from = k;
+    }
+    true
+}
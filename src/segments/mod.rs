@@ -0,0 +1,15 @@
+pub mod handlers;
+pub mod matcher;
+pub mod models;
+pub mod repository;
+pub mod service;
+
+use actix_web::web;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers::create_segment)
+        .service(handlers::list_segments)
+        .service(handlers::get_segment)
+        .service(handlers::delete_segment)
+        .service(handlers::get_leaderboard);
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::activities::pace::Pace;
+
+/// Row of `segments`: a stretch of road that matching activities are timed on.
+#[derive(Debug, Clone, Serialize, ToSchema, sqlx::FromRow)]
+pub struct Segment {
+    pub id: Uuid,
+    /// The user who created the segment.
+    pub user_id: Uuid,
+    pub name: String,
+    /// Only activities of this type are timed on the segment.
+    pub activity_type: String,
+    /// Metres.
+    pub distance_m: f64,
+    /// The segment's points, start first; parallel to `longitudes`.
+    pub latitudes: Vec<f64>,
+    pub longitudes: Vec<f64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Segment {
+    /// `(latitude, longitude)` pairs, start first.
+    pub fn coordinates(&self) -> Vec<(f64, f64)> {
+        self.latitudes
+            .iter()
+            .copied()
+            .zip(self.longitudes.iter().copied())
+            .collect()
+    }
+}
+
+/// Row of `segment_efforts`: one timed pass of an activity over a segment.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct SegmentEffort {
+    pub id: Uuid,
+    pub segment_id: Uuid,
+    pub activity_id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub elapsed_seconds: f64,
+    /// Over the segment's distance.
+    pub pace: Pace,
+}
+
+/// A point of a drawn segment.
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+pub struct SegmentPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Body of `POST /users/{user_id}/segments`.
+///
+/// Either pick a stretch of one of the user's activities (`activity_id` with
+/// `start_m` and `end_m`, metres along its track) or draw one (`points`).
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateSegmentRequest {
+    pub name: String,
+    pub activity_id: Option<Uuid>,
+    pub start_m: Option<f64>,
+    pub end_m: Option<f64>,
+    pub points: Option<Vec<SegmentPoint>>,
+    /// For drawn segments; defaults to `"Running"`.  Picked segments take
+    /// the activity's type.
+    pub activity_type: Option<String>,
+}
+
+/// A segment with one user's efforts on it, fastest first.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SegmentDetail {
+    pub segment: Segment,
+    pub personal_best: Option<SegmentEffort>,
+    pub efforts: Vec<SegmentEffort>,
+}
+
+/// A segment in a user's list.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SegmentSummary {
+    pub segment: Segment,
+    pub personal_best: Option<SegmentEffort>,
+    pub effort_count: i64,
+}
+
+/// One user's best effort on a segment leaderboard.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SegmentLeaderboardEntry {
+    pub rank: i64,
+    /// Anonymous label, e.g. `"Athlete 3f2a9c1e"`.
+    pub display_name: String,
+    pub effort: SegmentEffort,
+}
+
+/// Response for `GET /segments/{segment_id}/leaderboard`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SegmentLeaderboard {
+    pub segment_id: Uuid,
+    pub segment_name: String,
+    pub entries: Vec<SegmentLeaderboardEntry>,
+}
@@ -0,0 +1,245 @@
+/// SQL layer for segments and segment efforts.
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+use super::models::{Segment, SegmentEffort};
+
+const SEGMENT_COLUMNS: &str =
+    "id, user_id, name, activity_type, distance_m, latitudes, longitudes, created_at";
+
+const EFFORT_COLUMNS: &str =
+    "e.id, e.segment_id, e.activity_id, e.start_time, e.elapsed_seconds, e.pace";
+
+pub async fn insert_segment(db: &PgPool, s: &Segment) -> Result<(), AppError> {
+    let lat = |f: fn(f64, f64) -> f64| s.latitudes.iter().copied().fold(s.latitudes[0], f);
+    let lon = |f: fn(f64, f64) -> f64| s.longitudes.iter().copied().fold(s.longitudes[0], f);
+    sqlx::query(
+        "INSERT INTO segments
+            (id, user_id, name, activity_type, distance_m, latitudes, longitudes,
+             min_lat, max_lat, min_lon, max_lon, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+    )
+    .bind(s.id)
+    .bind(s.user_id)
+    .bind(&s.name)
+    .bind(&s.activity_type)
+    .bind(s.distance_m)
+    .bind(&s.latitudes)
+    .bind(&s.longitudes)
+    .bind(lat(f64::min))
+    .bind(lat(f64::max))
+    .bind(lon(f64::min))
+    .bind(lon(f64::max))
+    .bind(s.created_at)
+    .execute(db)
+    .await
+    .map_err(AppError::from)?;
+    Ok(())
+}
+
+pub async fn find_segment(db: &PgPool, segment_id: Uuid) -> Result<Option<Segment>, AppError> {
+    sqlx::query_as::<_, Segment>(&format!(
+        "SELECT {SEGMENT_COLUMNS} FROM segments WHERE id = $1"
+    ))
+    .bind(segment_id)
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Segments a user created or has efforts on, newest first.
+pub async fn find_user_segments(db: &PgPool, user_id: Uuid) -> Result<Vec<Segment>, AppError> {
+    sqlx::query_as::<_, Segment>(&format!(
+        "SELECT {SEGMENT_COLUMNS} FROM segments s
+         WHERE s.user_id = $1
+            OR EXISTS (SELECT 1 FROM segment_efforts e
+                       JOIN activities a ON a.id = e.activity_id
+                       WHERE e.segment_id = s.id AND a.user_id = $1)
+         ORDER BY s.created_at DESC"
+    ))
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Delete a segment the user created; its efforts cascade.  Returns `true`
+/// if a row was deleted.
+pub async fn delete_segment(
+    db: &PgPool,
+    user_id: Uuid,
+    segment_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM segments WHERE id = $1 AND user_id = $2")
+        .bind(segment_id)
+        .bind(user_id)
+        .execute(db)
+        .await
+        .map_err(AppError::from)?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Segments of `activity_type` whose bounding box overlaps the given one.
+pub async fn find_segments_in_bbox(
+    db: &PgPool,
+    activity_type: &str,
+    (min_lat, max_lat, min_lon, max_lon): (f64, f64, f64, f64),
+) -> Result<Vec<Segment>, AppError> {
+    sqlx::query_as::<_, Segment>(&format!(
+        "SELECT {SEGMENT_COLUMNS} FROM segments
+         WHERE activity_type = $1
+           AND min_lat <= $3 AND max_lat >= $2
+           AND min_lon <= $5 AND max_lon >= $4"
+    ))
+    .bind(activity_type)
+    .bind(min_lat)
+    .bind(max_lat)
+    .bind(min_lon)
+    .bind(max_lon)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Whose activities `find_activities_in_cells` returns.
+#[derive(Debug, Clone, Copy)]
+pub enum Owners {
+    Only(Uuid),
+    AllBut(Uuid),
+}
+
+/// Activities of `activity_type` and `owners` with heatmap cells in the
+/// inclusive ranges `(x_min, x_max, y_min, y_max)` at `level`.
+pub async fn find_activities_in_cells(
+    db: &PgPool,
+    activity_type: &str,
+    level: i16,
+    (x_min, x_max, y_min, y_max): (i32, i32, i32, i32),
+    owners: Owners,
+) -> Result<Vec<Uuid>, AppError> {
+    let (user_id, own) = match owners {
+        Owners::Only(user_id) => (user_id, true),
+        Owners::AllBut(user_id) => (user_id, false),
+    };
+    sqlx::query_scalar(
+        "SELECT DISTINCT c.activity_id
+         FROM   heatmap_cells c
+         JOIN   activities a ON a.id = c.activity_id
+         WHERE  c.level = $2
+           AND  c.x BETWEEN $3 AND $4
+           AND  c.y BETWEEN $5 AND $6
+           AND  a.activity_type = $1
+           AND  (a.user_id = $7) = $8",
+    )
+    .bind(activity_type)
+    .bind(level)
+    .bind(x_min)
+    .bind(x_max)
+    .bind(y_min)
+    .bind(y_max)
+    .bind(user_id)
+    .bind(own)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+fn effort_insert(efforts: &[SegmentEffort]) -> QueryBuilder<'_, Postgres> {
+    let mut builder = QueryBuilder::new(
+        "INSERT INTO segment_efforts
+            (id, segment_id, activity_id, start_time, elapsed_seconds, pace) ",
+    );
+    builder.push_values(efforts, |mut b, e| {
+        b.push_bind(e.id)
+            .push_bind(e.segment_id)
+            .push_bind(e.activity_id)
+            .push_bind(e.start_time)
+            .push_bind(e.elapsed_seconds)
+            .push_bind(e.pace);
+    });
+    builder.push(" ON CONFLICT DO NOTHING");
+    builder
+}
+
+pub async fn insert_efforts(db: &PgPool, efforts: &[SegmentEffort]) -> Result<(), AppError> {
+    if efforts.is_empty() {
+        return Ok(());
+    }
+    effort_insert(efforts).build().execute(db).await?;
+    Ok(())
+}
+
+/// Replace every effort of one activity.
+pub async fn replace_activity_efforts(
+    db: &PgPool,
+    activity_id: Uuid,
+    efforts: &[SegmentEffort],
+) -> Result<(), AppError> {
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM segment_efforts WHERE activity_id = $1")
+        .bind(activity_id)
+        .execute(&mut *tx)
+        .await?;
+    if !efforts.is_empty() {
+        effort_insert(efforts).build().execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// A user's efforts, on one segment or on all of them, fastest first.
+pub async fn find_user_efforts(
+    db: &PgPool,
+    user_id: Uuid,
+    segment_id: Option<Uuid>,
+) -> Result<Vec<SegmentEffort>, AppError> {
+    sqlx::query_as::<_, SegmentEffort>(&format!(
+        "SELECT {EFFORT_COLUMNS}
+         FROM   segment_efforts e
+         JOIN   activities a ON a.id = e.activity_id
+         WHERE  a.user_id = $1
+           AND  ($2::uuid IS NULL OR e.segment_id = $2)
+         ORDER  BY e.elapsed_seconds, e.start_time"
+    ))
+    .bind(user_id)
+    .bind(segment_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Best effort of every user who opted in to segment leaderboards, with
+/// their ID, fastest first.
+pub async fn find_leaderboard(
+    db: &PgPool,
+    segment_id: Uuid,
+) -> Result<Vec<(Uuid, SegmentEffort)>, AppError> {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        user_id: Uuid,
+        #[sqlx(flatten)]
+        effort: SegmentEffort,
+    }
+
+    let rows = sqlx::query_as::<_, Row>(&format!(
+        "SELECT * FROM (
+             SELECT DISTINCT ON (a.user_id) a.user_id, {EFFORT_COLUMNS}
+             FROM   segment_efforts e
+             JOIN   activities a ON a.id = e.activity_id
+             JOIN   users u ON u.id = a.user_id
+             WHERE  e.segment_id = $1 AND u.segment_leaderboards
+             ORDER  BY a.user_id, e.elapsed_seconds, e.start_time
+         ) best
+         ORDER BY elapsed_seconds, start_time"
+    ))
+    .bind(segment_id)
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)?;
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.user_id, r.effort))
+        .collect())
+}
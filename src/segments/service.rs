@@ -0,0 +1,313 @@
+/// User-defined segments and the efforts timed on them.
+///
+/// A new segment is matched against every stored activity of its type that
+/// passes its start (the creator's at once, other users' in the background);
+/// `activities::service` matches each newly stored or
+/// re-cleaned track against the segments it crosses.
+use std::collections::HashMap;
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    activities::{self, geometry, heatmap, models::TrackPoint, pace::Pace, parser},
+    error::AppError,
+};
+
+use super::{
+    matcher::{self, ENDPOINT_RADIUS_M},
+    models::{
+        CreateSegmentRequest, Segment, SegmentDetail, SegmentEffort, SegmentLeaderboard,
+        SegmentLeaderboardEntry, SegmentSummary,
+    },
+    repository::{self, Owners},
+};
+
+/// Shortest segment accepted, in metres.
+const MIN_SEGMENT_M: f64 = 100.0;
+
+/// Tolerance the geometry of a segment picked from a track is simplified
+/// with, in metres.
+const SIMPLIFY_TOLERANCE_M: f64 = 2.0;
+
+const DEFAULT_ACTIVITY_TYPE: &str = "Running";
+
+/// Create a segment and time every stored activity on it.  Returns the
+/// creator's efforts.
+pub async fn create_segment(
+    db: &PgPool,
+    user_id: Uuid,
+    req: CreateSegmentRequest,
+) -> Result<SegmentDetail, AppError> {
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("name must not be empty".into()));
+    }
+    let (activity_type, coordinates) = match (req.activity_id, req.points) {
+        (Some(activity_id), None) => {
+            let activity = activities::repository::find_by_id(db, activity_id)
+                .await?
+                .filter(|a| a.user_id == user_id)
+                .ok_or(AppError::NotFound)?;
+            let (Some(start_m), Some(end_m)) = (req.start_m, req.end_m) else {
+                return Err(AppError::BadRequest(
+                    "start_m and end_m are required with activity_id".into(),
+                ));
+            };
+            let track = activities::repository::find_trackpoints(db, activity_id).await?;
+            (activity.activity_type, stretch(&track, start_m, end_m))
+        }
+        (None, Some(points)) => (
+            req.activity_type
+                .unwrap_or_else(|| DEFAULT_ACTIVITY_TYPE.to_string()),
+            points.iter().map(|p| (p.latitude, p.longitude)).collect(),
+        ),
+        _ => {
+            return Err(AppError::BadRequest(
+                "give either activity_id or points".into(),
+            ))
+        }
+    };
+    if coordinates
+        .iter()
+        .any(|&(lat, lon)| !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon))
+    {
+        return Err(AppError::BadRequest("invalid coordinates".into()));
+    }
+    let distance_m = matcher::polyline_length(&coordinates);
+    if coordinates.len() < 2 || distance_m < MIN_SEGMENT_M {
+        return Err(AppError::BadRequest(format!(
+            "a segment must be at least {MIN_SEGMENT_M} m long"
+        )));
+    }
+
+    let segment = Segment {
+        id: Uuid::new_v4(),
+        user_id,
+        name: name.to_string(),
+        activity_type,
+        distance_m,
+        latitudes: coordinates.iter().map(|c| c.0).collect(),
+        longitudes: coordinates.iter().map(|c| c.1).collect(),
+        created_at: Utc::now(),
+    };
+    repository::insert_segment(db, &segment).await?;
+    // The creator's efforts are returned; everyone else's history is timed
+    // in the background so the request does not scan every user's tracks.
+    match_stored_activities(db, &segment, Owners::Only(user_id)).await?;
+    let (db_bg, segment_bg) = (db.clone(), segment.clone());
+    tokio::spawn(async move {
+        let owners = Owners::AllBut(segment_bg.user_id);
+        if let Err(e) = match_stored_activities(&db_bg, &segment_bg, owners).await {
+            tracing::warn!("Could not time stored activities on segment {}: {e}", segment_bg.id);
+        }
+    });
+    segment_detail(db, user_id, segment).await
+}
+
+/// The points of a track between `start_m` and `end_m` metres along it,
+/// simplified, as `(latitude, longitude)` pairs.
+fn stretch(track: &[TrackPoint], start_m: f64, end_m: f64) -> Vec<(f64, f64)> {
+    let mut along = 0.0;
+    let mut picked = Vec::new();
+    for (i, tp) in track.iter().enumerate() {
+        if i > 0 {
+            let prev = &track[i - 1];
+            along += parser::haversine_distance_m(
+                prev.latitude,
+                prev.longitude,
+                tp.latitude,
+                tp.longitude,
+            );
+        }
+        if along > end_m {
+            break;
+        }
+        if along >= start_m {
+            picked.push(tp.clone());
+        }
+    }
+    geometry::simplify(&picked, SIMPLIFY_TOLERANCE_M)
+        .iter()
+        .map(|tp| (tp.latitude, tp.longitude))
+        .collect()
+}
+
+/// Time every stored activity of `owners` of the segment's type that passes
+/// its start.
+async fn match_stored_activities(
+    db: &PgPool,
+    segment: &Segment,
+    owners: Owners,
+) -> Result<(), AppError> {
+    let (lat, lon) = (segment.latitudes[0], segment.longitudes[0]);
+    let dlat = ENDPOINT_RADIUS_M / 111_195.0;
+    let dlon = dlat / lat.to_radians().cos().max(0.01);
+    let around_start = heatmap::Bbox {
+        min_lon: lon - dlon,
+        min_lat: lat - dlat,
+        max_lon: lon + dlon,
+        max_lat: lat + dlat,
+    };
+    let level = heatmap::LEVELS[heatmap::LEVELS.len() - 1];
+    let candidates = repository::find_activities_in_cells(
+        db,
+        &segment.activity_type,
+        level,
+        around_start.cell_range(level),
+        owners,
+    )
+    .await?;
+
+    let coordinates = segment.coordinates();
+    for activity_id in candidates {
+        let track = activities::repository::find_trackpoints(db, activity_id).await?;
+        let efforts = efforts(segment, &coordinates, activity_id, &track);
+        repository::insert_efforts(db, &efforts).await?;
+    }
+    Ok(())
+}
+
+/// Re-time an activity's cleaned track on every segment it crosses,
+/// replacing its earlier efforts.
+pub async fn match_activity(
+    db: &PgPool,
+    activity_id: Uuid,
+    activity_type: &str,
+    track: &[TrackPoint],
+) -> Result<(), AppError> {
+    let mut found = Vec::new();
+    if let Some(first) = track.first() {
+        let mut bbox = (
+            first.latitude,
+            first.latitude,
+            first.longitude,
+            first.longitude,
+        );
+        for tp in track {
+            bbox.0 = bbox.0.min(tp.latitude);
+            bbox.1 = bbox.1.max(tp.latitude);
+            bbox.2 = bbox.2.min(tp.longitude);
+            bbox.3 = bbox.3.max(tp.longitude);
+        }
+        for segment in repository::find_segments_in_bbox(db, activity_type, bbox).await? {
+            found.extend(efforts(
+                &segment,
+                &segment.coordinates(),
+                activity_id,
+                track,
+            ));
+        }
+    }
+    repository::replace_activity_efforts(db, activity_id, &found).await
+}
+
+fn efforts(
+    segment: &Segment,
+    coordinates: &[(f64, f64)],
+    activity_id: Uuid,
+    track: &[TrackPoint],
+) -> Vec<SegmentEffort> {
+    matcher::find_passes(coordinates, track)
+        .into_iter()
+        .map(|pass| {
+            let (start, end) = (&track[pass.start], &track[pass.end]);
+            let elapsed_seconds = (end.time - start.time).num_milliseconds() as f64 / 1000.0;
+            SegmentEffort {
+                id: Uuid::new_v4(),
+                segment_id: segment.id,
+                activity_id,
+                start_time: start.time,
+                elapsed_seconds,
+                pace: Pace::from_duration(elapsed_seconds, segment.distance_m / 1000.0),
+            }
+        })
+        .collect()
+}
+
+/// A segment with the user's efforts on it.
+pub async fn get_segment(
+    db: &PgPool,
+    user_id: Uuid,
+    segment_id: Uuid,
+) -> Result<SegmentDetail, AppError> {
+    let segment = repository::find_segment(db, segment_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    segment_detail(db, user_id, segment).await
+}
+
+async fn segment_detail(
+    db: &PgPool,
+    user_id: Uuid,
+    segment: Segment,
+) -> Result<SegmentDetail, AppError> {
+    let efforts = repository::find_user_efforts(db, user_id, Some(segment.id)).await?;
+    Ok(SegmentDetail {
+        segment,
+        personal_best: efforts.first().cloned(),
+        efforts,
+    })
+}
+
+/// Segments the user created or has efforts on, with their personal bests.
+pub async fn list_segments(db: &PgPool, user_id: Uuid) -> Result<Vec<SegmentSummary>, AppError> {
+    let segments = repository::find_user_segments(db, user_id).await?;
+    let mut efforts: HashMap<Uuid, Vec<SegmentEffort>> = HashMap::new();
+    for e in repository::find_user_efforts(db, user_id, None).await? {
+        efforts.entry(e.segment_id).or_default().push(e);
+    }
+    Ok(segments
+        .into_iter()
+        .map(|segment| {
+            let mine = efforts.remove(&segment.id).unwrap_or_default();
+            SegmentSummary {
+                personal_best: mine.first().cloned(),
+                effort_count: mine.len() as i64,
+                segment,
+            }
+        })
+        .collect())
+}
+
+/// Delete a segment the user created.
+pub async fn delete_segment(db: &PgPool, user_id: Uuid, segment_id: Uuid) -> Result<(), AppError> {
+    if repository::delete_segment(db, user_id, segment_id).await? {
+        Ok(())
+    } else {
+        Err(AppError::NotFound)
+    }
+}
+
+/// Best effort of every opted-in user, fastest first.  Users are shown by an
+/// anonymous label derived from their ID, never by anything from their
+/// account.
+pub async fn get_leaderboard(
+    db: &PgPool,
+    segment_id: Uuid,
+) -> Result<SegmentLeaderboard, AppError> {
+    let segment = repository::find_segment(db, segment_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let entries = repository::find_leaderboard(db, segment_id)
+        .await?
+        .into_iter()
+        .enumerate()
+        .map(|(i, (user_id, effort))| SegmentLeaderboardEntry {
+            rank: i as i64 + 1,
+            display_name: anonymous_name(user_id),
+            effort,
+        })
+        .collect();
+    Ok(SegmentLeaderboard {
+        segment_id,
+        segment_name: segment.name,
+        entries,
+    })
+}
+
+/// Leaderboard label of a user: stable, but unrelated to their email.
+fn anonymous_name(user_id: Uuid) -> String {
+    format!("Athlete {}", &user_id.simple().to_string()[..8])
+}
@@ -45,6 +45,8 @@ pub struct StravaDetailedActivity {
     pub name:                 String,
     pub sport_type:           String,
     pub start_date:           String, // ISO 8601 UTC
+    #[serde(default)]
+    pub utc_offset:           Option<f64>, // seconds east of UTC at the start
     pub elapsed_time:         i64,    // seconds
     pub distance:             f64,    // metres
     pub total_elevation_gain: f64,    // metres
@@ -61,12 +63,17 @@ pub struct StravaSummaryActivity {
 }
 
 /// Streams response from `GET /activities/{id}/streams?key_by_type=true`.
-#[derive(Debug, Deserialize)]
+/// Sensor streams hold `null` where the sensor dropped out.
+#[derive(Debug, Default, Deserialize)]
 pub struct StreamSet {
     pub latlng:          Option<StreamData<[f64; 2]>>,
     pub altitude:        Option<StreamData<f64>>,
     pub time:            Option<StreamData<i64>>,
     pub velocity_smooth: Option<StreamData<f64>>,
+    pub heartrate:       Option<StreamData<Option<f64>>>,
+    pub cadence:         Option<StreamData<Option<f64>>>,
+    pub watts:           Option<StreamData<Option<f64>>>,
+    pub temp:            Option<StreamData<Option<f64>>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -279,7 +286,7 @@ impl StravaClient {
         })
     }
 
-    /// `GET /activities/{id}/streams?keys=latlng,altitude,time,velocity_smooth&key_by_type=true`
+    /// `GET /activities/{id}/streams?keys=latlng,altitude,time,velocity_smooth,heartrate,cadence,watts,temp&key_by_type=true`
     pub async fn get_streams(
         &self,
         token: &str,
@@ -294,7 +301,7 @@ impl StravaClient {
             .get(&url)
             .bearer_auth(token)
             .query(&[
-                ("keys",         "latlng,altitude,time,velocity_smooth"),
+                ("keys",         "latlng,altitude,time,velocity_smooth,heartrate,cadence,watts,temp"),
                 ("key_by_type",  "true"),
             ])
             .send()
@@ -303,11 +310,11 @@ impl StravaClient {
 
         if resp.status().as_u16() == 404 {
             // Activity has no streams (e.g. manually entered) — return empty set.
-            return Ok(StreamSet { latlng: None, altitude: None, time: None, velocity_smooth: None });
+            return Ok(StreamSet::default());
         }
         if !resp.status().is_success() {
             tracing::warn!("get_streams {} HTTP {}", activity_id, resp.status());
-            return Ok(StreamSet { latlng: None, altitude: None, time: None, velocity_smooth: None });
+            return Ok(StreamSet::default());
         }
 
         resp.json::<StreamSet>().await.map_err(|e| {
@@ -320,6 +327,7 @@ impl StravaClient {
 
 // ─── Strava → NormalizedActivity conversion ────────────────────────────────
 
+use crate::activities::pace::Pace;
 use crate::sync::normalized::{NormalizedActivity, NormalizedTrackPoint};
 
 /// Normalise a `StravaDetailedActivity` + its `StreamSet` into a `NormalizedActivity`.
@@ -333,11 +341,11 @@ pub fn normalize(
     let avg_speed_kh = (detail.average_speed * 3.6) as f32;
     let activity_type = sport_type_to_activity_type(&detail.sport_type);
 
-    // Pace (min/km) — meaningful only for running-like activities.
-    let average_pace = if detail.average_speed > 0.01 && activity_type == "Running" {
-        (1000.0 / detail.average_speed / 60.0) as f32
+    // Pace — meaningful only for running-like activities.
+    let average_pace = if activity_type == "Running" {
+        Pace::from_speed_ms(detail.average_speed)
     } else {
-        0.0
+        Pace::default()
     };
 
     let duration = seconds_to_hms(detail.elapsed_time);
@@ -352,6 +360,9 @@ pub fn normalize(
     let alt     = streams.altitude        .as_ref().map(|s| s.data.as_slice()).unwrap_or(&empty_alt);
     let times   = streams.time            .as_ref().map(|s| s.data.as_slice()).unwrap_or(&empty_time);
     let vels    = streams.velocity_smooth .as_ref().map(|s| s.data.as_slice()).unwrap_or(&empty_vel);
+    let sensor  = |stream: &Option<StreamData<Option<f64>>>, i: usize| {
+        stream.as_ref().and_then(|s| s.data.get(i).copied().flatten())
+    };
 
     let n = latlng.len();
     let mut track_points = Vec::with_capacity(n);
@@ -362,13 +373,24 @@ pub fn normalize(
         let t_offset  = times.get(i).copied().unwrap_or(0);
         let speed     = vels.get(i).copied();
         let time      = start_dt + chrono::Duration::seconds(t_offset);
-        track_points.push(NormalizedTrackPoint { latitude: lat, longitude: lon, elevation, time, speed });
+        track_points.push(NormalizedTrackPoint {
+            latitude: lat,
+            longitude: lon,
+            elevation,
+            time,
+            speed,
+            heart_rate: sensor(&streams.heartrate, i).map(|v| v as i16),
+            cadence: sensor(&streams.cadence, i).map(|v| v as i16),
+            power: sensor(&streams.watts, i).map(|v| v as i16),
+            temperature: sensor(&streams.temp, i).map(|v| v as f32),
+        });
     }
 
     NormalizedActivity {
         source:         "strava".to_string(),
         external_id:    Some(detail.id.to_string()),
-        date:           start_dt.naive_utc(),
+        date:           start_dt,
+        utc_offset:     detail.utc_offset.map(|o| o as i32),
         name:           detail.name.clone(),
         activity_type,
         distance:       distance_km,
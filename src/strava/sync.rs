@@ -117,9 +117,7 @@ pub async fn run_sync(
 
             let streams = stream_result.unwrap_or_else(|e| {
                 tracing::warn!("streams for {activity_id} unavailable: {e:?}");
-                super::client::StreamSet {
-                    latlng: None, altitude: None, time: None, velocity_smooth: None,
-                }
+                super::client::StreamSet::default()
             });
 
             let normalized = normalize(&detail, streams, start_dt);
@@ -0,0 +1,47 @@
+/// Adapter for standalone activity files (GPX, FIT) uploaded by the user.
+///
+/// Unlike the Runkeeper export, these files carry or imply their own summary
+/// and need no CSV row.  The parser has already produced an `Activity` + `TrackPoint`
+/// pair; this module only converts it into the canonical `NormalizedActivity`
+/// so the file goes through `ingest_activities` like a Strava activity.
+use crate::activities::models::{Activity, TrackPoint};
+
+use super::normalized::{NormalizedActivity, NormalizedTrackPoint};
+
+/// Convert a parsed file activity into a `NormalizedActivity`.
+///
+/// `source` and `external_id` are taken from the parsed `Activity`, so the
+/// dedup key chosen by the parser (a content hash) is preserved.
+pub fn from_parsed(activity: Activity, track_points: Vec<TrackPoint>) -> NormalizedActivity {
+    let normalized_tps = track_points
+        .into_iter()
+        .map(|tp| NormalizedTrackPoint {
+            latitude: tp.latitude,
+            longitude: tp.longitude,
+            elevation: tp.elevation,
+            time: tp.time,
+            speed: tp.speed,
+            heart_rate: tp.heart_rate,
+            cadence: tp.cadence,
+            power: tp.power,
+            temperature: tp.temperature,
+        })
+        .collect();
+
+    NormalizedActivity {
+        source: activity.source,
+        external_id: activity.external_id,
+        date: activity.date,
+        utc_offset: activity.utc_offset,
+        name: activity.name,
+        activity_type: activity.activity_type,
+        distance: activity.distance,
+        duration: activity.duration,
+        average_pace: activity.average_pace,
+        average_speed: activity.average_speed,
+        calories: activity.calories,
+        climb: activity.climb,
+        gps_file: activity.gps_file,
+        track_points: normalized_tps,
+    }
+}
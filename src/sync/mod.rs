@@ -1,3 +1,4 @@
 pub mod facade;
+pub mod file_adapter;
 pub mod normalized;
 pub mod runkeeper_adapter;
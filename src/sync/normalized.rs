@@ -4,18 +4,24 @@
 /// values. The ingestion pipeline (`activities::service::ingest_activities`) only
 /// speaks this type, ensuring XP/achievements/PR pipelines run identically for
 /// all sources.
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
+
+use uuid::Uuid;
+
+use crate::activities::{models::TrackPoint, pace::Pace};
 
 #[derive(Debug, Clone)]
 pub struct NormalizedActivity {
-    /// Data source identifier: `"runkeeper"` or `"strava"`.
+    /// Data source identifier: `"runkeeper"`, `"strava"`, `"gpx"`, `"fit"` or `"tcx"`.
     pub source: String,
 
     /// Source-specific stable ID used for deduplication.
     /// `None` for legacy Runkeeper rows (dedup falls back to `(user_id, date)`).
     pub external_id: Option<String>,
 
-    pub date: NaiveDateTime,
+    pub date: DateTime<Utc>,
+    /// Seconds east of UTC of the recording clock, if the source reports it.
+    pub utc_offset: Option<i32>,
     pub name: String,
     /// E.g. `"Running"`, `"Cycling"`, `"Swimming"`.
     pub activity_type: String,
@@ -23,14 +29,14 @@ pub struct NormalizedActivity {
     pub distance: f32,
     /// `HH:MM:SS` string.
     pub duration: String,
-    /// Minutes per kilometre (0.0 for non-running types).
-    pub average_pace: f32,
+    /// Seconds per kilometre (unset for non-running types).
+    pub average_pace: Pace,
     /// Kilometres per hour.
     pub average_speed: f32,
     pub calories: f32,
     /// Metres of positive elevation gain.
     pub climb: f32,
-    /// Original GPX filename (empty string when none).
+    /// Original GPX/FIT/TCX filename (empty string when none).
     pub gps_file: String,
 
     /// GPS track points, if available.
@@ -45,4 +51,31 @@ pub struct NormalizedTrackPoint {
     pub time: chrono::DateTime<chrono::Utc>,
     /// Speed in m/s, if recorded by the device.
     pub speed: Option<f64>,
+    /// Heart rate in beats per minute, if recorded.
+    pub heart_rate: Option<i16>,
+    /// Cadence in steps (or revolutions) per minute, if recorded.
+    pub cadence: Option<i16>,
+    /// Power in watts, if recorded.
+    pub power: Option<i16>,
+    /// Temperature in degrees Celsius, if recorded.
+    pub temperature: Option<f32>,
+}
+
+impl NormalizedTrackPoint {
+    /// The stored form of this point, for activity `activity_id`.
+    pub fn to_track_point(&self, activity_id: Uuid) -> TrackPoint {
+        TrackPoint {
+            id: None,
+            activity_id,
+            latitude: self.latitude,
+            longitude: self.longitude,
+            elevation: self.elevation,
+            time: self.time,
+            speed: self.speed,
+            heart_rate: self.heart_rate,
+            cadence: self.cadence,
+            power: self.power,
+            temperature: self.temperature,
+        }
+    }
 }
@@ -24,6 +24,10 @@ pub fn from_parsed(activity: Activity, track_points: Vec<TrackPoint>) -> Normali
             elevation: tp.elevation,
             time: tp.time,
             speed: tp.speed,
+            heart_rate: tp.heart_rate,
+            cadence: tp.cadence,
+            power: tp.power,
+            temperature: tp.temperature,
         })
         .collect();
 
@@ -31,6 +35,7 @@ pub fn from_parsed(activity: Activity, track_points: Vec<TrackPoint>) -> Normali
         source: "runkeeper".to_string(),
         external_id: None, // Runkeeper rows dedup by (user_id, date)
         date: activity.date,
+        utc_offset: activity.utc_offset,
         name: activity.name,
         activity_type: activity.activity_type,
         distance: activity.distance,
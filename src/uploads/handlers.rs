@@ -0,0 +1,30 @@
+use actix_web::{get, web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+use super::{models::UploadJob, service};
+
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/uploads/{job_id}",
+    tag = "uploads",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID (UUID v4)"),
+        ("job_id" = Uuid, Path, description = "Upload job ID returned by POST /activities/upload/{user_id}")
+    ),
+    responses(
+        (status = 200, description = "Job state, progress counters and, once completed, the UploadResponse", body = UploadJob),
+        (status = 404, description = "Unknown job, or a job of another user")
+    )
+)]
+#[get("/users/{user_id}/uploads/{job_id}")]
+pub async fn get_upload_job(
+    db: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, AppError> {
+    let (user_id, job_id) = path.into_inner();
+    let job = service::get_job(db.get_ref(), user_id, job_id).await?;
+    Ok(HttpResponse::Ok().json(job))
+}
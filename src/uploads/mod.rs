@@ -0,0 +1,13 @@
+pub mod handlers;
+pub mod models;
+pub mod progress;
+pub mod repository;
+pub mod service;
+pub mod spool;
+pub mod status;
+
+use actix_web::web;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers::get_upload_job);
+}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::types::Json;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::activities::models::UploadResponse;
+
+use super::status::UploadJobStatus;
+
+/// Row of `upload_jobs`, returned as-is by `GET /users/{user_id}/uploads/{job_id}`.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct UploadJob {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub status: UploadJobStatus,
+    /// CSV rows plus uploaded GPX/FIT/TCX files.
+    pub total_items: i32,
+    /// Items whose outcome is already known.
+    pub processed_items: i32,
+    pub inserted: i32,
+    pub duplicates: i32,
+    pub skipped: i32,
+    /// Final upload result; present once the job has completed.
+    #[schema(value_type = Option<UploadResponse>)]
+    pub result: Option<Json<UploadResponse>>,
+    /// Failure reason when `status = "failed"`.
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Returned by `POST /activities/upload/{user_id}` (202 Accepted).
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UploadJobAccepted {
+    pub job_id: Uuid,
+    pub status: UploadJobStatus,
+    /// Where to poll for progress, e.g. `/users/{user_id}/uploads/{job_id}`.
+    pub status_url: String,
+}
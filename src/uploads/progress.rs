@@ -0,0 +1,63 @@
+/// Per-item progress of a running upload job.
+///
+/// The ingest paths call `advance` once per CSV row or file; the count is
+/// written to `upload_jobs` at most once per `SAVE_INTERVAL`, and always for
+/// the last item, so a long import is visible while polling without one
+/// UPDATE per row.
+use std::time::{Duration, Instant};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::repository;
+
+/// Shortest time between two progress writes.
+const SAVE_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub struct JobProgress {
+    /// `None` outside an upload job (e.g. Strava sync): nothing is recorded.
+    job_id: Option<Uuid>,
+    processed: usize,
+    total: usize,
+    last_saved: Option<Instant>,
+}
+
+impl JobProgress {
+    pub fn new(job_id: Option<Uuid>, total: usize) -> Self {
+        JobProgress {
+            job_id,
+            processed: 0,
+            total,
+            last_saved: None,
+        }
+    }
+
+    /// Progress that is never recorded.
+    pub fn untracked() -> Self {
+        Self::new(None, 0)
+    }
+
+    /// Count `items` more as processed.
+    pub async fn advance(&mut self, db: &PgPool, items: usize) {
+        self.processed = (self.processed + items).min(self.total);
+        let due = self.last_saved.is_none_or(|t| t.elapsed() >= SAVE_INTERVAL);
+        if due || self.processed == self.total {
+            self.save(db).await;
+        }
+    }
+
+    /// Persist the current count.  Failures are logged, not propagated — a
+    /// missed progress update must not abort the import.
+    pub async fn save(&mut self, db: &PgPool) {
+        let Some(job_id) = self.job_id else {
+            return;
+        };
+        self.last_saved = Some(Instant::now());
+        if let Err(e) =
+            repository::update_progress(db, job_id, self.processed as i32, self.total as i32).await
+        {
+            tracing::warn!("Could not record progress for upload job {job_id}: {e}");
+        }
+    }
+}
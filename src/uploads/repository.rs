@@ -0,0 +1,108 @@
+/// Database access for upload jobs.
+use sqlx::{types::Json, PgPool};
+use uuid::Uuid;
+
+use crate::{activities::models::UploadResponse, error::AppError};
+
+use super::{models::UploadJob, status::UploadJobStatus};
+
+pub async fn insert_job(
+    db: &PgPool,
+    job_id: Uuid,
+    user_id: Uuid,
+    total_items: i32,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO upload_jobs (id, user_id, total_items) VALUES ($1, $2, $3)")
+        .bind(job_id)
+        .bind(user_id)
+        .bind(total_items)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn find_job(
+    db: &PgPool,
+    user_id: Uuid,
+    job_id: Uuid,
+) -> Result<Option<UploadJob>, AppError> {
+    sqlx::query_as::<_, UploadJob>("SELECT * FROM upload_jobs WHERE id = $1 AND user_id = $2")
+        .bind(job_id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+        .map_err(AppError::from)
+}
+
+pub async fn mark_running(db: &PgPool, job_id: Uuid) -> Result<(), AppError> {
+    sqlx::query("UPDATE upload_jobs SET status = $2, started_at = NOW() WHERE id = $1")
+        .bind(job_id)
+        .bind(UploadJobStatus::Running)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn update_progress(
+    db: &PgPool,
+    job_id: Uuid,
+    processed_items: i32,
+    total_items: i32,
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE upload_jobs SET processed_items = $2, total_items = $3 WHERE id = $1")
+        .bind(job_id)
+        .bind(processed_items)
+        .bind(total_items)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn mark_completed(
+    db: &PgPool,
+    job_id: Uuid,
+    counts: (i32, i32, i32),
+    result: &UploadResponse,
+) -> Result<(), AppError> {
+    let (inserted, duplicates, skipped) = counts;
+    sqlx::query(
+        "UPDATE upload_jobs
+         SET status = $2, inserted = $3, duplicates = $4, skipped = $5,
+             processed_items = total_items, result = $6, finished_at = NOW()
+         WHERE id = $1",
+    )
+    .bind(job_id)
+    .bind(UploadJobStatus::Completed)
+    .bind(inserted)
+    .bind(duplicates)
+    .bind(skipped)
+    .bind(Json(result))
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn mark_failed(db: &PgPool, job_id: Uuid, error: &str) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE upload_jobs SET status = $2, error = $3, finished_at = NOW() WHERE id = $1",
+    )
+    .bind(job_id)
+    .bind(UploadJobStatus::Failed)
+    .bind(error)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Jobs still `queued`/`running` when the server starts were interrupted by
+/// a restart; their background task is gone, so mark them failed.
+pub async fn fail_interrupted_jobs(db: &PgPool) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        "UPDATE upload_jobs
+         SET status = 'failed', error = 'Interrupted by server restart', finished_at = NOW()
+         WHERE status IN ('queued', 'running')",
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
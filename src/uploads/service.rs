@@ -0,0 +1,92 @@
+/// Business logic for background upload jobs.
+///
+/// The HTTP request only spools the files to disk and creates the job row;
+/// extracting, parsing, inserting and the post-ingest pipeline run in a
+/// spawned task that reports progress through `JobProgress`.
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    activities::{self, models::IngestOutcome},
+    error::AppError,
+    users,
+};
+
+use super::{
+    models::{UploadJob, UploadJobAccepted},
+    repository,
+    spool::SpooledUpload,
+    status::UploadJobStatus,
+};
+
+/// Create a job for `files` and start processing it in the background.
+pub async fn start_upload(
+    db: &PgPool,
+    user_id: Uuid,
+    files: SpooledUpload,
+) -> Result<UploadJobAccepted, AppError> {
+    users::service::get_user(db, user_id).await?;
+
+    let job_id = Uuid::new_v4();
+    repository::insert_job(db, job_id, user_id, 0).await?;
+
+    let db_bg = db.clone();
+    tokio::spawn(async move { run_job(db_bg, job_id, user_id, files).await });
+
+    Ok(UploadJobAccepted {
+        job_id,
+        status: UploadJobStatus::Queued,
+        status_url: format!("/users/{user_id}/uploads/{job_id}"),
+    })
+}
+
+/// One of the user's upload jobs.
+pub async fn get_job(db: &PgPool, user_id: Uuid, job_id: Uuid) -> Result<UploadJob, AppError> {
+    repository::find_job(db, user_id, job_id)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+async fn run_job(db: PgPool, job_id: Uuid, user_id: Uuid, files: SpooledUpload) {
+    if let Err(e) = repository::mark_running(&db, job_id).await {
+        tracing::warn!("Could not mark upload job {job_id} running: {e}");
+    }
+
+    // Run the import in its own task so a panic is observed as a JoinError
+    // and the job is marked failed instead of staying `running` forever.
+    let db_task = db.clone();
+    let task = tokio::spawn(async move {
+        let files = tokio::task::spawn_blocking(move || files.load())
+            .await
+            .map_err(|e| e.to_string())??;
+        activities::service::upload(&db_task, user_id, files, Some(job_id))
+            .await
+            .map_err(|_| "Upload processing failed".to_string())
+    });
+
+    let outcome = match task.await {
+        Ok(Ok(response)) => {
+            let count = |o: IngestOutcome| {
+                response.report.iter().filter(|e| e.outcome == o).count() as i32
+            };
+            let counts = (
+                count(IngestOutcome::Inserted),
+                count(IngestOutcome::Duplicate),
+                count(IngestOutcome::Skipped),
+            );
+            repository::mark_completed(&db, job_id, counts, &response).await
+        }
+        Ok(Err(error)) => {
+            tracing::warn!("Upload job {job_id} failed: {error}");
+            repository::mark_failed(&db, job_id, &error).await
+        }
+        Err(e) => {
+            tracing::error!("Upload job {job_id} failed: {e}");
+            repository::mark_failed(&db, job_id, "Upload processing failed").await
+        }
+    };
+
+    if let Err(e) = outcome {
+        tracing::error!("Could not record final state of upload job {job_id}: {e}");
+    }
+}
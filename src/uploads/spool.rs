@@ -0,0 +1,65 @@
+/// Uploaded files kept on disk between the HTTP request and the upload job.
+///
+/// The upload handler only streams each multipart field into an anonymous
+/// temporary file; reading the files back and extracting ZIP archives happens
+/// in the job (or the dry run), on a blocking thread.
+use std::{
+    fs::File,
+    io::{Read, Seek},
+};
+
+use actix_multipart::Field;
+use futures_util::stream::StreamExt as _;
+use tokio::io::AsyncWriteExt as _;
+
+use crate::{
+    activities::{archive, models::UploadFiles},
+    error::AppError,
+};
+
+/// The files of one upload request, by sanitised file name.  Each file is
+/// removed by the OS once its handle is dropped.
+#[derive(Debug, Default)]
+pub struct SpooledUpload {
+    files: Vec<(String, File)>,
+}
+
+impl SpooledUpload {
+    /// Stream a multipart field into a temporary file.
+    pub async fn push(&mut self, name: String, field: &mut Field) -> Result<(), AppError> {
+        let io_error = |e: std::io::Error| {
+            tracing::error!("Could not spool upload to a temporary file: {}", e);
+            AppError::Internal
+        };
+        let mut file = tokio::fs::File::from_std(tempfile::tempfile().map_err(io_error)?);
+        while let Some(chunk) = field.next().await {
+            let bytes = chunk.map_err(|e| {
+                tracing::error!("Error reading chunk: {}", e);
+                AppError::BadRequest("Error reading upload chunk".into())
+            })?;
+            file.write_all(&bytes).await.map_err(io_error)?;
+        }
+        file.flush().await.map_err(io_error)?;
+        self.files.push((name, file.into_std().await));
+        Ok(())
+    }
+
+    /// Read the files back into memory, extracting ZIP archives (see
+    /// `archive::extract_zip`).  Blocking — run it on a blocking thread.
+    pub fn load(self) -> Result<UploadFiles, String> {
+        let mut files = UploadFiles::default();
+        for (name, mut file) in self.files {
+            file.rewind()
+                .map_err(|e| format!("Error reading {}: {}", name, e))?;
+            if name.to_lowercase().ends_with(".zip") {
+                archive::extract_zip(file, &mut files)?;
+            } else {
+                let mut content = Vec::new();
+                file.read_to_end(&mut content)
+                    .map_err(|e| format!("Error reading {}: {}", name, e))?;
+                files.insert(name, content)?;
+            }
+        }
+        Ok(files)
+    }
+}
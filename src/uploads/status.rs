@@ -0,0 +1,79 @@
+/// Lifecycle status for an upload job.
+///
+/// Stored as TEXT in the `upload_jobs.status` column, with the same TEXT-backed
+/// sqlx integration as `challenges::status::ChallengeStatus`.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadJobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl UploadJobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued    => "queued",
+            Self::Running   => "running",
+            Self::Completed => "completed",
+            Self::Failed    => "failed",
+        }
+    }
+}
+
+impl fmt::Display for UploadJobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for UploadJobStatus {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued"    => Ok(Self::Queued),
+            "running"   => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "failed"    => Ok(Self::Failed),
+            other       => Err(format!("unknown upload job status: {other}")),
+        }
+    }
+}
+
+// ─── sqlx TEXT-backed integration (same boilerplate as ChallengeStatus) ──────
+
+impl sqlx::Type<sqlx::Postgres> for UploadJobStatus {
+    fn type_info() -> PgTypeInfo {
+        <String as sqlx::Type<sqlx::Postgres>>::type_info()
+    }
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, sqlx::Postgres> for UploadJobStatus {
+    fn decode(
+        value: PgValueRef<'r>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let s = <&str as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        s.parse().map_err(|e: String| e.into())
+    }
+}
+
+impl sqlx::Encode<'_, sqlx::Postgres> for UploadJobStatus {
+    fn encode_by_ref(
+        &self,
+        buf: &mut PgArgumentBuffer,
+    ) -> Result<sqlx::encode::IsNull, Box<dyn std::error::Error + Send + Sync>> {
+        let s = self.as_str();
+        <&str as sqlx::Encode<sqlx::Postgres>>::encode_by_ref(&s, buf)
+    }
+}
@@ -2,14 +2,17 @@
 ///
 /// Each handler parses the request, delegates to `service`, and maps the
 /// result to an HTTP response.  No SQL lives here.
-use actix_web::{get, post, web, HttpResponse};
+use actix_web::{get, patch, post, web, HttpResponse};
 use sqlx::PgPool;
 use uuid::Uuid;
 use validator::ValidateEmail;
 
 use crate::error::AppError;
 
-use super::{models::CreateUser, service};
+use super::{
+    models::{CreateUser, UpdateUser},
+    service,
+};
 
 #[utoipa::path(
     get,
@@ -58,3 +61,29 @@ pub async fn create_user(
     let user = service::upsert_user(db.get_ref(), &payload).await?;
     Ok(HttpResponse::Created().json(user))
 }
+
+#[utoipa::path(
+    patch,
+    path = "/users/{user_id}",
+    params(
+        ("user_id" = String, description = "User ID (UUID v4)")
+    ),
+    request_body = UpdateUser,
+    responses(
+        (status = 200, description = "User updated", body = super::models::User, content_type = "application/json"),
+        (status = 400, description = "Invalid UUID or unknown timezone"),
+        (status = 404, description = "User not found")
+    )
+)]
+#[patch("/users/{user_id}")]
+pub async fn update_user(
+    path: web::Path<String>,
+    body: web::Json<UpdateUser>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&path.into_inner())
+        .map_err(|_| AppError::BadRequest("Invalid UUID".into()))?;
+
+    let user = service::update_user(db.get_ref(), user_id, &body.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
@@ -1,11 +1,13 @@
 pub mod handlers;
 pub mod models;
 mod repository;
-mod service;
+pub mod service;
+pub mod timezone;
 
 use actix_web::web;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(handlers::get_user)
-        .service(handlers::create_user);
+        .service(handlers::create_user)
+        .service(handlers::update_user);
 }
@@ -7,6 +7,11 @@ pub struct User {
     pub google_id: String,
     pub email: String,
     pub created_at: chrono::NaiveDateTime,
+    /// IANA zone that days, weeks and months are counted in, e.g.
+    /// `"Australia/Brisbane"`.
+    pub timezone: String,
+    /// Whether the user's best efforts appear on segment leaderboards.
+    pub segment_leaderboards: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, utoipa::ToSchema)]
@@ -15,3 +20,12 @@ pub struct CreateUser {
     pub google_id: String,
     pub email: String,
 }
+
+/// Body of `PATCH /users/{user_id}`.
+#[derive(Debug, Clone, Deserialize, utoipa::ToSchema)]
+pub struct UpdateUser {
+    /// IANA zone name, e.g. `"Europe/Stockholm"`.
+    pub timezone: Option<String>,
+    /// Opt in to (or out of) segment leaderboards.
+    pub segment_leaderboards: Option<bool>,
+}
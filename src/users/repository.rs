@@ -38,3 +38,35 @@ pub async fn upsert(db: &PgPool, payload: &CreateUser) -> Result<User, AppError>
     .await
     .map_err(AppError::from)
 }
+
+pub async fn set_timezone(db: &PgPool, user_id: Uuid, timezone: &str) -> Result<Option<User>, AppError> {
+    sqlx::query_as::<_, User>("UPDATE users SET timezone = $2 WHERE id = $1 RETURNING *")
+        .bind(user_id)
+        .bind(timezone)
+        .fetch_optional(db)
+        .await
+        .map_err(AppError::from)
+}
+
+pub async fn set_segment_leaderboards(
+    db: &PgPool,
+    user_id: Uuid,
+    opt_in: bool,
+) -> Result<Option<User>, AppError> {
+    sqlx::query_as::<_, User>(
+        "UPDATE users SET segment_leaderboards = $2 WHERE id = $1 RETURNING *",
+    )
+    .bind(user_id)
+    .bind(opt_in)
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn find_timezone(db: &PgPool, user_id: Uuid) -> Result<Option<String>, AppError> {
+    sqlx::query_scalar("SELECT timezone FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+        .map_err(AppError::from)
+}
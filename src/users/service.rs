@@ -2,14 +2,15 @@
 ///
 /// Thin orchestration between handlers and repository.
 /// All validation belongs in handlers; all SQL belongs in repository.
+use chrono_tz::Tz;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::error::AppError;
 
 use super::{
-    models::{CreateUser, User},
-    repository,
+    models::{CreateUser, UpdateUser, User},
+    repository, timezone,
 };
 
 pub async fn get_user(db: &PgPool, user_id: Uuid) -> Result<User, AppError> {
@@ -21,3 +22,39 @@ pub async fn get_user(db: &PgPool, user_id: Uuid) -> Result<User, AppError> {
 pub async fn upsert_user(db: &PgPool, payload: &CreateUser) -> Result<User, AppError> {
     repository::upsert(db, payload).await
 }
+
+pub async fn update_user(db: &PgPool, user_id: Uuid, payload: &UpdateUser) -> Result<User, AppError> {
+    let tz = payload
+        .timezone
+        .as_deref()
+        .map(timezone::parse)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+    if let Some(tz) = tz {
+        repository::set_timezone(db, user_id, tz.name())
+            .await?
+            .ok_or(AppError::NotFound)?;
+    }
+    if let Some(opt_in) = payload.segment_leaderboards {
+        repository::set_segment_leaderboards(db, user_id, opt_in)
+            .await?
+            .ok_or(AppError::NotFound)?;
+    }
+    get_user(db, user_id).await
+}
+
+/// The zone a user's days, weeks and months are counted in.  Falls back to
+/// UTC for unknown users and unreadable settings so period logic never fails.
+pub async fn timezone(db: &PgPool, user_id: Uuid) -> Tz {
+    match repository::find_timezone(db, user_id).await {
+        Ok(Some(name)) => timezone::parse(&name).unwrap_or_else(|e| {
+            tracing::warn!("User {user_id}: {e}; using UTC");
+            Tz::UTC
+        }),
+        Ok(None) => Tz::UTC,
+        Err(e) => {
+            tracing::warn!("Could not load timezone of user {user_id}: {e}; using UTC");
+            Tz::UTC
+        }
+    }
+}
@@ -0,0 +1,62 @@
+/// Calendar arithmetic in a user's timezone.
+///
+/// Activities are stored as UTC instants.  Days, ISO weeks (Monday start) and
+/// months are always cut in the user's IANA zone (`users.timezone`, `UTC`
+/// unless set), so a 7am run in Brisbane lands on the day it was run.  SQL
+/// does the same with `date AT TIME ZONE $tz`, binding [`Tz::name`].
+///
+/// Pure functions — no DB access.
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+
+/// Parse an IANA zone name such as `"Australia/Brisbane"`.
+pub fn parse(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("Unknown timezone '{name}'"))
+}
+
+/// Read a wall-clock time in `tz`.  A time skipped by a DST change is moved
+/// forward by an hour; an ambiguous one resolves to its first occurrence.
+pub fn localize(tz: Tz, local: NaiveDateTime) -> DateTime<Tz> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .unwrap_or_else(|| tz.from_utc_datetime(&local))
+}
+
+/// Offset of `tz` at `instant`, in seconds east of UTC.
+pub fn offset_seconds(tz: Tz, instant: DateTime<Utc>) -> i32 {
+    instant.with_timezone(&tz).offset().fix().local_minus_utc()
+}
+
+/// The calendar day `instant` falls on in `tz`.
+pub fn local_date(tz: Tz, instant: DateTime<Utc>) -> NaiveDate {
+    instant.with_timezone(&tz).date_naive()
+}
+
+pub fn today(tz: Tz) -> NaiveDate {
+    local_date(tz, Utc::now())
+}
+
+/// The instant `date` starts in `tz`.
+pub fn start_of_day(tz: Tz, date: NaiveDate) -> DateTime<Utc> {
+    localize(tz, date.and_hms_opt(0, 0, 0).unwrap()).with_timezone(&Utc)
+}
+
+/// The ISO Monday starting the week of `date`.
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// The 1st of the month of `date`.
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap()
+}
+
+/// The 1st of the month after the one starting at `month_start`.
+pub fn next_month_start(month_start: NaiveDate) -> NaiveDate {
+    month_start.checked_add_months(chrono::Months::new(1)).unwrap()
+}
@@ -1,10 +1,13 @@
 use chrono::{Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
+    activities::pace::Pace,
     error::AppError,
     missions::common::{dow_name, CompletedMissionSummary},
+    users::{self, timezone},
     xp::{models::AwardXpInput, service as xp_service},
 };
 
@@ -13,11 +16,9 @@ use super::{
     repository,
 };
 
-/// Returns the ISO Monday that starts the current week (UTC).
-pub fn current_week_start() -> NaiveDate {
-    let today = Utc::now().date_naive();
-    let days_since_monday = today.weekday().num_days_from_monday();
-    today - Duration::days(days_since_monday as i64)
+/// Returns the ISO Monday that starts the current week in `tz`.
+pub fn current_week_start(tz: Tz) -> NaiveDate {
+    timezone::week_start(timezone::today(tz))
 }
 
 /// Stats used to personalise mission generation.
@@ -26,10 +27,15 @@ struct UserWeeklyStats {
     avg_weekly_runs: f64,
     last_week_km: f64,
     most_skipped_dow: Option<u32>, // 0=Sun…6=Sat (PostgreSQL extract(dow))
-    avg_pace_secs: f64,            // secs/km, across all activities
+    avg_pace: Pace,                // across all activities
 }
 
-async fn fetch_weekly_stats(pool: &PgPool, user_id: Uuid, week_start: NaiveDate) -> UserWeeklyStats {
+async fn fetch_weekly_stats(
+    pool: &PgPool,
+    user_id: Uuid,
+    week_start: NaiveDate,
+    tz: Tz,
+) -> UserWeeklyStats {
     // Average weekly km & run count (all historical weeks)
     // AVG on empty set returns one row with NULLs, so fetch_one is safe.
     let agg: (Option<f64>, Option<f64>) = sqlx::query_as::<_, (Option<f64>, Option<f64>)>(
@@ -39,7 +45,7 @@ async fn fetch_weekly_stats(pool: &PgPool, user_id: Uuid, week_start: NaiveDate)
             AVG(weekly_count) AS avg_weekly_runs
         FROM (
             SELECT
-                date_trunc('week', date) AS w,
+                date_trunc('week', date AT TIME ZONE $2) AS w,
                 SUM(distance::FLOAT8) AS weekly_km,
                 COUNT(*)              AS weekly_count
             FROM activities
@@ -49,6 +55,7 @@ async fn fetch_weekly_stats(pool: &PgPool, user_id: Uuid, week_start: NaiveDate)
         "#,
     )
     .bind(user_id)
+    .bind(tz.name())
     .fetch_one(pool)
     .await
     .unwrap_or((None, None));
@@ -69,8 +76,8 @@ async fn fetch_weekly_stats(pool: &PgPool, user_id: Uuid, week_start: NaiveDate)
         "#,
     )
     .bind(user_id)
-    .bind(last_week_start.and_hms_opt(0, 0, 0))
-    .bind(last_week_end.and_hms_opt(0, 0, 0))
+    .bind(timezone::start_of_day(tz, last_week_start))
+    .bind(timezone::start_of_day(tz, last_week_end))
     .fetch_optional(pool)
     .await
     .ok()
@@ -81,15 +88,16 @@ async fn fetch_weekly_stats(pool: &PgPool, user_id: Uuid, week_start: NaiveDate)
     let most_skipped_dow: Option<u32> = {
         let row: Option<(f64,)> = sqlx::query_as::<_, (f64,)>(
             r#"
-            SELECT CAST(extract(dow FROM date) AS FLOAT) AS dow
+            SELECT CAST(extract(dow FROM date AT TIME ZONE $2) AS FLOAT) AS dow
             FROM activities
             WHERE user_id = $1
-            GROUP BY CAST(extract(dow FROM date) AS FLOAT)
+            GROUP BY CAST(extract(dow FROM date AT TIME ZONE $2) AS FLOAT)
             ORDER BY COUNT(*) ASC
             LIMIT 1
             "#,
         )
         .bind(user_id)
+        .bind(tz.name())
         .fetch_optional(pool)
         .await
         .ok()
@@ -98,11 +106,7 @@ async fn fetch_weekly_stats(pool: &PgPool, user_id: Uuid, week_start: NaiveDate)
         row.map(|(v,)| v as u32)
     };
 
-    // Average pace in secs/km (only for activities with positive pace)
-    // average_pace is stored as M.SS (e.g. 5.12 means 5:12/km).
-    // Convert: floor(m.ss) * 60 + round((m.ss - floor(m.ss)) * 100)
-    // We approximate: average_pace * 60 works for rough generation.
-    // We use a simpler approximation: average_pace (minutes decimal) * 60 = secs/km.
+    // Average pace (only for activities with a pace)
     let avg_pace: Option<f64> = sqlx::query_scalar(
         r#"
         SELECT AVG(CAST(average_pace AS FLOAT8))
@@ -117,24 +121,23 @@ async fn fetch_weekly_stats(pool: &PgPool, user_id: Uuid, week_start: NaiveDate)
     .flatten()
     .flatten();
 
-    // Convert M.SS format to seconds: e.g. 5.12 -> 5*60 + 12 = 312
-    let avg_pace_secs = avg_pace
-        .map(|p| {
-            let mins = p.floor();
-            let secs = (p - mins) * 100.0;
-            mins * 60.0 + secs
-        })
-        .unwrap_or(360.0); // default 6:00/km = 360 secs
+    let avg_pace = Pace::from_secs_per_km(avg_pace.unwrap_or(360.0)); // default 6:00/km
 
     UserWeeklyStats {
         avg_weekly_km,
         avg_weekly_runs,
         last_week_km: last_week_km.unwrap_or(0.0),
         most_skipped_dow,
-        avg_pace_secs,
+        avg_pace,
     }
 }
 
+/// `run_sub_pace` target: 10 secs/km faster than the user's average, but
+/// never under 3:00/km.
+fn sub_pace_target(avg_pace: Pace) -> Pace {
+    Pace::from_secs_per_km((avg_pace.secs_per_km() - 10.0).max(180.0))
+}
+
 /// Generate 3 personalised missions for the given user/week.
 fn generate_missions(
     user_id: Uuid,
@@ -183,18 +186,15 @@ fn generate_missions(
         });
     } else {
         // Target pace 10 secs/km faster than average
-        let target_pace_secs = (stats.avg_pace_secs - 10.0).max(180.0);
-        let mins = (target_pace_secs / 60.0).floor() as u32;
-        let secs = (target_pace_secs % 60.0).round() as u32;
-        let pace_str = format!("{mins}:{secs:02}");
+        let target_pace = sub_pace_target(stats.avg_pace);
         missions.push(WeeklyMission {
             id: Uuid::new_v4(),
             user_id,
             week_start,
             mission_type: "run_sub_pace".to_string(),
-            title: format!("Run sub {pace_str}/km for 5km+"),
-            description: format!("Complete a 5km+ run under {pace_str}/km"),
-            target_value: target_pace_secs,
+            title: format!("Run sub {target_pace}/km for 5km+"),
+            description: format!("Complete a 5km+ run under {target_pace}/km"),
+            target_value: target_pace.secs_per_km(),
             current_value: f64::MAX, // lower is better; starts "worst"
             xp_reward: 100,
             completed_at: None,
@@ -254,7 +254,8 @@ pub async fn get_or_generate_missions(
     pool: &PgPool,
     user_id: Uuid,
 ) -> Result<WeeklyMissionsResponse, AppError> {
-    let week_start = current_week_start();
+    let tz = users::service::timezone(pool, user_id).await;
+    let week_start = current_week_start(tz);
     let existing = repository::get_missions_for_week(pool, user_id, week_start).await?;
 
     if existing.len() < 3 {
@@ -262,7 +263,7 @@ pub async fn get_or_generate_missions(
         let existing_types: std::collections::HashSet<String> =
             existing.iter().map(|m| m.mission_type.clone()).collect();
 
-        let stats = fetch_weekly_stats(pool, user_id, week_start).await;
+        let stats = fetch_weekly_stats(pool, user_id, week_start, tz).await;
         let generated = generate_missions(user_id, week_start, &stats);
 
         // Only insert types not already present
@@ -296,7 +297,8 @@ pub async fn reroll_mission(
     user_id: Uuid,
     mission_id: Uuid,
 ) -> Result<WeeklyMission, AppError> {
-    let week_start = current_week_start();
+    let tz = users::service::timezone(pool, user_id).await;
+    let week_start = current_week_start(tz);
 
     // Validate: mission belongs to this user and current week
     let mission = repository::get_mission_by_id(pool, mission_id)
@@ -326,7 +328,7 @@ pub async fn reroll_mission(
         .map(|m| m.mission_type.clone())
         .collect();
 
-    let stats = fetch_weekly_stats(pool, user_id, week_start).await;
+    let stats = fetch_weekly_stats(pool, user_id, week_start, tz).await;
     let all_candidates = generate_replacement_candidates(user_id, week_start, &stats);
 
     let replacement = all_candidates
@@ -385,18 +387,15 @@ fn generate_replacement_candidates(
     });
 
     // run_sub_pace
-    let target_pace_secs = (stats.avg_pace_secs - 10.0).max(180.0);
-    let mins = (target_pace_secs / 60.0).floor() as u32;
-    let secs = (target_pace_secs % 60.0).round() as u32;
-    let pace_str = format!("{mins}:{secs:02}");
+    let target_pace = sub_pace_target(stats.avg_pace);
     candidates.push(WeeklyMission {
         id: Uuid::new_v4(),
         user_id,
         week_start,
         mission_type: "run_sub_pace".to_string(),
-        title: format!("Run sub {pace_str}/km for 5km+"),
-        description: format!("Complete a 5km+ run under {pace_str}/km"),
-        target_value: target_pace_secs,
+        title: format!("Run sub {target_pace}/km for 5km+"),
+        description: format!("Complete a 5km+ run under {target_pace}/km"),
+        target_value: target_pace.secs_per_km(),
         current_value: f64::MAX,
         xp_reward: 100,
         completed_at: None,
@@ -414,15 +413,35 @@ pub async fn update_progress_after_upload(
     pool: &PgPool,
     user_id: Uuid,
 ) -> Result<Vec<CompletedMissionSummary>, AppError> {
-    let week_start = current_week_start();
+    recalculate_progress(pool, user_id, false).await
+}
+
+/// Recalculate progress after an activity was edited or deleted.
+///
+/// Unlike an upload, an edit can take progress away: missions that are no
+/// longer met lose their completion and the XP it awarded.
+pub async fn update_progress_after_edit(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<CompletedMissionSummary>, AppError> {
+    recalculate_progress(pool, user_id, true).await
+}
+
+async fn recalculate_progress(
+    pool: &PgPool,
+    user_id: Uuid,
+    revoke_unmet: bool,
+) -> Result<Vec<CompletedMissionSummary>, AppError> {
+    let tz = users::service::timezone(pool, user_id).await;
+    let week_start = current_week_start(tz);
     let missions = repository::get_missions_for_week(pool, user_id, week_start).await?;
 
     if missions.is_empty() {
         return Ok(vec![]);
     }
 
-    let week_start_dt = week_start.and_hms_opt(0, 0, 0).unwrap();
-    let week_end_dt = (week_start + Duration::days(7)).and_hms_opt(0, 0, 0).unwrap();
+    let week_start_dt = timezone::start_of_day(tz, week_start);
+    let week_end_dt = timezone::start_of_day(tz, week_start + Duration::days(7));
 
     // Fetch this week's activity stats in one query.
     // Aggregate SELECT always returns one row (even for empty set), so fetch_one is safe.
@@ -459,34 +478,27 @@ pub async fn update_progress_after_upload(
         longest_km: ws_row.2.unwrap_or(0.0),
     };
 
-    // Best pace this week for runs ≥ 5km (secs/km, lower is better)
-    let best_pace_secs: Option<f64> = {
-        let pace_raw: Option<Option<f64>> = sqlx::query_scalar(
-            r#"
-            SELECT MIN(
-                CASE
-                    WHEN average_pace > 0 THEN
-                        (FLOOR(average_pace) * 60.0 + ((average_pace - FLOOR(average_pace)) * 100.0))
-                    ELSE NULL
-                END
-            )
-            FROM activities
-            WHERE user_id = $1
-              AND date >= $2
-              AND date < $3
-              AND distance >= 5
-            "#,
-        )
-        .bind(user_id)
-        .bind(week_start_dt)
-        .bind(week_end_dt)
-        .fetch_optional(pool)
-        .await
-        .ok()
-        .flatten();
-
-        pace_raw.flatten()
-    };
+    // Best pace this week for runs ≥ 5km (lower is better)
+    let best_pace: Option<Pace> = sqlx::query_scalar(
+        r#"
+        SELECT MIN(average_pace)
+        FROM activities
+        WHERE user_id = $1
+          AND date >= $2
+          AND date < $3
+          AND distance >= 5
+          AND average_pace > 0
+        "#,
+    )
+    .bind(user_id)
+    .bind(week_start_dt)
+    .bind(week_end_dt)
+    .fetch_optional(pool)
+    .await
+    .ok()
+    .flatten()
+    .flatten();
+    let best_pace_secs = best_pace.map(Pace::secs_per_km);
 
     let mut newly_completed: Vec<CompletedMissionSummary> = Vec::new();
 
@@ -528,13 +540,14 @@ pub async fn update_progress_after_upload(
                     WHERE user_id = $1
                       AND date >= $2
                       AND date < $3
-                      AND CAST(extract(dow FROM date) AS INT) = $4
+                      AND CAST(extract(dow FROM date AT TIME ZONE $5) AS INT) = $4
                     "#,
                 )
                 .bind(user_id)
                 .bind(week_start_dt)
                 .bind(week_end_dt)
                 .bind(target_dow)
+                .bind(tz.name())
                 .fetch_optional(pool)
                 .await
                 .ok()
@@ -547,14 +560,26 @@ pub async fn update_progress_after_upload(
             _ => continue,
         };
 
+        let revoke = revoke_unmet && was_completed && !is_done;
         let completed_at = if is_done && !was_completed {
             Some(Utc::now())
+        } else if revoke {
+            None
         } else {
             mission.completed_at
         };
 
         repository::update_mission_progress(pool, mission.id, new_value, completed_at).await?;
 
+        if revoke {
+            let description = format!("Mission no longer complete: {}", mission.title);
+            if let Err(e) =
+                xp_service::revoke_xp(pool, user_id, "mission", mission.id, description).await
+            {
+                tracing::warn!("Failed to revoke XP for mission {}: {e}", mission.id);
+            }
+        }
+
         if is_done && !was_completed {
             // Award XP for newly completed mission
             let input = AwardXpInput {
@@ -1,4 +1,4 @@
-use sqlx::PgPool;
+use sqlx::{Connection, PgConnection, PgPool};
 use uuid::Uuid;
 
 use crate::error::AppError;
@@ -27,8 +27,10 @@ pub async fn get_or_create_user_xp(db: &PgPool, user_id: Uuid) -> Result<UserXp,
     .map_err(AppError::from)
 }
 
-pub async fn award_xp(db: &PgPool, input: AwardXpInput) -> Result<XpEvent, AppError> {
-    let mut tx = db.begin().await.map_err(AppError::from)?;
+/// Record an XP event and update the user's total.  On a connection inside
+/// the caller's transaction this runs in a savepoint.
+pub async fn award_xp(conn: &mut PgConnection, input: AwardXpInput) -> Result<XpEvent, AppError> {
+    let mut tx = conn.begin().await.map_err(AppError::from)?;
 
     let event = sqlx::query_as::<_, XpEvent>(
         "INSERT INTO xp_events (user_id, source_type, source_id, xp_amount, description)
@@ -76,6 +78,28 @@ pub async fn award_xp(db: &PgPool, input: AwardXpInput) -> Result<XpEvent, AppEr
     Ok(event)
 }
 
+/// Net XP a user holds from one source (awards minus earlier revocations).
+pub async fn net_xp_for_source<'c, E>(
+    db: E,
+    user_id: Uuid,
+    source_type: &str,
+    source_id: Uuid,
+) -> Result<i64, AppError>
+where
+    E: sqlx::Executor<'c, Database = sqlx::Postgres>,
+{
+    sqlx::query_scalar(
+        "SELECT COALESCE(SUM(xp_amount), 0)::BIGINT FROM xp_events
+         WHERE user_id = $1 AND source_type = $2 AND source_id = $3",
+    )
+    .bind(user_id)
+    .bind(source_type)
+    .bind(source_id)
+    .fetch_one(db)
+    .await
+    .map_err(AppError::from)
+}
+
 pub async fn get_recent_events(
     db: &PgPool,
     user_id: Uuid,
@@ -1,4 +1,4 @@
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
 use crate::error::AppError;
@@ -42,6 +42,47 @@ pub async fn get_user_xp_summary(db: &PgPool, user_id: Uuid) -> Result<UserXpRes
 
 /// Award XP — public API used by activities, achievements, missions, PRs.
 pub async fn award_xp(db: &PgPool, input: AwardXpInput) -> Result<(), AppError> {
-    repository::award_xp(db, input).await?;
+    repository::award_xp(&mut *db.acquire().await?, input).await?;
     Ok(())
 }
+
+/// Take back whatever XP is still held from one source (an activity, PR,
+/// achievement, mission or goal) by recording a compensating negative event.
+///
+/// Returns the amount revoked; 0 when nothing was outstanding, so calling it
+/// twice is harmless.
+pub async fn revoke_xp(
+    db: &PgPool,
+    user_id: Uuid,
+    source_type: &str,
+    source_id: Uuid,
+    description: String,
+) -> Result<i64, AppError> {
+    revoke_xp_on(&mut *db.acquire().await?, user_id, source_type, source_id, description).await
+}
+
+/// `revoke_xp` on a connection, e.g. inside the caller's transaction.
+pub async fn revoke_xp_on(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    source_type: &str,
+    source_id: Uuid,
+    description: String,
+) -> Result<i64, AppError> {
+    let net = repository::net_xp_for_source(&mut *conn, user_id, source_type, source_id).await?;
+    if net <= 0 {
+        return Ok(0);
+    }
+    repository::award_xp(
+        conn,
+        AwardXpInput {
+            user_id,
+            source_type: source_type.to_string(),
+            source_id: Some(source_id),
+            xp_amount: -(net as i32),
+            description,
+        },
+    )
+    .await?;
+    Ok(net)
+}
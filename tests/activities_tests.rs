@@ -2,9 +2,19 @@
 mod tests {
 
     use activity_api::activities::{
-        handlers::{get_activities, get_heatmap, get_trackpoints},
-        models::{HeatmapPoint, TrackPoint},
+        handlers::{delete_activity, get_activities, get_heatmap, get_heatmap_tile, get_trackpoints},
+        models::{
+            Activity, ActivityCursor, ActivitySort, CreateActivityRequest, HeatmapPoint,
+            TrackMetrics, TrackPoint,
+        },
+        pace::Pace,
+        service,
     };
+    use activity_api::achievements::{
+        models::AchievementWithStatus, service::get_user_achievements,
+    };
+    use activity_api::personal_records::service::get_user_prs;
+    use activity_api::users::{models::CreateUser, service::upsert_user};
     use actix_web::{test, App};
     use sqlx::PgPool;
     use uuid::Uuid;
@@ -108,4 +118,279 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), 400);
     }
+
+    #[actix_web::test]
+    async fn test_get_heatmap_invalid_bbox() {
+        // A bbox with min_lon > max_lon must return 400 Bad Request.
+        let db = setup_db().await;
+        let user_id = Uuid::new_v4();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(get_heatmap),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/users/{}/heatmap?zoom=12&bbox=14,52,13,53", user_id))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_get_heatmap_tile() {
+        // No activities: a fully transparent tile.  Tiles outside the zoom
+        // level's grid are a 400.
+        let db = setup_db().await;
+        let user_id = Uuid::new_v4();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(get_heatmap_tile),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/users/{}/heatmap/tiles/12/2200/1343.png", user_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers().get("content-type").unwrap(), "image/png");
+        let body = test::read_body(resp).await;
+        let mut reader = png::Decoder::new(&body[..]).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(reader.info().width, 256);
+        assert!(pixels.iter().all(|&b| b == 0));
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/users/{}/heatmap/tiles/2/4/0.png", user_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_delete_unknown_activity() {
+        // Deleting an activity that does not exist must be a 404, not a no-op.
+        let db = setup_db().await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(delete_activity),
+        )
+        .await;
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/users/{}/activities/{}", Uuid::new_v4(), Uuid::new_v4()))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_get_activities_cursor_must_match_sort() {
+        // A cursor issued for a date-sorted list is rejected for a distance sort.
+        let db = setup_db().await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(get_activities),
+        )
+        .await;
+
+        let activity = Activity {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            date: chrono::NaiveDate::from_ymd_opt(2025, 6, 1)
+                .unwrap()
+                .and_hms_opt(7, 30, 0)
+                .unwrap()
+                .and_utc(),
+            utc_offset: None,
+            name: "Morning run".into(),
+            activity_type: "Running".into(),
+            distance: 5.0,
+            duration: "25:00".into(),
+            average_pace: Pace::from_secs_per_km(300.0),
+            average_speed: 12.0,
+            calories: 0.0,
+            climb: 0.0,
+            gps_file: String::new(),
+            source: "manual".into(),
+            external_id: None,
+            track_metrics: TrackMetrics::default(),
+        };
+        let cursor = ActivityCursor::after(&activity, ActivitySort::DateDesc).encode();
+        assert_eq!(
+            ActivityCursor::decode(&cursor, ActivitySort::DateAsc),
+            Ok(ActivityCursor::Date(activity.date, activity.id))
+        );
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/users/{}/activities?sort=distance_desc&cursor={}",
+                activity.user_id, cursor
+            ))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_delete_keeps_achievements_other_runs_earn() {
+        // The first of three 6 km runs unlocks first_run and run_5k_once;
+        // deleting it must hand them to a remaining run, not drop them.
+        let db = setup_db().await;
+        let user_id = upsert_user(
+            &db,
+            &CreateUser {
+                google_id: format!("delete-{}", Uuid::new_v4()),
+                email: format!("delete-{}@example.com", Uuid::new_v4()),
+            },
+        )
+        .await
+        .unwrap()
+        .id;
+
+        let mut ids = Vec::new();
+        for day in 1..=3 {
+            let req: CreateActivityRequest = serde_json::from_value(serde_json::json!({
+                "date": format!("2025-06-0{day}T07:30:00Z"),
+                "activity_type": "Running",
+                "distance": 6.0,
+                "duration": "33:00",
+            }))
+            .unwrap();
+            let created = service::create_activity(&db, user_id, req).await.unwrap();
+            ids.push(created.activity.unwrap().id);
+        }
+
+        let unlocked_by = |achievements: &[AchievementWithStatus], slug: &str| {
+            achievements
+                .iter()
+                .find(|a| a.slug == slug && a.unlocked)
+                .and_then(|a| a.activity_id)
+        };
+        let before = get_user_achievements(&db, user_id).await.unwrap();
+        assert_eq!(unlocked_by(&before, "first_run"), Some(ids[0]));
+        assert_eq!(unlocked_by(&before, "run_5k_once"), Some(ids[0]));
+
+        let deleted = service::delete_activity(&db, user_id, ids[0]).await.unwrap();
+        assert!(deleted
+            .revoked_achievements
+            .contains(&"run_5k_once".to_string()));
+
+        let after = get_user_achievements(&db, user_id).await.unwrap();
+        assert_eq!(unlocked_by(&after, "first_run"), Some(ids[1]));
+        assert_eq!(unlocked_by(&after, "run_5k_once"), Some(ids[1]));
+        assert!(service::delete_activity(&db, user_id, ids[0]).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_get_activities_pages_skip_aggregations_by_default() {
+        let db = setup_db().await;
+        let user_id = upsert_user(
+            &db,
+            &CreateUser {
+                google_id: format!("paging-{}", Uuid::new_v4()),
+                email: format!("paging-{}@example.com", Uuid::new_v4()),
+            },
+        )
+        .await
+        .unwrap()
+        .id;
+        for day in 1..=2 {
+            let req: CreateActivityRequest = serde_json::from_value(serde_json::json!({
+                "date": format!("2025-06-0{day}T07:30:00Z"),
+                "activity_type": "Running",
+                "distance": 5.0,
+                "duration": "25:00",
+            }))
+            .unwrap();
+            service::create_activity(&db, user_id, req).await.unwrap();
+        }
+
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(get_activities),
+        )
+        .await;
+        let get = |query: &str| {
+            test::TestRequest::get()
+                .uri(&format!("/users/{user_id}/activities{query}"))
+                .to_request()
+        };
+
+        let all: serde_json::Value = test::call_and_read_body_json(&app, get("")).await;
+        assert_eq!(all["aggregation"]["Running"]["basic"]["total_activities"], 2);
+
+        let page: serde_json::Value = test::call_and_read_body_json(&app, get("?limit=1")).await;
+        assert_eq!(page["activities"].as_array().unwrap().len(), 1);
+        assert!(page["aggregation"].is_null());
+        assert!(page["time_aggregations"].is_null());
+
+        // Asked for explicitly, a page still aggregates over every match.
+        let page: serde_json::Value =
+            test::call_and_read_body_json(&app, get("?limit=1&include_aggregations=true")).await;
+        assert_eq!(page["aggregation"]["Running"]["basic"]["total_activities"], 2);
+    }
+
+    #[actix_web::test]
+    async fn test_clean_trackpoints_re_evaluates_personal_records() {
+        let db = setup_db().await;
+        let user_id = upsert_user(
+            &db,
+            &CreateUser {
+                google_id: format!("clean-{}", Uuid::new_v4()),
+                email: format!("clean-{}@example.com", Uuid::new_v4()),
+            },
+        )
+        .await
+        .unwrap()
+        .id;
+        let req: CreateActivityRequest = serde_json::from_value(serde_json::json!({
+            "date": "2025-06-01T07:30:00Z",
+            "activity_type": "Running",
+            "distance": 6.0,
+            "duration": "33:00",
+        }))
+        .unwrap();
+        let activity_id = service::create_activity(&db, user_id, req)
+            .await
+            .unwrap()
+            .activity
+            .unwrap()
+            .id;
+
+        // A record a GPS jump made up, as stored before the track is cleaned.
+        sqlx::query(
+            "UPDATE personal_records SET distance_m = 60000 \
+             WHERE user_id = $1 AND category = 'longest_run'",
+        )
+        .bind(user_id)
+        .execute(&db)
+        .await
+        .unwrap();
+
+        service::clean_trackpoints(&db, user_id, activity_id).await.unwrap();
+
+        let prs = get_user_prs(&db, user_id).await.unwrap();
+        let longest = prs
+            .records
+            .iter()
+            .find(|r| r.category == "longest_run")
+            .unwrap();
+        assert_eq!(longest.distance_m, Some(6000.0));
+        assert_eq!(longest.activity_id, Some(activity_id));
+    }
 }
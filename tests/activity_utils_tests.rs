@@ -1,12 +1,41 @@
-use activity_api::activities::parser::{haversine_distance_m, parse_csv_row};
+mod common;
+
+use std::io::Write;
+
+use activity_api::activities::{
+    archive::extract_zip,
+    cleaning::{clean_track, CleaningConfig},
+    geometry::{encode_polyline, geojson, simplify},
+    heatmap::{cell_centre, cell_of, cells, level_for_zoom, Bbox},
+    lap_kind::LapKind,
+    laps::detect_laps,
+    metrics::{best_efforts, splits, track_metrics},
+    models::{Activity, TrackPoint, UploadFiles},
+    pace::Pace,
+    parser::{
+        haversine_distance_m, parse_csv, parse_fit, parse_gpx_activity, parse_tcx, CsvRowError,
+    },
+    tiles::{render_tile, tile_cell_range, tile_level},
+};
+use activity_api::duplicates::matcher::{score, Fingerprint, AUTO_MERGE_SCORE, SUSPECT_SCORE};
+use chrono_tz::Tz;
 use uuid::Uuid;
 
+use common::{METRE, START};
+
 const USER_ID: &str = "123e4567-e89b-12d3-a456-426614174000";
 
 fn user_id() -> Uuid {
     Uuid::parse_str(USER_ID).unwrap()
 }
 
+/// Parse a header-less CSV file holding a single row in the legacy layout.
+fn parse_legacy_row(row: &str, tz: Tz) -> Result<Activity, CsvRowError> {
+    let mut rows = parse_csv(row, user_id(), tz);
+    assert_eq!(rows.len(), 1);
+    rows.remove(0).map(|(_, activity)| activity)
+}
+
 #[test]
 fn test_parse_csv_row_valid() {
     let id = Uuid::new_v4();
@@ -14,24 +43,60 @@ fn test_parse_csv_row_valid() {
         "{},2025-05-05 17:06:59,Run,Running,6.14,32:09,5.14,11.46,700.0,94,nil,nil,nil,test.gpx",
         id
     );
-    let result = parse_csv_row(&csv, user_id());
+    let result = parse_legacy_row(&csv, Tz::UTC);
     assert!(result.is_ok());
     let activity = result.unwrap();
     assert_eq!(activity.id, id);
     assert_eq!(activity.gps_file, "test.gpx");
+    assert_eq!(activity.average_pace, Pace::from_secs_per_km(314.0)); // 5.14 = 5:14/km
+}
+
+#[test]
+fn test_parse_csv_row_reads_local_time_in_user_zone() {
+    let id = Uuid::new_v4();
+    let csv = format!(
+        "{},2025-05-05 07:06:59,Run,Running,6.14,32:09,5.14,11.46,700.0,94,nil,nil,nil,test.gpx",
+        id
+    );
+    let activity = parse_legacy_row(&csv, Tz::Australia__Brisbane).unwrap();
+    assert_eq!(activity.date.to_string(), "2025-05-04 21:06:59 UTC");
+    assert_eq!(activity.utc_offset, Some(10 * 3600));
+    assert_eq!(
+        activity.local_date(Tz::Australia__Brisbane).to_string(),
+        "2025-05-05"
+    );
+}
+
+#[test]
+fn test_pace_conversions() {
+    assert_eq!(Pace::from_mss(6.56).secs_per_km(), 416.0);
+    assert_eq!(Pace::from_mss(6.56).to_string(), "6:56");
+    assert_eq!(Pace::from_speed_ms(1000.0 / 330.0).to_string(), "5:30");
+    assert_eq!(Pace::from_secs_per_km(359.7).to_string(), "6:00");
+    assert!(!Pace::from_speed_ms(0.0).is_set());
+    assert!(!Pace::from_duration(600.0, 0.0).is_set());
 }
 
 #[test]
 fn test_parse_csv_row_wrong_column_count() {
-    let result = parse_csv_row("invalid,csv,line", user_id());
+    let row = format!("{},2025-05-05 17:06:59,Run", Uuid::new_v4());
+    let result = parse_legacy_row(&row, Tz::UTC);
     assert!(result.is_err());
 }
 
 #[test]
 fn test_parse_csv_row_invalid_uuid() {
-    let row = "not-a-uuid,2025-05-05 17:06:59,Run,Running,6.14,32:09,5.14,11.46,700.0,94,nil,nil,nil,test.gpx";
-    let result = parse_csv_row(row, user_id());
-    assert!(result.is_err());
+    // A first field that is not a UUID marks a header row, so the bad row
+    // follows a valid one.
+    let csv = format!(
+        "{},2025-05-05 17:06:59,Run,Running,6.14,32:09,5.14,11.46,700.0,94,nil,nil,nil,test.gpx\n\
+         not-a-uuid,2025-05-06 17:06:59,Run,Running,6.14,32:09,5.14,11.46,700.0,94,nil,nil,nil,test.gpx",
+        Uuid::new_v4()
+    );
+    let rows = parse_csv(&csv, user_id(), Tz::UTC);
+    assert_eq!(rows.len(), 2);
+    assert!(rows[0].is_ok());
+    assert_eq!(rows[1].as_ref().unwrap_err().line, 2);
 }
 
 #[test]
@@ -41,7 +106,7 @@ fn test_parse_csv_row_invalid_number() {
         "{},2025-05-05 17:06:59,Run,Running,not-a-number,32:09,5.14,11.46,700.0,94,nil,nil,nil,test.gpx",
         id
     );
-    let result = parse_csv_row(&row, user_id());
+    let result = parse_legacy_row(&row, Tz::UTC);
     assert!(result.is_err());
 }
 
@@ -56,3 +121,544 @@ fn test_haversine_known_distance() {
         d
     );
 }
+
+/// Build a minimal FIT file: one `record` definition + two records, and one
+/// `session` definition + one session.  Little-endian throughout.
+fn build_fit() -> Vec<u8> {
+    let mut body = Vec::new();
+
+    // Definition: local 0 → global 20 (record):
+    // timestamp(253,u32) lat(0,s32) long(1,s32) enhanced_altitude(78,u32)
+    body.extend([0x40, 0, 0, 20, 0, 4]);
+    body.extend([253, 4, 0x86, 0, 4, 0x85, 1, 4, 0x85, 78, 4, 0x86]);
+    let semis = |deg: f64| ((deg / 180.0) * 2_147_483_648.0) as i32;
+    for (ts, lat) in [(1_000_000_000u32, 59.330), (1_000_000_010u32, 59.331)] {
+        body.push(0x00);
+        body.extend(ts.to_le_bytes());
+        body.extend(semis(lat).to_le_bytes());
+        body.extend(semis(18.059).to_le_bytes());
+        body.extend(2_600u32.to_le_bytes()); // (20 m + 500) * 5
+    }
+
+    // Definition: local 1 → global 18 (session):
+    // start_time(2,u32) sport(5,enum) total_elapsed_time(7,u32) total_distance(9,u32)
+    body.extend([0x41, 0, 0, 18, 0, 4]);
+    body.extend([2, 4, 0x86, 5, 1, 0x00, 7, 4, 0x86, 9, 4, 0x86]);
+    body.push(0x01);
+    body.extend(1_000_000_000u32.to_le_bytes());
+    body.push(1); // running
+    body.extend(600_000u32.to_le_bytes()); // 600 s, scale 1000
+    body.extend(200_000u32.to_le_bytes()); // 2000 m, scale 100
+
+    let mut file = vec![12, 0x10, 0, 0];
+    file.extend((body.len() as u32).to_le_bytes());
+    file.extend(b".FIT");
+    file.extend(body);
+    file.extend([0, 0]); // CRC (not verified)
+    file
+}
+
+#[test]
+fn test_parse_fit_summary_and_track() {
+    let (activity, tps) = parse_fit(&build_fit(), "morning.fit", user_id()).unwrap();
+
+    assert_eq!(activity.activity_type, "Running");
+    assert_eq!(activity.source, "fit");
+    assert_eq!(activity.duration, "00:10:00");
+    assert!((activity.distance - 2.0).abs() < 1e-6);
+    assert_eq!(activity.gps_file, "morning.fit");
+    assert_eq!(activity.external_id.as_deref().map(str::len), Some(64));
+    // 1_000_000_000 s after the FIT epoch (1989-12-31) is 2021-09-08.
+    assert_eq!(activity.date.to_string(), "2021-09-08 01:46:40 UTC");
+
+    assert_eq!(tps.len(), 2);
+    assert!((tps[0].latitude - 59.330).abs() < 1e-6);
+    assert!((tps[0].elevation - 20.0).abs() < 1e-3);
+    // No speed field recorded → computed from the track (~111 m in 10 s).
+    let speed = tps[0].speed.unwrap();
+    assert!((speed - 11.1).abs() < 0.2, "got {speed}");
+}
+
+#[test]
+fn test_parse_fit_rejects_non_fit_data() {
+    assert!(parse_fit(b"not a fit file at all", "x.fit", user_id()).is_err());
+}
+
+const TCX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2"
+    xmlns:ns3="http://www.garmin.com/xmlschemas/ActivityExtension/v2">
+  <Activities>
+    <Activity Sport="Running">
+      <Id>2024-05-01T06:00:00Z</Id>
+      <Lap StartTime="2024-05-01T06:00:00Z">
+        <TotalTimeSeconds>300</TotalTimeSeconds>
+        <DistanceMeters>1000</DistanceMeters>
+        <Calories>60</Calories>
+        <Track>
+          <Trackpoint>
+            <Time>2024-05-01T06:00:00Z</Time>
+            <Position><LatitudeDegrees>52.0</LatitudeDegrees><LongitudeDegrees>13.0</LongitudeDegrees></Position>
+            <AltitudeMeters>30</AltitudeMeters>
+            <HeartRateBpm><Value>140</Value></HeartRateBpm>
+            <Extensions><ns3:TPX><ns3:RunCadence>85</ns3:RunCadence></ns3:TPX></Extensions>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2024-05-01T06:05:00Z</Time>
+            <Position><LatitudeDegrees>52.009</LatitudeDegrees><LongitudeDegrees>13.0</LongitudeDegrees></Position>
+            <AltitudeMeters>35</AltitudeMeters>
+            <HeartRateBpm><Value>155</Value></HeartRateBpm>
+          </Trackpoint>
+        </Track>
+      </Lap>
+      <Lap StartTime="2024-05-01T06:05:00Z">
+        <TotalTimeSeconds>300</TotalTimeSeconds>
+        <DistanceMeters>1000</DistanceMeters>
+        <Calories>65</Calories>
+      </Lap>
+    </Activity>
+  </Activities>
+</TrainingCenterDatabase>"#;
+
+#[test]
+fn test_parse_tcx_laps_and_heart_rate() {
+    let activities = parse_tcx(TCX.as_bytes(), "run.tcx").unwrap();
+    assert_eq!(activities.len(), 1);
+    let a = &activities[0];
+
+    assert_eq!(a.source, "tcx");
+    assert_eq!(a.activity_type, "Running");
+    assert_eq!(a.date.to_string(), "2024-05-01 06:00:00 UTC");
+    assert_eq!(a.duration, "00:10:00");
+    assert!((a.distance - 2.0).abs() < 1e-6);
+    assert!((a.calories - 125.0).abs() < 1e-6);
+    assert!((a.average_pace.secs_per_km() - 300.0).abs() < 0.1);
+    assert!((a.climb - 5.0).abs() < 1e-6);
+
+    assert_eq!(a.track_points.len(), 2);
+    assert_eq!(a.track_points[0].heart_rate, Some(140));
+    assert_eq!(a.track_points[0].cadence, Some(85));
+    assert_eq!(a.track_points[1].heart_rate, Some(155));
+    assert!(a.track_points[0].speed.is_some());
+}
+
+#[test]
+fn test_parse_standalone_gpx_derives_summary() {
+    let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="Other App" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk>
+    <name>Evening Run</name>
+    <type>running</type>
+    <trkseg>
+      <trkpt lat="52.0" lon="13.0"><ele>10</ele><time>2024-05-01T18:00:00Z</time></trkpt>
+      <trkpt lat="52.0045" lon="13.0"><ele>14</ele><time>2024-05-01T18:02:30Z</time></trkpt>
+      <trkpt lat="52.009" lon="13.0"><ele>12</ele><time>2024-05-01T18:05:00Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+    let (activity, tps) = parse_gpx_activity(gpx.as_bytes(), "evening.gpx", user_id()).unwrap();
+
+    assert_eq!(tps.len(), 3);
+    assert_eq!(activity.name, "Evening Run");
+    assert_eq!(activity.activity_type, "Running");
+    assert_eq!(activity.source, "gpx");
+    assert_eq!(activity.date.to_string(), "2024-05-01 18:00:00 UTC");
+    assert_eq!(activity.duration, "00:05:00");
+    assert!((activity.distance - 1.0).abs() < 0.01);
+    assert!((activity.climb - 4.0).abs() < 1e-6);
+    assert!((activity.average_pace.secs_per_km() - 300.0).abs() < 3.0);
+
+    let (again, _) = parse_gpx_activity(gpx.as_bytes(), "renamed.gpx", user_id()).unwrap();
+    assert_eq!(activity.external_id, again.external_id);
+}
+
+#[test]
+fn test_parse_gpx_reads_sensor_extensions() {
+    let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="Garmin Connect" xmlns="http://www.topografix.com/GPX/1/1"
+     xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+  <trk>
+    <name>Tempo</name>
+    <type>running</type>
+    <trkseg>
+      <trkpt lat="52.0" lon="13.0"><time>2024-05-01T18:00:00Z</time>
+        <extensions><power>250</power><gpxtpx:TrackPointExtension>
+          <gpxtpx:atemp>21.5</gpxtpx:atemp><gpxtpx:hr>150</gpxtpx:hr><gpxtpx:cad>88</gpxtpx:cad>
+        </gpxtpx:TrackPointExtension></extensions>
+      </trkpt>
+      <trkpt lat="52.0045" lon="13.0"><time>2024-05-01T18:02:30Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+    let (_, tps) = parse_gpx_activity(gpx.as_bytes(), "tempo.gpx", user_id()).unwrap();
+
+    assert_eq!(tps.len(), 2);
+    assert_eq!(tps[0].heart_rate, Some(150));
+    assert_eq!(tps[0].cadence, Some(88));
+    assert_eq!(tps[0].power, Some(250));
+    assert_eq!(tps[0].temperature, Some(21.5));
+    assert_eq!(tps[1].heart_rate, None);
+    assert_eq!(tps[1].power, None);
+}
+
+#[test]
+fn test_extract_runkeeper_zip() {
+    let mut buf = std::io::Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        let opts = zip::write::SimpleFileOptions::default();
+        zip.add_directory("runkeeper-export/", opts).unwrap();
+        zip.start_file("runkeeper-export/cardioActivities.csv", opts).unwrap();
+        zip.write_all(b"Activity Id,Date\nrow").unwrap();
+        zip.start_file("runkeeper-export/2024-05-01-180000.gpx", opts).unwrap();
+        zip.write_all(b"<gpx/>").unwrap();
+        zip.start_file("__MACOSX/runkeeper-export/._2024-05-01-180000.gpx", opts).unwrap();
+        zip.write_all(b"junk").unwrap();
+        zip.finish().unwrap();
+    }
+
+    let mut files = UploadFiles::default();
+    extract_zip(buf, &mut files).unwrap();
+
+    assert_eq!(files.csv_text, "Activity Id,Date\nrow");
+    assert_eq!(files.gpx_files.len(), 1);
+    assert!(files.gpx_files.contains_key("2024-05-01-180000.gpx"));
+
+    let junk = std::io::Cursor::new(b"not a zip");
+    assert!(extract_zip(junk, &mut UploadFiles::default()).is_err());
+}
+
+#[test]
+fn test_parse_csv_quoted_fields_and_header_mapping() {
+    let id1 = Uuid::new_v4();
+    let id2 = Uuid::new_v4();
+    let csv = format!(
+        "\u{feff}Activity Id,Date,Type,Route Name,Distance (km),Duration,Average Pace,Average Speed (km/h),Calories Burned,Climb (m),Notes,GPX File,Extra\r\n\
+         {id1},2025-05-05 17:06:59,Running,\"Park, lap \"\"two\"\"\",6.14,32:09,5:14,11.46,700.0,94,\"line one\nline two\",a.gpx,x\r\n\
+         {id2},2025-05-06 07:00:00,Running,,oops,30:00,5:00,12,,,,b.gpx,y\r\n"
+    );
+
+    let rows = parse_csv(&csv, user_id(), Tz::UTC);
+    assert_eq!(rows.len(), 2);
+
+    let (line, a) = rows[0].as_ref().unwrap();
+    assert_eq!(*line, 2);
+    assert_eq!(a.id, id1);
+    assert_eq!(a.name, "Park, lap \"two\"");
+    assert_eq!(a.average_pace, Pace::from_secs_per_km(314.0));
+    assert_eq!(a.gps_file, "a.gpx");
+
+    let e = rows[1].as_ref().unwrap_err();
+    assert_eq!(e.line, 4);
+    assert_eq!(e.field.as_deref(), Some("Distance (km)"));
+}
+
+#[test]
+fn test_parse_csv_missing_required_column() {
+    let rows = parse_csv("Activity Id,Date,Type\n", user_id(), Tz::UTC);
+    assert_eq!(rows.len(), 1);
+    assert!(rows[0].as_ref().unwrap_err().message.contains("Distance"));
+}
+
+fn fingerprint(date: &str, distance_km: f32, duration_secs: i64) -> Fingerprint {
+    Fingerprint {
+        date: chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S")
+            .unwrap()
+            .and_utc(),
+        distance_km,
+        duration_secs,
+        track: vec![],
+    }
+}
+
+#[test]
+fn test_duplicate_score_separates_same_and_different_runs() {
+    let strava = fingerprint("2024-05-01 06:00:00", 10.0, 3000);
+    let runkeeper = fingerprint("2024-05-01 06:00:30", 10.05, 3010);
+    let later_run = fingerprint("2024-05-01 06:14:00", 5.0, 1500);
+
+    assert!(score(&strava, &runkeeper) >= AUTO_MERGE_SCORE);
+    assert!(score(&strava, &later_run) < SUSPECT_SCORE);
+}
+
+#[test]
+fn test_duplicate_score_uses_track_overlap() {
+    let mut a = fingerprint("2024-05-01 06:00:00", 2.0, 600);
+    let mut b = fingerprint("2024-05-01 06:03:00", 2.1, 640);
+    let without_tracks = score(&a, &b);
+
+    a.track = (0..20).map(|i| (52.0 + i as f64 * 0.001, 13.0)).collect();
+    b.track = a.track.clone();
+    assert!(score(&a, &b) > without_tracks);
+
+    b.track = (0..20).map(|i| (48.0 + i as f64 * 0.001, 2.0)).collect();
+    assert!(score(&a, &b) < without_tracks);
+}
+
+/// A point on 13° E with a steady heart rate.
+fn track_point(secs: i64, lat: f64, elevation: f32) -> TrackPoint {
+    TrackPoint {
+        elevation,
+        heart_rate: Some(150),
+        ..common::track_point(Uuid::nil(), secs, lat, 13.0)
+    }
+}
+
+#[test]
+fn test_clean_track_interpolates_gps_jumps() {
+    // 10 s apart, ~3.3 m/s; point 3 jumps ~2 km north, point 5 repeats a time.
+    let mut raw: Vec<TrackPoint> = (0..8)
+        .map(|i| track_point(i * 10, 52.0 + i as f64 * 0.0003, 30.0))
+        .collect();
+    raw[3].latitude += 0.018;
+    raw[3].elevation = 400.0;
+    raw.insert(5, track_point(40, 52.5, 30.0));
+
+    let cleaned = clean_track(&raw, "Running", &CleaningConfig::default());
+
+    assert_eq!(cleaned.len(), 8);
+    assert!((cleaned[3].latitude - (52.0 + 3.0 * 0.0003)).abs() < 1e-9);
+    assert_eq!(cleaned[3].heart_rate, Some(150));
+    assert!((cleaned[4].latitude - (52.0 + 4.0 * 0.0003)).abs() < 1e-9);
+    assert!(cleaned.iter().all(|tp| (tp.elevation - 30.0).abs() < 1e-3));
+    for tp in &cleaned {
+        let speed = tp.speed.unwrap();
+        assert!((speed - 3.34).abs() < 0.05, "speed {speed}");
+    }
+}
+
+#[test]
+fn test_clean_track_rejects_bad_first_fix() {
+    let mut raw: Vec<TrackPoint> = (0..5)
+        .map(|i| track_point(i * 10, 52.0 + i as f64 * 0.0003, 30.0))
+        .collect();
+    raw[0].latitude = 51.0;
+
+    let cleaned = clean_track(&raw, "Running", &CleaningConfig::default());
+
+    assert_eq!(cleaned.len(), 4);
+    assert_eq!(cleaned[0].time, raw[1].time);
+}
+
+#[test]
+fn test_track_metrics_skip_pauses_and_elevation_noise() {
+    // 60 s running at ~3.3 m/s, a 60 s stop with GPS jitter, 60 s running;
+    // ±1 m of elevation noise on a 10 m climb.
+    let mut points = Vec::new();
+    for i in 0..=6 {
+        points.push(track_point(i * 10, 52.0 + i as f64 * 0.0003, 30.0 + (i % 2) as f32));
+    }
+    for i in 1..=6 {
+        points.push(track_point(60 + i * 10, 52.0018 + (i % 2) as f64 * 0.00002, 30.0));
+    }
+    for i in 1..=6 {
+        points.push(track_point(120 + i * 10, 52.0018 + i as f64 * 0.0003, 30.0 + i as f32 * 2.0));
+    }
+
+    let m = track_metrics(&points, "Running", &CleaningConfig::default());
+
+    assert_eq!(m.elapsed_time, Some(180));
+    assert_eq!(m.moving_time, Some(120));
+    assert!((m.track_distance.unwrap() - 0.4003).abs() < 0.001);
+    assert!((m.moving_pace.unwrap().secs_per_km() - 299.8).abs() < 1.0);
+    assert_eq!(m.elevation_gain, Some(12.0));
+    assert_eq!(m.elevation_loss, Some(0.0));
+}
+
+#[test]
+fn test_splits_include_partial_final_split() {
+    // 0.0003° of latitude ≈ 33.36 m every 10 s: 2.5 km takes ~750 s.
+    let points: Vec<TrackPoint> = (0..=75)
+        .map(|i| track_point(i * 10, 52.0 + i as f64 * 0.0003, 30.0 + i as f32))
+        .collect();
+
+    let km = splits(&points, 1000.0, &CleaningConfig::default());
+
+    assert_eq!(km.len(), 3);
+    assert_eq!(km[0].split, 1);
+    assert_eq!(km[0].distance, 1.0);
+    assert_eq!(km[0].elapsed_time, 300);
+    assert!((km[0].pace.secs_per_km() - 300.0).abs() < 1.0);
+    assert!((km[0].average_speed - 12.0).abs() < 0.1);
+    assert!((km[0].elevation_change - 30.0).abs() < 0.1);
+    assert!((km[2].distance - 0.502).abs() < 0.001);
+    let total: f32 = km.iter().map(|s| s.distance).sum();
+    let track_km = track_metrics(&points, "Running", &CleaningConfig::default()).track_distance;
+    assert!((total - track_km.unwrap()).abs() < 1e-4);
+
+    let miles = splits(&points, 1609.344, &CleaningConfig::default());
+    assert_eq!(miles.len(), 2);
+    assert!((miles[0].elapsed_time - 482).abs() <= 1);
+}
+
+#[test]
+fn test_best_efforts_find_fast_stretch_inside_longer_run() {
+    // 3 km north in 100 m steps: 30 s per 100 m, except 24 s in the second km.
+    let step = 100.0 / 111_195.0; // degrees of latitude per 100 m
+    let mut secs = 0;
+    let mut points = vec![track_point(0, 52.0, 30.0)];
+    for i in 1..=30 {
+        secs += if (11..=20).contains(&i) { 24 } else { 30 };
+        points.push(track_point(secs, 52.0 + i as f64 * step, 30.0));
+    }
+
+    let efforts = best_efforts(&points, &CleaningConfig::default());
+    let categories: Vec<&str> = efforts.iter().map(|e| e.category.as_str()).collect();
+    assert_eq!(categories, ["400m", "1k", "1_mile"]);
+
+    let one_k = &efforts[1];
+    assert!((one_k.duration_seconds - 240.0).abs() < 0.5);
+    assert!((one_k.start_offset_seconds - 300.0).abs() < 0.5);
+    assert!((efforts[0].duration_seconds - 96.0).abs() < 0.5);
+    // 1 mile: the fast km plus 609 m at 30 s per 100 m.
+    assert!((efforts[2].duration_seconds - (240.0 + 6.09344 * 30.0)).abs() < 0.5);
+}
+
+#[test]
+fn test_detect_laps_finds_intervals_and_auto_laps() {
+    // 10 s steps: 300 s warm-up at 2.5 m/s with one 10 s surge, then
+    // 4 × (120 s at 5 m/s, 60 s at 2.5 m/s).
+    let mut speeds = vec![2.5; 30];
+    speeds[10] = 5.0;
+    for _ in 0..4 {
+        speeds.extend([5.0; 12]);
+        speeds.extend([2.5; 6]);
+    }
+    let mut lat = 52.0;
+    let mut points = Vec::new();
+    for (i, &speed) in speeds.iter().enumerate() {
+        let mut tp = track_point(i as i64 * 10, lat, 30.0);
+        tp.speed = Some(speed);
+        points.push(tp);
+        lat += speed * 10.0 * METRE;
+    }
+    let mut last = track_point(speeds.len() as i64 * 10, lat, 30.0);
+    last.speed = Some(2.5);
+    points.push(last);
+
+    let laps = detect_laps(&points, &CleaningConfig::default());
+    let of = |kind| laps.iter().filter(|l| l.kind == kind).collect::<Vec<_>>();
+
+    let auto = of(LapKind::Auto);
+    assert_eq!(auto.len(), 4);
+    let total: f64 = auto.iter().map(|l| l.distance_m).sum();
+    assert!((total - 3775.0).abs() < 1.0);
+    assert!(auto[..3].iter().all(|l| (l.distance_m - 1000.0).abs() < 50.0));
+
+    // The surge is too short to be an interval.
+    let work = of(LapKind::Work);
+    assert_eq!(work.len(), 4);
+    assert_eq!(work[0].start_offset_seconds, 300.0);
+    assert!(work
+        .iter()
+        .all(|l| l.duration_seconds == 120.0 && (l.distance_m - 600.0).abs() < 1.0));
+    assert_eq!(work[3].lap, 4);
+    assert!((work[0].pace.secs_per_km() - 200.0).abs() < 0.5);
+
+    let recovery = of(LapKind::Recovery);
+    assert_eq!(recovery.len(), 5);
+    assert_eq!(recovery[0].duration_seconds, 300.0);
+    assert!(recovery[1..].iter().all(|l| l.duration_seconds == 60.0));
+}
+
+#[test]
+fn test_simplify_and_encode_track() {
+    // A straight line north with a 2 m wobble and one 30 m detour east.
+    let points: Vec<TrackPoint> = (0..=20)
+        .map(|i| {
+            let mut tp = track_point(i * 10, 52.0 + i as f64 * 20.0 * METRE, 30.0);
+            let east = match i {
+                10 => 30.0,
+                _ if i % 2 == 1 => 2.0,
+                _ => 0.0,
+            };
+            tp.longitude += east * METRE / 52f64.to_radians().cos();
+            tp
+        })
+        .collect();
+
+    assert_eq!(simplify(&points, 0.0).len(), 21);
+    let simplified = simplify(&points, 5.0);
+    let times: Vec<i64> = simplified
+        .iter()
+        .map(|tp| tp.time.timestamp() - START)
+        .collect();
+    // The wobble goes; the detour and the points framing it stay.
+    assert_eq!(times, [0, 90, 100, 110, 200]);
+
+    let json = geojson(&simplified);
+    assert_eq!(json["geometry"]["type"], "LineString");
+    assert_eq!(json["geometry"]["coordinates"][1][1], simplified[1].latitude);
+
+    // Google's reference example.
+    let reference: Vec<TrackPoint> = [(38.5, -120.2), (40.7, -120.95), (43.252, -126.453)]
+        .iter()
+        .map(|&(lat, lon)| TrackPoint {
+            longitude: lon,
+            ..track_point(0, lat, 0.0)
+        })
+        .collect();
+    assert_eq!(encode_polyline(&reference), "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+}
+
+#[test]
+fn test_heatmap_cells_per_level() {
+    assert_eq!(level_for_zoom(None), 14);
+    assert_eq!(level_for_zoom(Some(3)), 5);
+    assert_eq!(level_for_zoom(Some(10)), 8);
+    assert_eq!(level_for_zoom(Some(18)), 14);
+
+    // Three points ~1 m apart and one ~1 km away.
+    let points = vec![
+        track_point(0, 52.5, 30.0),
+        track_point(1, 52.5 + METRE, 30.0),
+        track_point(2, 52.5 + 2.0 * METRE, 30.0),
+        track_point(600, 52.5 + 1000.0 * METRE, 30.0),
+    ];
+    let all = cells(&points);
+    let at = |level| all.iter().filter(|c| c.level == level).collect::<Vec<_>>();
+    assert_eq!(at(5).len(), 1);
+    assert_eq!(at(5)[0].weight, 4);
+    let fine = at(14);
+    assert_eq!(fine.len(), 2);
+    assert_eq!(fine.iter().map(|c| c.weight).sum::<i32>(), 4);
+
+    // The centre of the busy cell lies within a cell's width of the points.
+    let busy = fine.iter().find(|c| c.weight == 3).unwrap();
+    let (lat, lon) = cell_centre(busy.x, busy.y, 14);
+    assert!((lat - 52.5).abs() < 10.0 * METRE);
+    assert!((lon - 13.0).abs() < 20.0 * METRE);
+
+    let bbox: Bbox = "12.9,52.4,13.1,52.501".parse().unwrap();
+    let (x0, x1, y0, y1) = bbox.cell_range(14);
+    assert!((x0..=x1).contains(&busy.x) && (y0..=y1).contains(&busy.y));
+    let far = fine.iter().find(|c| c.weight == 1).unwrap();
+    assert!(!(y0..=y1).contains(&far.y));
+
+    assert!("13,52,12,53".parse::<Bbox>().is_err());
+    assert!("13,52,14".parse::<Bbox>().is_err());
+}
+
+#[test]
+fn test_render_heatmap_tile() {
+    // Zoom 16 is drawn from level 14: each cell is a 4×4 pixel square.
+    let (z, level) = (16, 14);
+    assert_eq!(tile_level(z), level);
+    let (cx, cy) = cell_of(52.5, 13.4, level);
+    let (x, y) = ((cx as u32 * 4) / 256, (cy as u32 * 4) / 256);
+    let (x0, x1, y0, y1) = tile_cell_range(level, z, x, y);
+    assert_eq!(x1 - x0, 63);
+    assert!((x0..=x1).contains(&cx) && (y0..=y1).contains(&cy));
+
+    let png = render_tile(&[(cx, cy, 100), (x0 - 1, y0, 5)], level, z, x, y).unwrap();
+    let mut reader = png::Decoder::new(&png[..]).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut pixels).unwrap();
+    let alpha = |px: i32, py: i32| pixels[((py * 256 + px) * 4 + 3) as usize];
+
+    let (px, py) = ((cx - x0) * 4, (cy - y0) * 4);
+    for (dx, dy) in [(0, 0), (3, 3)] {
+        assert_eq!(alpha(px + dx, py + dy), 255, "saturated cell is opaque");
+    }
+    let painted = (0..256 * 256).filter(|i| pixels[i * 4 + 3] > 0).count();
+    assert_eq!(painted, 16, "the cell left of the tile is not drawn");
+}
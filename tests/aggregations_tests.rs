@@ -1,10 +1,17 @@
 #[cfg(test)]
 mod tests {
-    use activity_api::{activities::models::Activity, aggregate::aggregate_activities};
+    use activity_api::{
+        activities::{
+            models::{Activity, TrackMetrics},
+            pace::Pace,
+        },
+        aggregate::aggregate_activities,
+    };
     use chrono::NaiveDateTime;
+    use chrono_tz::Tz;
     use uuid::Uuid;
 
-    fn create_activity(date_str: &str, activity_type: &str, distance: f32, pace: f32) -> Activity {
+    fn create_activity(date_str: &str, activity_type: &str, distance: f32, pace_secs: f64) -> Activity {
         Activity {
             id: Uuid::new_v4(),
             user_id: Uuid::new_v4(),
@@ -12,27 +19,31 @@ mod tests {
             activity_type: activity_type.to_string(),
             distance,
             duration: "00:30:00".to_string(),
-            average_pace: pace,
+            average_pace: Pace::from_secs_per_km(pace_secs),
             average_speed: 10.0,
             calories: 100.0,
             climb: 50.0,
-            date: NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S").unwrap(),
+            date: NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S")
+                .unwrap()
+                .and_utc(),
+            utc_offset: None,
             gps_file: "test.gpx".to_string(),
             source: "runkeeper".to_string(),
             external_id: None,
+            track_metrics: TrackMetrics::default(),
         }
     }
 
     #[test]
     fn test_aggregates_per_type_and_month() {
         let activities = vec![
-            create_activity("2024-01-05 08:00:00", "Running", 5.0, 5.0),
-            create_activity("2024-01-10 08:00:00", "Running", 10.0, 5.5),
-            create_activity("2024-02-15 08:00:00", "Running", 7.0, 6.0),
-            create_activity("2024-01-20 08:00:00", "Cycling", 20.0, 3.0),
+            create_activity("2024-01-05 08:00:00", "Running", 5.0, 300.0),
+            create_activity("2024-01-10 08:00:00", "Running", 10.0, 330.0),
+            create_activity("2024-02-15 08:00:00", "Running", 7.0, 360.0),
+            create_activity("2024-01-20 08:00:00", "Cycling", 20.0, 180.0),
         ];
 
-        let (agg, time_agg) = aggregate_activities(&activities);
+        let (agg, time_agg) = aggregate_activities(&activities, Tz::UTC);
 
         // Top-level
         assert_eq!(agg.len(), 2);
@@ -48,9 +59,24 @@ mod tests {
         assert_eq!(cyc_months["2024-01"].total_distance, 20.0);
     }
 
+    #[test]
+    fn test_average_pace_is_distance_weighted() {
+        let activities = vec![
+            create_activity("2024-01-05 08:00:00", "Running", 5.0, 300.0),
+            create_activity("2024-01-10 08:00:00", "Running", 10.0, 330.0),
+            create_activity("2024-01-12 08:00:00", "Running", 3.0, 0.0), // no pace recorded
+        ];
+
+        let (agg, _) = aggregate_activities(&activities, Tz::UTC);
+        let basic = &agg["Running"].basic;
+
+        assert!((basic.average_pace.secs_per_km() - 320.0).abs() < 1e-3);
+        assert_eq!(basic.best_pace, Pace::from_secs_per_km(300.0));
+    }
+
     #[test]
     fn test_empty_input() {
-        let (agg, time_agg) = aggregate_activities(&[]);
+        let (agg, time_agg) = aggregate_activities(&[], Tz::UTC);
         assert!(agg.is_empty());
         assert!(time_agg.is_empty());
     }
@@ -58,15 +84,15 @@ mod tests {
     #[test]
     fn test_time_aggregation_sums_match_totals() {
         let activities = vec![
-            create_activity("2024-01-05 08:00:00", "Running", 5.0, 5.0),
-            create_activity("2024-01-10 08:00:00", "Running", 10.0, 5.5),
-            create_activity("2024-02-15 08:00:00", "Running", 7.0, 6.0),
-            create_activity("2024-03-01 08:00:00", "Running", 3.0, 4.0),
-            create_activity("2024-01-20 08:00:00", "Cycling", 20.0, 3.0),
-            create_activity("2024-02-25 08:00:00", "Cycling", 10.0, 3.5),
+            create_activity("2024-01-05 08:00:00", "Running", 5.0, 300.0),
+            create_activity("2024-01-10 08:00:00", "Running", 10.0, 330.0),
+            create_activity("2024-02-15 08:00:00", "Running", 7.0, 360.0),
+            create_activity("2024-03-01 08:00:00", "Running", 3.0, 240.0),
+            create_activity("2024-01-20 08:00:00", "Cycling", 20.0, 180.0),
+            create_activity("2024-02-25 08:00:00", "Cycling", 10.0, 210.0),
         ];
 
-        let (total, time_agg) = aggregate_activities(&activities);
+        let (total, time_agg) = aggregate_activities(&activities, Tz::UTC);
 
         for (activity_type, dto) in &total {
             let monthly = time_agg
@@ -93,16 +119,16 @@ mod tests {
     #[test]
     fn test_advanced_aggregation_fields() {
         let activities = vec![
-            create_activity("2024-01-01 06:00:00", "Running", 5.0, 5.0), // Tuesday
-            create_activity("2024-01-02 07:00:00", "Running", 6.0, 5.5), // Wednesday
-            create_activity("2024-01-03 08:00:00", "Running", 7.0, 6.0), // Thursday
-            create_activity("2024-01-04 09:00:00", "Running", 8.0, 4.8), // Friday
-            create_activity("2024-01-05 10:00:00", "Running", 9.0, 4.9), // Saturday
-            create_activity("2024-01-06 11:00:00", "Running", 10.0, 4.7), // Sunday
-            create_activity("2024-01-07 12:00:00", "Running", 11.0, 4.6), // Monday
+            create_activity("2024-01-01 06:00:00", "Running", 5.0, 300.0), // Tuesday
+            create_activity("2024-01-02 07:00:00", "Running", 6.0, 330.0), // Wednesday
+            create_activity("2024-01-03 08:00:00", "Running", 7.0, 360.0), // Thursday
+            create_activity("2024-01-04 09:00:00", "Running", 8.0, 288.0), // Friday
+            create_activity("2024-01-05 10:00:00", "Running", 9.0, 294.0), // Saturday
+            create_activity("2024-01-06 11:00:00", "Running", 10.0, 282.0), // Sunday
+            create_activity("2024-01-07 12:00:00", "Running", 11.0, 276.0), // Monday
         ];
 
-        let (agg, _) = aggregate_activities(&activities);
+        let (agg, _) = aggregate_activities(&activities, Tz::UTC);
         let dto = agg.get("Running").expect("Expected running aggregation");
         let advanced = dto.advanced.as_ref().unwrap();
 
@@ -115,7 +141,7 @@ mod tests {
         assert!(advanced.most_consistent_week.is_some());
 
         assert!(
-            advanced.slowest_pace > 0.0,
+            advanced.slowest_pace.is_set(),
             "Should have a slowest pace value"
         );
         assert!(
@@ -144,12 +170,12 @@ mod tests {
     #[test]
     fn test_advanced_aggregation_edge_cases() {
         // Empty input
-        let (agg, _) = aggregate_activities(&[]);
+        let (agg, _) = aggregate_activities(&[], Tz::UTC);
         assert!(agg.is_empty());
 
         // One entry
-        let activities = vec![create_activity("2024-01-01 06:00:00", "Running", 10.0, 6.0)];
-        let (agg, _) = aggregate_activities(&activities);
+        let activities = vec![create_activity("2024-01-01 06:00:00", "Running", 10.0, 360.0)];
+        let (agg, _) = aggregate_activities(&activities, Tz::UTC);
         let dto = agg.get("Running").unwrap();
         let advanced = dto.advanced.as_ref().unwrap();
 
@@ -161,7 +187,7 @@ mod tests {
         assert!(advanced.most_frequent_weekday.is_some());
         assert!(advanced.most_consistent_week.is_some());
 
-        assert_eq!(advanced.slowest_pace, 6.0);
+        assert_eq!(advanced.slowest_pace, Pace::from_secs_per_km(360.0));
         assert!(advanced.speed_demon_hour.is_some());
         assert!(advanced.sweatiest_week.is_some());
         assert!(advanced.most_skipped_weekday.is_some());
@@ -169,4 +195,25 @@ mod tests {
         assert_eq!(advanced.pace_std_dev, 0.0); // only one activity = no deviation
         assert!(advanced.max_effort_cal_per_min > 0.0);
     }
+
+    #[test]
+    fn test_days_and_months_follow_user_timezone() {
+        // 22:00 UTC on Jan 31 is 08:00 on Feb 1 in Brisbane (UTC+10).
+        let activities = vec![
+            create_activity("2024-01-31 22:00:00", "Running", 5.0, 300.0),
+            create_activity("2024-02-01 21:00:00", "Running", 5.0, 330.0),
+        ];
+
+        let (agg, time_agg) = aggregate_activities(&activities, Tz::Australia__Brisbane);
+        let months = time_agg.get("Running").unwrap();
+        assert_eq!(months.len(), 1);
+        assert_eq!(months["2024-02"].total_activities, 2);
+
+        let advanced = agg["Running"].advanced.as_ref().unwrap();
+        assert_eq!(advanced.longest_streak_days, 2);
+        assert_eq!(advanced.speed_demon_hour.as_deref(), Some("08:00"));
+
+        let (_, time_agg) = aggregate_activities(&activities, Tz::UTC);
+        assert_eq!(time_agg["Running"].len(), 2);
+    }
 }
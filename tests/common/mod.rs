@@ -0,0 +1,41 @@
+//! Track builders shared by the integration tests.
+//!
+//! Each test crate uses only some of them.
+#![allow(dead_code)]
+
+use activity_api::activities::models::TrackPoint;
+use chrono::DateTime;
+use uuid::Uuid;
+
+/// Degrees of latitude per metre.
+pub const METRE: f64 = 1.0 / 111_195.0;
+
+/// Unix time every test track starts at: 2024-05-01 18:00 UTC.
+pub const START: i64 = 1_714_586_400;
+
+/// A track point `secs` after [`START`] at `latitude`, `longitude`, 30 m up.
+pub fn track_point(activity_id: Uuid, secs: i64, latitude: f64, longitude: f64) -> TrackPoint {
+    TrackPoint {
+        id: None,
+        activity_id,
+        latitude,
+        longitude,
+        elevation: 30.0,
+        time: DateTime::from_timestamp(START + secs, 0).unwrap(),
+        speed: None,
+        heart_rate: None,
+        cadence: None,
+        power: None,
+        temperature: None,
+    }
+}
+
+/// A track point `north_m` and `east_m` metres from 52° N 13° E.
+pub fn point(activity_id: Uuid, secs: i64, north_m: f64, east_m: f64) -> TrackPoint {
+    track_point(
+        activity_id,
+        secs,
+        52.0 + north_m * METRE,
+        13.0 + east_m * METRE / 52f64.to_radians().cos(),
+    )
+}
@@ -0,0 +1,256 @@
+use activity_api::achievements::{models::AchievementWithStatus, service::get_user_achievements};
+use activity_api::activities::{
+    models::{IngestOutcome, IngestReason, UploadFiles},
+    pace::Pace,
+    repository::find_by_id,
+    service::{ingest_activities, upload},
+};
+use activity_api::duplicates::{
+    models::{ResolveAction, ResolveDuplicateRequest},
+    service::{list_duplicates, resolve_duplicate},
+    status::DuplicateStatus,
+};
+use activity_api::error::AppError;
+use activity_api::sync::normalized::NormalizedActivity;
+use activity_api::users::{models::CreateUser, service::upsert_user};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn setup_db() -> PgPool {
+    dotenv::from_filename(".env.test").ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+    PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+async fn new_user(db: &PgPool) -> Uuid {
+    upsert_user(
+        db,
+        &CreateUser {
+            google_id: format!("duplicates-{}", Uuid::new_v4()),
+            email: format!("duplicates-{}@example.com", Uuid::new_v4()),
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 3, 1, 8, 0, 0).unwrap()
+}
+
+/// A 50-minute run without a track, as a device or service would deliver it.
+/// The watch starts its recording three minutes after the phone.
+fn run(source: &str, distance: f32) -> NormalizedActivity {
+    let date = match source {
+        "fit" => start() + Duration::minutes(3),
+        _ => start(),
+    };
+    NormalizedActivity {
+        source: source.into(),
+        external_id: Some(format!("{source}-{}", Uuid::new_v4())),
+        date,
+        utc_offset: None,
+        name: format!("{source} run"),
+        activity_type: "Running".into(),
+        distance,
+        duration: "00:50:00".into(),
+        average_pace: Pace::from_secs_per_km(3000.0 / distance as f64),
+        average_speed: distance * 60.0 / 50.0,
+        calories: 0.0,
+        climb: 0.0,
+        gps_file: String::new(),
+        track_points: vec![],
+    }
+}
+
+/// Import one activity and return its ID and report entry.
+async fn import(
+    db: &PgPool,
+    user_id: Uuid,
+    activity: &NormalizedActivity,
+) -> (Uuid, IngestOutcome, Option<IngestReason>) {
+    let response = ingest_activities(db, user_id, std::slice::from_ref(activity)).await;
+    let entry = &response.report[0];
+    (entry.activity_id.unwrap(), entry.outcome, entry.reason)
+}
+
+fn unlocked_by(achievements: &[AchievementWithStatus], slug: &str) -> Option<Uuid> {
+    achievements
+        .iter()
+        .find(|a| a.slug == slug && a.unlocked)
+        .and_then(|a| a.activity_id)
+}
+
+#[actix_web::test]
+async fn test_runkeeper_rows_match_other_sources_and_insert_in_batch() {
+    let db = setup_db().await;
+    let user_id = new_user(&db).await;
+    let (strava_id, _, _) = import(&db, user_id, &run("strava", 10.0)).await;
+
+    let csv = format!(
+        "Activity Id,Date,Type,Route Name,Distance (km),Duration,Average Pace,Average Speed (km/h),Calories Burned,Climb (m),Notes,GPX File\n\
+         {},2025-03-01 08:00:00,Running,,10.0,50:00,5:00,12,600,40,,\n\
+         {},2025-03-02 08:00:00,Running,,5.0,25:00,5:00,12,300,20,,\n\
+         {},2025-03-03 08:00:00,Running,,6.0,30:00,5:00,12,360,20,,\n",
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+    );
+    let mut files = UploadFiles::default();
+    files
+        .insert("cardioActivities.csv".into(), csv.into_bytes())
+        .unwrap();
+
+    let response = upload(&db, user_id, files, None).await.unwrap();
+    let mut report: Vec<_> = response
+        .report
+        .iter()
+        .map(|e| (e.line, e.outcome, e.reason))
+        .collect();
+    report.sort_by_key(|r| r.0);
+    assert_eq!(
+        report,
+        [
+            (
+                Some(2),
+                IngestOutcome::Duplicate,
+                Some(IngestReason::CrossSourceDuplicate)
+            ),
+            (Some(3), IngestOutcome::Inserted, None),
+            (Some(4), IngestOutcome::Inserted, None),
+        ]
+    );
+    let linked = response.report.iter().find(|e| e.line == Some(2)).unwrap();
+    assert_eq!(linked.activity_id, Some(strava_id));
+    assert_eq!(response.processed, 2);
+}
+
+#[actix_web::test]
+async fn test_merge_keeps_achievements_the_kept_activity_earns() {
+    let db = setup_db().await;
+    let user_id = new_user(&db).await;
+
+    // 10 km from Strava, then 8.9 km from the watch: close enough to review,
+    // too far apart to merge on their own.
+    let strava = run("strava", 10.0);
+    let (strava_id, _, _) = import(&db, user_id, &strava).await;
+    let (fit_id, outcome, reason) = import(&db, user_id, &run("fit", 8.9)).await;
+    assert_eq!(outcome, IngestOutcome::Inserted);
+    assert_eq!(reason, Some(IngestReason::SuspectedDuplicate));
+
+    let suspected = list_duplicates(&db, user_id, None).await.unwrap();
+    assert_eq!(suspected.len(), 1);
+    let duplicate_id = suspected[0].duplicate.id;
+    assert_eq!(suspected[0].duplicate.activity_id, strava_id);
+    assert_eq!(suspected[0].duplicate.other_id, Some(fit_id));
+
+    let before = get_user_achievements(&db, user_id).await.unwrap();
+    assert_eq!(unlocked_by(&before, "first_run"), Some(strava_id));
+    assert_eq!(unlocked_by(&before, "run_10k_once"), Some(strava_id));
+
+    // Without `keep` the FIT record wins: it is the higher-priority source.
+    let resolved = resolve_duplicate(
+        &db,
+        user_id,
+        duplicate_id,
+        ResolveDuplicateRequest {
+            action: ResolveAction::Merge,
+            keep: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(resolved.duplicate.duplicate.status, DuplicateStatus::Merged);
+    assert_eq!(resolved.duplicate.duplicate.activity_id, fit_id);
+    assert_eq!(
+        resolved.duplicate.duplicate.external_id,
+        strava.external_id
+    );
+    assert!(find_by_id(&db, strava_id).await.unwrap().is_none());
+
+    // The kept 8.9 km run still earns the first run and the 5k, not the 10k.
+    let removed = resolved.removed.unwrap();
+    assert!(removed
+        .revoked_achievements
+        .contains(&"run_10k_once".to_string()));
+    let after = get_user_achievements(&db, user_id).await.unwrap();
+    assert_eq!(unlocked_by(&after, "first_run"), Some(fit_id));
+    assert_eq!(unlocked_by(&after, "run_5k_once"), Some(fit_id));
+    assert_eq!(unlocked_by(&after, "run_10k_once"), None);
+
+    // The next sync of the Strava record links it to the kept activity.
+    let (linked_id, outcome, reason) = import(&db, user_id, &strava).await;
+    assert_eq!(linked_id, fit_id);
+    assert_eq!(outcome, IngestOutcome::Duplicate);
+    assert_eq!(reason, Some(IngestReason::CrossSourceDuplicate));
+
+    let again = resolve_duplicate(
+        &db,
+        user_id,
+        duplicate_id,
+        ResolveDuplicateRequest {
+            action: ResolveAction::Merge,
+            keep: None,
+        },
+    )
+    .await;
+    assert!(matches!(again, Err(AppError::BadRequest(_))));
+}
+
+#[actix_web::test]
+async fn test_dismiss_keeps_both_activities() {
+    let db = setup_db().await;
+    let user_id = new_user(&db).await;
+    let (strava_id, _, _) = import(&db, user_id, &run("strava", 10.0)).await;
+    let (fit_id, _, _) = import(&db, user_id, &run("fit", 8.9)).await;
+    let duplicate_id = list_duplicates(&db, user_id, None).await.unwrap()[0]
+        .duplicate
+        .id;
+
+    // `keep` must name one of the pair.
+    let invalid = resolve_duplicate(
+        &db,
+        user_id,
+        duplicate_id,
+        ResolveDuplicateRequest {
+            action: ResolveAction::Merge,
+            keep: Some(Uuid::new_v4()),
+        },
+    )
+    .await;
+    assert!(matches!(invalid, Err(AppError::BadRequest(_))));
+
+    // Another user's pair is not found.
+    let foreign = resolve_duplicate(
+        &db,
+        Uuid::new_v4(),
+        duplicate_id,
+        ResolveDuplicateRequest {
+            action: ResolveAction::Dismiss,
+            keep: None,
+        },
+    )
+    .await;
+    assert!(matches!(foreign, Err(AppError::NotFound)));
+
+    let resolved = resolve_duplicate(
+        &db,
+        user_id,
+        duplicate_id,
+        ResolveDuplicateRequest {
+            action: ResolveAction::Dismiss,
+            keep: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(resolved.duplicate.duplicate.status, DuplicateStatus::Dismissed);
+    assert!(resolved.removed.is_none());
+    assert!(find_by_id(&db, strava_id).await.unwrap().is_some());
+    assert!(find_by_id(&db, fit_id).await.unwrap().is_some());
+    assert!(list_duplicates(&db, user_id, None).await.unwrap().is_empty());
+}
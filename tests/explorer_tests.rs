@@ -0,0 +1,182 @@
+mod common;
+
+use std::collections::HashSet;
+
+use activity_api::activities::models::TrackPoint;
+use activity_api::explorer::tiles::{max_cluster, max_square, tile_of, tiles};
+use uuid::Uuid;
+
+use common::{track_point, START};
+
+/// Width of a zoom-14 tile in degrees of longitude.
+const TILE_DEG: f64 = 360.0 / 16_384.0;
+
+/// A track heading east along 52° N from 13° E, a point every 0.001°.
+fn eastward(activity_id: Uuid, degrees: f64) -> Vec<TrackPoint> {
+    (0..=(degrees * 1000.0) as i64)
+        .map(|i| track_point(activity_id, i * 5, 52.0, 13.0 + i as f64 / 1000.0))
+        .collect()
+}
+
+fn block(x0: i32, y0: i32, side: i32) -> impl Iterator<Item = (i32, i32)> {
+    (x0..x0 + side).flat_map(move |x| (y0..y0 + side).map(move |y| (x, y)))
+}
+
+#[test]
+fn test_tile_of_and_track_tiles() {
+    assert_eq!(tile_of(0.0, 0.0), (8192, 8192));
+    assert_eq!(tile_of(-0.0001, -0.0001), (8191, 8192));
+
+    let track = eastward(Uuid::nil(), 5.0 * TILE_DEG);
+    let (x0, y) = tile_of(52.0, 13.0);
+    let expected: Vec<(i32, i32)> = (x0..=x0 + 5).map(|x| (x, y)).collect();
+    assert_eq!(tiles(&track), expected);
+}
+
+#[test]
+fn test_max_square_and_cluster() {
+    // A 5×5 block, a 3-tile tail off its corner and a separate 4×4 block.
+    let mut visited: HashSet<(i32, i32)> = block(0, 0, 5).collect();
+    visited.extend([(5, 5), (6, 5), (7, 5)]);
+    visited.extend(block(20, 0, 4));
+
+    assert_eq!(max_square(&visited), 5);
+    // Inner 3×3 of the 5×5 block vs inner 2×2 of the 4×4 one.
+    assert_eq!(max_cluster(&visited), 9);
+
+    // Bridging the blocks with a 3-wide band (rows 1 to 3) joins them into
+    // one cluster: 7, 22 and 4 surrounded tiles in rows 1, 2 and 3.
+    visited.extend((5..20).flat_map(|x| (1..4).map(move |y| (x, y))));
+    assert_eq!(max_cluster(&visited), 7 + 22 + 4);
+    assert_eq!(max_square(&visited), 5);
+
+    assert_eq!(max_square(&HashSet::new()), 0);
+    assert_eq!(max_cluster(&HashSet::new()), 0);
+}
+
+mod http {
+    use std::collections::HashMap;
+
+    use activity_api::activities::{
+        heatmap,
+        models::{Activity, TrackMetrics},
+        pace::Pace,
+        repository,
+    };
+    use activity_api::explorer::{
+        handlers::{get_summary, list_activity_tiles},
+        service,
+    };
+    use activity_api::users::{models::CreateUser, service::upsert_user};
+    use actix_web::{test, App};
+    use chrono::{DateTime, Duration};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use super::{eastward, START, TILE_DEG};
+
+    async fn setup_db() -> PgPool {
+        dotenv::from_filename(".env.test").ok();
+        let database_url =
+            std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+        PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to test database")
+    }
+
+    /// Store a run `days` after the first one heading `tiles` tiles east,
+    /// with its heatmap cells, and record its explorer tiles.
+    async fn store_run(db: &PgPool, user_id: Uuid, days: i64, tiles: f64) -> Uuid {
+        let activity = Activity {
+            id: Uuid::new_v4(),
+            user_id,
+            date: DateTime::from_timestamp(START, 0).unwrap() + Duration::days(days),
+            utc_offset: None,
+            name: format!("Run {days}"),
+            activity_type: "Running".into(),
+            distance: 5.0,
+            duration: "30:00".into(),
+            average_pace: Pace::default(),
+            average_speed: 0.0,
+            calories: 0.0,
+            climb: 0.0,
+            gps_file: String::new(),
+            source: "manual".into(),
+            external_id: None,
+            track_metrics: TrackMetrics::default(),
+        };
+        let id = repository::insert_activity(db, &activity)
+            .await
+            .unwrap()
+            .unwrap();
+        let track = eastward(id, tiles * TILE_DEG);
+        repository::replace_heatmap_cells(db, id, &heatmap::cells(&track))
+            .await
+            .unwrap();
+        repository::insert_trackpoints(db, &HashMap::from([(id, track.clone())])).await;
+        service::record_activity(db, id, &track).await.unwrap();
+        id
+    }
+
+    #[actix_web::test]
+    async fn test_explorer_totals_and_new_tiles() {
+        let db = setup_db().await;
+        let user = upsert_user(
+            &db,
+            &CreateUser {
+                google_id: format!("explorer-{}", Uuid::new_v4()),
+                email: format!("explorer-{}@example.com", Uuid::new_v4()),
+            },
+        )
+        .await
+        .unwrap();
+        // Six tiles, then ten tiles imported later but run a week earlier:
+        // the earlier run visited all six first.
+        let short = store_run(&db, user.id, 7, 5.0).await;
+        let long = store_run(&db, user.id, 0, 9.0).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(get_summary)
+                .service(list_activity_tiles),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/users/{}/explorer", user.id))
+            .to_request();
+        let summary: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(summary["zoom"], 14);
+        assert_eq!(summary["total_tiles"], 10);
+        assert_eq!(summary["max_square"], 1);
+        assert_eq!(summary["max_cluster"], 0);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/users/{}/explorer/activities", user.id))
+            .to_request();
+        let per_activity: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            per_activity,
+            serde_json::json!([{
+                "activity_id": long,
+                "date": "2024-05-01T18:00:00Z",
+                "name": "Run 0",
+                "new_tiles": 10
+            }])
+        );
+
+        // Rebuilding from the heatmap cells gives the same tiles; without the
+        // long run the short one visited its six first.
+        repository::delete_activity(&db, user.id, long)
+            .await
+            .unwrap();
+        service::rebuild(&db, user.id).await.unwrap();
+        let summary = service::get_summary(&db, user.id).await.unwrap();
+        assert_eq!(summary.total_tiles, 6);
+        let per_activity = service::list_activity_tiles(&db, user.id).await.unwrap();
+        assert_eq!(per_activity.len(), 1);
+        assert_eq!(per_activity[0].activity_id, short);
+        assert_eq!(per_activity[0].new_tiles, 6);
+    }
+}
@@ -0,0 +1,245 @@
+mod common;
+
+use activity_api::activities::{models::TrackPoint, pace::Pace};
+use activity_api::routes::{
+    matcher::{signature, similarity, MIN_OVERLAP},
+    service::pace_trend,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use common::{point, METRE, START};
+
+/// A 1 km square loop (north, east, south, west), a point every 10 m, with
+/// GPS error slowly drifting up to `noise` metres off the road.
+fn square_loop(activity_id: Uuid, noise: f64, clockwise: bool) -> Vec<TrackPoint> {
+    let corners = if clockwise {
+        [
+            (0.0, 0.0),
+            (250.0, 0.0),
+            (250.0, 250.0),
+            (0.0, 250.0),
+            (0.0, 0.0),
+        ]
+    } else {
+        [
+            (0.0, 0.0),
+            (0.0, 250.0),
+            (250.0, 250.0),
+            (250.0, 0.0),
+            (0.0, 0.0),
+        ]
+    };
+    let mut track = Vec::new();
+    for w in corners.windows(2) {
+        for s in 0..25 {
+            let f = s as f64 / 25.0;
+            let scatter = noise * (track.len() as f64 / 4.0).sin();
+            let north = w[0].0 + (w[1].0 - w[0].0) * f + scatter;
+            let east = w[0].1 + (w[1].1 - w[0].1) * f + scatter;
+            track.push(point(activity_id, track.len() as i64 * 3, north, east));
+        }
+    }
+    track.push(point(activity_id, track.len() as i64 * 3, 0.0, 0.0));
+    track
+}
+
+/// 200 m north from the loop's start: too short for a route.
+fn short_dash(activity_id: Uuid) -> Vec<TrackPoint> {
+    (0..=20)
+        .map(|i| point(activity_id, i as i64 * 3, i as f64 * 10.0, 0.0))
+        .collect()
+}
+
+/// 500 m north and back from the loop's start.
+fn out_and_back(activity_id: Uuid) -> Vec<TrackPoint> {
+    (0..=50)
+        .chain((0..50).rev())
+        .enumerate()
+        .map(|(t, i)| point(activity_id, t as i64 * 3, i as f64 * 10.0, 0.0))
+        .collect()
+}
+
+#[test]
+fn test_similarity_clusters_repeated_loops() {
+    let id = Uuid::nil();
+    let loop_a = signature(&square_loop(id, 0.0, true));
+    let loop_b = signature(&square_loop(id, 8.0, true));
+    let reversed = signature(&square_loop(id, 0.0, false));
+    let other = signature(&out_and_back(id));
+
+    assert!(similarity(&loop_a, &loop_b) >= MIN_OVERLAP);
+    assert!(similarity(&loop_a, &reversed) >= MIN_OVERLAP);
+    assert!(similarity(&loop_a, &other) < MIN_OVERLAP);
+
+    // The same loop started 1 km away is another route.
+    let shifted: Vec<(f64, f64)> = loop_a
+        .iter()
+        .map(|&(lat, lon)| (lat + 1000.0 * METRE, lon))
+        .collect();
+    assert_eq!(similarity(&loop_a, &shifted), 0.0);
+}
+
+#[test]
+fn test_pace_trend() {
+    let day = |d: i64| DateTime::<Utc>::from_timestamp(START + d * 86_400, 0).unwrap();
+    let runs = [
+        (day(0), Pace::from_secs_per_km(360.0)),
+        (day(15), Pace::from_secs_per_km(345.0)),
+        (day(30), Pace::from_secs_per_km(330.0)),
+        (day(31), Pace::default()),
+    ];
+    let trend = pace_trend(&runs).unwrap();
+    assert!((trend + 30.0).abs() < 1e-6, "trend {trend}");
+
+    assert_eq!(pace_trend(&runs[..1]), None);
+    assert_eq!(pace_trend(&[]), None);
+}
+
+mod http {
+    use std::collections::HashMap;
+
+    use activity_api::activities::{
+        models::{Activity, TrackMetrics},
+        pace::Pace,
+        repository,
+    };
+    use activity_api::routes::{
+        handlers::{get_route, list_routes, update_route},
+        service::backfill,
+    };
+    use activity_api::users::{models::CreateUser, service::upsert_user};
+    use actix_web::{test, App};
+    use chrono::{DateTime, Duration};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use super::{out_and_back, short_dash, square_loop, START};
+
+    async fn setup_db() -> PgPool {
+        dotenv::from_filename(".env.test").ok();
+        let database_url =
+            std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+        PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to test database")
+    }
+
+    async fn store_run(
+        db: &PgPool,
+        user_id: Uuid,
+        days: i64,
+        duration: &str,
+        track: fn(Uuid) -> Vec<activity_api::activities::models::TrackPoint>,
+    ) -> Uuid {
+        let activity = Activity {
+            id: Uuid::new_v4(),
+            user_id,
+            date: DateTime::from_timestamp(START, 0).unwrap() + Duration::days(days),
+            utc_offset: None,
+            name: format!("Run {days}"),
+            activity_type: "Running".into(),
+            distance: 1.0,
+            duration: duration.into(),
+            average_pace: Pace::default(),
+            average_speed: 0.0,
+            calories: 0.0,
+            climb: 0.0,
+            gps_file: String::new(),
+            source: "manual".into(),
+            external_id: None,
+            track_metrics: TrackMetrics::default(),
+        };
+        let id = repository::insert_activity(db, &activity)
+            .await
+            .unwrap()
+            .unwrap();
+        repository::insert_trackpoints(db, &HashMap::from([(id, track(id))])).await;
+        id
+    }
+
+    #[actix_web::test]
+    async fn test_routes_cluster_stored_runs() {
+        let db = setup_db().await;
+        let user = upsert_user(
+            &db,
+            &CreateUser {
+                google_id: format!("routes-{}", Uuid::new_v4()),
+                email: format!("routes-{}@example.com", Uuid::new_v4()),
+            },
+        )
+        .await
+        .unwrap();
+        let slow = store_run(&db, user.id, 0, "6:00", |id| square_loop(id, 0.0, true)).await;
+        let fast = store_run(&db, user.id, 14, "5:40", |id| square_loop(id, 8.0, true)).await;
+        store_run(&db, user.id, 7, "5:50", out_and_back).await;
+        store_run(&db, user.id, 10, "1:00", short_dash).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(list_routes)
+                .service(get_route)
+                .service(update_route),
+        )
+        .await;
+        let list = |query: &str| {
+            test::TestRequest::get()
+                .uri(&format!("/users/{}/routes{query}", user.id))
+                .to_request()
+        };
+
+        // Listing only reads: runs stored without a route wait for the backfill.
+        let routes: serde_json::Value = test::call_and_read_body_json(&app, list("?min_runs=1")).await;
+        assert_eq!(routes, serde_json::json!([]));
+
+        // Every stored track is checked, the short one marked as on no route.
+        backfill(&db).await.unwrap();
+        let (checked, routed): (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), COUNT(r.route_id) FROM route_activities r
+             JOIN activities a ON a.id = r.activity_id
+             WHERE a.user_id = $1",
+        )
+        .bind(user.id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!((checked, routed), (4, 3));
+
+        let routes: serde_json::Value = test::call_and_read_body_json(&app, list("")).await;
+        let routes = routes.as_array().unwrap();
+        assert_eq!(routes.len(), 1);
+        let route = &routes[0];
+        assert_eq!(route["run_count"], 2);
+        assert_eq!(route["best"]["activity_id"], fast.to_string());
+        assert_eq!(route["runs"][0]["activity_id"], fast.to_string());
+        assert_eq!(route["runs"][1]["activity_id"], slow.to_string());
+        assert_eq!(route["runs"][1]["behind_best_seconds"], 20);
+        assert!(route["pace_trend"].as_f64().unwrap() < 0.0);
+        let route_id = route["route"]["id"].as_str().unwrap().to_string();
+
+        let routes: serde_json::Value = test::call_and_read_body_json(&app, list("?min_runs=1")).await;
+        assert_eq!(routes.as_array().unwrap().len(), 2);
+
+        assert_eq!(test::call_service(&app, list("?min_runs=0")).await.status(), 400);
+
+        let req = test::TestRequest::patch()
+            .uri(&format!("/users/{}/routes/{}", user.id, route_id))
+            .set_json(serde_json::json!({ "name": "Park loop" }))
+            .to_request();
+        let renamed: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(renamed["route"]["name"], "Park loop");
+        assert_eq!(renamed["route"]["id"], route_id);
+
+        let req = test::TestRequest::patch()
+            .uri(&format!("/users/{}/routes/{}", user.id, route_id))
+            .set_json(serde_json::json!({ "name": "  " }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/users/{}/routes/{}", Uuid::new_v4(), route_id))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+}
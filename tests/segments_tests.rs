@@ -0,0 +1,273 @@
+mod common;
+
+use activity_api::activities::models::TrackPoint;
+use activity_api::segments::matcher::{find_passes, polyline_length, Pass};
+use uuid::Uuid;
+
+use common::{point, METRE};
+
+/// A 500 m segment running north from the origin.
+fn segment() -> Vec<(f64, f64)> {
+    [0.0, 250.0, 500.0]
+        .iter()
+        .map(|&n| (52.0 + n * METRE, 13.0))
+        .collect()
+}
+
+#[test]
+fn test_polyline_length() {
+    assert!((polyline_length(&segment()) - 500.0).abs() < 0.5);
+}
+
+#[test]
+fn test_find_passes_on_out_and_back_laps() {
+    // Out along the segment and back, twice, 10 m every 3 s with 5 m of
+    // sideways GPS noise.  Only the northbound legs are passes.
+    let mut track = Vec::new();
+    let mut secs = 0;
+    for _ in 0..2 {
+        let legs = (0..=50)
+            .map(|i| i as f64 * 10.0)
+            .chain((1..50).rev().map(|i| i as f64 * 10.0));
+        for (i, north) in legs.enumerate() {
+            track.push(point(Uuid::nil(), secs, north, if i % 2 == 0 { 5.0 } else { -5.0 }));
+            secs += 3;
+        }
+    }
+
+    let passes = find_passes(&segment(), &track);
+    assert_eq!(
+        passes,
+        [
+            Pass { start: 0, end: 50 },
+            Pass {
+                start: 100,
+                end: 150
+            }
+        ]
+    );
+    let elapsed = track[50].time - track[0].time;
+    assert_eq!(elapsed.num_seconds(), 150);
+}
+
+#[test]
+fn test_find_passes_rejects_shortcut_and_partial() {
+    // Reaches the end by a 100 m detour east: leaves the corridor.
+    let detour: Vec<TrackPoint> = (0..=50)
+        .map(|i| {
+            let east = if (15..=35).contains(&i) { 100.0 } else { 0.0 };
+            point(Uuid::nil(), i * 3, i as f64 * 10.0, east)
+        })
+        .collect();
+    assert!(find_passes(&segment(), &detour).is_empty());
+
+    // Turns back halfway.
+    let partial: Vec<TrackPoint> = (0..=25)
+        .chain((0..25).rev())
+        .enumerate()
+        .map(|(t, i)| point(Uuid::nil(), t as i64 * 3, i as f64 * 10.0, 0.0))
+        .collect();
+    assert!(find_passes(&segment(), &partial).is_empty());
+}
+
+mod http {
+    use activity_api::activities::{pace::Pace, service::ingest_activities};
+    use activity_api::segments::handlers::{create_segment, delete_segment, get_leaderboard};
+    use activity_api::sync::normalized::{NormalizedActivity, NormalizedTrackPoint};
+    use activity_api::users::{
+        models::{CreateUser, UpdateUser},
+        service::{update_user, upsert_user},
+    };
+    use actix_web::{test, App};
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    async fn setup_db() -> PgPool {
+        dotenv::from_filename(".env.test").ok();
+        let database_url =
+            std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+        PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to test database")
+    }
+
+    #[actix_web::test]
+    async fn test_create_and_delete_drawn_segment() {
+        let db = setup_db().await;
+        let user = upsert_user(
+            &db,
+            &CreateUser {
+                google_id: format!("segments-{}", Uuid::new_v4()),
+                email: format!("segments-{}@example.com", Uuid::new_v4()),
+            },
+        )
+        .await
+        .unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(create_segment)
+                .service(delete_segment)
+                .service(get_leaderboard),
+        )
+        .await;
+
+        // Too short.
+        let req = test::TestRequest::post()
+            .uri(&format!("/users/{}/segments", user.id))
+            .set_json(serde_json::json!({
+                "name": "Stub",
+                "points": [
+                    { "latitude": 52.0, "longitude": 13.0 },
+                    { "latitude": 52.0001, "longitude": 13.0 }
+                ]
+            }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/users/{}/segments", user.id))
+            .set_json(serde_json::json!({
+                "name": "Canal straight",
+                "points": [
+                    { "latitude": 52.0, "longitude": 13.0 },
+                    { "latitude": 52.0045, "longitude": 13.0 }
+                ]
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let detail: serde_json::Value = test::read_body_json(resp).await;
+        let segment_id = detail["segment"]["id"].as_str().unwrap().to_string();
+        assert_eq!(detail["segment"]["activity_type"], "Running");
+        let distance_m = detail["segment"]["distance_m"].as_f64().unwrap();
+        assert!((distance_m - 500.0).abs() < 1.0);
+        assert!(detail["personal_best"].is_null());
+        assert_eq!(detail["efforts"], serde_json::json!([]));
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/segments/{}/leaderboard", segment_id))
+            .to_request();
+        let board: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(board["segment_name"], "Canal straight");
+        assert_eq!(board["entries"], serde_json::json!([]));
+
+        let req = test::TestRequest::delete()
+            .uri(&format!(
+                "/users/{}/segments/{}",
+                Uuid::new_v4(),
+                segment_id
+            ))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/users/{}/segments/{}", user.id, segment_id))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 204);
+    }
+
+    #[actix_web::test]
+    async fn test_leaderboard_times_other_users_and_hides_their_email() {
+        let db = setup_db().await;
+        let new_user = |name: &str| CreateUser {
+            google_id: format!("{name}-{}", Uuid::new_v4()),
+            email: format!("{name}-{}@example.com", Uuid::new_v4()),
+        };
+        let runner = upsert_user(&db, &new_user("runner")).await.unwrap();
+        update_user(
+            &db,
+            runner.id,
+            &UpdateUser {
+                timezone: None,
+                segment_leaderboards: Some(true),
+            },
+        )
+        .await
+        .unwrap();
+        let creator = upsert_user(&db, &new_user("creator")).await.unwrap();
+
+        // 600 m north from (60.0, 25.0), 10 m every 3 s.
+        let start = chrono::Utc::now() - chrono::Duration::days(1);
+        let track: Vec<NormalizedTrackPoint> = (0..=60)
+            .map(|i| NormalizedTrackPoint {
+                latitude: 60.0 + i as f64 * 10.0 / 111_195.0,
+                longitude: 25.0,
+                elevation: 10.0,
+                time: start + chrono::Duration::seconds(i * 3),
+                speed: None,
+                heart_rate: None,
+                cadence: None,
+                power: None,
+                temperature: None,
+            })
+            .collect();
+        let run = NormalizedActivity {
+            source: "gpx".into(),
+            external_id: None,
+            date: start,
+            utc_offset: None,
+            name: "Harbour run".into(),
+            activity_type: "Running".into(),
+            distance: 0.6,
+            duration: "00:03:00".into(),
+            average_pace: Pace::from_secs_per_km(300.0),
+            average_speed: 12.0,
+            calories: 0.0,
+            climb: 0.0,
+            gps_file: "harbour.gpx".into(),
+            track_points: track,
+        };
+        let ingested = ingest_activities(&db, runner.id, &[run]).await;
+        let activity_id = ingested.report[0].activity_id.unwrap().to_string();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(db.clone()))
+                .service(create_segment)
+                .service(get_leaderboard),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/users/{}/segments", creator.id))
+            .set_json(serde_json::json!({
+                "name": "Harbour straight",
+                "points": [
+                    { "latitude": 60.0, "longitude": 25.0 },
+                    { "latitude": 60.0045, "longitude": 25.0 }
+                ]
+            }))
+            .to_request();
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        // The creator has no runs there; the response holds only their efforts.
+        assert_eq!(detail["efforts"], serde_json::json!([]));
+        let segment_id = detail["segment"]["id"].as_str().unwrap().to_string();
+
+        // The runner's stored run is timed in the background.
+        let mut entry = None;
+        for _ in 0..100 {
+            let req = test::TestRequest::get()
+                .uri(&format!("/segments/{}/leaderboard", segment_id))
+                .to_request();
+            let board: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            // Nothing on the public board leads back to the runner's account.
+            assert!(!board.to_string().contains(&runner.id.to_string()));
+            entry = board["entries"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|e| e["effort"]["activity_id"] == activity_id.as_str())
+                .cloned();
+            if entry.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        let entry = entry.expect("runner's effort was not timed");
+        let name = entry["display_name"].as_str().unwrap();
+        assert!(name.starts_with("Athlete "));
+        assert!(!name.contains("runner"));
+        assert!(entry.get("user_id").is_none());
+    }
+}
@@ -0,0 +1,254 @@
+use activity_api::activities::{
+    models::{IngestOutcome, IngestReason, UploadFiles},
+    service::upload,
+};
+use activity_api::users::{models::CreateUser, service::upsert_user};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn setup_db() -> PgPool {
+    dotenv::from_filename(".env.test").ok();
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+    PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+async fn new_user(db: &PgPool) -> Uuid {
+    upsert_user(
+        db,
+        &CreateUser {
+            google_id: format!("uploads-{}", Uuid::new_v4()),
+            email: format!("uploads-{}@example.com", Uuid::new_v4()),
+        },
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+/// A `cardioActivities.csv` with two valid rows around one that fails to parse.
+fn runkeeper_csv() -> UploadFiles {
+    let csv = format!(
+        "Activity Id,Date,Type,Route Name,Distance (km),Duration,Average Pace,Average Speed (km/h),Calories Burned,Climb (m),Notes,GPX File\n\
+         {},2024-05-01 18:00:00,Running,,5.0,25:00,5:00,12,300,20,,\n\
+         {},2024-05-02 18:00:00,Running,,not-a-number,25:00,5:00,12,300,20,,\n\
+         {},2024-05-03 18:00:00,Running,,10.0,50:00,5:00,12,600,40,,\n",
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+    );
+    let mut files = UploadFiles::default();
+    files
+        .insert("cardioActivities.csv".into(), csv.into_bytes())
+        .unwrap();
+    files
+}
+
+#[actix_web::test]
+async fn test_upload_report_inserted_duplicate_and_invalid_rows() {
+    let db = setup_db().await;
+    let user_id = new_user(&db).await;
+
+    let first = upload(&db, user_id, runkeeper_csv(), None).await.unwrap();
+    let mut report: Vec<_> = first
+        .report
+        .iter()
+        .map(|e| (e.line, e.outcome, e.reason))
+        .collect();
+    report.sort_by_key(|r| r.0);
+    assert_eq!(
+        report,
+        [
+            (Some(2), IngestOutcome::Inserted, None),
+            (
+                Some(3),
+                IngestOutcome::Skipped,
+                Some(IngestReason::InvalidRow)
+            ),
+            (Some(4), IngestOutcome::Inserted, None),
+        ]
+    );
+    assert_eq!(first.processed, 2);
+    assert!(first
+        .report
+        .iter()
+        .all(|e| e.file == "cardioActivities.csv"));
+
+    // The same rows again under new IDs: they start at the same times as the
+    // activities just stored.
+    let second = upload(&db, user_id, runkeeper_csv(), None).await.unwrap();
+    let duplicates = second
+        .report
+        .iter()
+        .filter(|e| e.outcome == IngestOutcome::Duplicate)
+        .count();
+    assert_eq!(duplicates, 2);
+    assert_eq!(second.processed, 0);
+}
+
+#[actix_web::test]
+async fn test_upload_report_failed_insert_is_not_a_duplicate() {
+    let db = setup_db().await;
+    // No such user: every insert violates the foreign key.
+    let response = upload(&db, Uuid::new_v4(), runkeeper_csv(), None)
+        .await
+        .unwrap();
+
+    let failed: Vec<_> = response
+        .report
+        .iter()
+        .filter(|e| e.outcome == IngestOutcome::Failed)
+        .collect();
+    assert_eq!(failed.len(), 2);
+    assert!(failed
+        .iter()
+        .all(|e| e.reason == Some(IngestReason::InsertFailed) && e.activity_id.is_none()));
+    assert!(response
+        .report
+        .iter()
+        .all(|e| e.outcome != IngestOutcome::Duplicate));
+    assert_eq!(response.processed, 0);
+}
+
+mod http {
+    use activity_api::activities::handlers::upload_files;
+    use activity_api::uploads::{handlers::get_upload_job, service, status::UploadJobStatus};
+    use actix_web::{test, web, App};
+    use serde_json::Value;
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use super::{new_user, setup_db};
+
+    const BOUNDARY: &str = "upload-test-boundary";
+
+    /// A multipart body with one file field per `(file name, content)`.
+    fn multipart(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (name, content) in files {
+            body.extend(
+                format!(
+                    "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"files\"; filename=\"{name}\"\r\n\
+                     Content-Type: application/octet-stream\r\n\r\n"
+                )
+                .as_bytes(),
+            );
+            body.extend(*content);
+            body.extend(b"\r\n");
+        }
+        body.extend(format!("--{BOUNDARY}--\r\n").as_bytes());
+        body
+    }
+
+    fn upload_request(user_id: Uuid, query: &str, body: Vec<u8>) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri(&format!("/activities/upload/{user_id}{query}"))
+            .insert_header((
+                "content-type",
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            ))
+            .set_payload(body)
+    }
+
+    #[actix_web::test]
+    async fn test_upload_job_lifecycle() {
+        let db = setup_db().await;
+        let user_id = new_user(&db).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(db.clone()))
+                .service(upload_files)
+                .service(get_upload_job),
+        )
+        .await;
+
+        let csv = super::runkeeper_csv().csv_text;
+        let body = multipart(&[("cardioActivities.csv", csv.as_bytes())]);
+
+        // A dry run reads the same files but stores nothing.
+        let preview: Value = test::call_and_read_body_json(
+            &app,
+            upload_request(user_id, "?dry_run=true", body.clone()).to_request(),
+        )
+        .await;
+        assert_eq!(preview["would_insert"].as_array().unwrap().len(), 2);
+        assert_eq!(preview["skipped"].as_array().unwrap().len(), 1);
+
+        let resp = test::call_service(&app, upload_request(user_id, "", body).to_request()).await;
+        assert_eq!(resp.status(), 202);
+        let accepted: Value = test::read_body_json(resp).await;
+        let status_url = accepted["status_url"].as_str().unwrap().to_string();
+        let job_id = accepted["job_id"].as_str().unwrap().to_string();
+        assert_eq!(status_url, format!("/users/{user_id}/uploads/{job_id}"));
+
+        let job = wait_for_job(&db, user_id, &accepted["job_id"]).await;
+        assert_eq!(job["status"], "completed");
+        assert_eq!(job["total_items"], 3);
+        assert_eq!(job["processed_items"], 3);
+        assert_eq!(job["inserted"], 2);
+        assert_eq!(job["skipped"], 1);
+        assert_eq!(job["result"]["report"].as_array().unwrap().len(), 3);
+
+        let req = test::TestRequest::get().uri(&status_url).to_request();
+        let polled: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(polled["status"], "completed");
+
+        // Another user cannot read the job.
+        let req = test::TestRequest::get()
+            .uri(&format!("/users/{}/uploads/{job_id}", Uuid::new_v4()))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_upload_job_fails_on_invalid_zip() {
+        let db = setup_db().await;
+        let user_id = new_user(&db).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(db.clone()))
+                .service(upload_files)
+                .service(get_upload_job),
+        )
+        .await;
+
+        // The archive is only opened by the job, so the request is accepted.
+        let body = multipart(&[("export.zip", b"not a zip")]);
+        let resp = test::call_service(&app, upload_request(user_id, "", body).to_request()).await;
+        assert_eq!(resp.status(), 202);
+        let accepted: Value = test::read_body_json(resp).await;
+
+        let job = wait_for_job(&db, user_id, &accepted["job_id"]).await;
+        assert_eq!(job["status"], "failed");
+        assert!(job["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid ZIP archive"));
+
+        // A dry run reads the archive in the request and rejects it.
+        let body = multipart(&[("export.zip", b"not a zip")]);
+        let resp = test::call_service(
+            &app,
+            upload_request(user_id, "?dry_run=true", body).to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    /// Wait for a job to complete or fail, then return it as JSON.
+    async fn wait_for_job(db: &PgPool, user_id: Uuid, job_id: &Value) -> Value {
+        let job_id = Uuid::parse_str(job_id.as_str().unwrap()).unwrap();
+        for _ in 0..100 {
+            let job = service::get_job(db, user_id, job_id).await.unwrap();
+            if matches!(
+                job.status,
+                UploadJobStatus::Completed | UploadJobStatus::Failed
+            ) {
+                return serde_json::to_value(job).unwrap();
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        panic!("upload job {job_id} did not finish");
+    }
+}
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests {
     use activity_api::users::{
-        handlers::{create_user, get_user},
+        handlers::{create_user, get_user, update_user},
         models::{CreateUser, User},
     };
     use actix_web::{test, web, App};
@@ -101,4 +101,41 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), 404);
     }
+
+    #[actix_web::test]
+    async fn test_update_user_timezone() {
+        let db = setup_db().await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(db.clone()))
+                .service(create_user)
+                .service(update_user),
+        )
+        .await;
+
+        let create_payload = CreateUser {
+            google_id: "tz-google-id".to_string(),
+            email: "tz@example.com".to_string(),
+        };
+        let req = test::TestRequest::post()
+            .uri("/users")
+            .set_json(&create_payload)
+            .to_request();
+        let user: User = test::call_and_read_body_json(&app, req).await;
+
+        let req = test::TestRequest::patch()
+            .uri(&format!("/users/{}", user.id))
+            .set_json(serde_json::json!({ "timezone": "Australia/Brisbane" }))
+            .to_request();
+        let updated: User = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(updated.timezone, "Australia/Brisbane");
+
+        let req = test::TestRequest::patch()
+            .uri(&format!("/users/{}", user.id))
+            .set_json(serde_json::json!({ "timezone": "Mars/Olympus_Mons" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
 }